@@ -76,7 +76,9 @@ impl<T: Serialize> OAlgorithmSerialisedExport<T> {
                 ind.update_objective(name, *value)?;
             }
             for (name, value) in &individual_data.constraint_values {
-                ind.update_constraint(name, value.clone())?;
+                if let Some(value) = value {
+                    ind.update_constraint(name, value.clone())?;
+                }
             }
             for (name, value) in &individual_data.variable_values {
                 ind.update_variable(name, value.clone())?;
@@ -137,13 +139,13 @@ impl OAlgorithmExport {
     ///
     /// * `name`: The variable name.
     ///
-    /// returns: `Result<f64, OError>`
-    // pub fn get_real_variables(&self, name: &str) -> Result<Vec<f64>, OError> {
-    //     self.individuals
-    //         .iter()
-    //         .map(|i| i.get_real_value(name))
-    //         .collect()
-    // }
+    /// returns: `Result<Vec<f64>, OError>`
+    pub fn get_real_variables(&self, name: &str) -> Result<Vec<f64>, OOError> {
+        self.individuals
+            .iter()
+            .map(|i| i.get_variable_value(name)?.as_real())
+            .collect()
+    }
 
     /// Get the objective values grouped by objective name.
     ///
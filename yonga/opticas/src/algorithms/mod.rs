@@ -1,9 +1,13 @@
 pub use algorithm::{OAlgorithm, OAlgorithmExport, OAlgorithmSerialisedExport, OExportHistory};
+pub use moeadopticas::{MOEADOPTICASArg, OScalarisation, MOEADOPTICAS};
 pub use nsga2opticas::{NSGA2OPTICASArg, NSGA2OPTICAS};
+pub use nsga3opticas::{NSGA3OPTICASArg, NSGA3OPTICAS};
 pub use stopping_condition::{
     OMaxDurationValue, OMaxGenerationValue, OStoppingCondition, OStoppingConditionType,
 };
 
 mod algorithm;
+mod moeadopticas;
 mod nsga2opticas;
+mod nsga3opticas;
 mod stopping_condition;
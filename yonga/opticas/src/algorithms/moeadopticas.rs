@@ -0,0 +1,568 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+use log::{debug, info};
+use rand::prelude::SliceRandom;
+use rand::RngCore;
+
+use opticas_macros::{as_algorithm, as_algorithm_args, impl_algorithm_trait_items};
+
+use crate::algorithms::OAlgorithm;
+use crate::core::utils::get_rng;
+use crate::core::{ODataValue, OIndividual, OOError, OVariableValue};
+use crate::operators::{
+    OCrossover, OMutation, OPolynomialMutation, OPolynomialMutationArgs, OSimulatedBinaryCrossover,
+    OSimulatedBinaryCrossoverArgs,
+};
+use crate::utils::{argsort, dot_product, vector_magnitude, DasDarren1998, NumberOfPartitions, Sort};
+
+/// The weight used in place of a zero weight in the Tchebycheff function, so that every objective
+/// counts for the boundary sub-problems.
+const MIN_WEIGHT: f64 = 1e-6;
+
+/// The function turning the objectives of an individual into the scalar value of a sub-problem.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum OScalarisation {
+    /// The weighted Tchebycheff distance from the ideal point: `max_j w_j * |f_j - z_j|`.
+    Tchebycheff,
+    /// The penalty-based boundary intersection: the distance from the ideal point along the weight
+    /// direction, plus `penalty` times the distance from the direction. A penalty of `5` is
+    /// commonly used.
+    PBI { penalty: f64 },
+}
+
+impl OScalarisation {
+    /// Calculate the value of a sub-problem. The lower the better.
+    ///
+    /// # Arguments
+    ///
+    /// * `objectives`: The objective values.
+    /// * `weights`: The weights of the sub-problem.
+    /// * `ideal_point`: The ideal point.
+    ///
+    /// returns: `Result<f64, OError>`
+    pub fn value(
+        &self,
+        objectives: &[f64],
+        weights: &[f64],
+        ideal_point: &[f64],
+    ) -> Result<f64, OOError> {
+        let translated: Vec<f64> = objectives
+            .iter()
+            .zip(ideal_point)
+            .map(|(f, z)| f - z)
+            .collect();
+
+        match self {
+            OScalarisation::Tchebycheff => Ok(translated
+                .iter()
+                .zip(weights)
+                .map(|(f, w)| w.max(MIN_WEIGHT) * f.abs())
+                .fold(f64::NEG_INFINITY, f64::max)),
+            OScalarisation::PBI { penalty } => {
+                let to_error = |e: String| OOError::Generic(e);
+                let norm = vector_magnitude(weights).map_err(to_error)?;
+                let d1 = dot_product(&translated, weights).map_err(to_error)? / norm;
+                let distance: Vec<f64> = translated
+                    .iter()
+                    .zip(weights)
+                    .map(|(f, w)| f - d1 * w / norm)
+                    .collect();
+                let d2 = vector_magnitude(&distance).map_err(to_error)?;
+                Ok(d1 + penalty * d2)
+            }
+        }
+    }
+}
+
+/// Input arguments for the MOEADOPTICAS algorithm.
+#[as_algorithm_args]
+pub struct MOEADOPTICASArg {
+    /// The number of partitions used to generate the weight vectors with [`DasDarren1998`]. There is
+    /// one sub-problem, and one individual in the population, per weight vector.
+    pub number_of_partitions: NumberOfPartitions,
+    /// The number of closest weight vectors making the neighbourhood of a sub-problem. The parents
+    /// of a sub-problem are picked in its neighbourhood and its offspring can only replace the
+    /// solutions of its neighbours. This is `T` in the paper.
+    pub neighbourhood_size: usize,
+    /// The maximum number of neighbours an offspring replaces. This defaults to the neighbourhood
+    /// size; a small number (such as `2`) keeps one good offspring from taking over the
+    /// neighbourhood.
+    pub max_replacements: Option<usize>,
+    /// The function used to scalarise the objectives of each sub-problem.
+    pub scalarisation: OScalarisation,
+    /// The options of the Simulated Binary Crossover (SBX) operator. This defaults to
+    /// [`OSimulatedBinaryCrossoverArgs::default()`].
+    pub crossover_operator_options: Option<OSimulatedBinaryCrossoverArgs>,
+    /// The options to Polynomial Mutation (PM) operator used to mutate the variables of an
+    /// individual. This defaults to [`OPolynomialMutationArgs::default()`].
+    pub mutation_operator_options: Option<OPolynomialMutationArgs>,
+    /// Instead of initialising the population with random variables, see the initial population
+    /// with  the variable values from a JSON files exported with this tool.
+    pub resume_from_file: Option<PathBuf>,
+    /// Seed the initial population with these solutions. Each map sets the variables of one
+    /// individual; the variables it does not set and the remaining individuals are random. This is
+    /// ignored when `resume_from_file` is set.
+    pub initial_solutions: Option<Vec<HashMap<String, OVariableValue>>>,
+    /// The seed used in the random number generator (RNG). The seed is randomly picked if this is
+    /// `None`.
+    pub seed: Option<u64>,
+}
+
+/// The Multi-Objective Evolutionary Algorithm based on Decomposition (MOEA/D). The problem is
+/// decomposed into one scalar sub-problem per weight vector, and each individual of the population
+/// solves one sub-problem. The offspring of a sub-problem is bred from its neighbours and replaces
+/// the neighbours it improves on.
+///
+/// The offsprings of a generation are all created before being evaluated, so that they can be
+/// evaluated in parallel, and then update the ideal point and the neighbourhoods one by one. A
+/// feasible solution is preferred to an unfeasible one, and the least violating solution is
+/// preferred among the unfeasible ones.
+///
+/// Implemented based on:
+/// > Q. Zhang and H. Li, "MOEA/D: A Multiobjective Evolutionary Algorithm Based on Decomposition,"
+/// > in IEEE Transactions on Evolutionary Computation, vol. 11, no. 6, pp. 712-731, Dec. 2007,
+/// > doi: 10.1109/TEVC.2007.892759.
+///
+/// See: <https://doi.org/10.1109/TEVC.2007.892759>.
+#[as_algorithm(MOEADOPTICASArg)]
+pub struct MOEADOPTICAS {
+    /// The weight vector of each sub-problem.
+    weights: Vec<Vec<f64>>,
+    /// The indexes of the sub-problems in the neighbourhood of each sub-problem, closest first.
+    neighbourhoods: Vec<Vec<usize>>,
+    /// The best value of each objective found so far.
+    ideal_point: Vec<f64>,
+    /// The SBX operator to use to generate a new children by recombining the variables of parent
+    /// solutions.
+    crossover_operator: OSimulatedBinaryCrossover,
+    /// The PM operator to use to mutate the variables of an individual.
+    mutation_operator: OPolynomialMutation,
+    /// The seed to use.
+    rng: Box<dyn RngCore>,
+}
+
+impl MOEADOPTICAS {
+    /// Initialise the MOEADOPTICAS algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `problem`: The problem being solved.
+    /// * `options`: The [`MOEADOPTICASArg`] arguments to customise the algorithm behaviour.
+    ///
+    /// returns: `Result<MOEADOPTICAS, OError>`.
+    pub fn new(problem: OProblem, options: MOEADOPTICASArg) -> Result<Self, OOError> {
+        let name = "MOEADOPTICAS".to_string();
+
+        let das_darren = DasDarren1998::new(
+            problem.number_of_objectives(),
+            &options.number_of_partitions,
+        )?;
+        let weights = das_darren.get_weights();
+        let number_of_individuals = weights.len();
+        if number_of_individuals < 3 {
+            return Err(OOError::AlgorithmInit(
+                name,
+                "The number of partitions must give at least 3 weight vectors".to_string(),
+            ));
+        }
+        if options.neighbourhood_size < 2 || options.neighbourhood_size > number_of_individuals {
+            return Err(OOError::AlgorithmInit(
+                name,
+                format!(
+                    "The neighbourhood size must be between 2 and the number of weight vectors ({})",
+                    number_of_individuals
+                ),
+            ));
+        }
+        if options.max_replacements == Some(0) {
+            return Err(OOError::AlgorithmInit(
+                name,
+                "An offspring must replace at least one neighbour".to_string(),
+            ));
+        }
+        if let OScalarisation::PBI { penalty } = options.scalarisation {
+            if penalty < 0.0 {
+                return Err(OOError::AlgorithmInit(
+                    name,
+                    "The PBI penalty cannot be negative".to_string(),
+                ));
+            }
+        }
+
+        let neighbourhoods = Self::neighbourhoods(&weights, options.neighbourhood_size);
+
+        let moead_args = options.clone();
+        let problem = Arc::new(problem);
+        let population = if let Some(init_file) = options.resume_from_file {
+            info!("Loading initial population from {:?}", init_file);
+            MOEADOPTICAS::seed_population_from_file(
+                problem.clone(),
+                &name,
+                number_of_individuals,
+                &init_file,
+            )?
+        } else if let Some(solutions) = &options.initial_solutions {
+            info!(
+                "Created initial population seeded with {} solution(s)",
+                solutions.len().min(number_of_individuals)
+            );
+            OPopulation::init_with_solutions(problem.clone(), number_of_individuals, solutions)?
+        } else {
+            info!("Created initial random population");
+            OPopulation::init(problem.clone(), number_of_individuals)
+        };
+
+        let mutation_options = match options.mutation_operator_options {
+            Some(o) => o,
+            None => OPolynomialMutationArgs::default(problem.clone().as_ref()),
+        };
+        let mutation_operator = OPolynomialMutation::new(mutation_options.clone())?;
+
+        let crossover_options = options.crossover_operator_options.unwrap_or_default();
+        let crossover_operator = OSimulatedBinaryCrossover::new(crossover_options.clone())?;
+
+        info!(
+            "Algorithm options are:\n\t* Number of variables {:>13}\n\t* Number of objectives {:>12}\n\t* Number of constraints {:>11}\n\t* Number of sub-problems {:>10}\n\t* Neighbourhood size {:>14}\n\t* Scalarisation {:>19?}",
+            problem.number_of_variables(),
+            problem.number_of_objectives(),
+            problem.number_of_constraints(),
+            number_of_individuals,
+            options.neighbourhood_size,
+            options.scalarisation
+        );
+
+        Ok(Self {
+            number_of_individuals,
+            ideal_point: vec![f64::INFINITY; problem.number_of_objectives()],
+            weights,
+            neighbourhoods,
+            problem,
+            population,
+            crossover_operator,
+            mutation_operator,
+            generation: 0,
+            nfe: 0,
+            stopping_condition: options.stopping_condition,
+            start_time: Instant::now(),
+            parallel: options.parallel.unwrap_or(true),
+            export_history: options.export_history,
+            rng: get_rng(options.seed),
+            args: moead_args,
+        })
+    }
+
+    /// Get the weight vectors.
+    ///
+    /// returns: `Vec<Vec<f64>>`
+    pub fn weights(&self) -> Vec<Vec<f64>> {
+        self.weights.clone()
+    }
+
+    /// Find the `size` closest weight vectors (by euclidean distance) of each weight vector. Each
+    /// neighbourhood includes its own weight vector first.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights`: The weight vectors.
+    /// * `size`: The size of each neighbourhood.
+    ///
+    /// returns: `Vec<Vec<usize>>`
+    fn neighbourhoods(weights: &[Vec<f64>], size: usize) -> Vec<Vec<usize>> {
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| {
+                let distances: Vec<f64> = weights
+                    .iter()
+                    .enumerate()
+                    .map(|(j, other)| {
+                        // keep the sub-problem ahead of its equally distant neighbours
+                        if i == j {
+                            return f64::NEG_INFINITY;
+                        }
+                        weight
+                            .iter()
+                            .zip(other)
+                            .map(|(a, b)| (a - b).powi(2))
+                            .sum::<f64>()
+                    })
+                    .collect();
+                let mut closest = argsort(&distances, Sort::Ascending);
+                closest.truncate(size);
+                closest
+            })
+            .collect()
+    }
+
+    /// Update the ideal point with the objectives of an individual.
+    ///
+    /// # Arguments
+    ///
+    /// * `individual`: The evaluated individual.
+    ///
+    /// returns: `Result<(), OError>`
+    fn update_ideal_point(&mut self, individual: &OIndividual) -> Result<(), OOError> {
+        for (ideal, value) in self
+            .ideal_point
+            .iter_mut()
+            .zip(individual.get_objective_values()?)
+        {
+            *ideal = ideal.min(value);
+        }
+        Ok(())
+    }
+
+    /// Check whether an offspring is better than an individual at a sub-problem. The least
+    /// violating solution is better, then the one with the lowest scalarised value.
+    ///
+    /// # Arguments
+    ///
+    /// * `offspring`: The offspring.
+    /// * `individual`: The individual currently solving the sub-problem.
+    /// * `sub_problem`: The index of the sub-problem.
+    ///
+    /// returns: `Result<bool, OError>`
+    fn is_better(
+        &self,
+        offspring: &OIndividual,
+        individual: &OIndividual,
+        sub_problem: usize,
+    ) -> Result<bool, OOError> {
+        let cv_offspring = offspring.constraint_violation();
+        let cv_individual = individual.constraint_violation();
+        if cv_offspring != cv_individual {
+            return Ok(cv_offspring < cv_individual);
+        }
+
+        let weights = &self.weights[sub_problem];
+        let scalarisation = &self.args.scalarisation;
+        Ok(
+            scalarisation.value(&offspring.get_objective_values()?, weights, &self.ideal_point)?
+                < scalarisation.value(
+                    &individual.get_objective_values()?,
+                    weights,
+                    &self.ideal_point,
+                )?,
+        )
+    }
+}
+
+/// Implementation of Section III-A of the paper.
+#[impl_algorithm_trait_items(MOEADOPTICASArg)]
+impl OAlgorithm<MOEADOPTICASArg> for MOEADOPTICAS {
+    /// This evaluates the initial population and initialises the ideal point.
+    ///
+    /// return: `Result<(), OError>`
+    fn initialise(&mut self) -> Result<(), OOError> {
+        info!("Evaluating initial population");
+        if self.parallel {
+            MOEADOPTICAS::do_parallel_evaluation(self.population.individuals_as_mut(), &mut self.nfe)?;
+        } else {
+            MOEADOPTICAS::do_evaluation(self.population.individuals_as_mut(), &mut self.nfe)?;
+        }
+
+        debug!("Calculating ideal point");
+        for individual in self.population.individuals().to_vec() {
+            self.update_ideal_point(&individual)?;
+        }
+
+        info!("Initial evaluation completed");
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    fn evolve(&mut self) -> Result<(), OOError> {
+        // Breed one offspring per sub-problem from two of its neighbours
+        debug!("Generating offsprings (neighbourhood mating + crossover + mutation)");
+        let mut offsprings: Vec<OIndividual> = Vec::new();
+        for neighbourhood in &self.neighbourhoods {
+            let parents: Vec<usize> = neighbourhood
+                .choose_multiple(&mut self.rng, 2)
+                .copied()
+                .collect();
+            let children = self.crossover_operator.generate_offsprings(
+                self.population.individual(parents[0]).unwrap(),
+                self.population.individual(parents[1]).unwrap(),
+                &mut self.rng,
+            )?;
+            offsprings.push(
+                self.mutation_operator
+                    .mutate_offspring(&children.child1, &mut self.rng)?,
+            );
+        }
+
+        debug!("Evaluating offsprings");
+        if self.parallel {
+            MOEADOPTICAS::do_parallel_evaluation(&mut offsprings, &mut self.nfe)?;
+        } else {
+            MOEADOPTICAS::do_evaluation(&mut offsprings, &mut self.nfe)?;
+        }
+        debug!("Evaluation done");
+
+        // Update the ideal point and the neighbours of each sub-problem
+        debug!("Updating neighbourhoods");
+        let max_replacements = self
+            .args
+            .max_replacements
+            .unwrap_or(self.args.neighbourhood_size);
+        let mut replaced = 0;
+        for (sub_problem, offspring) in offsprings.into_iter().enumerate() {
+            self.update_ideal_point(&offspring)?;
+
+            let mut neighbourhood = self.neighbourhoods[sub_problem].clone();
+            neighbourhood.shuffle(&mut self.rng);
+
+            let mut count = 0;
+            for neighbour in neighbourhood {
+                if count == max_replacements {
+                    break;
+                }
+                let individual = self.population.individual(neighbour).unwrap();
+                if self.is_better(&offspring, individual, neighbour)? {
+                    self.population.individuals_as_mut()[neighbour] = offspring.clone();
+                    count += 1;
+                }
+            }
+            replaced += count;
+        }
+        debug!("Offsprings replaced {} solutions", replaced);
+
+        self.generation += 1;
+        Ok(())
+    }
+
+    /// Export the weight vectors and the ideal point.
+    ///
+    /// return: `Option<HashMap<String, ODataValue>>`
+    fn additional_export_data(&self) -> Option<HashMap<String, ODataValue>> {
+        let weights = self
+            .weights
+            .iter()
+            .map(|weight| ODataValue::Vector(weight.clone()))
+            .collect();
+
+        Some(HashMap::from([
+            ("weights".to_string(), ODataValue::DataVector(weights)),
+            (
+                "ideal_point".to_string(),
+                ODataValue::Vector(self.ideal_point.clone()),
+            ),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use float_cmp::assert_approx_eq;
+    use opticas_macros::test_with_retries;
+
+    use crate::algorithms::{
+        MOEADOPTICASArg, OAlgorithm, OMaxGenerationValue, OScalarisation,
+        OStoppingConditionType, MOEADOPTICAS,
+    };
+    use crate::core::builtin_problems::DTLZ2Problem;
+    use crate::utils::{DasDarren1998, NumberOfPartitions};
+
+    /// Get the arguments for a problem with 3 objectives and 91 weight vectors.
+    fn args(scalarisation: OScalarisation, generations: usize) -> MOEADOPTICASArg {
+        MOEADOPTICASArg {
+            number_of_partitions: NumberOfPartitions::OneLayer(12),
+            neighbourhood_size: 20,
+            max_replacements: None,
+            scalarisation,
+            crossover_operator_options: None,
+            mutation_operator_options: None,
+            stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(
+                generations,
+            )),
+            parallel: Some(false),
+            export_history: None,
+            resume_from_file: None,
+            initial_solutions: None,
+            seed: Some(1),
+        }
+    }
+
+    #[test]
+    /// Test the Tchebycheff and PBI functions
+    fn test_scalarisation() {
+        let ideal_point = [0.0, 0.0];
+        let weights = [0.5, 0.5];
+
+        let value = OScalarisation::Tchebycheff
+            .value(&[1.0, 3.0], &weights, &ideal_point)
+            .unwrap();
+        assert_approx_eq!(f64, value, 1.5);
+
+        // d1 is the projection on the weight direction and d2 the distance from it
+        let value = OScalarisation::PBI { penalty: 5.0 }
+            .value(&[1.0, 3.0], &weights, &ideal_point)
+            .unwrap();
+        let (d1, d2) = (4.0 / 2.0_f64.sqrt(), 2.0 / 2.0_f64.sqrt());
+        assert_approx_eq!(f64, value, d1 + 5.0 * d2, epsilon = 0.00001);
+    }
+
+    #[test]
+    /// The neighbourhood of a sub-problem starts with itself
+    fn test_neighbourhoods() {
+        let weights = DasDarren1998::new(2, &NumberOfPartitions::OneLayer(4))
+            .unwrap()
+            .get_weights();
+        let neighbourhoods = MOEADOPTICAS::neighbourhoods(&weights, 3);
+
+        assert_eq!(neighbourhoods[0], vec![0, 1, 2]);
+        assert_eq!(neighbourhoods[2][0], 2);
+        let mut middle = neighbourhoods[2].clone();
+        middle.sort();
+        assert_eq!(middle, vec![1, 2, 3]);
+    }
+
+    #[test]
+    /// The neighbourhood cannot be larger than the population
+    fn test_neighbourhood_size() {
+        let problem = DTLZ2Problem::create(12, 3).unwrap();
+        let mut args = args(OScalarisation::Tchebycheff, 1);
+        args.neighbourhood_size = 92;
+        assert!(MOEADOPTICAS::new(problem, args).is_err());
+    }
+
+    #[test_with_retries(3)]
+    /// Test the DTLZ2 problem with both scalarisations. The Pareto-optimal solutions lie on the
+    /// unit sphere.
+    fn test_dtlz2_problem() {
+        for scalarisation in [
+            OScalarisation::Tchebycheff,
+            OScalarisation::PBI { penalty: 5.0 },
+        ] {
+            let problem = DTLZ2Problem::create(12, 3).unwrap();
+            let mut algo = MOEADOPTICAS::new(problem, args(scalarisation.clone(), 250)).unwrap();
+            algo.run().unwrap();
+
+            let results = algo.get_results();
+            let on_sphere = results
+                .individuals
+                .iter()
+                .filter(|individual| {
+                    let radius: f64 = individual
+                        .get_objective_values()
+                        .unwrap()
+                        .iter()
+                        .map(|v| v.powi(2))
+                        .sum();
+                    (radius - 1.0).abs() < 0.1
+                })
+                .count();
+
+            assert!(
+                on_sphere as f64 >= 0.9 * results.individuals.len() as f64,
+                "Only {} of {} solutions are on the Pareto front with {:?}",
+                on_sphere,
+                results.individuals.len(),
+                scalarisation
+            );
+        }
+    }
+}
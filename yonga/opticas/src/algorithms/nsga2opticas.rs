@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::ops::Rem;
 use std::path::PathBuf;
@@ -9,7 +10,7 @@ use opticas_macros::{as_algorithm, as_algorithm_args, impl_algorithm_trait_items
 
 use crate::algorithms::OAlgorithm;
 use crate::core::utils::get_rng;
-use crate::core::{ODataValue, OIndividual, OIndividuals, OIndividualsMut, OOError};
+use crate::core::{ODataValue, OIndividual, OIndividuals, OIndividualsMut, OOError, OVariableValue};
 use crate::operators::{
     OCrossover, OCrowdedComparison, OMutation, OPolynomialMutation, OPolynomialMutationArgs, OSelector,
     OSimulatedBinaryCrossover, OSimulatedBinaryCrossoverArgs, OTournamentSelector,
@@ -39,6 +40,12 @@ pub struct NSGA2OPTICASArg {
     /// restart the evolution from a previous generation; you can use any history file (exported
     /// when the field `export_history`) or the file exported when the stopping condition was reached.
     pub resume_from_file: Option<PathBuf>,
+    /// Seed the initial population with these solutions, for example the solution currently in use
+    /// and its neighbours, to search near them. Each map sets the variables of one individual;
+    /// the variables it does not set and the remaining individuals are random. The solutions in
+    /// excess of `number_of_individuals` are ignored, and so is this option when `resume_from_file`
+    /// is set.
+    pub initial_solutions: Option<Vec<HashMap<String, OVariableValue>>>,
     /// The seed used in the random number generator (RNG). You can specify a seed in case you want
     /// to try to reproduce results. NSGA2 is a stochastic algorithm that relies on a RNG at
     /// different steps (when population is initially generated, during selection, crossover and
@@ -115,6 +122,16 @@ impl NSGA2OPTICAS {
                 options.number_of_individuals,
                 &init_file,
             )?
+        } else if let Some(solutions) = &options.initial_solutions {
+            info!(
+                "Created initial population seeded with {} solution(s)",
+                solutions.len().min(options.number_of_individuals)
+            );
+            OPopulation::init_with_solutions(
+                problem.clone(),
+                options.number_of_individuals,
+                solutions,
+            )?
         } else {
             info!("Created initial random population");
             OPopulation::init(problem.clone(), options.number_of_individuals)
@@ -642,301 +659,408 @@ impl OAlgorithm<NSGA2OPTICASArg> for NSGA2OPTICAS {
 //     }
 // }
 
-// #[cfg(test)]
-// mod test_problems {
-//     use opticas_macros::test_with_retries;
-
-//     use crate::algorithms::{
-//         Algorithm, MaxGenerationValue, NSGA2OPTICASArg, StoppingConditionType, NSGA2OPTICAS,
-//     };
-//     use crate::core::builtin_problems::{
-//         SCHProblem, ZTD1Problem, ZTD2Problem, ZTD3Problem, ZTD4Problem,
-//     };
-//     use crate::core::test_utils::{check_exact_value, check_value_in_range};
-
-//     const BOUND_TOL: f64 = 1.0 / 1000.0;
-//     const LOOSE_BOUND_TOL: f64 = 0.1;
-
-//     #[test_with_retries(10)]
-//     /// Test problem 1 from Deb et al. (2002). Optional solution x in [0; 2]
-//     fn test_sch_problem() {
-//         let problem = SCHProblem::create().unwrap();
-//         let args = NSGA2OPTICASArg {
-//             number_of_individuals: 10,
-//             stopping_condition: StoppingConditionType::MaxGeneration(MaxGenerationValue(1000)),
-//             crossover_operator_options: None,
-//             mutation_operator_options: None,
-//             parallel: Some(false),
-//             export_history: None,
-//             resume_from_file: None,
-//             seed: Some(10),
-//         };
-//         let mut algo = NSGA2OPTICAS::new(problem, args).unwrap();
-//         algo.run().unwrap();
-//         let results = algo.get_results();
-
-//         // increase tolerance
-//         let bounds = -0.1..2.1;
-//         let invalid_x = check_value_in_range(&results.get_real_variables("x").unwrap(), &bounds);
-//         if !invalid_x.is_empty() {
-//             panic!("Some variables are outside the bounds: {:?}", invalid_x);
-//         }
-//     }
+#[cfg(test)]
+mod test_problems {
+    use std::collections::HashMap;
+    use std::error::Error;
+
+    use opticas_macros::test_with_retries;
+
+    use crate::algorithms::{
+        OAlgorithm, OMaxGenerationValue, NSGA2OPTICASArg, OStoppingConditionType, NSGA2OPTICAS,
+    };
+    use crate::core::builtin_problems::{
+        FonProblem, SCHProblem, ZTD1Problem, ZTD2Problem, ZTD3Problem, ZTD4Problem, ZTD6Problem,
+    };
+    use crate::core::test_utils::{check_exact_value, check_value_in_range};
+    use crate::core::{
+        OBoolean, OBoundedNumber, OEvaluationResult, OEvaluator, OIndividual, OObjective,
+        OObjectiveDirection, OProblem, OVariableType,
+    };
+
+    const BOUND_TOL: f64 = 1.0 / 1000.0;
+    const LOOSE_BOUND_TOL: f64 = 0.1;
+
+    #[test_with_retries(10)]
+    /// Test problem 1 from Deb et al. (2002). Optional solution x in [0; 2]
+    fn test_sch_problem() {
+        let problem = SCHProblem::create().unwrap();
+        let args = NSGA2OPTICASArg {
+            number_of_individuals: 10,
+            stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(1000)),
+            crossover_operator_options: None,
+            mutation_operator_options: None,
+            parallel: Some(false),
+            export_history: None,
+            resume_from_file: None,
+            seed: Some(10),
+            initial_solutions: None,
+        };
+        let mut algo = NSGA2OPTICAS::new(problem, args).unwrap();
+        algo.run().unwrap();
+        let results = algo.get_results();
+
+        // increase tolerance
+        let bounds = -0.1..2.1;
+        let invalid_x = check_value_in_range(&results.get_real_variables("x").unwrap(), &bounds);
+        if !invalid_x.is_empty() {
+            panic!("Some variables are outside the bounds: {:?}", invalid_x);
+        }
+    }
 
-//     #[test_with_retries(10)]
-//     /// Test the ZTD1 problem from Deb et al. (2002) with 30 variables. Solution x1 in [0; 1] and
-//     /// x2 to x30 = 0. The exact solutions are tested using a strict and loose bounds.
-//     fn test_ztd1_problem() {
-//         let number_of_individuals: usize = 30;
-//         let problem = ZTD1Problem::create(number_of_individuals).unwrap();
-//         let args = NSGA2OPTICASArg {
-//             number_of_individuals,
-//             stopping_condition: StoppingConditionType::MaxGeneration(MaxGenerationValue(2500)),
-//             crossover_operator_options: None,
-//             mutation_operator_options: None,
-//             parallel: Some(false),
-//             export_history: None,
-//             resume_from_file: None,
-//             seed: Some(1),
-//         };
-//         let mut algo = NSGA2OPTICAS::new(problem, args).unwrap();
-//         algo.run().unwrap();
-//         let results = algo.get_results();
-
-//         let x_bounds = 0.0 - BOUND_TOL..1.0 + BOUND_TOL;
-//         let invalid_x1 =
-//             check_value_in_range(&results.get_real_variables("x1").unwrap(), &x_bounds);
-//         if !invalid_x1.is_empty() {
-//             panic!("Some X1 variables are outside the bounds: {:?}", invalid_x1);
-//         }
+    #[test_with_retries(10)]
+    /// Test the ZTD1 problem from Deb et al. (2002) with 30 variables. Solution x1 in [0; 1] and
+    /// x2 to x30 = 0. The exact solutions are tested using a strict and loose bounds.
+    fn test_ztd1_problem() {
+        let number_of_individuals: usize = 30;
+        let problem = ZTD1Problem::create(number_of_individuals).unwrap();
+        let args = NSGA2OPTICASArg {
+            number_of_individuals,
+            stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(2500)),
+            crossover_operator_options: None,
+            mutation_operator_options: None,
+            parallel: Some(false),
+            export_history: None,
+            resume_from_file: None,
+            seed: Some(1),
+            initial_solutions: None,
+        };
+        let mut algo = NSGA2OPTICAS::new(problem, args).unwrap();
+        algo.run().unwrap();
+        let results = algo.get_results();
+
+        let x_bounds = 0.0 - BOUND_TOL..1.0 + BOUND_TOL;
+        let invalid_x1 =
+            check_value_in_range(&results.get_real_variables("x1").unwrap(), &x_bounds);
+        if !invalid_x1.is_empty() {
+            panic!("Some X1 variables are outside the bounds: {:?}", invalid_x1);
+        }
 
-//         let x_bounds = -BOUND_TOL..BOUND_TOL;
-//         let x_other_bounds = -LOOSE_BOUND_TOL..LOOSE_BOUND_TOL;
-//         for xi in 2..=number_of_individuals {
-//             let var_values = results
-//                 .get_real_variables(format!("x{xi}").as_str())
-//                 .unwrap();
-//             let (x_other_outside_bounds, breached_range, b_type) =
-//                 check_exact_value(&var_values, &x_bounds, &x_other_bounds, 5);
-//             if !x_other_outside_bounds.is_empty() {
-//                 panic!(
-//                     "Found {} X2 to X30 solutions ({:?}) outside the {} bounds {:?}",
-//                     x_other_outside_bounds.len(),
-//                     x_other_outside_bounds,
-//                     b_type,
-//                     breached_range
-//                 );
-//             }
-//         }
-//     }
+        let x_bounds = -BOUND_TOL..BOUND_TOL;
+        let x_other_bounds = -LOOSE_BOUND_TOL..LOOSE_BOUND_TOL;
+        for xi in 2..=number_of_individuals {
+            let var_values = results
+                .get_real_variables(format!("x{xi}").as_str())
+                .unwrap();
+            let (x_other_outside_bounds, breached_range, b_type) =
+                check_exact_value(&var_values, &x_bounds, &x_other_bounds, 5);
+            if !x_other_outside_bounds.is_empty() {
+                panic!(
+                    "Found {} X2 to X30 solutions ({:?}) outside the {} bounds {:?}",
+                    x_other_outside_bounds.len(),
+                    x_other_outside_bounds,
+                    b_type,
+                    breached_range
+                );
+            }
+        }
+    }
 
-//     #[test_with_retries(10)]
-//     /// Test the ZTD2 problem from Deb et al. (2002) with 30 variables. Solution x1 in [0; 1] and
-//     /// x2 to x30 = 0. The exact solutions are tested using a strict and loose bounds.
-//     fn test_ztd2_problem() {
-//         let number_of_individuals: usize = 30;
-//         let problem = ZTD2Problem::create(number_of_individuals).unwrap();
-//         let args = NSGA2OPTICASArg {
-//             number_of_individuals,
-//             stopping_condition: StoppingConditionType::MaxGeneration(MaxGenerationValue(2500)),
-//             crossover_operator_options: None,
-//             mutation_operator_options: None,
-//             parallel: Some(false),
-//             export_history: None,
-//             resume_from_file: None,
-//             seed: Some(1),
-//         };
-//         let mut algo = NSGA2OPTICAS::new(problem, args).unwrap();
-//         algo.run().unwrap();
-//         let results = algo.get_results();
-
-//         let x_bounds = 0.0 - BOUND_TOL..1.0 + BOUND_TOL;
-//         let invalid_x1 =
-//             check_value_in_range(&results.get_real_variables("x1").unwrap(), &x_bounds);
-//         if !invalid_x1.is_empty() {
-//             panic!(
-//                 "Found {} X1 variables outside the bounds {:?}",
-//                 invalid_x1.len(),
-//                 invalid_x1
-//             );
-//         }
+    #[test_with_retries(10)]
+    /// Test the ZTD2 problem from Deb et al. (2002) with 30 variables. Solution x1 in [0; 1] and
+    /// x2 to x30 = 0. The exact solutions are tested using a strict and loose bounds.
+    fn test_ztd2_problem() {
+        let number_of_individuals: usize = 30;
+        let problem = ZTD2Problem::create(number_of_individuals).unwrap();
+        let args = NSGA2OPTICASArg {
+            number_of_individuals,
+            stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(2500)),
+            crossover_operator_options: None,
+            mutation_operator_options: None,
+            parallel: Some(false),
+            export_history: None,
+            resume_from_file: None,
+            seed: Some(1),
+            initial_solutions: None,
+        };
+        let mut algo = NSGA2OPTICAS::new(problem, args).unwrap();
+        algo.run().unwrap();
+        let results = algo.get_results();
+
+        let x_bounds = 0.0 - BOUND_TOL..1.0 + BOUND_TOL;
+        let invalid_x1 =
+            check_value_in_range(&results.get_real_variables("x1").unwrap(), &x_bounds);
+        if !invalid_x1.is_empty() {
+            panic!(
+                "Found {} X1 variables outside the bounds {:?}",
+                invalid_x1.len(),
+                invalid_x1
+            );
+        }
 
-//         let x_bounds = -BOUND_TOL..BOUND_TOL;
-//         let x_other_bounds = -LOOSE_BOUND_TOL..LOOSE_BOUND_TOL;
-//         for xi in 2..=number_of_individuals {
-//             let var_name = format!("x{xi}");
-//             let var_values = results.get_real_variables(&var_name).unwrap();
-
-//             let (x_other_outside_bounds, breached_range, b_type) =
-//                 check_exact_value(&var_values, &x_bounds, &x_other_bounds, 3);
-//             if !x_other_outside_bounds.is_empty() {
-//                 panic!(
-//                     "Found {} {} solutions ({:?}) outside the {} bounds {:?}",
-//                     x_other_outside_bounds.len(),
-//                     var_name,
-//                     x_other_outside_bounds,
-//                     b_type,
-//                     breached_range
-//                 );
-//             }
-//         }
-//     }
+        let x_bounds = -BOUND_TOL..BOUND_TOL;
+        let x_other_bounds = -LOOSE_BOUND_TOL..LOOSE_BOUND_TOL;
+        for xi in 2..=number_of_individuals {
+            let var_name = format!("x{xi}");
+            let var_values = results.get_real_variables(&var_name).unwrap();
+
+            let (x_other_outside_bounds, breached_range, b_type) =
+                check_exact_value(&var_values, &x_bounds, &x_other_bounds, 3);
+            if !x_other_outside_bounds.is_empty() {
+                panic!(
+                    "Found {} {} solutions ({:?}) outside the {} bounds {:?}",
+                    x_other_outside_bounds.len(),
+                    var_name,
+                    x_other_outside_bounds,
+                    b_type,
+                    breached_range
+                );
+            }
+        }
+    }
 
-//     #[test_with_retries(10)]
-//     /// Test the ZTD3 problem from Deb et al. (2002) with 30 variables. Solution x1 in [0; 1] and
-//     /// x2 to x30 = 0. The exact solutions are tested using a strict and loose bounds.
-//     fn test_ztd3_problem() {
-//         let number_of_individuals: usize = 30;
-//         let problem = ZTD3Problem::create(number_of_individuals).unwrap();
-//         let args = NSGA2OPTICASArg {
-//             number_of_individuals,
-//             stopping_condition: StoppingConditionType::MaxGeneration(MaxGenerationValue(2500)),
-//             crossover_operator_options: None,
-//             mutation_operator_options: None,
-//             parallel: Some(false),
-//             export_history: None,
-//             resume_from_file: None,
-//             seed: Some(1),
-//         };
-//         let mut algo = NSGA2OPTICAS::new(problem, args).unwrap();
-//         algo.run().unwrap();
-//         let results = algo.get_results();
-
-//         let x_bounds = 0.0 - BOUND_TOL..1.0 + BOUND_TOL;
-//         let invalid_x1 =
-//             check_value_in_range(&results.get_real_variables("x1").unwrap(), &x_bounds);
-//         if !invalid_x1.is_empty() {
-//             panic!(
-//                 "Found {} X1 variables outside the bounds {:?}",
-//                 invalid_x1.len(),
-//                 invalid_x1
-//             );
-//         }
+    #[test_with_retries(10)]
+    /// Test the ZTD3 problem from Deb et al. (2002) with 30 variables. Solution x1 in [0; 1] and
+    /// x2 to x30 = 0. The exact solutions are tested using a strict and loose bounds.
+    fn test_ztd3_problem() {
+        let number_of_individuals: usize = 30;
+        let problem = ZTD3Problem::create(number_of_individuals).unwrap();
+        let args = NSGA2OPTICASArg {
+            number_of_individuals,
+            stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(2500)),
+            crossover_operator_options: None,
+            mutation_operator_options: None,
+            parallel: Some(false),
+            export_history: None,
+            resume_from_file: None,
+            seed: Some(1),
+            initial_solutions: None,
+        };
+        let mut algo = NSGA2OPTICAS::new(problem, args).unwrap();
+        algo.run().unwrap();
+        let results = algo.get_results();
+
+        let x_bounds = 0.0 - BOUND_TOL..1.0 + BOUND_TOL;
+        let invalid_x1 =
+            check_value_in_range(&results.get_real_variables("x1").unwrap(), &x_bounds);
+        if !invalid_x1.is_empty() {
+            panic!(
+                "Found {} X1 variables outside the bounds {:?}",
+                invalid_x1.len(),
+                invalid_x1
+            );
+        }
 
-//         let x_bounds = -BOUND_TOL..BOUND_TOL;
-//         let x_other_bounds = -LOOSE_BOUND_TOL..LOOSE_BOUND_TOL;
-//         for xi in 2..=number_of_individuals {
-//             let var_name = format!("x{xi}");
-//             let var_values = results.get_real_variables(&var_name).unwrap();
-
-//             let (x_other_outside_bounds, breached_range, b_type) =
-//                 check_exact_value(&var_values, &x_bounds, &x_other_bounds, 3);
-//             if !x_other_outside_bounds.is_empty() {
-//                 panic!(
-//                     "Found {} {} solutions ({:?}) outside the {} bounds {:?}",
-//                     x_other_outside_bounds.len(),
-//                     var_name,
-//                     x_other_outside_bounds,
-//                     b_type,
-//                     breached_range
-//                 );
-//             }
-//         }
-//     }
+        let x_bounds = -BOUND_TOL..BOUND_TOL;
+        let x_other_bounds = -LOOSE_BOUND_TOL..LOOSE_BOUND_TOL;
+        for xi in 2..=number_of_individuals {
+            let var_name = format!("x{xi}");
+            let var_values = results.get_real_variables(&var_name).unwrap();
+
+            let (x_other_outside_bounds, breached_range, b_type) =
+                check_exact_value(&var_values, &x_bounds, &x_other_bounds, 3);
+            if !x_other_outside_bounds.is_empty() {
+                panic!(
+                    "Found {} {} solutions ({:?}) outside the {} bounds {:?}",
+                    x_other_outside_bounds.len(),
+                    var_name,
+                    x_other_outside_bounds,
+                    b_type,
+                    breached_range
+                );
+            }
+        }
+    }
 
-//     #[test_with_retries(10)]
-//     /// Test the ZTD4 problem from Deb et al. (2002) with 30 variables. Solution x1 in [0; 1] and
-//     /// x2 to x10 = 0. The exact solutions are tested using a strict and loose bounds.
-//     fn test_ztd4_problem() {
-//         let number_of_individuals: usize = 10;
-//         let args = NSGA2OPTICASArg {
-//             number_of_individuals,
-//             stopping_condition: StoppingConditionType::MaxGeneration(MaxGenerationValue(3000)),
-//             crossover_operator_options: None,
-//             mutation_operator_options: None,
-//             parallel: Some(false),
-//             export_history: None,
-//             resume_from_file: None,
-//             seed: Some(1),
-//         };
-//         let problem = ZTD4Problem::create(number_of_individuals).unwrap();
-//         let mut algo = NSGA2OPTICAS::new(problem, args.clone()).unwrap();
-//         algo.run().unwrap();
-//         let results = algo.get_results();
-
-//         let x_bounds = 0.0 - BOUND_TOL..1.0 + BOUND_TOL;
-//         let invalid_x1 =
-//             check_value_in_range(&results.get_real_variables("x1").unwrap(), &x_bounds);
-//         if !invalid_x1.is_empty() {
-//             panic!(
-//                 "Found {} X1 variables outside the bounds {:?}",
-//                 invalid_x1.len(),
-//                 invalid_x1
-//             );
-//         }
+    #[test_with_retries(10)]
+    /// Test the ZTD4 problem from Deb et al. (2002) with 30 variables. Solution x1 in [0; 1] and
+    /// x2 to x10 = 0. The exact solutions are tested using a strict and loose bounds.
+    fn test_ztd4_problem() {
+        let number_of_individuals: usize = 10;
+        let args = NSGA2OPTICASArg {
+            number_of_individuals,
+            stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(3000)),
+            crossover_operator_options: None,
+            mutation_operator_options: None,
+            parallel: Some(false),
+            export_history: None,
+            resume_from_file: None,
+            seed: Some(1),
+            initial_solutions: None,
+        };
+        let problem = ZTD4Problem::create(number_of_individuals).unwrap();
+        let mut algo = NSGA2OPTICAS::new(problem, args.clone()).unwrap();
+        algo.run().unwrap();
+        let results = algo.get_results();
+
+        let x_bounds = 0.0 - BOUND_TOL..1.0 + BOUND_TOL;
+        let invalid_x1 =
+            check_value_in_range(&results.get_real_variables("x1").unwrap(), &x_bounds);
+        if !invalid_x1.is_empty() {
+            panic!(
+                "Found {} X1 variables outside the bounds {:?}",
+                invalid_x1.len(),
+                invalid_x1
+            );
+        }
 
-//         // relax strict bounds O(2). The final solution is still acceptable.
-//         let x_bounds = -BOUND_TOL * 10.0..BOUND_TOL * 10.0;
-//         let x_other_bounds = -LOOSE_BOUND_TOL..LOOSE_BOUND_TOL;
-//         for xi in 2..=number_of_individuals {
-//             let var_name = format!("x{xi}");
-//             let var_values = results.get_real_variables(&var_name).unwrap();
-
-//             let (x_other_outside_bounds, breached_range, b_type) =
-//                 check_exact_value(&var_values, &x_bounds, &x_other_bounds, 3);
-//             if !x_other_outside_bounds.is_empty() {
-//                 panic!(
-//                     "Found {} {} solutions ({:?}) outside the {} bounds {:?}",
-//                     x_other_outside_bounds.len(),
-//                     var_name,
-//                     x_other_outside_bounds,
-//                     b_type,
-//                     breached_range
-//                 );
-//             }
-//         }
-//     }
+        // relax strict bounds O(2). The final solution is still acceptable.
+        let x_bounds = -BOUND_TOL * 10.0..BOUND_TOL * 10.0;
+        let x_other_bounds = -LOOSE_BOUND_TOL..LOOSE_BOUND_TOL;
+        for xi in 2..=number_of_individuals {
+            let var_name = format!("x{xi}");
+            let var_values = results.get_real_variables(&var_name).unwrap();
+
+            let (x_other_outside_bounds, breached_range, b_type) =
+                check_exact_value(&var_values, &x_bounds, &x_other_bounds, 3);
+            if !x_other_outside_bounds.is_empty() {
+                panic!(
+                    "Found {} {} solutions ({:?}) outside the {} bounds {:?}",
+                    x_other_outside_bounds.len(),
+                    var_name,
+                    x_other_outside_bounds,
+                    b_type,
+                    breached_range
+                );
+            }
+        }
+    }
 
-//     #[test_with_retries(10)]
-//     /// Test the ZTD6 problem from Deb et al. (2002) with 30 variables. Solution x1 in [0; 1] and
-//     /// x2 to x10 = 0. The exact solutions are tested using a strict and loose bounds.
-//     fn test_ztd6_problem() {
-//         let number_of_individuals: usize = 10;
-//         let problem = ZTD4Problem::create(number_of_individuals).unwrap();
-//         let args = NSGA2OPTICASArg {
-//             number_of_individuals,
-//             stopping_condition: StoppingConditionType::MaxGeneration(MaxGenerationValue(1000)),
-//             crossover_operator_options: None,
-//             mutation_operator_options: None,
-//             parallel: Some(false),
-//             export_history: None,
-//             resume_from_file: None,
-//             seed: Some(1),
-//         };
-//         let mut algo = NSGA2::new(problem, args).unwrap();
-//         algo.run().unwrap();
-//         let results = algo.get_results();
-
-//         let x_bounds = 0.0 - BOUND_TOL..1.0 + BOUND_TOL;
-//         let invalid_x1 =
-//             check_value_in_range(&results.get_real_variables("x1").unwrap(), &x_bounds);
-//         if !invalid_x1.is_empty() {
-//             panic!(
-//                 "Found {} X1 variables outside the bounds {:?}",
-//                 invalid_x1.len(),
-//                 invalid_x1
-//             );
-//         }
+    #[test_with_retries(10)]
+    /// Test the ZTD6 problem from Deb et al. (2002) with 30 variables. Solution x1 in [0; 1] and
+    /// x2 to x10 = 0. The exact solutions are tested using a strict and loose bounds.
+    fn test_ztd6_problem() {
+        let number_of_individuals: usize = 10;
+        let problem = ZTD6Problem::create(number_of_individuals).unwrap();
+        let args = NSGA2OPTICASArg {
+            number_of_individuals,
+            stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(1000)),
+            crossover_operator_options: None,
+            mutation_operator_options: None,
+            parallel: Some(false),
+            export_history: None,
+            resume_from_file: None,
+            seed: Some(1),
+            initial_solutions: None,
+        };
+        let mut algo = NSGA2OPTICAS::new(problem, args).unwrap();
+        algo.run().unwrap();
+        let results = algo.get_results();
+
+        let x_bounds = 0.0 - BOUND_TOL..1.0 + BOUND_TOL;
+        let invalid_x1 =
+            check_value_in_range(&results.get_real_variables("x1").unwrap(), &x_bounds);
+        if !invalid_x1.is_empty() {
+            panic!(
+                "Found {} X1 variables outside the bounds {:?}",
+                invalid_x1.len(),
+                invalid_x1
+            );
+        }
 
-//         // relax strict bounds O(2). The final solution is still acceptable.
-//         let x_bounds = -BOUND_TOL * 10.0..BOUND_TOL * 10.0;
-//         let x_other_bounds = -LOOSE_BOUND_TOL..LOOSE_BOUND_TOL;
-//         for xi in 2..=number_of_individuals {
-//             let var_name = format!("x{xi}");
-//             let var_values = results.get_real_variables(&var_name).unwrap();
-
-//             let (x_other_outside_bounds, breached_range, b_type) =
-//                 check_exact_value(&var_values, &x_bounds, &x_other_bounds, 3);
-//             if !x_other_outside_bounds.is_empty() {
-//                 panic!(
-//                     "Found {} {} solutions ({:?}) outside the {} bounds {:?}",
-//                     x_other_outside_bounds.len(),
-//                     var_name,
-//                     x_other_outside_bounds,
-//                     b_type,
-//                     breached_range
-//                 );
-//             }
-//         }
-//     }
-// }
+        // relax strict bounds O(2). The final solution is still acceptable.
+        let x_bounds = -BOUND_TOL * 10.0..BOUND_TOL * 10.0;
+        let x_other_bounds = -LOOSE_BOUND_TOL..LOOSE_BOUND_TOL;
+        for xi in 2..=number_of_individuals {
+            let var_name = format!("x{xi}");
+            let var_values = results.get_real_variables(&var_name).unwrap();
+
+            let (x_other_outside_bounds, breached_range, b_type) =
+                check_exact_value(&var_values, &x_bounds, &x_other_bounds, 3);
+            if !x_other_outside_bounds.is_empty() {
+                panic!(
+                    "Found {} {} solutions ({:?}) outside the {} bounds {:?}",
+                    x_other_outside_bounds.len(),
+                    var_name,
+                    x_other_outside_bounds,
+                    b_type,
+                    breached_range
+                );
+            }
+        }
+    }
+
+    #[test_with_retries(10)]
+    /// Test the FON problem from Deb et al. (2002). Solution x1 = x2 = x3 in [-1/sqrt(3); 1/sqrt(3)].
+    fn test_fon_problem() {
+        let problem = FonProblem::create().unwrap();
+        let args = NSGA2OPTICASArg {
+            number_of_individuals: 20,
+            stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(1000)),
+            crossover_operator_options: None,
+            mutation_operator_options: None,
+            parallel: Some(false),
+            export_history: None,
+            resume_from_file: None,
+            seed: Some(10),
+            initial_solutions: None,
+        };
+        let mut algo = NSGA2OPTICAS::new(problem, args).unwrap();
+        algo.run().unwrap();
+        let results = algo.get_results();
+
+        let bound = 1.0 / 3.0_f64.sqrt() + LOOSE_BOUND_TOL;
+        for var_name in ["x1", "x2", "x3"] {
+            let invalid_x =
+                check_value_in_range(&results.get_real_variables(var_name).unwrap(), &(-bound..bound));
+            if !invalid_x.is_empty() {
+                panic!("Some {} variables are outside the bounds: {:?}", var_name, invalid_x);
+            }
+        }
+
+    }
+
+    /// A problem mixing the variable types: x is an integer in [-10; 10], y is real in [-1; 1] and
+    /// the flag adds 1 to both objectives when set. The only solution is x = 2, y = 0 and no flag.
+    #[derive(Debug)]
+    struct MixedProblem;
+
+    impl MixedProblem {
+        fn create() -> OProblem {
+            let objectives = vec![
+                OObjective::new("f1", OObjectiveDirection::OMinimise),
+                OObjective::new("f2", OObjectiveDirection::OMinimise),
+            ];
+            let variables = vec![
+                OVariableType::OInteger(OBoundedNumber::new("x", -10, 10).unwrap()),
+                OVariableType::OReal(OBoundedNumber::new("y", -1.0, 1.0).unwrap()),
+                OVariableType::OBoolean(OBoolean::new("flag")),
+            ];
+            OProblem::new(objectives, variables, None, Box::new(MixedProblem)).unwrap()
+        }
+    }
+
+    impl OEvaluator for MixedProblem {
+        fn evaluate(&self, i: &OIndividual) -> Result<OEvaluationResult, Box<dyn Error>> {
+            let x = i.get_variable_value("x")?.as_integer()? as f64;
+            let y = i.get_variable_value("y")?.as_real()?;
+            let flag = if i.get_variable_value("flag")?.as_boolean()? { 1.0 } else { 0.0 };
+
+            let mut objectives = HashMap::new();
+            objectives.insert("f1".to_string(), (x - 2.0).powi(2) + flag);
+            objectives.insert("f2".to_string(), y.powi(2) + flag);
+            Ok(OEvaluationResult {
+                constraints: None,
+                objectives,
+            })
+        }
+    }
+
+    #[test_with_retries(10)]
+    /// Test the integer, real and boolean variables together.
+    fn test_mixed_variables() {
+        let args = NSGA2OPTICASArg {
+            number_of_individuals: 10,
+            stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(500)),
+            crossover_operator_options: None,
+            mutation_operator_options: None,
+            parallel: Some(false),
+            export_history: None,
+            resume_from_file: None,
+            seed: Some(10),
+            initial_solutions: None,
+        };
+        let mut algo = NSGA2OPTICAS::new(MixedProblem::create(), args).unwrap();
+        algo.run().unwrap();
+        let results = algo.get_results();
+
+        for individual in &results.individuals {
+            let x = individual.get_variable_value("x").unwrap().as_integer().unwrap();
+            let y = individual.get_variable_value("y").unwrap().as_real().unwrap();
+            let flag = individual.get_variable_value("flag").unwrap().as_boolean().unwrap();
+            if x != 2 || y.abs() > LOOSE_BOUND_TOL || flag {
+                panic!("Found the solution x = {}, y = {}, flag = {}", x, y, flag);
+            }
+        }
+    }
+}
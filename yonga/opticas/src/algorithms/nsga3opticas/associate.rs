@@ -0,0 +1,94 @@
+use crate::algorithms::nsga3opticas::{
+    NORMALISED_OBJECTIVE_KEY, REFERENCE_POINT_DISTANCE_KEY, REFERENCE_POINT_INDEX_KEY,
+};
+use crate::core::{ODataValue, OIndividual, OOError};
+use crate::utils::{argmin_by, perpendicular_distance};
+
+/// Associate each individual with the closest reference direction, as described in Section IV-D of
+/// Deb & Jain (2014). The reference direction is the line joining the origin with a reference point
+/// and the distance is the perpendicular distance of the normalised objectives from the line. The
+/// reference point index and the distance are stored on each individual's data with the keys
+/// [`REFERENCE_POINT_INDEX_KEY`] and [`REFERENCE_POINT_DISTANCE_KEY`].
+///
+/// # Arguments
+///
+/// * `individuals`: The individuals whose objectives were normalised.
+/// * `reference_points`: The reference points.
+///
+/// returns: `Result<(), OError>`
+pub(crate) fn associate(
+    individuals: &mut [OIndividual],
+    reference_points: &[Vec<f64>],
+) -> Result<(), OOError> {
+    for individual in individuals.iter_mut() {
+        let normalised = individual
+            .get_data(NORMALISED_OBJECTIVE_KEY)?
+            .as_f64_vec()?
+            .clone();
+
+        let distances = reference_points
+            .iter()
+            .map(|point| perpendicular_distance(point, &normalised))
+            .collect::<Result<Vec<f64>, String>>()
+            .map_err(|e| OOError::SurvivalOperator("associate".to_string(), e))?;
+
+        let (index, distance) = argmin_by(&distances, |(_, d)| *d).ok_or(
+            OOError::SurvivalOperator(
+                "associate".to_string(),
+                "No reference point to associate the individuals with".to_string(),
+            ),
+        )?;
+
+        individual.set_data(REFERENCE_POINT_INDEX_KEY, ODataValue::USize(index));
+        individual.set_data(REFERENCE_POINT_DISTANCE_KEY, ODataValue::Real(*distance));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use float_cmp::assert_approx_eq;
+
+    use crate::algorithms::nsga3opticas::associate::associate;
+    use crate::algorithms::nsga3opticas::{
+        NORMALISED_OBJECTIVE_KEY, REFERENCE_POINT_DISTANCE_KEY, REFERENCE_POINT_INDEX_KEY,
+    };
+    use crate::core::test_utils::individuals_from_obj_values_dummy;
+    use crate::core::{ODataValue, OObjectiveDirection};
+
+    #[test]
+    /// Each individual goes to the closest reference line
+    fn test_associate() {
+        let normalised = vec![vec![0.1, 0.9], vec![0.6, 0.4], vec![1.0, 0.0]];
+        let mut individuals = individuals_from_obj_values_dummy(
+            &normalised,
+            &[OObjectiveDirection::OMinimise, OObjectiveDirection::OMinimise],
+            None,
+        );
+        for (individual, values) in individuals.iter_mut().zip(&normalised) {
+            individual.set_data(NORMALISED_OBJECTIVE_KEY, ODataValue::Vector(values.clone()));
+        }
+
+        let reference_points = vec![vec![0.0, 1.0], vec![0.5, 0.5], vec![1.0, 0.0]];
+        associate(&mut individuals, &reference_points).unwrap();
+
+        let expected = [(0, 0.1), (1, 0.2 / 2.0_f64.sqrt()), (2, 0.0)];
+        for (individual, (index, distance)) in individuals.iter().zip(expected) {
+            assert_eq!(
+                individual.get_data(REFERENCE_POINT_INDEX_KEY).unwrap(),
+                ODataValue::USize(index)
+            );
+            assert_approx_eq!(
+                f64,
+                individual
+                    .get_data(REFERENCE_POINT_DISTANCE_KEY)
+                    .unwrap()
+                    .as_real()
+                    .unwrap(),
+                distance,
+                epsilon = 0.0001
+            );
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::Rem;
+use std::path::PathBuf;
+
+use log::{debug, info};
+use rand::RngCore;
+
+use opticas_macros::{as_algorithm, as_algorithm_args, impl_algorithm_trait_items};
+
+use crate::algorithms::OAlgorithm;
+use crate::core::utils::get_rng;
+use crate::core::{ODataValue, OIndividual, OOError, OVariableValue};
+use crate::operators::{
+    OCrossover, OMutation, OParetoConstrainedDominance, OPolynomialMutation,
+    OPolynomialMutationArgs, OSelector, OSimulatedBinaryCrossover, OSimulatedBinaryCrossoverArgs,
+    OTournamentSelector,
+};
+use crate::utils::{fast_non_dominated_sort, DasDarren1998, NumberOfPartitions};
+
+use associate::associate;
+use niching::niching;
+use normalise::normalise;
+
+mod associate;
+mod niching;
+mod normalise;
+
+/// The data key where the normalised objectives are stored for each [`OIndividual`].
+const NORMALISED_OBJECTIVE_KEY: &str = "normalised_objectives";
+
+/// The data key where the index of the associated reference point is stored for each
+/// [`OIndividual`].
+const REFERENCE_POINT_INDEX_KEY: &str = "reference_point_index";
+
+/// The data key where the distance from the associated reference direction is stored for each
+/// [`OIndividual`].
+const REFERENCE_POINT_DISTANCE_KEY: &str = "reference_point_distance";
+
+/// Input arguments for the NSGA3OPTICAS algorithm.
+#[as_algorithm_args]
+pub struct NSGA3OPTICASArg {
+    /// The number of individuals to use in the population. This must be a multiple of `2` and at
+    /// least the number of reference points.
+    pub number_of_individuals: usize,
+    /// The number of partitions used to generate the reference points with [`DasDarren1998`]. Use
+    /// two layers of points with four or more objectives to keep the number of points small.
+    pub number_of_partitions: NumberOfPartitions,
+    /// The options of the Simulated Binary Crossover (SBX) operator. This operator is used to
+    /// generate new children by recombining the variables of parent solutions. This defaults to
+    /// [`OSimulatedBinaryCrossoverArgs::default()`].
+    pub crossover_operator_options: Option<OSimulatedBinaryCrossoverArgs>,
+    /// The options to Polynomial Mutation (PM) operator used to mutate the variables of an
+    /// individual. This defaults to [`OPolynomialMutationArgs::default()`].
+    pub mutation_operator_options: Option<OPolynomialMutationArgs>,
+    /// Instead of initialising the population with random variables, see the initial population
+    /// with  the variable values from a JSON files exported with this tool.
+    pub resume_from_file: Option<PathBuf>,
+    /// Seed the initial population with these solutions. Each map sets the variables of one
+    /// individual; the variables it does not set and the remaining individuals are random. This is
+    /// ignored when `resume_from_file` is set.
+    pub initial_solutions: Option<Vec<HashMap<String, OVariableValue>>>,
+    /// The seed used in the random number generator (RNG). The seed is randomly picked if this is
+    /// `None`.
+    pub seed: Option<u64>,
+}
+
+/// The reference-point based Non-dominated Sorting Genetic Algorithm (NSGA3). The population is
+/// sorted into fronts as in NSGA2, but the last front that does not fit in the new population is
+/// not truncated by crowding distance, which loses its meaning with four or more objectives.
+/// The objectives are instead normalised against the ideal and extreme points, each individual is
+/// associated with the closest reference direction and the individuals of the least crowded
+/// directions are kept.
+///
+/// Implemented based on:
+/// > K. Deb and H. Jain, "An Evolutionary Many-Objective Optimization Algorithm Using
+/// > Reference-Point-Based Nondominated Sorting Approach, Part I: Solving Problems With Box
+/// > Constraints," in IEEE Transactions on Evolutionary Computation, vol. 18, no. 4, pp. 577-601,
+/// > Aug. 2014, doi: 10.1109/TEVC.2013.2281535.
+///
+/// See: <https://doi.org/10.1109/TEVC.2013.2281535>.
+#[as_algorithm(NSGA3OPTICASArg)]
+pub struct NSGA3OPTICAS {
+    /// The reference points on the normalised hyperplane.
+    reference_points: Vec<Vec<f64>>,
+    /// The best value of each objective found so far.
+    ideal_point: Vec<f64>,
+    /// The operator to use to select the individuals for reproduction. This is a binary tournament
+    /// selector ([`OTournamentSelector`]) with the [`OParetoConstrainedDominance`] comparison
+    /// operator, which prefers the feasible solutions and then the least violating ones.
+    selector_operator: OTournamentSelector<OParetoConstrainedDominance>,
+    /// The SBX operator to use to generate a new children by recombining the variables of parent
+    /// solutions.
+    crossover_operator: OSimulatedBinaryCrossover,
+    /// The PM operator to use to mutate the variables of an individual.
+    mutation_operator: OPolynomialMutation,
+    /// The seed to use.
+    rng: Box<dyn RngCore>,
+}
+
+impl NSGA3OPTICAS {
+    /// Initialise the NSGA3OPTICAS algorithm.
+    ///
+    /// # Arguments
+    ///
+    /// * `problem`: The problem being solved.
+    /// * `options`: The [`NSGA3OPTICASArg`] arguments to customise the algorithm behaviour.
+    ///
+    /// returns: `Result<NSGA3OPTICAS, OError>`.
+    pub fn new(problem: OProblem, options: NSGA3OPTICASArg) -> Result<Self, OOError> {
+        let name = "NSGA3OPTICAS".to_string();
+        if options.number_of_individuals < 3 {
+            return Err(OOError::AlgorithmInit(
+                name,
+                "The population size must have at least 3 individuals".to_string(),
+            ));
+        }
+        // force the population size as multiple of 2 so that the new number of generated offsprings
+        // matches `number_of_individuals`
+        if options.number_of_individuals.rem(2) != 0 {
+            return Err(OOError::AlgorithmInit(
+                name,
+                "The population size must be a multiple of 2".to_string(),
+            ));
+        }
+
+        let das_darren = DasDarren1998::new(
+            problem.number_of_objectives(),
+            &options.number_of_partitions,
+        )?;
+        let reference_points = das_darren.get_weights();
+        if options.number_of_individuals < reference_points.len() {
+            return Err(OOError::AlgorithmInit(
+                name,
+                format!(
+                    "The population size ({}) must be at least the number of reference points ({})",
+                    options.number_of_individuals,
+                    reference_points.len()
+                ),
+            ));
+        }
+
+        let nsga3_args = options.clone();
+        let problem = Arc::new(problem);
+        let population = if let Some(init_file) = options.resume_from_file {
+            info!("Loading initial population from {:?}", init_file);
+            NSGA3OPTICAS::seed_population_from_file(
+                problem.clone(),
+                &name,
+                options.number_of_individuals,
+                &init_file,
+            )?
+        } else if let Some(solutions) = &options.initial_solutions {
+            info!(
+                "Created initial population seeded with {} solution(s)",
+                solutions.len().min(options.number_of_individuals)
+            );
+            OPopulation::init_with_solutions(
+                problem.clone(),
+                options.number_of_individuals,
+                solutions,
+            )?
+        } else {
+            info!("Created initial random population");
+            OPopulation::init(problem.clone(), options.number_of_individuals)
+        };
+
+        let mutation_options = match options.mutation_operator_options {
+            Some(o) => o,
+            None => OPolynomialMutationArgs::default(problem.clone().as_ref()),
+        };
+        let mutation_operator = OPolynomialMutation::new(mutation_options.clone())?;
+
+        let crossover_options = options.crossover_operator_options.unwrap_or_default();
+        let crossover_operator = OSimulatedBinaryCrossover::new(crossover_options.clone())?;
+
+        info!(
+            "Algorithm options are:\n\t* Number of variables {:>13}\n\t* Number of objectives {:>12}\n\t* Number of constraints {:>11}\n\t* Number of reference points {:>6}",
+            problem.number_of_variables(),
+            problem.number_of_objectives(),
+            problem.number_of_constraints(),
+            reference_points.len()
+        );
+
+        Ok(Self {
+            number_of_individuals: options.number_of_individuals,
+            ideal_point: vec![f64::INFINITY; problem.number_of_objectives()],
+            reference_points,
+            problem,
+            population,
+            selector_operator: OTournamentSelector::<OParetoConstrainedDominance>::new(2),
+            crossover_operator,
+            mutation_operator,
+            generation: 0,
+            nfe: 0,
+            stopping_condition: options.stopping_condition,
+            start_time: Instant::now(),
+            parallel: options.parallel.unwrap_or(true),
+            export_history: options.export_history,
+            rng: get_rng(options.seed),
+            args: nsga3_args,
+        })
+    }
+
+    /// Get the reference points.
+    ///
+    /// returns: `Vec<Vec<f64>>`
+    pub fn reference_points(&self) -> Vec<Vec<f64>> {
+        self.reference_points.clone()
+    }
+}
+
+/// Implementation of Section IV of the paper.
+#[impl_algorithm_trait_items(NSGA3OPTICASArg)]
+impl OAlgorithm<NSGA3OPTICASArg> for NSGA3OPTICAS {
+    /// This evaluates the initial population and sets the individual's ranks.
+    ///
+    /// return: `Result<(), OError>`
+    fn initialise(&mut self) -> Result<(), OOError> {
+        info!("Evaluating initial population");
+        if self.parallel {
+            NSGA3OPTICAS::do_parallel_evaluation(self.population.individuals_as_mut(), &mut self.nfe)?;
+        } else {
+            NSGA3OPTICAS::do_evaluation(self.population.individuals_as_mut(), &mut self.nfe)?;
+        }
+
+        debug!("Calculating rank");
+        fast_non_dominated_sort(self.population.individuals_as_mut(), false)?;
+
+        info!("Initial evaluation completed");
+        self.generation += 1;
+
+        Ok(())
+    }
+
+    fn evolve(&mut self) -> Result<(), OOError> {
+        // Create the new population of size self.number_of_individuals. The loop adds two
+        // individuals at the time.
+        debug!("Generating new population (selection + crossover + mutation)");
+        let mut offsprings: Vec<OIndividual> = Vec::new();
+        for _ in 0..self.number_of_individuals / 2 {
+            let parents =
+                self.selector_operator
+                    .select(self.population.individuals(), 2, &mut self.rng)?;
+
+            // generate the 2 children with crossover
+            let children = self.crossover_operator.generate_offsprings(
+                &parents[0],
+                &parents[1],
+                &mut self.rng,
+            )?;
+
+            // mutate them
+            offsprings.push(
+                self.mutation_operator
+                    .mutate_offspring(&children.child1, &mut self.rng)?,
+            );
+            offsprings.push(
+                self.mutation_operator
+                    .mutate_offspring(&children.child2, &mut self.rng)?,
+            );
+        }
+        debug!("Combining parents and offsprings in new population");
+        self.population.add_new_individuals(offsprings);
+        debug!("New population size is {}", self.population.len());
+
+        debug!("Evaluating population");
+        if self.parallel {
+            NSGA3OPTICAS::do_parallel_evaluation(self.population.individuals_as_mut(), &mut self.nfe)?;
+        } else {
+            NSGA3OPTICAS::do_evaluation(self.population.individuals_as_mut(), &mut self.nfe)?;
+        }
+        debug!("Evaluation done");
+
+        debug!("Calculating fronts and ranks for new population");
+        let sorting_results = fast_non_dominated_sort(self.population.individuals_as_mut(), false)?;
+        debug!("Collected {} fronts", sorting_results.fronts.len());
+
+        // Add the fronts until the population almost reaches `self.number_of_individuals` (this
+        // is S_t in the paper without the last front F_l)
+        debug!("Selecting best individuals");
+        let mut selected: Vec<OIndividual> = Vec::new();
+        let mut last_front: Option<Vec<OIndividual>> = None;
+        for (fi, front) in sorting_results.fronts.into_iter().enumerate() {
+            if selected.len() + front.len() <= self.number_of_individuals {
+                debug!("Adding front #{} (size: {})", fi + 1, front.len());
+                selected.extend(front);
+            } else if selected.len() == self.number_of_individuals {
+                debug!("Population reached target size");
+                break;
+            } else {
+                debug!("Population almost full ({} individuals)", selected.len());
+                last_front = Some(front);
+                break;
+            }
+        }
+
+        // Complete the population with the last front using the reference points
+        if let Some(last_front) = last_front {
+            let split = selected.len();
+            let missing = self.number_of_individuals - split;
+            let mut candidates = selected;
+            candidates.extend(last_front);
+
+            debug!("Normalising objectives");
+            normalise(&mut candidates, &mut self.ideal_point)?;
+
+            debug!("Associating individuals with the reference points");
+            associate(&mut candidates, &self.reference_points)?;
+
+            debug!("Picking {} individuals from the last front", missing);
+            let last_front = candidates.split_off(split);
+            let picked = niching(
+                &candidates,
+                last_front,
+                self.reference_points.len(),
+                missing,
+                &mut self.rng,
+            )?;
+
+            selected = candidates;
+            selected.extend(picked);
+        }
+
+        self.population = OPopulation::new_with(selected);
+        self.generation += 1;
+        Ok(())
+    }
+
+    /// Export the reference points and the ideal point.
+    ///
+    /// return: `Option<HashMap<String, ODataValue>>`
+    fn additional_export_data(&self) -> Option<HashMap<String, ODataValue>> {
+        let reference_points = self
+            .reference_points
+            .iter()
+            .map(|point| ODataValue::Vector(point.clone()))
+            .collect();
+
+        Some(HashMap::from([
+            (
+                "reference_points".to_string(),
+                ODataValue::DataVector(reference_points),
+            ),
+            (
+                "ideal_point".to_string(),
+                ODataValue::Vector(self.ideal_point.clone()),
+            ),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use opticas_macros::test_with_retries;
+
+    use crate::algorithms::{
+        OAlgorithm, OMaxGenerationValue, OStoppingConditionType, NSGA3OPTICAS, NSGA3OPTICASArg,
+    };
+    use crate::core::builtin_problems::{DTLZ1Problem, DTLZ2Problem};
+    use crate::utils::NumberOfPartitions;
+
+    /// Get the arguments for a problem with 3 objectives and 91 reference points.
+    fn args(generations: usize) -> NSGA3OPTICASArg {
+        NSGA3OPTICASArg {
+            number_of_individuals: 92,
+            number_of_partitions: NumberOfPartitions::OneLayer(12),
+            crossover_operator_options: None,
+            mutation_operator_options: None,
+            stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(
+                generations,
+            )),
+            parallel: Some(false),
+            export_history: None,
+            resume_from_file: None,
+            initial_solutions: None,
+            seed: Some(1),
+        }
+    }
+
+    #[test]
+    /// The population must hold at least one individual per reference point
+    fn test_too_few_individuals() {
+        let problem = DTLZ1Problem::create(7, 3, false).unwrap();
+        let mut args = args(1);
+        args.number_of_individuals = 50;
+        assert!(NSGA3OPTICAS::new(problem, args).is_err());
+    }
+
+    #[test_with_retries(3)]
+    /// Test the DTLZ2 problem from Deb & Jain (2014) with 3 objectives. The Pareto-optimal
+    /// solutions lie on the unit sphere.
+    fn test_dtlz2_problem() {
+        let problem = DTLZ2Problem::create(12, 3).unwrap();
+        let mut algo = NSGA3OPTICAS::new(problem, args(250)).unwrap();
+        algo.run().unwrap();
+
+        let results = algo.get_results();
+        let on_sphere = results
+            .individuals
+            .iter()
+            .filter(|individual| {
+                let radius: f64 = individual
+                    .get_objective_values()
+                    .unwrap()
+                    .iter()
+                    .map(|v| v.powi(2))
+                    .sum();
+                (radius - 1.0).abs() < 0.1
+            })
+            .count();
+
+        assert!(
+            on_sphere as f64 >= 0.9 * results.individuals.len() as f64,
+            "Only {} of {} solutions are on the Pareto front",
+            on_sphere,
+            results.individuals.len()
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use rand::prelude::SliceRandom;
+use rand::RngCore;
+
+use crate::algorithms::nsga3opticas::{REFERENCE_POINT_DISTANCE_KEY, REFERENCE_POINT_INDEX_KEY};
+use crate::core::{OIndividual, OOError};
+use crate::utils::argmin_by;
+
+/// Get the index of the reference point an individual is associated with.
+///
+/// # Arguments
+///
+/// * `individual`: The individual.
+///
+/// returns: `Result<usize, OError>`
+fn reference_point_index(individual: &OIndividual) -> Result<usize, OOError> {
+    individual.get_data(REFERENCE_POINT_INDEX_KEY)?.as_usize()
+}
+
+/// Pick the individuals of the last front to add to the new population with the niche-preserving
+/// operation of Section IV-E of Deb & Jain (2014). The niche count of a reference point is the
+/// number of selected individuals associated with it. At each step, a reference point with the
+/// smallest niche count is picked at random: if none of the remaining individuals of the last front
+/// is associated with it, the point is excluded; otherwise the closest individual (when the niche is
+/// empty) or a random one (when it is not) joins the population.
+///
+/// # Arguments
+///
+/// * `selected`: The individuals already in the new population.
+/// * `last_front`: The individuals of the last front that does not fit in the new population.
+/// * `number_of_reference_points`: The number of reference points.
+/// * `count`: The number of individuals to pick.
+/// * `rng`: The random number generator.
+///
+/// returns: `Result<Vec<OIndividual>, OError>`
+pub(crate) fn niching(
+    selected: &[OIndividual],
+    mut last_front: Vec<OIndividual>,
+    number_of_reference_points: usize,
+    count: usize,
+    rng: &mut dyn RngCore,
+) -> Result<Vec<OIndividual>, OOError> {
+    let mut niche_count = vec![0_usize; number_of_reference_points];
+    for individual in selected {
+        niche_count[reference_point_index(individual)?] += 1;
+    }
+
+    let mut excluded = vec![false; number_of_reference_points];
+    let mut picked: Vec<OIndividual> = Vec::new();
+
+    while picked.len() < count && !last_front.is_empty() {
+        let min_count = match (0..number_of_reference_points)
+            .filter(|j| !excluded[*j])
+            .map(|j| niche_count[j])
+            .min()
+        {
+            Some(min_count) => min_count,
+            None => break,
+        };
+        let min_points: Vec<usize> = (0..number_of_reference_points)
+            .filter(|j| !excluded[*j] && niche_count[*j] == min_count)
+            .collect();
+        let j = *min_points.choose(rng).unwrap();
+
+        let members: Vec<usize> = last_front
+            .iter()
+            .enumerate()
+            .filter(|(_, individual)| reference_point_index(individual).ok() == Some(j))
+            .map(|(index, _)| index)
+            .collect();
+        if members.is_empty() {
+            excluded[j] = true;
+            continue;
+        }
+
+        let chosen = if niche_count[j] == 0 {
+            let distances = members
+                .iter()
+                .map(|index| {
+                    last_front[*index]
+                        .get_data(REFERENCE_POINT_DISTANCE_KEY)?
+                        .as_real()
+                })
+                .collect::<Result<Vec<f64>, OOError>>()?;
+            let (position, _) = argmin_by(&distances, |(_, d)| *d).unwrap();
+            members[position]
+        } else {
+            *members.choose(rng).unwrap()
+        };
+
+        picked.push(last_front.swap_remove(chosen));
+        niche_count[j] += 1;
+    }
+
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::algorithms::nsga3opticas::niching::niching;
+    use crate::algorithms::nsga3opticas::{
+        REFERENCE_POINT_DISTANCE_KEY, REFERENCE_POINT_INDEX_KEY,
+    };
+    use crate::core::test_utils::individuals_from_obj_values_dummy;
+    use crate::core::utils::get_rng;
+    use crate::core::{ODataValue, OObjectiveDirection};
+
+    #[test]
+    /// The closest individual of an empty niche is picked first
+    fn test_niching() {
+        // (reference point, distance) of one selected individual and three in the last front
+        let associations = [(1, 0.1), (0, 0.3), (0, 0.2), (1, 0.05)];
+        let objectives: Vec<Vec<f64>> = (0..associations.len())
+            .map(|i| vec![i as f64, 0.0])
+            .collect();
+        let mut individuals = individuals_from_obj_values_dummy(
+            &objectives,
+            &[OObjectiveDirection::OMinimise, OObjectiveDirection::OMinimise],
+            None,
+        );
+        for (individual, (index, distance)) in individuals.iter_mut().zip(associations) {
+            individual.set_data(REFERENCE_POINT_INDEX_KEY, ODataValue::USize(index));
+            individual.set_data(REFERENCE_POINT_DISTANCE_KEY, ODataValue::Real(distance));
+        }
+
+        let last_front = individuals.split_off(1);
+        let mut rng = get_rng(Some(1));
+        let picked = niching(&individuals, last_front, 3, 1, &mut rng).unwrap();
+
+        assert_eq!(picked.len(), 1);
+        assert_eq!(picked[0].get_objective_values().unwrap(), vec![2.0, 0.0]);
+    }
+}
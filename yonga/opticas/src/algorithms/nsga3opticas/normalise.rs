@@ -0,0 +1,197 @@
+use crate::algorithms::nsga3opticas::NORMALISED_OBJECTIVE_KEY;
+use crate::core::{ODataValue, OIndividual, OOError};
+use crate::utils::{argmin_by, solve_linear_system, LinearSolverTolerance};
+
+/// The weight of the objectives other than the axis when the extreme points are searched with the
+/// achievement scalarising function.
+const ASF_WEIGHT: f64 = 1e-6;
+
+/// The smallest intercept accepted on an objective axis. Below this the hyperplane is degenerate.
+const MIN_INTERCEPT: f64 = 1e-6;
+
+/// Normalise the objectives of the individuals as described in Section IV-C of Deb & Jain (2014).
+/// The objectives are first translated so that the ideal point becomes the origin, then divided by
+/// the intercepts on each objective axis of the hyperplane passing through the extreme points. The
+/// normalised objectives are stored on each individual's data with the key
+/// [`NORMALISED_OBJECTIVE_KEY`].
+///
+/// When the hyperplane cannot be built (the extreme points are not linearly independent or an
+/// intercept is negative or too small), the intercepts are the worst translated values of each
+/// objective.
+///
+/// > K. Deb and H. Jain, "An Evolutionary Many-Objective Optimization Algorithm Using
+/// > Reference-Point-Based Nondominated Sorting Approach, Part I: Solving Problems With Box
+/// > Constraints," in IEEE Transactions on Evolutionary Computation, vol. 18, no. 4, pp. 577-601,
+/// > Aug. 2014, doi: 10.1109/TEVC.2013.2281535.
+///
+/// # Arguments
+///
+/// * `individuals`: The individuals to normalise.
+/// * `ideal_point`: The ideal point found so far. This is updated with the best objectives of the
+///    individuals.
+///
+/// returns: `Result<(), OError>`
+pub(crate) fn normalise(
+    individuals: &mut [OIndividual],
+    ideal_point: &mut [f64],
+) -> Result<(), OOError> {
+    let objectives = individuals
+        .iter()
+        .map(|i| i.get_objective_values())
+        .collect::<Result<Vec<Vec<f64>>, OOError>>()?;
+
+    // update the ideal point
+    for values in &objectives {
+        for (ideal, value) in ideal_point.iter_mut().zip(values) {
+            *ideal = ideal.min(*value);
+        }
+    }
+
+    // translate the objectives
+    let translated: Vec<Vec<f64>> = objectives
+        .iter()
+        .map(|values| values.iter().zip(&*ideal_point).map(|(v, z)| v - z).collect())
+        .collect();
+
+    let intercepts = intercepts(&translated)?;
+    for (individual, values) in individuals.iter_mut().zip(translated) {
+        let normalised = values.iter().zip(&intercepts).map(|(v, a)| v / a).collect();
+        individual.set_data(NORMALISED_OBJECTIVE_KEY, ODataValue::Vector(normalised));
+    }
+
+    Ok(())
+}
+
+/// Find the extreme point on each objective axis. This is the point minimising the achievement
+/// scalarising function (ASF) whose weight vector is the axis direction.
+///
+/// # Arguments
+///
+/// * `translated`: The translated objectives.
+///
+/// returns: `Result<Vec<Vec<f64>>, OError>`
+fn extreme_points(translated: &[Vec<f64>]) -> Result<Vec<Vec<f64>>, OOError> {
+    let number_of_objectives = translated.first().map_or(0, |v| v.len());
+
+    (0..number_of_objectives)
+        .map(|axis| {
+            let asf = |values: &Vec<f64>| {
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(j, v)| v / if j == axis { 1.0 } else { ASF_WEIGHT })
+                    .fold(f64::NEG_INFINITY, f64::max)
+            };
+            argmin_by(translated, |(_, values)| asf(values))
+                .map(|(_, values)| values.clone())
+                .ok_or(OOError::SurvivalOperator(
+                    "normalise".to_string(),
+                    "No individual to find the extreme points".to_string(),
+                ))
+        })
+        .collect()
+}
+
+/// Calculate the intercepts of the hyperplane through the extreme points with each objective axis.
+/// The worst translated objective is used when the hyperplane is degenerate.
+///
+/// # Arguments
+///
+/// * `translated`: The translated objectives.
+///
+/// returns: `Result<Vec<f64>, OError>`
+fn intercepts(translated: &[Vec<f64>]) -> Result<Vec<f64>, OOError> {
+    let extreme_points = extreme_points(translated)?;
+    let number_of_objectives = extreme_points.len();
+
+    // the plane is sum(b_j * f_j) = 1 and its intercepts are 1 / b_j
+    let b = vec![1.0; number_of_objectives];
+    let intercepts = solve_linear_system(
+        &extreme_points,
+        &b,
+        Some(LinearSolverTolerance::default()),
+    )
+    .map(|plane| plane.iter().map(|b| 1.0 / b).collect::<Vec<f64>>());
+
+    match intercepts {
+        Ok(intercepts)
+            if intercepts
+                .iter()
+                .all(|a| a.is_finite() && *a > MIN_INTERCEPT) =>
+        {
+            Ok(intercepts)
+        }
+        _ => Ok((0..number_of_objectives)
+            .map(|j| {
+                let worst = translated
+                    .iter()
+                    .map(|values| values[j])
+                    .fold(f64::NEG_INFINITY, f64::max);
+                // an objective without range is not scaled
+                if worst > MIN_INTERCEPT {
+                    worst
+                } else {
+                    1.0
+                }
+            })
+            .collect()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use float_cmp::assert_approx_eq;
+
+    use crate::algorithms::nsga3opticas::normalise::normalise;
+    use crate::algorithms::nsga3opticas::NORMALISED_OBJECTIVE_KEY;
+    use crate::core::test_utils::individuals_from_obj_values_dummy;
+    use crate::core::OObjectiveDirection;
+
+    #[test]
+    /// The extreme points define the intercepts
+    fn test_normalise() {
+        let objectives = vec![vec![1.0, 5.0], vec![3.0, 3.0], vec![5.0, 1.0]];
+        let mut individuals = individuals_from_obj_values_dummy(
+            &objectives,
+            &[OObjectiveDirection::OMinimise, OObjectiveDirection::OMinimise],
+            None,
+        );
+        let mut ideal_point = vec![f64::INFINITY; 2];
+        normalise(&mut individuals, &mut ideal_point).unwrap();
+
+        assert_eq!(ideal_point, vec![1.0, 1.0]);
+        let expected = [[0.0, 1.0], [0.5, 0.5], [1.0, 0.0]];
+        for (individual, expected) in individuals.iter().zip(expected) {
+            let values = individual
+                .get_data(NORMALISED_OBJECTIVE_KEY)
+                .unwrap()
+                .as_f64_vec()
+                .unwrap()
+                .clone();
+            for (value, expected) in values.iter().zip(expected) {
+                assert_approx_eq!(f64, *value, expected, epsilon = 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    /// The worst objectives are used when the extreme points coincide
+    fn test_normalise_degenerate() {
+        let objectives = vec![vec![2.0, 2.0], vec![2.0, 2.0], vec![4.0, 6.0]];
+        let mut individuals = individuals_from_obj_values_dummy(
+            &objectives,
+            &[OObjectiveDirection::OMinimise, OObjectiveDirection::OMinimise],
+            None,
+        );
+        let mut ideal_point = vec![f64::INFINITY; 2];
+        normalise(&mut individuals, &mut ideal_point).unwrap();
+
+        let values = individuals[2]
+            .get_data(NORMALISED_OBJECTIVE_KEY)
+            .unwrap()
+            .as_f64_vec()
+            .unwrap()
+            .clone();
+        assert_eq!(values, vec![1.0, 1.0]);
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt::{Display, Formatter};
 use std::collections::HashMap;
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
 /// Operator used to check a bounded constraint
@@ -19,46 +20,90 @@ pub enum ORelationalOperator {
     GreaterThan,
 }
 
-
-/// Operator used to check a bounded constraint on a service group
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum ServiceGroupORelationalOperator {
-    /// Value must equal the constraint value
-    EqualTo,
+/// The value the evaluator returns for a constraint. The constraint turns it into a violation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum OConstraintValue {
+    /// A number compared with the target of a bounded constraint (a latency, a count, ...).
+    Scalar(f64),
+    /// The node a service is assigned to.
+    Node(u64),
+    /// The node of each service of a group.
+    Group(HashMap<String, u64>),
+    /// The demand on a node (cpu, memory, disk, network).
+    Demand([f64; 4]),
 }
 
+/// A user-defined violation: 0.0 when the constraint is met, a positive magnitude otherwise.
+pub type OViolationFn = Arc<dyn Fn(&OConstraintValue) -> f64 + Send + Sync>;
 
+/// The kind of constraint, which decides how its value is turned into a violation.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum OConstraintKind {
+    /// The value is compared with the target with a relational operator. The violation is the
+    /// distance of the value from the target.
+    Bounded {
+        operator: ORelationalOperator,
+        target: f64,
+    },
+    /// A service runs on the given node. The violation is 1.0 on any other node.
+    Assignment { node: u64 },
+    /// The services of the group run on the same node. The violation is the number of services
+    /// off the node shared by most of them.
+    Group { services: Vec<String> },
+    /// The demand on a node stays within the available resources (cpu, memory, disk, network).
+    /// The violation is the overload in each resource relative to the total resources of the
+    /// node, summed.
+    Capacity { available: [f64; 4], total: [f64; 4] },
+    /// A user-defined violation. Problems with custom constraints cannot be serialised.
+    #[serde(skip)]
+    Custom(OViolationFn),
+}
+
+impl std::fmt::Debug for OConstraintKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OConstraintKind::Bounded { operator, target } => f
+                .debug_struct("Bounded")
+                .field("operator", operator)
+                .field("target", target)
+                .finish(),
+            OConstraintKind::Assignment { node } => {
+                f.debug_struct("Assignment").field("node", node).finish()
+            }
+            OConstraintKind::Group { services } => {
+                f.debug_struct("Group").field("services", services).finish()
+            }
+            OConstraintKind::Capacity { available, total } => f
+                .debug_struct("Capacity")
+                .field("available", available)
+                .field("total", total)
+                .finish(),
+            OConstraintKind::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
 
+/// The violation added to a strict bound (< or >) when the value equals the target.
+const STRICT_TOLERANCE: f64 = 1e-6;
 
-/// Define a constraint where a value is compared with a relational operator as follows:
-///  - Equality operator ([`ORelationalOperator::EqualTo`]): value == target
-///  - Inequality operator ([`ORelationalOperator::NotEqualTo`]): value != target
-///  - Greater than operator ([`ORelationalOperator::GreaterThan`]): value > target
-///  - Greater or equal to operator ([`ORelationalOperator::GreaterOrEqualTo`]): value >= target
-///  - less than operator ([`ORelationalOperator::LessThan`]): value < target
-///  - less or equal to operator ([`ORelationalOperator::LessOrEqualTo`]): value <= target
+/// Define a constraint of one of the [`OConstraintKind`]. The evaluator returns an
+/// [`OConstraintValue`] for each constraint and the constraint measures how far the value is
+/// from meeting it, as an `f64` violation.
 ///
 /// # Example
 ///
 /// ```
-///   use optirustic::core::{Constraint, ORelationalOperator};
-///   let c = Constraint::new("Z>=5.2",ORelationalOperator::GreaterOrEqualTo, 5.2);
-///   assert_eq!(c.is_met(10.1), true);
-///   assert_eq!(c.is_met(3.11), false);
+///   use opticas::core::{OConstraint, OConstraintValue, ORelationalOperator};
+///   let c = OConstraint::new("Z>=5.2", ORelationalOperator::GreaterOrEqualTo, 5.2);
+///   assert_eq!(c.is_met(&OConstraintValue::Scalar(10.1)), true);
+///   assert_eq!(c.is_met(&OConstraintValue::Scalar(3.11)), false);
 /// ```
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OConstraint {
     /// The constraint name.
     name: String,
-    /// The relational operator to use to compare a value against the constraint target value.
-    operator: ORelationalOperator,
-    /// The constraint target.
-    target: Option<u64>,
-    /// Option Group
-    service_group: Option<Vec<String>>,
-    /// Option Resource
-    resource: Option<HashMap<u64, (f64, f64, f64, f64)>> // target (cpu, mem, disk, net) - u64 is the target node id
-    
+    /// How the value of the constraint is checked.
+    kind: OConstraintKind,
 }
 
 impl OConstraint {
@@ -71,15 +116,9 @@ impl OConstraint {
     ///    target value.
     /// * `target`: The constraint target.
     ///
-    /// returns: `Constraint`
-    pub fn new(name: &str, operator: ORelationalOperator, target: Option<u64>, service_group: Option<Vec<String>>, resource: Option<HashMap<u64, (f64, f64, f64, f64)>>) -> Self {
-        Self {
-            name: name.to_owned(),
-            operator,
-            target,
-            service_group,
-            resource
-        }
+    /// returns: `OConstraint`
+    pub fn new(name: &str, operator: ORelationalOperator, target: f64) -> Self {
+        Self::with_kind(name, OConstraintKind::Bounded { operator, target })
     }
 
     /// Create a new relational constraint with a scale and offset. The target is first scaled,
@@ -94,26 +133,81 @@ impl OConstraint {
     /// * `scale`: Apply a scaling factor to the `target`.
     /// * `offset`: Apply an offset to the `target`.
     ///
-    /// returns: `Constraint`
+    /// returns: `OConstraint`
     pub fn new_with_modifiers(
         name: &str,
         operator: ORelationalOperator,
-        target: Option<u64>,
-        service_group: Option<Vec<String>>,
-        resource: Option<HashMap<u64, (f64, f64, f64, f64)>>,
-        scale: u64,
-        offset: u64,
+        target: f64,
+        scale: f64,
+        offset: f64,
     ) -> Self {
-        let target = match target {
-            Some(t) => t,
-            None => 0,
-        };
+        Self::new(name, operator, target * scale + offset)
+    }
+
+    /// Create a constraint fixing a service to a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The constraint name.
+    /// * `node`: The node the service must run on.
+    ///
+    /// returns: `OConstraint`
+    pub fn assignment(name: &str, node: u64) -> Self {
+        Self::with_kind(name, OConstraintKind::Assignment { node })
+    }
+
+    /// Create a constraint co-locating a group of services.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The constraint name.
+    /// * `services`: The services that must share a node.
+    ///
+    /// returns: `OConstraint`
+    pub fn group(name: &str, services: Vec<String>) -> Self {
+        Self::with_kind(name, OConstraintKind::Group { services })
+    }
+
+    /// Create a capacity constraint on a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The constraint name.
+    /// * `available`: The resources left on the node (cpu, memory, disk, network).
+    /// * `total`: The resources of the node, to normalise the overload with.
+    ///
+    /// returns: `OConstraint`
+    pub fn capacity(name: &str, available: [f64; 4], total: [f64; 4]) -> Self {
+        Self::with_kind(name, OConstraintKind::Capacity { available, total })
+    }
+
+    /// Create a constraint with a user-defined violation.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The constraint name.
+    /// * `violation`: The function returning the violation of a value - 0.0 when it is met.
+    ///
+    /// returns: `OConstraint`
+    pub fn custom(
+        name: &str,
+        violation: impl Fn(&OConstraintValue) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        Self::with_kind(name, OConstraintKind::Custom(Arc::new(violation)))
+    }
+
+    /// Create a constraint of any kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The constraint name.
+    /// * `kind`: The constraint kind.
+    ///
+    /// returns: `OConstraint`
+    pub fn with_kind(name: &str, kind: OConstraintKind) -> Self {
         Self {
             name: name.to_owned(),
-            operator,
-            target: Some(target * scale + offset),
-            service_group,
-            resource
+            kind,
         }
     }
 
@@ -122,82 +216,27 @@ impl OConstraint {
         self.name.clone()
     }
 
-    /// Check whether the constraint is met. This is assessed as follows:
-    ///  - Equality operator ([`ORelationalOperator::EqualTo`]): value == target
-    ///  - Inequality operator ([`ORelationalOperator::NotEqualTo`]): value != target
-    ///  - Greater than operator ([`ORelationalOperator::GreaterThan`]): value > target
-    ///  - Greater or equal to operator ([`ORelationalOperator::GreaterOrEqualTo`]): value >= target
-    ///  - less than operator ([`ORelationalOperator::LessThan`]): value < target
-    ///  - less or equal to operator ([`ORelationalOperator::LessOrEqualTo`]): value <= target
+    /// Get the constraint kind.
+    pub fn kind(&self) -> &OConstraintKind {
+        &self.kind
+    }
+
+    /// Check whether the constraint is met, i.e. its violation is 0.0.
     ///
     /// # Arguments
     ///
-    /// * `value`: The value to check against the constraint target.
+    /// * `value`: The value to check against the constraint.
     ///
     /// returns: `bool`
-    pub fn is_met(&self, value: (Option<u64>, Option<Vec<HashMap<String, u64>>>, Option<HashMap<u64, (f64, f64, f64, f64)>>)) -> bool {
-
-        if self.target.is_none() && self.service_group.is_none() && self.resource.is_none() {
-            return true; // no constraint
-        }
-
-        if let Some(service_group) = &self.service_group {
-            // all services in value.1 must have the same node id (u64)
-            let mut node = None;
-            for service in service_group {
-                if let Some(node_id) = value.1.clone().unwrap().iter().find(|&x| x.contains_key(service)) {
-                    if let Some(n) = node {
-                        if n != node_id[service] {
-                            return false;
-                        }
-                    } else {
-                        node = Some(node_id[service]);
-                    }
-                } else {
-                    return false;
-                }
-            }
-            return true;            
-        }
-
-        else if let Some(resource) = &self.resource {
-            // all resources in value.2 must be less than or equal to the target resource
-            for (node_id, ava) in resource {
-                if let Some(req) = value.2.clone().unwrap().get(node_id) {
-                    if req.0 > ava.0 || req.1 > ava.1 || req.2 > ava.2 || req.3 > ava.3 {
-                        return false;
-                    }
-                } else {
-                    return false;
-                }
-            }
-            return true;
-        }
-
-        else {
-            //println!("Checking constraint - the u64 (target) was provided");
-            match self.operator {
-                ORelationalOperator::EqualTo => {
-                    if value.0.unwrap() == self.target.unwrap() {
-                        //println!("Constraint met - value {} equal to target {}", value.0.unwrap(), self.target.unwrap());
-                        return true;
-                    }
-                    return false;
-                }
-                ORelationalOperator::NotEqualTo => value.0.unwrap() != self.target.unwrap(),
-                ORelationalOperator::LessOrEqualTo => value.0.unwrap() <= self.target.unwrap(),
-                ORelationalOperator::LessThan => value.0.unwrap() < self.target.unwrap(),
-                ORelationalOperator::GreaterOrEqualTo => value.0.unwrap() >= self.target.unwrap(),
-                ORelationalOperator::GreaterThan => value.0.unwrap() > self.target.unwrap(),
-            }
-        }
+    pub fn is_met(&self, value: &OConstraintValue) -> bool {
+        self.constraint_violation(value) == 0.0
     }
 
     /// Calculate the amount of violation of the constraint for a solution value. This is a measure
     /// about how close (or far) the constraint value is from the constraint target. If the
     /// constraint is met (i.e. the solution associated to the constraint is feasible), then the
-    /// violation is 0.0. Otherwise, the absolute difference between `target` and `value`
-    /// is returned.
+    /// violation is 0.0. A value that does not match the kind of the constraint (for example a
+    /// [`OConstraintValue::Group`] for a capacity constraint) is an infinite violation.
     ///
     /// See:
     ///  - Kalyanmoy Deb & Samir Agrawal. (2002). <https://doi.org/10.1007/978-3-7091-6384-9_40>.
@@ -205,204 +244,218 @@ impl OConstraint {
     ///
     /// # Arguments
     ///
-    /// * `value`: The value to check against the constraint target.
+    /// * `value`: The value to check against the constraint.
     ///
     /// return: `f64`
-    pub fn constraint_violation(&self, value: (Option<u64>, Option<Vec<HashMap<String, u64>>>, Option<HashMap<u64, (f64, f64, f64, f64)>>)) -> u64 {
-        let v = value.clone();
-        if self.is_met(v) {
-            0
-        } else {
-            match self.operator {
-                ORelationalOperator::EqualTo => self.target.unwrap_or(0).saturating_sub(value.0.clone().unwrap()),
-                ORelationalOperator::NotEqualTo => 1,
-                ORelationalOperator::LessOrEqualTo | ORelationalOperator::GreaterOrEqualTo => {
-                    self.target.unwrap_or(0) - value.0.unwrap()
+    pub fn constraint_violation(&self, value: &OConstraintValue) -> f64 {
+        let violation = match (&self.kind, value) {
+            (OConstraintKind::Bounded { operator, target }, OConstraintValue::Scalar(value)) => {
+                match operator {
+                    ORelationalOperator::EqualTo => (value - target).abs(),
+                    ORelationalOperator::NotEqualTo => {
+                        if value == target {
+                            1.0
+                        } else {
+                            0.0
+                        }
+                    }
+                    ORelationalOperator::LessOrEqualTo => (value - target).max(0.0),
+                    ORelationalOperator::GreaterOrEqualTo => (target - value).max(0.0),
+                    ORelationalOperator::LessThan => {
+                        if value < target {
+                            0.0
+                        } else {
+                            value - target + STRICT_TOLERANCE
+                        }
+                    }
+                    ORelationalOperator::GreaterThan => {
+                        if value > target {
+                            0.0
+                        } else {
+                            target - value + STRICT_TOLERANCE
+                        }
+                    }
                 }
-                ORelationalOperator::LessThan | ORelationalOperator::GreaterThan => {
-                    // add the tolerance
-                    (self.target.unwrap_or(0) - value.0.unwrap()) + 1000
+            }
+            (OConstraintKind::Assignment { node }, OConstraintValue::Node(value)) => {
+                if value == node {
+                    0.0
+                } else {
+                    1.0
                 }
             }
+            (OConstraintKind::Group { services }, OConstraintValue::Group(nodes)) => {
+                let mut counts: HashMap<u64, usize> = HashMap::new();
+                for service in services {
+                    if let Some(node) = nodes.get(service) {
+                        *counts.entry(*node).or_insert(0) += 1;
+                    }
+                }
+                (services.len() - counts.values().max().copied().unwrap_or(0)) as f64
+            }
+            (OConstraintKind::Capacity { available, total }, OConstraintValue::Demand(demand)) => {
+                (0..4)
+                    .filter(|k| total[*k] > 0.0)
+                    .map(|k| (demand[k].max(0.0) - available[k].max(0.0)).max(0.0) / total[k])
+                    .sum()
+            }
+            (OConstraintKind::Custom(violation), value) => violation(value).max(0.0),
+            _ => f64::INFINITY,
+        };
+
+        // a NaN value cannot meet the constraint
+        if violation.is_nan() {
+            f64::INFINITY
+        } else {
+            violation
         }
     }
 
-    /// Get the set constraint target.
+    /// Get the target of a bounded constraint.
     ///
-    /// returns: `f64`.
-    pub fn target(&self) -> Option<u64> {
-        self.target
+    /// returns: `Option<f64>`.
+    pub fn target(&self) -> Option<f64> {
+        match &self.kind {
+            OConstraintKind::Bounded { target, .. } => Some(*target),
+            _ => None,
+        }
     }
 
-    /// Get the set constraint operator.
+    /// Get the operator of a bounded constraint.
     ///
-    /// returns: `f64`.
-    pub fn operator(&self) -> ORelationalOperator {
-        self.operator.clone()
-    }
-
-    /// Get the set constraint service group.
-    /// returns: `Option<Vec<String>>`.
-    pub fn services(&self) -> Option<Vec<String>> {
-        self.service_group.clone()
-    }
-
-    /// Get the set constraint resource.
-    /// returns: `Option<(f64, f64, f64, f64)>`.
-    /// (cpu, mem, disk, net)
-    pub fn resource(&self) -> Option<HashMap<u64, (f64, f64, f64, f64)>> {
-        self.resource.clone()
+    /// returns: `Option<ORelationalOperator>`.
+    pub fn operator(&self) -> Option<ORelationalOperator> {
+        match &self.kind {
+            OConstraintKind::Bounded { operator, .. } => Some(operator.clone()),
+            _ => None,
+        }
     }
 }
 
 impl Display for OConstraint {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let sign = match self.operator {
-            ORelationalOperator::EqualTo => "==",
-            ORelationalOperator::NotEqualTo => "!=",
-            ORelationalOperator::LessOrEqualTo => "<=",
-            ORelationalOperator::LessThan => "<",
-            ORelationalOperator::GreaterOrEqualTo => ">=",
-            ORelationalOperator::GreaterThan => ">",
-        };
-        f.write_fmt(format_args!("{} {} {}", self.name, sign, self.target.unwrap_or(0)))
+        match &self.kind {
+            OConstraintKind::Bounded { operator, target } => {
+                let sign = match operator {
+                    ORelationalOperator::EqualTo => "==",
+                    ORelationalOperator::NotEqualTo => "!=",
+                    ORelationalOperator::LessOrEqualTo => "<=",
+                    ORelationalOperator::LessThan => "<",
+                    ORelationalOperator::GreaterOrEqualTo => ">=",
+                    ORelationalOperator::GreaterThan => ">",
+                };
+                f.write_fmt(format_args!("{} {} {}", self.name, sign, target))
+            }
+            OConstraintKind::Assignment { node } => {
+                f.write_fmt(format_args!("{} on node {}", self.name, node))
+            }
+            OConstraintKind::Group { services } => {
+                f.write_fmt(format_args!("{} on one node for: {:?}", self.name, services))
+            }
+            OConstraintKind::Capacity { available, .. } => {
+                f.write_fmt(format_args!("{} demand <= {:?}", self.name, available))
+            }
+            OConstraintKind::Custom(_) => f.write_fmt(format_args!("{} (custom)", self.name)),
+        }
     }
 }
 
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ServiceGroupOConstraint {
-    /// The constraint name of the service group
-    name: String,
-    /// The relational operator to use to compare a value against the constraint target value.
-    operator: ServiceGroupORelationalOperator,
-    /// The constraint target.
-    service_group: Vec<String>
-}
+    use float_cmp::assert_approx_eq;
 
-impl ServiceGroupOConstraint {
+    use crate::core::{OConstraint, OConstraintValue, ORelationalOperator};
 
-    pub fn new(
-        name: &str,
-        operator: ServiceGroupORelationalOperator,
-        service_group: Vec<String>,
-    ) -> Self {
-        Self {
-            name: name.to_owned(),
-            operator,
-            service_group,
-        }
+    fn scalar(value: f64) -> OConstraintValue {
+        OConstraintValue::Scalar(value)
     }
 
-    pub fn is_met(&self, service_placements: &std::collections::HashMap<String, u64>) -> bool {
-        // Check if all services in a service group are placed on the same node
-
-        let mut node = None;
+    #[test]
+    fn test_is_met() {
+        let c = OConstraint::new("test", ORelationalOperator::EqualTo, 5.2);
+        assert!(c.is_met(&scalar(5.2)));
+        assert!(!c.is_met(&scalar(15.0)));
+
+        let c = OConstraint::new("test", ORelationalOperator::NotEqualTo, 5.2);
+        assert!(!c.is_met(&scalar(5.2)));
+        assert!(c.is_met(&scalar(15.0)));
+
+        let c = OConstraint::new("test", ORelationalOperator::GreaterThan, 5.2);
+        assert!(!c.is_met(&scalar(5.2)));
+        assert!(c.is_met(&scalar(15.0)));
+        assert!(!c.is_met(&scalar(1.0)));
+
+        let c = OConstraint::new("test", ORelationalOperator::GreaterOrEqualTo, 5.2);
+        assert!(c.is_met(&scalar(5.2)));
+        assert!(c.is_met(&scalar(15.0)));
+        assert!(!c.is_met(&scalar(1.0)));
+
+        let c = OConstraint::new("test", ORelationalOperator::LessThan, 5.2);
+        assert!(!c.is_met(&scalar(5.2)));
+        assert!(c.is_met(&scalar(1.0)));
+        assert!(!c.is_met(&scalar(15.0)));
+
+        let c = OConstraint::new("test", ORelationalOperator::LessOrEqualTo, 5.2);
+        assert!(c.is_met(&scalar(5.2)));
+        assert!(!c.is_met(&scalar(15.0)));
+        assert!(c.is_met(&scalar(1.0)));
+
+        let c = OConstraint::new_with_modifiers("test", ORelationalOperator::EqualTo, 5.0, 0.5, 1.0);
+        assert!(c.is_met(&scalar(3.5)));
+
+        // a value of another kind does not meet the constraint
+        assert!(!c.is_met(&OConstraintValue::Node(3)));
+    }
 
-        for group in &self.service_group {
-            if let Some(node_id) = service_placements.get(group) {
-                if let Some(n) = node {
-                    if n != *node_id {
-                        return false;
-                    }
-                } else {
-                    node = Some(*node_id);
-                }
-            } else {
-                return false;
-            }
-        }
-        true
+    #[test]
+    fn test_constraint_violation() {
+        let c = OConstraint::new("test", ORelationalOperator::EqualTo, 5.2);
+        assert_eq!(c.constraint_violation(&scalar(5.2)), 0.0);
+        assert_approx_eq!(f64, c.constraint_violation(&scalar(1.2)), 4.0, epsilon = 0.001);
+        assert_approx_eq!(f64, c.constraint_violation(&scalar(-1.2)), 6.4, epsilon = 0.001);
+
+        let c = OConstraint::new("test", ORelationalOperator::LessOrEqualTo, 5.2);
+        assert_eq!(c.constraint_violation(&scalar(0.0)), 0.0);
+        assert_eq!(c.constraint_violation(&scalar(5.2)), 0.0);
+        assert_approx_eq!(f64, c.constraint_violation(&scalar(9.2)), 4.0, epsilon = 0.001);
+
+        let c = OConstraint::new("test", ORelationalOperator::GreaterOrEqualTo, 5.2);
+        assert_eq!(c.constraint_violation(&scalar(10.0)), 0.0);
+        assert_approx_eq!(f64, c.constraint_violation(&scalar(2.2)), 3.0, epsilon = 0.001);
     }
 
-    pub fn constraint_violation(&self, service_placements: &std::collections::HashMap<String, u64>) -> u64 {
-        if self.is_met(service_placements) {
-            0
-        } else {
-            1
-        }
+    #[test]
+    fn test_assignment_and_group() {
+        let c = OConstraint::assignment("frontend", 2);
+        assert_eq!(c.constraint_violation(&OConstraintValue::Node(2)), 0.0);
+        assert_eq!(c.constraint_violation(&OConstraintValue::Node(3)), 1.0);
+
+        let c = OConstraint::group("g", vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        let nodes = HashMap::from([("a".to_string(), 1), ("b".to_string(), 1), ("c".to_string(), 1)]);
+        assert_eq!(c.constraint_violation(&OConstraintValue::Group(nodes)), 0.0);
+        let nodes = HashMap::from([("a".to_string(), 1), ("b".to_string(), 2), ("c".to_string(), 1)]);
+        assert_eq!(c.constraint_violation(&OConstraintValue::Group(nodes)), 1.0);
     }
-}
 
-impl Display for ServiceGroupOConstraint {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let sign = match self.operator {
-            ServiceGroupORelationalOperator::EqualTo => "==",
-        };
-        f.write_fmt(format_args!("{} {} for groups: {:?}", self.name, sign, self.service_group))
+    #[test]
+    fn test_capacity_and_custom() {
+        let c = OConstraint::capacity("node", [1.0, 100.0, 10.0, 0.0], [2.0, 200.0, 20.0, 0.0]);
+        assert_eq!(c.constraint_violation(&OConstraintValue::Demand([1.0, 50.0, 5.0, 8.0])), 0.0);
+        // half a cpu and 50 MB over: 0.25 + 0.25
+        assert_approx_eq!(
+            f64,
+            c.constraint_violation(&OConstraintValue::Demand([1.5, 150.0, 5.0, 0.0])),
+            0.5,
+            epsilon = 0.001
+        );
+
+        let c = OConstraint::custom("even", |value| match value {
+            OConstraintValue::Scalar(v) => v % 2.0,
+            _ => 1.0,
+        });
+        assert!(c.is_met(&scalar(4.0)));
+        assert_eq!(c.constraint_violation(&scalar(5.0)), 1.0);
     }
 }
-
-// #[cfg(test)]
-// mod test {
-//     use float_cmp::assert_approx_eq;
-
-//     use crate::core::{Constraint, ORelationalOperator};
-
-//     #[test]
-//     fn test_is_met() {
-//         let c = Constraint::new("test", ORelationalOperator::EqualTo, 5.2);
-//         assert!(c.is_met(5.2));
-//         assert!(!c.is_met(15.0));
-
-//         let c = Constraint::new("test", ORelationalOperator::NotEqualTo, 5.2);
-//         assert!(!c.is_met(5.2));
-//         assert!(c.is_met(15.0));
-
-//         let c = Constraint::new("test", ORelationalOperator::GreaterThan, 5.2);
-//         assert!(!c.is_met(5.2));
-//         assert!(c.is_met(15.0));
-//         assert!(!c.is_met(1.0));
-
-//         let c = Constraint::new("test", ORelationalOperator::GreaterOrEqualTo, 5.2);
-//         assert!(c.is_met(5.2));
-//         assert!(c.is_met(15.0));
-//         assert!(!c.is_met(1.0));
-
-//         let c = Constraint::new("test", ORelationalOperator::LessThan, 5.2);
-//         assert!(!c.is_met(5.2));
-//         assert!(c.is_met(1.0));
-//         assert!(!c.is_met(15.0));
-
-//         let c = Constraint::new("test", ORelationalOperator::LessOrEqualTo, 5.2);
-//         assert!(c.is_met(5.2));
-//         assert!(!c.is_met(15.0));
-//         assert!(c.is_met(1.0));
-
-//         let c = Constraint::new_with_modifiers("test", ORelationalOperator::EqualTo, 5.2, 1.0, -1.0);
-//         assert!(c.is_met(4.2));
-
-//         let c = Constraint::new_with_modifiers("test", ORelationalOperator::EqualTo, 5.0, 0.5, 1.0);
-//         assert!(c.is_met(3.5));
-//     }
-
-//     #[test]
-//     fn test_constraint_violation() {
-//         let c = Constraint::new("test", ORelationalOperator::EqualTo, 5.2);
-//         assert_eq!(c.constraint_violation(5.2), 0.0);
-//         assert_eq!(c.constraint_violation(1.2), 4.0);
-//         assert_eq!(c.constraint_violation(-1.2), 6.4);
-
-//         let c = Constraint::new("test", ORelationalOperator::NotEqualTo, 5.2);
-//         assert_eq!(c.constraint_violation(5.2), 1.0);
-//         assert_eq!(c.constraint_violation(1.0), 0.0);
-
-//         let c = Constraint::new("test", ORelationalOperator::LessThan, 5.2);
-//         assert_eq!(c.constraint_violation(0.0), 0.0);
-//         assert_approx_eq!(f64, c.constraint_violation(9.2), 4.0, epsilon = 0.001);
-
-//         let c = Constraint::new("test", ORelationalOperator::GreaterThan, 5.2);
-//         assert_eq!(c.constraint_violation(10.0), 0.0);
-//         assert_approx_eq!(f64, c.constraint_violation(2.2), 3.0, epsilon = 0.001);
-
-//         let c = Constraint::new("test", ORelationalOperator::LessOrEqualTo, 5.2);
-//         assert_eq!(c.constraint_violation(0.0), 0.0);
-//         assert_eq!(c.constraint_violation(5.2), 0.0);
-//         assert_approx_eq!(f64, c.constraint_violation(9.2), 4.0, epsilon = 0.001);
-
-//         let c = Constraint::new("test", ORelationalOperator::GreaterOrEqualTo, 5.2);
-//         assert_eq!(c.constraint_violation(10.0), 0.0);
-//         assert_eq!(c.constraint_violation(5.2), 0.0);
-//         assert_approx_eq!(f64, c.constraint_violation(2.2), 3.0, epsilon = 0.001);
-//     }
-// }
@@ -25,6 +25,8 @@ pub enum OOError {
     WrongVariableTypeWithName(String, String),
     #[error("The min value ({0}) must be strictly smaller than the max value ({1}).")]
     TooLargeLowerBound(String, String),
+    #[error("The subset '{0}' must pick between 1 and {1} distinct choices, not {2}")]
+    WrongSubsetSize(String, usize, usize),
     #[error("The data named {0} is not set on the individual")]
     WrongDataName(String),
     #[error("The data type is not {0}")]
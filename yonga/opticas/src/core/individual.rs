@@ -5,7 +5,7 @@ use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
-use crate::core::{ODataValue, OOError, OProblem, OVariableValue};
+use crate::core::{OConstraintValue, ODataValue, OOError, OProblem, OVariableValue};
 use crate::utils::hasmap_eq_with_nans;
 
 /// An individual in the population containing the problem solution, and the objective and
@@ -50,8 +50,8 @@ pub struct OIndividual {
     problem: Arc<OProblem>,
     /// The value of the problem variables for the individual.
     variable_values: HashMap<String, OVariableValue>,
-    /// The value of the constraints.
-    constraint_values: HashMap<String, (Option<u64>, Option<Vec<HashMap<String, u64>>>, Option<HashMap<u64, (f64, f64, f64, f64)>>)>,
+    /// The value of the constraints, `None` until the constraint is evaluated.
+    constraint_values: HashMap<String, Option<OConstraintValue>>,
     /// The values of the objectives.
     objective_values: HashMap<String, f64>,
     /// Whether the individual has been evaluated and the problem constraint and objective values
@@ -81,12 +81,12 @@ impl PartialEq for OIndividual {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OIndividualExport {
-    /// The value of the constraints.
-    pub constraint_values: HashMap<String, (Option<u64>, Option<Vec<HashMap<String, u64>>>, Option<HashMap<u64, (f64, f64, f64, f64)>>)>,
+    /// The value of the constraints, `None` if the constraint was not evaluated.
+    pub constraint_values: HashMap<String, Option<OConstraintValue>>,
     /// The values of the objectives.
     pub objective_values: HashMap<String, f64>,
     /// The overall amount of violation of the solution constraints.
-    pub constraint_violation: u64,
+    pub constraint_violation: f64,
     /// The value of the problem variables for the individual.
     pub variable_values: HashMap<String, OVariableValue>,
     /// Whether the solution meets all the problem constraints.
@@ -128,9 +128,9 @@ impl OIndividual {
             objective_values.insert(objective_name, f64::NAN);
         }
 
-        let mut constraint_values: HashMap<String, (Option<u64>, Option<Vec<HashMap<String, u64>>>, Option<HashMap<u64, (f64, f64, f64, f64)>>)> = HashMap::new();
+        let mut constraint_values: HashMap<String, Option<OConstraintValue>> = HashMap::new();
         for constraint_name in problem.constraint_names() {
-            constraint_values.insert(constraint_name, (None, None, None));
+            constraint_values.insert(constraint_name, None);
         }
 
         Self {
@@ -226,18 +226,20 @@ impl OIndividual {
     /// * `value`: The value to set.
     ///
     /// returns: `Result<(), OError>`
-    pub(crate) fn update_constraint(&mut self, name: &str, value: (Option<u64>, Option<Vec<HashMap<String, u64>>>, Option<HashMap<u64, (f64, f64, f64, f64)>>)) -> Result<(), OOError> {
+    pub(crate) fn update_constraint(&mut self, name: &str, value: OConstraintValue) -> Result<(), OOError> {
         if !self.constraint_values.contains_key(name) {
             return Err(OOError::NonExistingName(
                 "constraint".to_string(),
                 name.to_string(),
             ));
         }
-        // if value.is_nan() {
-        //     return Err(OError::NaN("constraint".to_string(), name.to_string()));
-        // }
+        if let OConstraintValue::Scalar(value) = value {
+            if value.is_nan() {
+                return Err(OOError::NaN("constraint".to_string(), name.to_string()));
+            }
+        }
         if let Some(x) = self.constraint_values.get_mut(name) {
-            *x = value;
+            *x = Some(value);
         }
         Ok(())
     }
@@ -247,51 +249,23 @@ impl OIndividual {
     /// then the violation is 0.0. Otherwise, a positive number is returned.
     ///
     /// return: `f64`
-    pub fn constraint_violation(&self) -> u64 {
-        // return self
-        //     .problem
-        //     .constraints()
-        //     .iter()
-        //     .map(|(name, c)| c.constraint_violation(self.constraint_values[name]))
-        //     .sum();
-        // return self
-        //     .problem
-        //     .constraint_names()
-        //     .iter()
-        //     .map(|name| {
-        //         let (violation, _) = self.constraint_values[name];
-        //         violation.unwrap_or(0)
-        //     })
-        //     .sum();
-        return self
-            .problem
-            .constraint_names()
+    pub fn constraint_violation(&self) -> f64 {
+        self.problem
+            .constraints()
             .iter()
-            .map(|name| {
-                let (violation, _, _) = self.constraint_values[name];
-                violation.unwrap_or(0)
+            .map(|(name, c)| match &self.constraint_values[name] {
+                Some(value) => c.constraint_violation(value),
+                None => 0.0,
             })
-            .sum();
+            .sum()
     }
 
-    /// Return whether the solution meets all the problem constraints.
+    /// Return whether the solution meets all the problem constraints. A constraint that was not
+    /// evaluated is not checked.
     ///
     /// return: `bool`
     pub fn is_feasible(&self) -> bool {
-        for (name, constraint_value) in self.constraint_values.iter() {
-            if !self.problem.constraint_names().contains(name) {
-                continue;
-            }
-            if !self
-                .problem
-                .get_constraint(name)
-                .unwrap()
-                .is_met(constraint_value.clone())
-            {
-                return false;
-            }
-        }
-        true
+        self.constraint_violation() == 0.0
     }
 
     /// Ge all the variables.
@@ -303,8 +277,8 @@ impl OIndividual {
 
     /// Get all the constraints.
     ///
-    /// returns: `HashMap<String, f64>`
-    pub fn constraints(&self) -> HashMap<String, (Option<u64>, Option<Vec<HashMap<String, u64>>>, Option<HashMap<u64, (f64, f64, f64, f64)>>)> {
+    /// returns: `HashMap<String, Option<OConstraintValue>>`
+    pub fn constraints(&self) -> HashMap<String, Option<OConstraintValue>> {
         self.constraint_values.clone()
     }
 
@@ -350,8 +324,8 @@ impl OIndividual {
     ///
     /// * `name`: The constraint name.
     ///
-    /// returns: `Result<f64, OError>`
-    pub fn get_constraint_value(&self, name: &str) -> Result<(Option<u64>, Option<Vec<HashMap<String, u64>>>, Option<HashMap<u64, (f64, f64, f64, f64)>>), OOError> {
+    /// returns: `Result<Option<OConstraintValue>, OError>`
+    pub fn get_constraint_value(&self, name: &str) -> Result<Option<OConstraintValue>, OOError> {
         if !self.constraint_values.contains_key(name) {
             return Err(OOError::NonExistingName(
                 "constraint".to_string(),
@@ -511,7 +485,9 @@ impl OIndividual {
             ind.update_objective(obj_name, *obj_value)?;
         }
         for (const_name, const_value) in data.constraint_values.iter() {
-            ind.update_constraint(const_name, const_value.clone())?;
+            if let Some(const_value) = const_value {
+                ind.update_constraint(const_name, const_value.clone())?;
+            }
         }
         ind.set_evaluated();
         Ok(ind)
@@ -630,6 +606,32 @@ impl OPopulation {
         Self(population)
     }
 
+    /// Generate a population with a number of individuals equal to `number_of_individuals`, where
+    /// the first individuals take the variable values of the given solutions. The variables a
+    /// solution does not set, and the individuals beyond the solutions, are random. This returns an
+    /// error if a solution sets a variable that does not exist or with a value of the wrong type.
+    ///
+    /// # Arguments
+    ///
+    /// * `problem`: The problem being solved.
+    /// * `number_of_individuals`: The number of individuals to add to the population.
+    /// * `solutions`: The variable values of the seeded individuals.
+    ///
+    /// returns: `Result<Population, OError>`
+    pub fn init_with_solutions(
+        problem: Arc<OProblem>,
+        number_of_individuals: usize,
+        solutions: &[HashMap<String, OVariableValue>],
+    ) -> Result<Self, OOError> {
+        let mut population = Self::init(problem, number_of_individuals);
+        for (individual, solution) in population.0.iter_mut().zip(solutions) {
+            for (name, value) in solution {
+                individual.update_variable(name, value.clone())?;
+            }
+        }
+        Ok(population)
+    }
+
     /// Serialise the individuals for export.
     ///
     /// return: `Vec<IndividualExport>`
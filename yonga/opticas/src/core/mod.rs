@@ -1,10 +1,14 @@
-pub use constraint::{OConstraint, ORelationalOperator, ServiceGroupOConstraint, ServiceGroupORelationalOperator};
+pub use constraint::{OConstraint, OConstraintKind, OConstraintValue, ORelationalOperator, OViolationFn};
 pub use data::ODataValue;
 pub use error::OOError;
 pub use individual::{OIndividual, OIndividualExport, OIndividuals, OIndividualsMut, OPopulation};
 pub use objective::{OObjective, OObjectiveDirection};
 pub use problem::{OEvaluationResult, OEvaluator, OProblem, OProblemExport};
-pub use variable::{OChoice, OVariable, OVariableType, OVariableValue};
+#[cfg(test)]
+pub(crate) use problem::builtin_problems;
+pub use variable::{
+    OBoolean, OBoundedNumber, OChoice, OSubset, OVariable, OVariableType, OVariableValue,
+};
 
 mod constraint;
 mod data;
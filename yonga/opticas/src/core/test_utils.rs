@@ -7,7 +7,7 @@ use float_cmp::approx_eq;
 
 use crate::core::builtin_problems::ZTD1Problem;
 use crate::core::{
-    utils, BoundedNumber, OIndividual, Objective, ObjectiveDirection, Problem, OVariableType,
+    utils, OBoundedNumber, OIndividual, OObjective, OObjectiveDirection, OProblem, OVariableType,
     OVariableValue,
 };
 
@@ -119,7 +119,7 @@ pub(crate) fn read_csv_test_file(
 
 pub(crate) fn individuals_from_obj_values_dummy(
     objective_values: &[Vec<f64>],
-    objective_direction: &[ObjectiveDirection],
+    objective_direction: &[OObjectiveDirection],
     variable_values: Option<&[Vec<f64>]>,
 ) -> Vec<OIndividual> {
     // check lengths
@@ -129,21 +129,21 @@ pub(crate) fn individuals_from_obj_values_dummy(
 
     let mut objectives = Vec::new();
     for (i, direction) in objective_direction.iter().enumerate() {
-        objectives.push(Objective::new(format!("obj{i}").as_str(), *direction));
+        objectives.push(OObjective::new(format!("obj{i}").as_str(), *direction));
     }
     let variables = if let Some(variable_values) = variable_values {
         (0..variable_values.len())
             .map(|i| {
-                OVariableType::Real(BoundedNumber::new(format!("X{i}").as_str(), 0.0, 2.0).unwrap())
+                OVariableType::OReal(OBoundedNumber::new(format!("X{i}").as_str(), 0.0, 2.0).unwrap())
             })
             .collect()
     } else {
-        vec![OVariableType::Real(
-            BoundedNumber::new("X", 0.0, 2.0).unwrap(),
+        vec![OVariableType::OReal(
+            OBoundedNumber::new("X", 0.0, 2.0).unwrap(),
         )]
     };
     let problem =
-        Arc::new(Problem::new(objectives, variables, None, utils::dummy_evaluator()).unwrap());
+        Arc::new(OProblem::new(objectives, variables, None, utils::dummy_evaluator()).unwrap());
 
     // create the individuals
     let mut individuals: Vec<OIndividual> = Vec::new();
@@ -157,7 +157,7 @@ pub(crate) fn individuals_from_obj_values_dummy(
         if let Some(variable_values) = variable_values {
             for (vi, var_value) in variable_values[ind_idx].iter().enumerate() {
                 individual
-                    .update_variable(format!("X{vi}").as_str(), OVariableValue::Real(*var_value))
+                    .update_variable(format!("X{vi}").as_str(), OVariableValue::OReal(*var_value))
                     .unwrap();
             }
         }
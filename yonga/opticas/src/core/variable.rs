@@ -1,7 +1,11 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashSet};
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
 use std::sync::Arc;
-use rand::prelude::IteratorRandom;
+use rand::distributions::uniform::SampleUniform;
+use rand::prelude::{IteratorRandom, SliceRandom};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::core::{OOError, OProblem};
@@ -14,6 +18,122 @@ pub trait OVariable<T>: Display {
     fn name(&self) -> String;
 }
 
+/// A number between a lower and upper bound. This is used for real and integer variables.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OBoundedNumber<N> {
+    /// The variable name.
+    name: String,
+    /// The lower bound.
+    min_value: N,
+    /// The upper bound.
+    max_value: N,
+}
+
+impl<N: SampleUniform + PartialOrd + Display + Clone + Copy> OBoundedNumber<N> {
+    /// Create a new bounded number. This returns an error if the lower bound is not strictly
+    /// smaller than the upper bound.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The variable name.
+    /// * `min_value`: The lower bound.
+    /// * `max_value`: The upper bound.
+    ///
+    /// returns: `Result<OBoundedNumber<N>, OOError>`
+    pub fn new(name: &str, min_value: N, max_value: N) -> Result<Self, OOError> {
+        // a NaN bound cannot be compared either
+        if min_value.partial_cmp(&max_value) != Some(Ordering::Less) {
+            return Err(OOError::TooLargeLowerBound(
+                min_value.to_string(),
+                max_value.to_string(),
+            ));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            min_value,
+            max_value,
+        })
+    }
+
+    /// Get the lower bound.
+    pub fn min_value(&self) -> N {
+        self.min_value
+    }
+
+    /// Get the upper bound.
+    pub fn max_value(&self) -> N {
+        self.max_value
+    }
+
+    /// Get the lower and upper bounds.
+    pub fn bounds(&self) -> (N, N) {
+        (self.min_value, self.max_value)
+    }
+}
+
+impl<N: Display> Display for OBoundedNumber<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Bounded number '{}': [{}, {}]",
+            self.name, self.min_value, self.max_value
+        )
+    }
+}
+
+impl<N: SampleUniform + PartialOrd + Display + Clone + Copy> OVariable<N> for OBoundedNumber<N> {
+    /// Randomly pick a number between the bounds.
+    fn generate(&self) -> N {
+        let mut rng = rand::thread_rng();
+        rng.gen_range(self.min_value..=self.max_value)
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// A boolean variable.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OBoolean {
+    /// The variable name.
+    name: String,
+}
+
+impl OBoolean {
+    /// Create a new boolean variable.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The variable name.
+    ///
+    /// returns: `OBoolean`
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+        }
+    }
+}
+
+impl Display for OBoolean {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Boolean '{}'", self.name)
+    }
+}
+
+impl OVariable<bool> for OBoolean {
+    /// Randomly pick true or false.
+    fn generate(&self) -> bool {
+        let mut rng = rand::thread_rng();
+        rng.gen_bool(0.5)
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
 /// A variable choice.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct OChoice {
@@ -63,12 +183,107 @@ impl OVariable<u64> for OChoice {
     }
 }
 
-/// The types of variables to set on a problem.
+/// A fixed-size subset of a list of choices, such as the distinct nodes of the replicas of a
+/// service.
 #[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum OVariableType {
+pub struct OSubset {
+    /// The variable name.
+    name: String,
+    /// The list of choices.
+    choices: Vec<u64>,
+    /// The number of choices in the subset.
+    size: usize,
+}
+
+impl OSubset {
+    /// Create a new subset variable. This returns an error if the size is 0 or larger than the
+    /// number of distinct choices.
+    ///
+    /// # Arguments
+    ///
+    /// * `name`: The variable name.
+    /// * `choices`: The list of choices.
+    /// * `size`: The number of choices in the subset.
+    ///
+    /// returns: `Result<OSubset, OOError>`
+    pub fn new(name: &str, choices: Vec<u64>, size: usize) -> Result<Self, OOError> {
+        let mut unique = HashSet::new();
+        let choices: Vec<u64> = choices.into_iter().filter(|c| unique.insert(*c)).collect();
+
+        if size == 0 || size > choices.len() {
+            return Err(OOError::WrongSubsetSize(
+                name.to_string(),
+                choices.len(),
+                size,
+            ));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            choices,
+            size,
+        })
+    }
 
+    pub fn choices(&self) -> Vec<u64> {
+        self.choices.clone()
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Check if a value is a subset of the choices of the right size.
+    ///
+    /// # Arguments
+    ///
+    /// * `value`: The subset.
+    ///
+    /// returns: `bool`
+    pub fn is_valid(&self, value: &BTreeSet<u64>) -> bool {
+        value.len() == self.size && value.iter().all(|v| self.choices.contains(v))
+    }
+}
+
+impl Display for OSubset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Subset '{}' of {} from: {:?}",
+            self.name, self.size, self.choices
+        )
+    }
+}
+
+impl OVariable<BTreeSet<u64>> for OSubset {
+    /// Randomly pick `size` distinct choices.
+    fn generate(&self) -> BTreeSet<u64> {
+        let mut rng = rand::thread_rng();
+        self.choices
+            .choose_multiple(&mut rng, self.size)
+            .copied()
+            .collect()
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// The types of variables to set on a problem. The variants are tagged when serialised, since
+/// the values of a real, an integer and a choice variable cannot be told apart otherwise.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum OVariableType {
+    /// A real number between two bounds.
+    OReal(OBoundedNumber<f64>),
+    /// An integer number between two bounds.
+    OInteger(OBoundedNumber<i64>),
+    /// A boolean.
+    OBoolean(OBoolean),
+    /// One of a list of choices.
     OChoice(OChoice),
+    /// A fixed-size subset of a list of choices.
+    OSubset(OSubset),
 }
 
 impl OVariableType {
@@ -77,7 +292,11 @@ impl OVariableType {
     /// returns: `VariableValue`
     pub fn generate_random_value(&self) -> OVariableValue {
         match &self {
+            OVariableType::OReal(v) => OVariableValue::OReal(v.generate()),
+            OVariableType::OInteger(v) => OVariableValue::OInteger(v.generate()),
+            OVariableType::OBoolean(v) => OVariableValue::OBoolean(v.generate()),
             OVariableType::OChoice(v) => OVariableValue::OChoice(v.generate()),
+            OVariableType::OSubset(v) => OVariableValue::OSubset(v.generate()),
         }
     }
 
@@ -86,30 +305,70 @@ impl OVariableType {
     /// return: `String`
     pub fn name(&self) -> String {
         match self {
+            OVariableType::OReal(t) => t.name.clone(),
+            OVariableType::OInteger(t) => t.name.clone(),
+            OVariableType::OBoolean(t) => t.name.clone(),
             OVariableType::OChoice(t) => t.name.clone(),
+            OVariableType::OSubset(t) => t.name.clone(),
         }
     }
 
     pub fn label(&self) -> String {
         let label = match &self {
+            OVariableType::OReal(_) => "real",
+            OVariableType::OInteger(_) => "integer",
+            OVariableType::OBoolean(_) => "boolean",
             OVariableType::OChoice(_) => "choice",
+            OVariableType::OSubset(_) => "subset",
         };
         label.into()
     }
 
+    /// Check if the variable is Real.
+    ///
+    /// return: `bool`
+    pub fn is_real(&self) -> bool {
+        matches!(self, OVariableType::OReal(_))
+    }
+
+    /// Check if the variable is Integer.
+    ///
+    /// return: `bool`
+    pub fn is_integer(&self) -> bool {
+        matches!(self, OVariableType::OInteger(_))
+    }
+
+    /// Check if the variable is Boolean.
+    ///
+    /// return: `bool`
+    pub fn is_boolean(&self) -> bool {
+        matches!(self, OVariableType::OBoolean(_))
+    }
+
     /// Check if the variable is Choice.
     ///
     /// return: `bool`
-    pub(crate) fn is_choice(&self) -> bool {
+    pub fn is_choice(&self) -> bool {
         matches!(self, OVariableType::OChoice(_))
     }
 
+    /// Check if the variable is Subset.
+    ///
+    /// return: `bool`
+    pub fn is_subset(&self) -> bool {
+        matches!(self, OVariableType::OSubset(_))
+    }
+
 }
 
 impl Display for OVariableType {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
+            OVariableType::OReal(v) => write!(f, "{v}").unwrap(),
+            OVariableType::OInteger(v) => write!(f, "{v}").unwrap(),
+            OVariableType::OBoolean(v) => write!(f, "{v}").unwrap(),
             OVariableType::OChoice(v) => write!(f, "{v}").unwrap(),
+            OVariableType::OSubset(v) => write!(f, "{v}").unwrap(),
         };
         Ok(())
     }
@@ -117,17 +376,27 @@ impl Display for OVariableType {
 
 /// The value of a variable to set on an individual.
 #[derive(Clone, Serialize, Deserialize)]
-#[serde(untagged)]
 pub enum OVariableValue {
+    /// The value for a real variable.
+    OReal(f64),
+    /// The value for an integer variable.
+    OInteger(i64),
+    /// The value for a boolean variable.
+    OBoolean(bool),
     /// The value for a choice variable.
     OChoice(u64),
+    /// The value for a subset variable.
+    OSubset(BTreeSet<u64>),
 }
 
 impl PartialEq for OVariableValue {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-
+            (OVariableValue::OReal(s), OVariableValue::OReal(o)) => s == o,
+            (OVariableValue::OInteger(s), OVariableValue::OInteger(o)) => s == o,
+            (OVariableValue::OBoolean(s), OVariableValue::OBoolean(o)) => s == o,
             (OVariableValue::OChoice(s), OVariableValue::OChoice(o)) => s == o,
+            (OVariableValue::OSubset(s), OVariableValue::OSubset(o)) => s == o,
             _ => false,
         }
     }
@@ -145,18 +414,136 @@ impl OVariableValue {
     /// returns: `Result<bool, OError>`
     pub fn match_type(&self, name: &str, problem: Arc<OProblem>) -> Result<bool, OOError> {
         let value = match problem.get_variable(name)? {
+            OVariableType::OReal(_) => matches!(self, OVariableValue::OReal(_)),
+            OVariableType::OInteger(_) => matches!(self, OVariableValue::OInteger(_)),
+            OVariableType::OBoolean(_) => matches!(self, OVariableValue::OBoolean(_)),
             OVariableType::OChoice(_) => matches!(self, OVariableValue::OChoice(_)),
+            OVariableType::OSubset(t) => match self {
+                OVariableValue::OSubset(v) => t.is_valid(v),
+                _ => false,
+            },
         };
         Ok(value)
     }
 
+    /// Get the value if the variable is of real type. This returns an error if the variable is
+    /// not real.
+    ///
+    /// returns: `Result<f64, OError>`
+    pub fn as_real(&self) -> Result<f64, OOError> {
+        match self {
+            OVariableValue::OReal(v) => Ok(*v),
+            _ => Err(OOError::WrongVariableType("real".to_string())),
+        }
+    }
+
+    /// Get the value if the variable is of integer type. This returns an error if the variable
+    /// is not an integer.
+    ///
+    /// returns: `Result<i64, OError>`
+    pub fn as_integer(&self) -> Result<i64, OOError> {
+        match self {
+            OVariableValue::OInteger(v) => Ok(*v),
+            _ => Err(OOError::WrongVariableType("integer".to_string())),
+        }
+    }
+
+    /// Get the value if the variable is of boolean type. This returns an error if the variable
+    /// is not a boolean.
+    ///
+    /// returns: `Result<bool, OError>`
+    pub fn as_boolean(&self) -> Result<bool, OOError> {
+        match self {
+            OVariableValue::OBoolean(v) => Ok(*v),
+            _ => Err(OOError::WrongVariableType("boolean".to_string())),
+        }
+    }
+
+    /// Get the value if the variable is a choice. This returns an error if the variable is not
+    /// a choice.
+    ///
+    /// returns: `Result<u64, OError>`
+    pub fn as_choice(&self) -> Result<u64, OOError> {
+        match self {
+            OVariableValue::OChoice(v) => Ok(*v),
+            _ => Err(OOError::WrongVariableType("choice".to_string())),
+        }
+    }
+
+    /// Get the set of choices if the variable is a subset. This returns an error if the variable
+    /// is not a subset.
+    ///
+    /// returns: `Result<BTreeSet<u64>, OError>`
+    pub fn as_subset(&self) -> Result<BTreeSet<u64>, OOError> {
+        match self {
+            OVariableValue::OSubset(v) => Ok(v.clone()),
+            _ => Err(OOError::WrongVariableType("subset".to_string())),
+        }
+    }
 }
 
 impl Debug for OVariableValue {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
+            OVariableValue::OReal(v) => write!(f, "{v}").unwrap(),
+            OVariableValue::OInteger(v) => write!(f, "{v}").unwrap(),
+            OVariableValue::OBoolean(v) => write!(f, "{v}").unwrap(),
             OVariableValue::OChoice(v) => write!(f, "{v}").unwrap(),
+            OVariableValue::OSubset(v) => write!(f, "{v:?}").unwrap(),
         };
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use crate::core::{OBoundedNumber, OSubset, OVariable, OVariableType, OVariableValue};
+
+    #[test]
+    /// The bounds of a number must be ordered
+    fn test_bounded_number() {
+        assert!(OBoundedNumber::new("x", 1.0, 0.0).is_err());
+        assert!(OBoundedNumber::new("x", 1, 1).is_err());
+        assert!(OBoundedNumber::new("x", f64::NAN, 1.0).is_err());
+
+        let x = OBoundedNumber::new("x", -2, 3).unwrap();
+        for _ in 0..100 {
+            assert!((-2..=3).contains(&x.generate()));
+        }
+    }
+
+    #[test]
+    /// Values serialised with their type are read back with the same type
+    fn test_serialise_value() {
+        for value in [
+            OVariableValue::OReal(1.0),
+            OVariableValue::OInteger(1),
+            OVariableValue::OBoolean(true),
+            OVariableValue::OChoice(1),
+            OVariableValue::OSubset(BTreeSet::from([1, 3])),
+        ] {
+            let data = serde_json::to_string(&value).unwrap();
+            assert_eq!(serde_json::from_str::<OVariableValue>(&data).unwrap(), value);
+        }
+
+        let var = OVariableType::OInteger(OBoundedNumber::new("x", 0, 10).unwrap());
+        let data = serde_json::to_string(&var).unwrap();
+        assert!(serde_json::from_str::<OVariableType>(&data).unwrap().is_integer());
+    }
+
+    #[test]
+    /// A subset picks distinct choices
+    fn test_subset() {
+        assert!(OSubset::new("s", vec![1, 2, 3], 0).is_err());
+        assert!(OSubset::new("s", vec![1, 2, 2], 3).is_err());
+
+        let s = OSubset::new("s", vec![1, 2, 3, 4], 3).unwrap();
+        for _ in 0..100 {
+            assert!(s.is_valid(&s.generate()));
+        }
+        assert!(!s.is_valid(&BTreeSet::from([1, 2])));
+        assert!(!s.is_valid(&BTreeSet::from([1, 2, 5])));
+    }
+}
@@ -328,7 +328,7 @@ mod test {
     use float_cmp::assert_approx_eq;
 
     use crate::core::test_utils::individuals_from_obj_values_dummy;
-    use crate::core::ObjectiveDirection;
+    use crate::core::OObjectiveDirection;
     use crate::metrics::Distance;
 
     #[test]
@@ -343,7 +343,7 @@ mod test {
         ];
         let a = [vec![2., 4.], vec![3., 3.], vec![4., 2.]];
         let b = [vec![2., 8.], vec![4., 4.], vec![8., 2.]];
-        let directions = [ObjectiveDirection::Minimise; 2];
+        let directions = [OObjectiveDirection::OMinimise; 2];
 
         // Column I(A)
         let individuals_a = individuals_from_obj_values_dummy(&a, &directions, None);
@@ -430,7 +430,7 @@ mod test {
         expected_2.insert("hausdorff", 3.6270499);
 
         let expected = [expected_1, expected_2];
-        let directions = [ObjectiveDirection::Minimise; 2];
+        let directions = [OObjectiveDirection::OMinimise; 2];
         for (objective, expected) in [objective_1, objective_2].iter().zip(expected) {
             let individuals = individuals_from_obj_values_dummy(objective, &directions, None);
             let metric = Distance::new(&individuals, &ref_points).unwrap();
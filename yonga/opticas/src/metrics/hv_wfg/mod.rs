@@ -105,7 +105,7 @@ mod test {
     use float_cmp::approx_eq;
 
     use crate::core::test_utils::individuals_from_obj_values_dummy;
-    use crate::core::ObjectiveDirection;
+    use crate::core::OObjectiveDirection;
     use crate::metrics::test_utils::parse_pagmo_test_data_file;
     use crate::metrics::HyperVolumeWhile2012;
 
@@ -119,7 +119,7 @@ mod test {
     fn assert_test_file(file: &str) {
         let all_test_data = parse_pagmo_test_data_file(file).unwrap();
         let obj_count = all_test_data.first().unwrap().reference_point.len();
-        let objective_direction = vec![ObjectiveDirection::Minimise; obj_count];
+        let objective_direction = vec![OObjectiveDirection::OMinimise; obj_count];
 
         for (ti, test_data) in all_test_data.iter().enumerate() {
             let mut individuals = individuals_from_obj_values_dummy(
@@ -109,7 +109,7 @@ mod test {
     use float_cmp::approx_eq;
 
     use crate::core::test_utils::individuals_from_obj_values_dummy;
-    use crate::core::ObjectiveDirection;
+    use crate::core::OObjectiveDirection;
     use crate::metrics::test_utils::parse_pagmo_test_data_file;
     use crate::metrics::HyperVolumeFonseca2006;
 
@@ -117,7 +117,7 @@ mod test {
     /// Reference point must be strictly larger than any objective
     fn test_wrong_ref_point() {
         let objective_values = vec![vec![1.0, 2.0, 1.0], vec![2.0, 1.0, 1.0]];
-        let objective_direction = [ObjectiveDirection::Minimise; 3];
+        let objective_direction = [OObjectiveDirection::OMinimise; 3];
 
         let mut individuals =
             individuals_from_obj_values_dummy(&objective_values, &objective_direction, None);
@@ -133,7 +133,7 @@ mod test {
     /// Test avery simple front
     fn test_simple_front() {
         let objective_values = vec![vec![1.0, 1.0, 1.0], vec![2.0, 2.0, 2.0]];
-        let objective_direction = vec![ObjectiveDirection::Minimise; 3];
+        let objective_direction = vec![OObjectiveDirection::OMinimise; 3];
         let ref_point = [3.0, 3.0, 3.0];
 
         let mut individuals = individuals_from_obj_values_dummy(
@@ -155,7 +155,7 @@ mod test {
     pub(crate) fn assert_test_file(file: &str) {
         let all_test_data = parse_pagmo_test_data_file(file).unwrap();
         let obj_count = all_test_data.first().unwrap().reference_point.len();
-        let objective_direction = vec![ObjectiveDirection::Minimise; obj_count];
+        let objective_direction = vec![OObjectiveDirection::OMinimise; obj_count];
 
         for (ti, test_data) in all_test_data.iter().enumerate() {
             let mut individuals = individuals_from_obj_values_dummy(
@@ -208,67 +208,66 @@ mod test_pareto_constrained_dominance {
 
     use crate::core::utils::dummy_evaluator;
     use crate::core::{
-        BoundedNumber, Constraint, Individual, Objective, ObjectiveDirection, Problem,
-        RelationalOperator, VariableType,
+        OBoundedNumber, OIndividual, OObjective, OObjectiveDirection, OProblem, OVariableType,
     };
     use crate::operators::{
-        BinaryComparisonOperator, ParetoConstrainedDominance, PreferredSolution,
+        OBinaryComparisonOperator, OParetoConstrainedDominance, OPreferredSolution,
     };
 
     #[test]
     /// Test unconstrained problem with one objective
     fn test_unconstrained_solutions_1_objective() {
-        let objectives = vec![Objective::new("obj1", ObjectiveDirection::Minimise)];
-        let variables = vec![VariableType::Real(
-            BoundedNumber::new("X1", 0.0, 2.0).unwrap(),
+        let objectives = vec![OObjective::new("obj1", OObjectiveDirection::OMinimise)];
+        let variables = vec![OVariableType::OReal(
+            OBoundedNumber::new("X1", 0.0, 2.0).unwrap(),
         )];
         let e = dummy_evaluator();
-        let problem = Arc::new(Problem::new(objectives, variables, None, e).unwrap());
+        let problem = Arc::new(OProblem::new(objectives, variables, None, e).unwrap());
 
-        let mut solution1 = Individual::new(problem.clone());
-        let mut solution2 = Individual::new(problem.clone());
+        let mut solution1 = OIndividual::new(problem.clone());
+        let mut solution2 = OIndividual::new(problem.clone());
 
         // Sol 1 dominates
         solution1.update_objective("obj1", 5.0).unwrap();
         solution2.update_objective("obj1", 15.0).unwrap();
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::First
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::First
         );
 
         // Sol 2 dominates
         solution1.update_objective("obj1", 5.0).unwrap();
         solution2.update_objective("obj1", 1.0).unwrap();
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::Second
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::Second
         );
 
         // Both are non dominated
         solution1.update_objective("obj1", 5.0).unwrap();
         solution2.update_objective("obj1", 5.0).unwrap();
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::MutuallyPreferred
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::MutuallyPreferred
         );
 
         // Maximisation problem
-        let objectives = vec![Objective::new("obj1", ObjectiveDirection::Maximise)];
-        let variables = vec![VariableType::Real(
-            BoundedNumber::new("X1", 0.0, 2.0).unwrap(),
+        let objectives = vec![OObjective::new("obj1", OObjectiveDirection::OMaximise)];
+        let variables = vec![OVariableType::OReal(
+            OBoundedNumber::new("X1", 0.0, 2.0).unwrap(),
         )];
         let e = dummy_evaluator();
-        let problem = Arc::new(Problem::new(objectives, variables, None, e).unwrap());
+        let problem = Arc::new(OProblem::new(objectives, variables, None, e).unwrap());
 
-        let mut solution1 = Individual::new(problem.clone());
-        let mut solution2 = Individual::new(problem.clone());
+        let mut solution1 = OIndividual::new(problem.clone());
+        let mut solution2 = OIndividual::new(problem.clone());
 
         // Sol 2 dominates with larger objective
         solution1.update_objective("obj1", 5.0).unwrap();
         solution2.update_objective("obj1", 15.0).unwrap();
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::Second
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::Second
         );
     }
 
@@ -276,17 +275,17 @@ mod test_pareto_constrained_dominance {
     /// Test unconstrained problem with two objectives
     fn test_unconstrained_solutions_2_objectives() {
         let objectives = vec![
-            Objective::new("obj1", ObjectiveDirection::Minimise),
-            Objective::new("obj2", ObjectiveDirection::Minimise),
+            OObjective::new("obj1", OObjectiveDirection::OMinimise),
+            OObjective::new("obj2", OObjectiveDirection::OMinimise),
         ];
-        let variables = vec![VariableType::Real(
-            BoundedNumber::new("X1", 0.0, 2.0).unwrap(),
+        let variables = vec![OVariableType::OReal(
+            OBoundedNumber::new("X1", 0.0, 2.0).unwrap(),
         )];
         let e = dummy_evaluator();
-        let problem = Arc::new(Problem::new(objectives, variables, None, e).unwrap());
+        let problem = Arc::new(OProblem::new(objectives, variables, None, e).unwrap());
 
-        let mut solution1 = Individual::new(problem.clone());
-        let mut solution2 = Individual::new(problem.clone());
+        let mut solution1 = OIndividual::new(problem.clone());
+        let mut solution2 = OIndividual::new(problem.clone());
 
         // Sol 1 dominates
         solution1.update_objective("obj1", 5.0).unwrap();
@@ -294,8 +293,8 @@ mod test_pareto_constrained_dominance {
         solution2.update_objective("obj1", 15.0).unwrap();
         solution2.update_objective("obj2", 25.0).unwrap();
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::First
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::First
         );
 
         // Sol 2 dominates
@@ -304,8 +303,8 @@ mod test_pareto_constrained_dominance {
         solution2.update_objective("obj1", -15.0).unwrap();
         solution2.update_objective("obj2", -25.0).unwrap();
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::Second
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::Second
         );
 
         // Obj1 of Sol 1 dominates and Obj2 of Sol 2 dominates
@@ -314,12 +313,12 @@ mod test_pareto_constrained_dominance {
         solution2.update_objective("obj1", 15.0).unwrap();
         solution2.update_objective("obj2", 25.0).unwrap();
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::MutuallyPreferred
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::MutuallyPreferred
         );
 
         // compare three solutions
-        let mut solution3 = Individual::new(problem.clone());
+        let mut solution3 = OIndividual::new(problem.clone());
         solution1.update_objective("obj1", 0.0).unwrap();
         solution1.update_objective("obj2", 0.0).unwrap();
         solution2.update_objective("obj1", 1.0).unwrap();
@@ -328,21 +327,21 @@ mod test_pareto_constrained_dominance {
         solution3.update_objective("obj2", 1.0).unwrap();
 
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::First
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::First
         );
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution2, &solution1).unwrap(),
-            PreferredSolution::Second
+            OParetoConstrainedDominance::compare(&solution2, &solution1).unwrap(),
+            OPreferredSolution::Second
         );
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution3).unwrap(),
-            PreferredSolution::First
+            OParetoConstrainedDominance::compare(&solution1, &solution3).unwrap(),
+            OPreferredSolution::First
         );
         // mutually dominated for obj1, but obj2 of sol1 dominates
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution3, &solution1).unwrap(),
-            PreferredSolution::Second
+            OParetoConstrainedDominance::compare(&solution3, &solution1).unwrap(),
+            OPreferredSolution::Second
         );
 
         // non-dominance
@@ -353,43 +352,43 @@ mod test_pareto_constrained_dominance {
         solution3.update_objective("obj1", 1.0).unwrap();
         solution3.update_objective("obj2", 0.0).unwrap();
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::MutuallyPreferred
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::MutuallyPreferred
         );
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution2, &solution1).unwrap(),
-            PreferredSolution::MutuallyPreferred
+            OParetoConstrainedDominance::compare(&solution2, &solution1).unwrap(),
+            OPreferredSolution::MutuallyPreferred
         );
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution2, &solution3).unwrap(),
-            PreferredSolution::MutuallyPreferred
+            OParetoConstrainedDominance::compare(&solution2, &solution3).unwrap(),
+            OPreferredSolution::MutuallyPreferred
         );
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution3, &solution2).unwrap(),
-            PreferredSolution::MutuallyPreferred
+            OParetoConstrainedDominance::compare(&solution3, &solution2).unwrap(),
+            OPreferredSolution::MutuallyPreferred
         );
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution3).unwrap(),
-            PreferredSolution::MutuallyPreferred
+            OParetoConstrainedDominance::compare(&solution1, &solution3).unwrap(),
+            OPreferredSolution::MutuallyPreferred
         );
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution3, &solution1).unwrap(),
-            PreferredSolution::MutuallyPreferred
+            OParetoConstrainedDominance::compare(&solution3, &solution1).unwrap(),
+            OPreferredSolution::MutuallyPreferred
         );
 
         // Maximisation problem
         let objectives = vec![
-            Objective::new("obj1", ObjectiveDirection::Minimise),
-            Objective::new("obj2", ObjectiveDirection::Maximise),
+            OObjective::new("obj1", OObjectiveDirection::OMinimise),
+            OObjective::new("obj2", OObjectiveDirection::OMaximise),
         ];
-        let variables = vec![VariableType::Real(
-            BoundedNumber::new("X1", 0.0, 2.0).unwrap(),
+        let variables = vec![OVariableType::OReal(
+            OBoundedNumber::new("X1", 0.0, 2.0).unwrap(),
         )];
         let e = dummy_evaluator();
-        let problem = Arc::new(Problem::new(objectives, variables, None, e).unwrap());
+        let problem = Arc::new(OProblem::new(objectives, variables, None, e).unwrap());
 
-        let mut solution1 = Individual::new(problem.clone());
-        let mut solution2 = Individual::new(problem.clone());
+        let mut solution1 = OIndividual::new(problem.clone());
+        let mut solution2 = OIndividual::new(problem.clone());
 
         // Neither dominates
         solution1.update_objective("obj1", 5.0).unwrap();
@@ -397,8 +396,8 @@ mod test_pareto_constrained_dominance {
         solution1.update_objective("obj2", 5.0).unwrap();
         solution2.update_objective("obj2", 15.0).unwrap();
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::MutuallyPreferred
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::MutuallyPreferred
         );
 
         // Sol 2 dominates
@@ -407,8 +406,8 @@ mod test_pareto_constrained_dominance {
         solution2.update_objective("obj1", 1.0).unwrap();
         solution2.update_objective("obj2", 15.0).unwrap();
         assert_eq!(
-            ParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
-            PreferredSolution::Second
+            OParetoConstrainedDominance::compare(&solution1, &solution2).unwrap(),
+            OPreferredSolution::Second
         );
     }
 
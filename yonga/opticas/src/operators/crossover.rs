@@ -1,3 +1,5 @@
+use std::collections::BTreeSet;
+
 use rand::prelude::SliceRandom;
 use rand::{Rng, RngCore};
 use serde::{Deserialize, Serialize};
@@ -63,7 +65,8 @@ impl Default for OSimulatedBinaryCrossoverArgs {
     }
 }
 
-/// Simulated Binary Crossover (SBX) operator for bounded real or integer variables.
+/// Simulated Binary Crossover (SBX) operator for bounded real or integer variables and choices.
+/// Boolean variables are exchanged between the children instead.
 ///
 /// Implemented based on:
 /// > Kalyanmoy Deb, Karthik Sindhya, and Tatsuya Okabe. 2007. Self-adaptive
@@ -266,6 +269,32 @@ impl OSimulatedBinaryCrossover {
     }
 }
 
+/// Truncate a real number to one of the two closest integers with the same probability, and
+/// clamp it to the variable bounds. An integer is returned unchanged. This is the truncation
+/// procedure in section 2.4 of Deep et al. (2009).
+///
+/// # Arguments
+///
+/// * `value`: The real number to truncate.
+/// * `y_lower`: The variable lower bound.
+/// * `y_upper`: The variable upper bound.
+/// * `rng`: The random number generator reference.
+///
+/// returns: `i64`
+pub(crate) fn truncate_integer(
+    value: f64,
+    y_lower: i64,
+    y_upper: i64,
+    rng: &mut dyn RngCore,
+) -> i64 {
+    let value = if value.fract() == 0.0 || rng.gen_bool(0.5) {
+        value.floor()
+    } else {
+        value.floor() + 1.0
+    };
+    (value as i64).clamp(y_lower, y_upper)
+}
+
 impl OCrossover for OSimulatedBinaryCrossover {
     fn generate_offsprings(
         &self,
@@ -277,18 +306,6 @@ impl OCrossover for OSimulatedBinaryCrossover {
         let mut child2 = parent2.clone_variables();
         let problem = parent1.problem();
 
-        // Return error if variable is not a number or choice
-        if !problem
-            .variables()
-            .iter()
-            .all(|(_, v)| v.is_choice())
-        {
-            return Err(OOError::CrossoverOperator(
-                "SBX".to_string(),
-                "The SBX operator only works with real, integer, or choice variables".to_string(),
-            ));
-        }
-
         // Do not apply crossover if probability is not reached
         if rng.gen_range(0.0..=1.0) <= self.crossover_probability {
             for (var_name, var_type) in problem.variables() {
@@ -303,7 +320,47 @@ impl OCrossover for OSimulatedBinaryCrossover {
                 let v2 = parent2.get_variable_value(&var_name)?;
 
                 match (&v1, &v2, var_type) {
-
+                    (
+                        OVariableValue::OReal(v1),
+                        OVariableValue::OReal(v2),
+                        OVariableType::OReal(vt),
+                    ) => {
+                        let (y_lower, y_upper) = vt.bounds();
+                        if let Some((new_v1, new_v2)) =
+                            self.crossover_variables(*v1, *v2, y_lower, y_upper, rng)
+                        {
+                            child1.update_variable(&var_name, OVariableValue::OReal(new_v1))?;
+                            child2.update_variable(&var_name, OVariableValue::OReal(new_v2))?;
+                        }
+                    }
+                    (
+                        OVariableValue::OInteger(v1),
+                        OVariableValue::OInteger(v2),
+                        OVariableType::OInteger(vt),
+                    ) => {
+                        let (y_lower, y_upper) = vt.bounds();
+                        if let Some((new_v1, new_v2)) = self.crossover_variables(
+                            *v1 as f64,
+                            *v2 as f64,
+                            y_lower as f64,
+                            y_upper as f64,
+                            rng,
+                        ) {
+                            let new_v1 = truncate_integer(new_v1, y_lower, y_upper, rng);
+                            let new_v2 = truncate_integer(new_v2, y_lower, y_upper, rng);
+                            child1.update_variable(&var_name, OVariableValue::OInteger(new_v1))?;
+                            child2.update_variable(&var_name, OVariableValue::OInteger(new_v2))?;
+                        }
+                    }
+                    (
+                        OVariableValue::OBoolean(v1),
+                        OVariableValue::OBoolean(v2),
+                        OVariableType::OBoolean(_),
+                    ) => {
+                        // Uniform crossover: the children swap the parent values
+                        child1.update_variable(&var_name, OVariableValue::OBoolean(*v2))?;
+                        child2.update_variable(&var_name, OVariableValue::OBoolean(*v1))?;
+                    }
                     (
                         OVariableValue::OChoice(v1),
                         OVariableValue::OChoice(v2),
@@ -337,6 +394,25 @@ impl OCrossover for OSimulatedBinaryCrossover {
                             }
                         }
                     }
+                    (
+                        OVariableValue::OSubset(v1),
+                        OVariableValue::OSubset(v2),
+                        OVariableType::OSubset(vt),
+                    ) => {
+                        // Each child keeps the choices both parents share and samples the rest
+                        // from the union of the parents
+                        let shared: BTreeSet<u64> = v1.intersection(v2).copied().collect();
+                        let others: Vec<u64> = v1.symmetric_difference(v2).copied().collect();
+                        let missing = vt.size() - shared.len();
+
+                        let mut new_v1 = shared.clone();
+                        new_v1.extend(others.choose_multiple(rng, missing));
+                        let mut new_v2 = shared;
+                        new_v2.extend(others.choose_multiple(rng, missing));
+
+                        child1.update_variable(&var_name, OVariableValue::OSubset(new_v1))?;
+                        child2.update_variable(&var_name, OVariableValue::OSubset(new_v2))?;
+                    }
                     _ => continue, // Skip non-matching variable types
                 }
             }
@@ -348,31 +424,32 @@ impl OCrossover for OSimulatedBinaryCrossover {
 
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeSet;
     use std::sync::Arc;
 
     use crate::core::utils::{dummy_evaluator, get_rng};
     use crate::core::{
-        BoundedNumber, Individual, Objective, ObjectiveDirection, Problem, VariableType,
-        VariableValue,
+        OBoolean, OBoundedNumber, OIndividual, OObjective, OObjectiveDirection, OProblem,
+        OSubset, OVariableType, OVariableValue,
     };
-    use crate::operators::{Crossover, SimulatedBinaryCrossover, SimulatedBinaryCrossoverArgs};
+    use crate::operators::{OCrossover, OSimulatedBinaryCrossover, OSimulatedBinaryCrossoverArgs};
 
     #[test]
     /// Check that the input arguments to SBX operator are valid.
     fn test_new_sbx_panic() {
-        assert!(SimulatedBinaryCrossover::new(SimulatedBinaryCrossoverArgs {
+        assert!(OSimulatedBinaryCrossover::new(OSimulatedBinaryCrossoverArgs {
             distribution_index: -2.0,
             crossover_probability: 1.0,
             variable_probability: 0.5,
         })
         .is_err());
-        assert!(SimulatedBinaryCrossover::new(SimulatedBinaryCrossoverArgs {
+        assert!(OSimulatedBinaryCrossover::new(OSimulatedBinaryCrossoverArgs {
             distribution_index: 1.0,
             crossover_probability: 2.0,
             variable_probability: 0.5,
         })
         .is_err());
-        assert!(SimulatedBinaryCrossover::new(SimulatedBinaryCrossoverArgs {
+        assert!(OSimulatedBinaryCrossover::new(OSimulatedBinaryCrossoverArgs {
             distribution_index: 1.0,
             crossover_probability: 1.0,
             variable_probability: -0.5,
@@ -383,35 +460,45 @@ mod test {
     #[test]
     /// Test that the SBX operator generates variables
     fn test_sbx_crossover() {
-        let objectives = vec![Objective::new("obj1", ObjectiveDirection::Minimise)];
+        let objectives = vec![OObjective::new("obj1", OObjectiveDirection::OMinimise)];
 
         let variables = vec![
-            VariableType::Real(BoundedNumber::new("var1", 0.0, 1000.0).unwrap()),
-            VariableType::Integer(BoundedNumber::new("var2", -10, 20).unwrap()),
+            OVariableType::OReal(OBoundedNumber::new("var1", 0.0, 1000.0).unwrap()),
+            OVariableType::OInteger(OBoundedNumber::new("var2", -10, 20).unwrap()),
+            OVariableType::OBoolean(OBoolean::new("var3")),
+            OVariableType::OSubset(OSubset::new("var4", vec![1, 2, 3, 4, 5], 3).unwrap()),
         ];
 
         let problem =
-            Arc::new(Problem::new(objectives, variables, None, dummy_evaluator()).unwrap());
+            Arc::new(OProblem::new(objectives, variables, None, dummy_evaluator()).unwrap());
 
         // add new individuals
-        let mut a = Individual::new(problem.clone());
-        a.update_variable("var1", VariableValue::Real(0.2)).unwrap();
-        a.update_variable("var2", VariableValue::Integer(0))
+        let mut a = OIndividual::new(problem.clone());
+        a.update_variable("var1", OVariableValue::OReal(0.2)).unwrap();
+        a.update_variable("var2", OVariableValue::OInteger(0))
             .unwrap();
-        let mut b = Individual::new(problem.clone());
-        b.update_variable("var1", VariableValue::Real(0.8)).unwrap();
-        b.update_variable("var2", VariableValue::Integer(3))
+        a.update_variable("var3", OVariableValue::OBoolean(true))
+            .unwrap();
+        a.update_variable("var4", OVariableValue::OSubset(BTreeSet::from([1, 2, 3])))
+            .unwrap();
+        let mut b = OIndividual::new(problem.clone());
+        b.update_variable("var1", OVariableValue::OReal(0.8)).unwrap();
+        b.update_variable("var2", OVariableValue::OInteger(3))
+            .unwrap();
+        b.update_variable("var3", OVariableValue::OBoolean(false))
+            .unwrap();
+        b.update_variable("var4", OVariableValue::OSubset(BTreeSet::from([2, 4, 5])))
             .unwrap();
 
         // crossover
-        let parameters = SimulatedBinaryCrossoverArgs {
+        let parameters = OSimulatedBinaryCrossoverArgs {
             // ensure different variable value (with integers)
             distribution_index: 1.0,
             crossover_probability: 1.0,
             // always force crossover
             variable_probability: 1.0,
         };
-        let sbx = SimulatedBinaryCrossover::new(parameters).unwrap();
+        let sbx = OSimulatedBinaryCrossover::new(parameters).unwrap();
         // seed 1 to try reproducing test results
         let mut rng = get_rng(Some(1));
         let out = sbx.generate_offsprings(&a, &b, &mut rng).unwrap();
@@ -419,19 +506,28 @@ mod test {
         // Crossover always performed because variable_probability is 1
         assert_ne!(
             *out.child1.get_variable_value("var1").unwrap(),
-            VariableValue::Real(0.2)
-        );
-        assert_ne!(
-            *out.child1.get_variable_value("var2").unwrap(),
-            VariableValue::Integer(0)
+            OVariableValue::OReal(0.2)
         );
+        assert!((-10..=20).contains(&out.child1.get_variable_value("var2").unwrap().as_integer().unwrap()));
         assert_ne!(
             *out.child2.get_variable_value("var1").unwrap(),
-            VariableValue::Real(0.8)
+            OVariableValue::OReal(0.8)
         );
-        assert_ne!(
-            *out.child2.get_variable_value("var2").unwrap(),
-            VariableValue::Integer(3)
+        assert!((-10..=20).contains(&out.child2.get_variable_value("var2").unwrap().as_integer().unwrap()));
+        // the booleans are swapped
+        assert_eq!(
+            *out.child1.get_variable_value("var3").unwrap(),
+            OVariableValue::OBoolean(false)
         );
+        assert_eq!(
+            *out.child2.get_variable_value("var3").unwrap(),
+            OVariableValue::OBoolean(true)
+        );
+        // the subsets keep the shared choice and their size
+        for child in [&out.child1, &out.child2] {
+            let subset = child.get_variable_value("var4").unwrap().as_subset().unwrap();
+            assert_eq!(subset.len(), 3);
+            assert!(subset.contains(&2));
+        }
     }
 }
@@ -1,7 +1,9 @@
+use rand::prelude::SliceRandom;
 use rand::{Rng, RngCore};
 use serde::{Deserialize, Serialize};
 
 use crate::core::{OIndividual, OOError, OProblem, OVariableType, OVariableValue};
+use crate::operators::crossover::truncate_integer;
 
 /// The trait to implement a mutation operator to modify the genetic material of an individual.
 pub trait OMutation {
@@ -33,7 +35,7 @@ pub struct OPolynomialMutationArgs {
 impl OPolynomialMutationArgs {
     /// Initialise the Polynomial mutation (PM) operator with the default parameters. With a
     /// distribution index or index parameter of `20` and variable probability equal `1` divided by
-    /// the number of real variables in the problem (i.e. each variable will have the same
+    /// the number of variables in the problem (i.e. each variable will have the same
     /// probability of being mutated).
     ///
     /// # Arguments
@@ -42,12 +44,8 @@ impl OPolynomialMutationArgs {
     ///
     /// returns: `Self`
     pub fn default(problem: &OProblem) -> Self {
-        let num_real_vars = problem
-            .variables()
-            .iter()
-            .filter(|(_, v)| v.is_choice())
-            .count() as f64;
-        let variable_probability = 1.0 / num_real_vars;
+        let num_vars = problem.number_of_variables() as f64;
+        let variable_probability = 1.0 / num_vars;
         Self {
             index_parameter: 20.0,
             variable_probability,
@@ -55,7 +53,8 @@ impl OPolynomialMutationArgs {
     }
 }
 
-/// The Polynomial mutation (PM) operator.
+/// The Polynomial mutation (PM) operator for bounded real or integer variables. Boolean variables
+/// are flipped and choices are replaced by a random choice.
 ///
 /// Adapted from [Deb & Deb (2014)](https://dl.acm.org/doi/10.1504/IJAISC.2014.059280), full
 /// text available at <https://www.egr.msu.edu/~kdeb/papers/k2012016.pdf>.
@@ -194,23 +193,25 @@ impl OMutation for OPolynomialMutation {
         let mut mutated_individual = individual.clone_variables();
         let problem = individual.problem();
 
-        // Check that all variables are either real, integer, or Choice
-        if !problem
-            .variables()
-            .iter()
-            .all(|(_, v)| v.is_choice()) // Ensure Choice support
-        {
-            return Err(OOError::CrossoverOperator(
-                "PolynomialMutation".to_string(),
-                "The PM operator only works with OChoice variables".to_string(),
-            ));
-        }
-
         for (var_name, var_type) in problem.variables() {
             if rng.gen_range(0.0..=1.0) <= self.variable_probability {
                 let value = individual.get_variable_value(&var_name)?;
                 match (value, var_type) {
-
+                    (OVariableValue::OReal(y), OVariableType::OReal(vt)) => {
+                        let (y_lower, y_upper) = vt.bounds();
+                        let new_y = self.mutate_variable(*y, y_lower, y_upper, rng);
+                        mutated_individual.update_variable(&var_name, OVariableValue::OReal(new_y))?;
+                    }
+                    (OVariableValue::OInteger(y), OVariableType::OInteger(vt)) => {
+                        let (y_lower, y_upper) = vt.bounds();
+                        let new_y = self.mutate_variable(*y as f64, y_lower as f64, y_upper as f64, rng);
+                        let new_y = truncate_integer(new_y, y_lower, y_upper, rng);
+                        mutated_individual.update_variable(&var_name, OVariableValue::OInteger(new_y))?;
+                    }
+                    (OVariableValue::OBoolean(y), OVariableType::OBoolean(_)) => {
+                        // flip the bit
+                        mutated_individual.update_variable(&var_name, OVariableValue::OBoolean(!y))?;
+                    }
                     (OVariableValue::OChoice(_z), OVariableType::OChoice(choice)) => {
                         let choices = choice.choices();
                         let idx = rng.gen_range(0..choices.len());
@@ -218,6 +219,22 @@ impl OMutation for OPolynomialMutation {
                         mutated_individual.update_variable(&var_name, OVariableValue::OChoice(choices[idx].clone()))?;
 
                     }
+                    (OVariableValue::OSubset(z), OVariableType::OSubset(subset)) => {
+                        // swap one member for a choice outside the subset
+                        let outside: Vec<u64> = subset
+                            .choices()
+                            .into_iter()
+                            .filter(|c| !z.contains(c))
+                            .collect();
+                        let member = z.iter().copied().collect::<Vec<u64>>().choose(rng).copied();
+
+                        if let (Some(member), Some(new_member)) = (member, outside.choose(rng)) {
+                            let mut new_z = z.clone();
+                            new_z.remove(&member);
+                            new_z.insert(*new_member);
+                            mutated_individual.update_variable(&var_name, OVariableValue::OSubset(new_z))?;
+                        }
+                    }
 
                     _ => {} // Handle unsupported types if necessary
                 }
@@ -230,56 +247,64 @@ impl OMutation for OPolynomialMutation {
 
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeSet;
     use std::sync::Arc;
 
     use crate::core::utils::{dummy_evaluator, get_rng};
     use crate::core::{
-        BoundedNumber, Individual, Objective, ObjectiveDirection, Problem, OVariableType,
-        OVariableValue,
+        OBoolean, OBoundedNumber, OIndividual, OObjective, OObjectiveDirection, OProblem,
+        OSubset, OVariableType, OVariableValue,
     };
-    use crate::operators::{Mutation, PolynomialMutation, PolynomialMutationArgs};
+    use crate::operators::{OMutation, OPolynomialMutation, OPolynomialMutationArgs};
 
     #[test]
     /// Test that the PM operator mutates variables
-    fn test_sbx_crossover() {
-        let objectives = vec![Objective::new("obj1", ObjectiveDirection::Minimise)];
+    fn test_pm_mutation() {
+        let objectives = vec![OObjective::new("obj1", OObjectiveDirection::OMinimise)];
 
         let variables = vec![
-            OVariableType::Real(BoundedNumber::new("var1", 0.0, 1000.0).unwrap()),
-            OVariableType::Integer(BoundedNumber::new("var2", -10, 20).unwrap()),
+            OVariableType::OReal(OBoundedNumber::new("var1", 0.0, 1000.0).unwrap()),
+            OVariableType::OInteger(OBoundedNumber::new("var2", -10, 20).unwrap()),
+            OVariableType::OBoolean(OBoolean::new("var3")),
+            OVariableType::OSubset(OSubset::new("var4", vec![1, 2, 3, 4, 5], 3).unwrap()),
         ];
 
         let problem =
-            Arc::new(Problem::new(objectives, variables, None, dummy_evaluator()).unwrap());
+            Arc::new(OProblem::new(objectives, variables, None, dummy_evaluator()).unwrap());
 
         // add new individuals
-        let mut a = Individual::new(problem.clone());
-        a.update_variable("var1", OVariableValue::Real(0.2)).unwrap();
-        a.update_variable("var2", OVariableValue::Integer(0))
+        let mut a = OIndividual::new(problem.clone());
+        a.update_variable("var1", OVariableValue::OReal(0.2)).unwrap();
+        a.update_variable("var2", OVariableValue::OInteger(0))
+            .unwrap();
+        a.update_variable("var3", OVariableValue::OBoolean(true))
             .unwrap();
-        let mut b = Individual::new(problem.clone());
-        b.update_variable("var1", OVariableValue::Real(0.8)).unwrap();
-        b.update_variable("var2", OVariableValue::Integer(3))
+        a.update_variable("var4", OVariableValue::OSubset(BTreeSet::from([1, 2, 3])))
             .unwrap();
 
-        let args = PolynomialMutationArgs {
+        let args = OPolynomialMutationArgs {
             // ensure different variable value (with integers)
             index_parameter: 1.0,
             // always force mutation
             variable_probability: 1.0,
         };
-        let pm = PolynomialMutation::new(args).unwrap();
+        let pm = OPolynomialMutation::new(args).unwrap();
         let mut rng = get_rng(Some(1));
         let mutated_offspring = pm.mutate_offspring(&a, &mut rng).unwrap();
 
         // Mutation always performed because variable_probability is 1
         assert_ne!(
             *mutated_offspring.get_variable_value("var1").unwrap(),
-            OVariableValue::Real(0.2)
+            OVariableValue::OReal(0.2)
         );
-        assert_ne!(
-            *mutated_offspring.get_variable_value("var2").unwrap(),
-            OVariableValue::Integer(0)
+        assert!((-10..=20).contains(&mutated_offspring.get_variable_value("var2").unwrap().as_integer().unwrap()));
+        assert_eq!(
+            *mutated_offspring.get_variable_value("var3").unwrap(),
+            OVariableValue::OBoolean(false)
         );
+        // one member of the subset is swapped
+        let subset = mutated_offspring.get_variable_value("var4").unwrap().as_subset().unwrap();
+        assert_eq!(subset.len(), 3);
+        assert_eq!(subset.intersection(&BTreeSet::from([1, 2, 3])).count(), 2);
     }
 }
@@ -160,7 +160,7 @@ pub fn fast_non_dominated_sort(
 #[cfg(test)]
 mod test {
     use crate::core::test_utils::individuals_from_obj_values_dummy;
-    use crate::core::{DataValue, ObjectiveDirection};
+    use crate::core::{ODataValue, OObjectiveDirection};
     use crate::utils::fast_non_dominated_sort;
     use crate::utils::fast_non_dominated_sort::RANK_KEY;
 
@@ -183,7 +183,7 @@ mod test {
         ];
         let mut individuals = individuals_from_obj_values_dummy(
             &objectives,
-            &[ObjectiveDirection::Minimise, ObjectiveDirection::Minimise],
+            &[OObjectiveDirection::OMinimise, OObjectiveDirection::OMinimise],
             None,
         );
         let result = fast_non_dominated_sort(&mut individuals, false).unwrap();
@@ -196,7 +196,7 @@ mod test {
         for idx in &expected_first {
             assert_eq!(
                 individuals[*idx].get_data(RANK_KEY).unwrap(),
-                DataValue::Integer(1)
+                ODataValue::Integer(1)
             );
         }
 
@@ -206,7 +206,7 @@ mod test {
         for idx in expected_second {
             assert_eq!(
                 individuals[idx].get_data(RANK_KEY).unwrap(),
-                DataValue::Integer(2)
+                ODataValue::Integer(2)
             );
         }
 
@@ -215,7 +215,7 @@ mod test {
         for idx in expected_third {
             assert_eq!(
                 individuals[idx].get_data(RANK_KEY).unwrap(),
-                DataValue::Integer(3)
+                ODataValue::Integer(3)
             );
         }
 
@@ -245,7 +245,7 @@ mod test {
         ];
         let mut individuals = individuals_from_obj_values_dummy(
             &objectives,
-            &[ObjectiveDirection::Maximise, ObjectiveDirection::Minimise],
+            &[OObjectiveDirection::OMaximise, OObjectiveDirection::OMinimise],
             None,
         );
         let result = fast_non_dominated_sort(&mut individuals, false).unwrap();
@@ -258,7 +258,7 @@ mod test {
         for idx in &expected_first {
             assert_eq!(
                 individuals[*idx].get_data(RANK_KEY).unwrap(),
-                DataValue::Integer(1)
+                ODataValue::Integer(1)
             );
         }
 
@@ -281,7 +281,7 @@ mod test {
         ];
         let mut individuals = individuals_from_obj_values_dummy(
             &objectives,
-            &[ObjectiveDirection::Minimise, ObjectiveDirection::Maximise],
+            &[OObjectiveDirection::OMinimise, OObjectiveDirection::OMaximise],
             None,
         );
         let result = fast_non_dominated_sort(&mut individuals, false).unwrap();
@@ -294,7 +294,7 @@ mod test {
         for idx in &expected_first {
             assert_eq!(
                 individuals[*idx].get_data(RANK_KEY).unwrap(),
-                DataValue::Integer(1)
+                ODataValue::Integer(1)
             );
         }
 
@@ -316,9 +316,9 @@ mod test {
         let mut individuals = individuals_from_obj_values_dummy(
             &objectives,
             &[
-                ObjectiveDirection::Minimise,
-                ObjectiveDirection::Minimise,
-                ObjectiveDirection::Minimise,
+                OObjectiveDirection::OMinimise,
+                OObjectiveDirection::OMinimise,
+                OObjectiveDirection::OMinimise,
             ],
             None,
         );
@@ -332,7 +332,7 @@ mod test {
         for idx in &expected_first {
             assert_eq!(
                 individuals[*idx].get_data(RANK_KEY).unwrap(),
-                DataValue::Integer(1)
+                ODataValue::Integer(1)
             );
         }
 
@@ -342,7 +342,7 @@ mod test {
         for idx in expected_second {
             assert_eq!(
                 individuals[idx].get_data(RANK_KEY).unwrap(),
-                DataValue::Integer(2)
+                ODataValue::Integer(2)
             );
         }
 
@@ -351,7 +351,7 @@ mod test {
         for idx in expected_third {
             assert_eq!(
                 individuals[idx].get_data(RANK_KEY).unwrap(),
-                DataValue::Integer(3)
+                ODataValue::Integer(3)
             );
         }
 
@@ -0,0 +1,451 @@
+use std::collections::{HashMap, HashSet};
+use serde::Deserialize;
+
+use crate::strategy::{self, PlacementMap, ReplicaNodes};
+use crate::utility::{Config, Node, Resource, Service};
+
+// passes over the rules before the services that still break them are left where they are
+const MAX_PASSES: usize = 10;
+
+// How the services of a rule relate to each other's nodes
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AffinityKind {
+    // the services share a node, or the placement is infeasible
+    MustColocate,
+    // the services share a node, or the placement pays the penalty
+    ShouldColocate,
+    // no two of the services share a node
+    MustSeparate,
+}
+
+impl AffinityKind {
+    pub fn is_hard(&self) -> bool {
+        *self != AffinityKind::ShouldColocate
+    }
+}
+
+// A rule over a set of services - replicated services share a node if any of their replicas does
+#[derive(Debug, Deserialize, Clone)]
+pub struct AffinityRule {
+    pub services: Vec<String>,
+    pub kind: AffinityKind,
+    // the cost of breaking a should_colocate rule, relative to the other rules
+    #[serde(default = "default_penalty")]
+    pub penalty: f64,
+}
+
+fn default_penalty() -> f64 {
+    1.0
+}
+
+// The affinity rules of the config
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Affinity {
+    // a rule over each service and its cache and db (the groups of grouped_services) - none if not set
+    pub relations: Option<AffinityKind>,
+    pub rules: Vec<AffinityRule>,
+}
+
+impl AffinityRule {
+    // The number of services out of place: those off the node shared by most of them (co-location), or
+    // those on a node an earlier service of the rule already has (separation). Unplaced services are left out.
+    pub fn violations(&self, nodes: &HashMap<String, HashSet<Node>>) -> u64 {
+        let placed: Vec<&HashSet<Node>> = self.services.iter().filter_map(|service| nodes.get(service)).filter(|nodes| !nodes.is_empty()).collect();
+
+        match self.kind {
+            AffinityKind::MustColocate | AffinityKind::ShouldColocate => {
+                let mut counts: HashMap<&Node, u64> = HashMap::new();
+                for service_nodes in &placed {
+                    for node in service_nodes.iter() {
+                        *counts.entry(node).or_insert(0) += 1;
+                    }
+                }
+                placed.len() as u64 - counts.values().max().copied().unwrap_or(0)
+            }
+            AffinityKind::MustSeparate => {
+                let mut used: HashSet<&Node> = HashSet::new();
+                let mut violations = 0;
+                for service_nodes in &placed {
+                    if service_nodes.iter().any(|node| used.contains(node)) {
+                        violations += 1;
+                    }
+                    used.extend(service_nodes.iter());
+                }
+                violations
+            }
+        }
+    }
+}
+
+// The rules of the config and the relation rules, over the services of the config
+pub fn rules(config: &Config) -> Vec<AffinityRule> {
+    let mut rules: Vec<AffinityRule> = config.affinity.rules.iter().map(|rule| AffinityRule {
+        services: rule.services.iter().filter(|name| config.services.iter().any(|service| service.name == **name)).cloned().collect(),
+        ..rule.clone()
+    }).filter(|rule| rule.services.len() > 1).collect();
+
+    if let Some(kind) = config.affinity.relations {
+        for (_, group) in config.grouped_services() {
+            if group.len() > 1 {
+                let mut services: Vec<String> = group.iter().map(|service| service.name.clone()).collect();
+                services.sort();
+                rules.push(AffinityRule { services, kind, penalty: default_penalty() });
+            }
+        }
+    }
+
+    rules
+}
+
+// The name of the constraint of the i-th rule
+pub fn constraint_name(index: usize) -> String {
+    format!("affinity_{}", index)
+}
+
+// The penalty of the broken soft rules, relative to the penalty of all of them
+pub fn penalty(rules: &[AffinityRule], nodes: &HashMap<String, HashSet<Node>>) -> f64 {
+    let soft: Vec<&AffinityRule> = rules.iter().filter(|rule| !rule.kind.is_hard()).collect();
+    let total: f64 = soft.iter().map(|rule| rule.penalty).sum();
+    let broken: f64 = soft.iter().filter(|rule| rule.violations(nodes) > 0).map(|rule| rule.penalty).sum();
+
+    if total > 0.0 { broken / total } else { 0.0 }
+}
+
+// The nodes of every service of a placement
+pub fn placement_nodes(placement_map: &PlacementMap) -> HashMap<String, HashSet<Node>> {
+    placement_map.iter()
+        .filter_map(|(service, nodes)| Some((service.name.clone(), nodes.clone()?)))
+        .collect()
+}
+
+// The nodes of every service of an assignment of replica nodes
+pub fn replica_nodes(replica_nodes: &HashMap<Service, HashSet<Node>>) -> HashMap<String, HashSet<Node>> {
+    replica_nodes.iter()
+        .map(|(service, nodes)| (service.name.clone(), nodes.clone()))
+        .collect()
+}
+
+// Move services until the rules hold, keeping the number of nodes of each service: co-located services
+// gather on a node of the rule (one of their nodes is swapped for it), a separated service swaps the nodes
+// it shares for nodes the rule does not use yet. A node only takes a service if its capacity left holds
+// the share of the service - the capacity of the nodes left out of `replica_nodes` is not checked.
+// The rules are passed over until they hold or no service can move. Returns the number of nodes swapped,
+// or the hard rules that still break.
+pub fn enforce(placement_map: &mut PlacementMap, rules: &[AffinityRule], nodes: &[Node], replica_nodes: &ReplicaNodes) -> Result<usize, String> {
+    let mut nodes = nodes.to_vec();
+    nodes.sort_by_key(|node| node.id);
+    let mut capacity = replica_nodes.capacity_left(placement_map);
+    let mut moved = 0;
+
+    // the moves of a rule can break another one - stop once a pass moves nothing
+    for _ in 0..MAX_PASSES {
+        let pass: usize = rules.iter()
+            .map(|rule| enforce_rule(placement_map, rule, rules, &nodes, replica_nodes, &mut capacity))
+            .sum();
+        moved += pass;
+
+        if pass == 0 || broken(rules, placement_map).is_empty() {
+            break;
+        }
+    }
+
+    let still_broken = broken(rules, placement_map);
+    for rule in still_broken.iter().filter(|rule| !rule.kind.is_hard()) {
+        println!("Affinity: the {:?} rule over {:?} still breaks - no node with capacity left to move to", rule.kind, rule.services);
+    }
+
+    let hard: Vec<String> = still_broken.iter()
+        .filter(|rule| rule.kind.is_hard())
+        .map(|rule| format!("{:?} {:?}", rule.kind, rule.services))
+        .collect();
+
+    if hard.is_empty() {
+        Ok(moved)
+    } else {
+        Err(format!("The affinity rules {} still break - no node with capacity left to move to", hard.join(", ")))
+    }
+}
+
+// the rules with a service out of place
+fn broken<'a>(rules: &'a [AffinityRule], placement_map: &PlacementMap) -> Vec<&'a AffinityRule> {
+    let nodes = placement_nodes(placement_map);
+    rules.iter().filter(|rule| rule.violations(&nodes) > 0).collect()
+}
+
+// The services out of place over all the rules
+fn total_violations(rules: &[AffinityRule], placement_map: &PlacementMap) -> u64 {
+    let nodes = placement_nodes(placement_map);
+    rules.iter().map(|rule| rule.violations(&nodes)).sum()
+}
+
+// The capacity left on a node holds the share of a service - a node of unknown capacity always does
+fn has_room(capacity: &HashMap<Node, Resource>, node: &Node, share: &Resource) -> bool {
+    match capacity.get(node) {
+        Some(left) => strategy::fits(left, share),
+        None => true,
+    }
+}
+
+// Move one node of a service to another, with its share of the capacity
+fn swap_node(placement_map: &mut PlacementMap, capacity: &mut HashMap<Node, Resource>, service: &Service, from: &Node, to: &Node, share: &Resource) {
+    let service_nodes = placement_map.get_mut(service).unwrap().as_mut().unwrap();
+    service_nodes.remove(from);
+    service_nodes.insert(to.clone());
+
+    if let Some(left) = capacity.get_mut(to) {
+        left.sub(share);
+    }
+    if let Some(left) = capacity.get_mut(from) {
+        left.add(share);
+    }
+}
+
+// One pass of a rule - the number of nodes swapped. Of the moves that repair it, the one that leaves the
+// fewest services out of place over all the rules is taken, so that the rules do not undo each other.
+fn enforce_rule(placement_map: &mut PlacementMap, rule: &AffinityRule, rules: &[AffinityRule], nodes: &[Node], replica_nodes: &ReplicaNodes, capacity: &mut HashMap<Node, Resource>) -> usize {
+    let services: Vec<Service> = rule.services.iter()
+        .filter_map(|name| placement_map.keys().find(|service| service.name == *name).cloned())
+        .filter(|service| matches!(&placement_map[service], Some(nodes) if !nodes.is_empty()))
+        .collect();
+
+    if rule.violations(&placement_nodes(placement_map)) == 0 {
+        return 0;
+    }
+
+    // the nodes of a placed service
+    let nodes_of = |placement_map: &PlacementMap, service: &Service| placement_map[service].clone().unwrap_or_default();
+    let mut moved = 0;
+
+    match rule.kind {
+        AffinityKind::MustColocate | AffinityKind::ShouldColocate => {
+            // the nodes of the rule, shared by most of its services first, then by id
+            let mut counts: HashMap<Node, usize> = HashMap::new();
+            for service in &services {
+                for node in nodes_of(placement_map, service) {
+                    *counts.entry(node).or_insert(0) += 1;
+                }
+            }
+            let mut anchors: Vec<(Node, usize)> = counts.into_iter().collect();
+            anchors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.id.cmp(&b.0.id)));
+
+            // gather the services on an anchor with room for all of those it misses
+            let mut best: Option<(u64, PlacementMap, HashMap<Node, Resource>)> = None;
+            for (anchor, _) in anchors {
+                let mut trial = placement_map.clone();
+                let mut left = capacity.clone();
+                let mut room = true;
+
+                for service in &services {
+                    let service_nodes = nodes_of(&trial, service);
+                    if service_nodes.contains(&anchor) {
+                        continue;
+                    }
+
+                    let share = replica_nodes.share(service, service_nodes.len());
+                    if !has_room(&left, &anchor, &share) {
+                        room = false;
+                        break;
+                    }
+
+                    let dropped = service_nodes.iter().max_by_key(|node| node.id).cloned().unwrap();
+                    swap_node(&mut trial, &mut left, service, &dropped, &anchor, &share);
+                }
+
+                if !room {
+                    continue;
+                }
+
+                let violations = total_violations(rules, &trial);
+                let better = match &best {
+                    Some((least, _, _)) => violations < *least,
+                    None => true,
+                };
+                if better {
+                    best = Some((violations, trial, left));
+                }
+            }
+
+            if let Some((_, trial, left)) = best {
+                for service in &services {
+                    let (before, after) = (nodes_of(placement_map, service), nodes_of(&trial, service));
+                    for (from, to) in before.difference(&after).zip(after.difference(&before)) {
+                        println!("Affinity: moving service {} from {} to {} with {:?}", service.name, from.name, to.name, rule.services);
+                        moved += 1;
+                    }
+                }
+
+                *placement_map = trial;
+                *capacity = left;
+            }
+        }
+        AffinityKind::MustSeparate => {
+            let mut used: HashSet<Node> = HashSet::new();
+
+            for service in &services {
+                let shared: Vec<Node> = nodes_of(placement_map, service).into_iter().filter(|node| used.contains(node)).collect();
+
+                for node in shared {
+                    let service_nodes = nodes_of(placement_map, service);
+                    let share = replica_nodes.share(service, service_nodes.len());
+
+                    // a node the rule does not use yet, with room for the service
+                    let mut best: Option<(u64, Node)> = None;
+                    for candidate in nodes.iter().filter(|candidate| !used.contains(*candidate) && !service_nodes.contains(*candidate)) {
+                        if !has_room(capacity, candidate, &share) {
+                            continue;
+                        }
+
+                        let mut trial = placement_map.clone();
+                        swap_node(&mut trial, &mut HashMap::new(), service, &node, candidate, &share);
+
+                        let violations = total_violations(rules, &trial);
+                        let better = match &best {
+                            Some((least, _)) => violations < *least,
+                            None => true,
+                        };
+                        if better {
+                            best = Some((violations, candidate.clone()));
+                        }
+                    }
+
+                    if let Some((_, free)) = best {
+                        swap_node(placement_map, capacity, service, &node, &free, &share);
+                        println!("Affinity: moving service {} from {} to {} away from {:?}", service.name, node.name, free.name, rule.services);
+                        moved += 1;
+                    }
+                }
+
+                used.extend(nodes_of(placement_map, service));
+            }
+        }
+    }
+
+    moved
+}
+
+#[cfg(test)]
+mod test {
+    use crate::affinity::{enforce, placement_nodes, AffinityKind, AffinityRule};
+    use crate::strategy::{PlacementMap, ReplicaNodes};
+    use crate::utility::{Node, Resource, ResourceInt, Service};
+
+    fn node(id: i64) -> Node {
+        Node::new(id, &format!("node{}", id), "127.0.0.1", ResourceInt::new(4, 8, 100_000, 1000))
+    }
+
+    fn nodes() -> Vec<Node> {
+        (1..=3).map(node).collect()
+    }
+
+    fn placement(services: &[(&str, &[i64])]) -> PlacementMap {
+        services.iter()
+            .map(|(name, ids)| (Service::new(name, name, None, None), Some(ids.iter().map(|id| node(*id)).collect())))
+            .collect()
+    }
+
+    fn rule(kind: AffinityKind, services: &[&str]) -> AffinityRule {
+        AffinityRule { services: services.iter().map(|name| name.to_string()).collect(), kind, penalty: 1.0 }
+    }
+
+    fn violations(kind: AffinityKind, services: &[&str], placement_map: &PlacementMap) -> u64 {
+        rule(kind, services).violations(&placement_nodes(placement_map))
+    }
+
+    fn nodes_of(placement_map: &PlacementMap, name: &str) -> Vec<i64> {
+        let service = placement_map.keys().find(|service| service.name == name).unwrap();
+        let mut ids: Vec<i64> = placement_map[service].as_ref().unwrap().iter().map(|node| node.id).collect();
+        ids.sort();
+        ids
+    }
+
+    // the cpu left on each node, every service takes 1 cpu
+    fn capacity(cpu: [f64; 3], services: &[&str]) -> ReplicaNodes {
+        ReplicaNodes {
+            ranked: nodes(),
+            capacity: nodes().into_iter().zip(cpu).map(|(node, cpu)| (node, Resource::new(cpu, 1e6, 1e6, 1e6))).collect(),
+            demand: services.iter().map(|name| (Service::new(name, name, None, None), Resource::new(1.0, 100.0, 1.0, 1.0))).collect(),
+        }
+    }
+
+    #[test]
+    fn test_colocate_violations() {
+        let placement_map = placement(&[("a", &[1]), ("b", &[1]), ("c", &[2])]);
+        assert_eq!(violations(AffinityKind::MustColocate, &["a", "b", "c"], &placement_map), 1);
+        assert_eq!(violations(AffinityKind::ShouldColocate, &["a", "b"], &placement_map), 0);
+        // a service that is not placed is left out
+        assert_eq!(violations(AffinityKind::MustColocate, &["a", "b", "d"], &placement_map), 0);
+
+        // replicas share a node if any of them does
+        let placement_map = placement(&[("a", &[1, 2]), ("b", &[2]), ("c", &[2])]);
+        assert_eq!(violations(AffinityKind::MustColocate, &["a", "b", "c"], &placement_map), 0);
+    }
+
+    #[test]
+    fn test_separate_violations() {
+        let placement_map = placement(&[("a", &[1]), ("b", &[1]), ("c", &[2])]);
+        assert_eq!(violations(AffinityKind::MustSeparate, &["a", "b", "c"], &placement_map), 1);
+        assert_eq!(violations(AffinityKind::MustSeparate, &["a", "c"], &placement_map), 0);
+
+        let placement_map = placement(&[("a", &[1, 2]), ("b", &[2])]);
+        assert_eq!(violations(AffinityKind::MustSeparate, &["a", "b"], &placement_map), 1);
+    }
+
+    #[test]
+    fn test_enforce_colocate() {
+        let mut placement_map = placement(&[("a", &[1]), ("b", &[1]), ("c", &[2])]);
+        let rules = [rule(AffinityKind::MustColocate, &["a", "b", "c"])];
+
+        assert_eq!(enforce(&mut placement_map, &rules, &nodes(), &ReplicaNodes::default()), Ok(1));
+        assert_eq!(nodes_of(&placement_map, "c"), vec![1]);
+    }
+
+    #[test]
+    fn test_enforce_skips_full_nodes() {
+        // node 1 has no room for b - a joins b on node 2
+        let mut placement_map = placement(&[("a", &[1]), ("b", &[2])]);
+        let rules = [rule(AffinityKind::MustColocate, &["a", "b"])];
+
+        assert_eq!(enforce(&mut placement_map, &rules, &nodes(), &capacity([1.5, 4.0, 4.0], &["a", "b"])), Ok(1));
+        assert_eq!(nodes_of(&placement_map, "a"), vec![2]);
+        assert_eq!(nodes_of(&placement_map, "b"), vec![2]);
+    }
+
+    #[test]
+    fn test_enforce_reports_broken_rules() {
+        let rules = [rule(AffinityKind::MustSeparate, &["a", "b", "c"])];
+
+        // node 3 is full - c has nowhere to go
+        let mut placement_map = placement(&[("a", &[1]), ("b", &[1]), ("c", &[1])]);
+        assert!(enforce(&mut placement_map, &rules, &nodes(), &capacity([4.0, 4.0, 0.0], &["a", "b", "c"])).is_err());
+        assert_eq!(nodes_of(&placement_map, "b"), vec![2]);
+
+        let mut placement_map = placement(&[("a", &[1]), ("b", &[1]), ("c", &[1])]);
+        assert_eq!(enforce(&mut placement_map, &rules, &nodes(), &ReplicaNodes::default()), Ok(2));
+
+        // a soft rule is left broken
+        let mut placement_map = placement(&[("a", &[1]), ("b", &[2])]);
+        let rules = [rule(AffinityKind::ShouldColocate, &["a", "b"])];
+        assert_eq!(enforce(&mut placement_map, &rules, &nodes(), &capacity([0.0, 0.0, 4.0], &["a", "b"])), Ok(0));
+        assert_eq!(nodes_of(&placement_map, "a"), vec![1]);
+    }
+
+    #[test]
+    fn test_enforce_rules_together() {
+        // b leaves a for node 2 - c follows b rather than bringing it back next to a
+        let mut placement_map = placement(&[("a", &[1]), ("b", &[1]), ("c", &[1])]);
+        let rules = [
+            rule(AffinityKind::MustSeparate, &["a", "b"]),
+            rule(AffinityKind::MustColocate, &["b", "c"]),
+        ];
+
+        assert_eq!(enforce(&mut placement_map, &rules, &nodes(), &ReplicaNodes::default()), Ok(2));
+        assert_eq!(nodes_of(&placement_map, "a"), vec![1]);
+        assert_eq!(nodes_of(&placement_map, "b"), vec![2]);
+        assert_eq!(nodes_of(&placement_map, "c"), vec![2]);
+
+        let nodes = placement_nodes(&placement_map);
+        assert!(rules.iter().all(|rule| rule.violations(&nodes) == 0));
+    }
+}
@@ -4,8 +4,10 @@ use prometheus_http_query::response;
 use std::sync::Arc;
 use clap::{Arg, Command, ArgAction};
 use std::fs;
+use serde::Deserialize;
 
 use yonga::utility::*;
+use yonga::history::{self, PlacementHistory};
 
 #[derive(Debug)]
 struct AppState {
@@ -465,6 +467,70 @@ async fn get_node_services(state: web::Data<Arc<AppState>>, node: web::Path<Stri
     }
 }
 
+// the strategy a revision list is filtered on, e.g. ?strategy=milp
+#[derive(Debug, Deserialize)]
+struct PlacementFilter {
+    strategy: Option<String>,
+}
+
+// get the placement revisions of a stack, latest first
+#[get("/stacks/{stack}/placements")]
+async fn get_placements(state: web::Data<Arc<AppState>>, stack: web::Path<String>, filter: web::Query<PlacementFilter>) -> impl Responder {
+    let stack = stack.into_inner();
+    let history = PlacementHistory::new(&state.database, &stack);
+
+    match history.list(100, filter.strategy.as_deref()).await {
+        Ok(records) => {
+            println!("Success: {} placement revisions of stack {}", records.len(), stack);
+            HttpResponse::Ok().json(records)
+        }
+        Err(e) => {
+            println!("Failed to retrieve the placement revisions of stack {}: {}", stack, e);
+            HttpResponse::InternalServerError().body("Failed to retrieve the placement revisions")
+        }
+    }
+}
+
+// get a placement revision of a stack
+#[get("/stacks/{stack}/placements/{rev}")]
+async fn get_placement(state: web::Data<Arc<AppState>>, path: web::Path<(String, u32)>) -> impl Responder {
+    let (stack, rev) = path.into_inner();
+    let history = PlacementHistory::new(&state.database, &stack);
+
+    match history.get(rev).await {
+        Ok(Some(record)) => HttpResponse::Ok().json(record),
+        Ok(None) => {
+            println!("Placement revision {} of stack {} not found", rev, stack);
+            HttpResponse::NotFound().body("Placement revision not found \n")
+        }
+        Err(e) => {
+            println!("Failed to retrieve placement revision {} of stack {}: {}", rev, stack, e);
+            HttpResponse::InternalServerError().body("Failed to retrieve the placement revision")
+        }
+    }
+}
+
+// get the services that moved between two placement revisions of a stack
+#[get("/stacks/{stack}/placements/{rev}/diff/{other}")]
+async fn get_placement_diff(state: web::Data<Arc<AppState>>, path: web::Path<(String, u32, u32)>) -> impl Responder {
+    let (stack, rev, other) = path.into_inner();
+    let history = PlacementHistory::new(&state.database, &stack);
+
+    let (from, to) = match (history.get(rev).await, history.get(other).await) {
+        (Ok(Some(from)), Ok(Some(to))) => (from, to),
+        (Ok(_), Ok(_)) => {
+            println!("Placement revision {} or {} of stack {} not found", rev, other, stack);
+            return HttpResponse::NotFound().body("Placement revision not found \n");
+        }
+        _ => {
+            println!("Failed to retrieve placement revisions {} and {} of stack {}", rev, other, stack);
+            return HttpResponse::InternalServerError().body("Failed to retrieve the placement revisions");
+        }
+    };
+
+    HttpResponse::Ok().json(history::diff_records(&from, &to))
+}
+
 
 // catch-all route for unknown routes
 async fn not_found() -> impl Responder {
@@ -519,6 +585,9 @@ async fn main() -> std::io::Result<()> {
             .service(get_service_utilization)
             .service(get_node_environment)
             .service(get_node_services)
+            .service(get_placements)
+            .service(get_placement_diff)
+            .service(get_placement)
             .default_service(web::route().to(not_found))
     })
     .bind(("0.0.0.0", port))?
@@ -1,11 +1,33 @@
 use std::collections::{HashMap, HashSet};
-use std::hash::Hash;
-use crate::utility::{Config, Node, Service};
+use async_trait::async_trait;
+use serde::Deserialize;
+use crate::utility::{self, Config, Node, Resource, Service};
 use crate::stack::{self, StackConfig};
-use chrono::Local;
+use crate::strategy::{self, PlacementMap, PlacementStrategy, ReplicaNodes, StrategyContext, StrategyRegistry};
 use crate::api_client::ApiClient;
-use std::process::Command;
-// use crate::utility::Resource;
+use crate::snapshot::{ClusterSnapshot, SnapshotCache};
+use crate::affinity;
+
+// How a replica picks its bin among the nodes it fits on - the services are packed by decreasing size
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PackingRule {
+    // the first node (by id) it fits on
+    #[default]
+    FirstFitDecreasing,
+    // the node left with the least capacity
+    BestFit,
+    // the node left with the most capacity
+    WorstFit,
+}
+
+// A service that could not be packed on as many nodes as it has replicas
+#[derive(Debug, Clone)]
+pub struct Unplaced {
+    pub service: Service,
+    pub wanted: u32,
+    pub placed: u32,
+}
 
 #[derive(Debug)]
 pub struct Binpack {
@@ -14,6 +36,9 @@ pub struct Binpack {
     pub stack_name: String,
     pub stack_config: StackConfig, //Docker Swarm
     pub api_client: ApiClient,
+    // the services left out of the last plan
+    pub unplaced: Vec<Unplaced>,
+    pub snapshots: SnapshotCache,
 }
 
 impl Binpack {
@@ -24,178 +49,352 @@ impl Binpack {
             stack_name,
             stack_config,
             api_client,
+            unplaced: Vec::new(),
+            snapshots: SnapshotCache::default(),
         }
     }
 
-    pub async fn get_bins(&self) -> HashMap<Node, u32> {
+    // The capacity left on each node in cpu, memory, disk and network
+    pub fn get_bins(&self, snapshot: &ClusterSnapshot) -> HashMap<Node, Resource> {
 
-        let mut bins: HashMap<Node, u32> = HashMap::new();
+        let mut bins: HashMap<Node, Resource> = HashMap::new();
 
         for node in &self.config.cluster.nodes {
-            // retrieve the node capacity in cpu, memory, network & storage
-            let node_utilization = self.api_client.get_node_utilization(&node.name).await;
+            match snapshot.node(node).and_then(|node_snapshot| node_snapshot.utilization.as_ref()) {
+                Some(utilization) => {
+                    bins.insert(node.clone(), utility::node_capacity(node, utilization));
+                },
+                None => {
+                    // nothing is packed on a node we know nothing about
+                    println!("No utilization for node {} - leaving its bin empty", node.name);
+                    bins.insert(node.clone(), Resource::default());
+                }
+            }
+        }
+
+        bins
+    }
 
-            match node_utilization {
-                Ok(utilization) => {
-                    let cpu = (utilization.cpu) * self.config.get_weight("cpu");
-                    let memory = (utilization.memory) * self.config.get_weight("memory");
-                    let network = utilization.network * self.config.get_weight("network");
-                    let storage = utilization.disk * self.config.get_weight("disk");
+    // The measured usage of each service - a service without measurements takes no capacity
+    pub fn get_demands(&self, snapshot: &ClusterSnapshot, services: &Vec<Service>) -> HashMap<Service, Resource> {
 
-                    // calculate the bin capacity
-                    let bin_capacity = (cpu + memory + network + storage) as u32;
+        let mut demands: HashMap<Service, Resource> = HashMap::new();
 
-                    bins.insert(node.clone(), bin_capacity);
+        for service in services {
+            match snapshot.services.get(service).cloned().flatten() {
+                Some(utilization) => {
+                    demands.insert(service.clone(), utilization);
                 },
-                Err(e) => {
-                    println!("Failed to retrieve the node utilization: {}", e);
-                    bins.insert(node.clone(), 0);
+                None => {
+                    println!("No utilization for service {} - packing it as empty", service.name);
+                    demands.insert(service.clone(), Resource::default());
                 }
-                
             }
         }
 
-        bins
+        demands
     }
 
-    pub fn run_deploy(&self, stack_name: &str, compose_file: &str) -> Result<std::process::Output, String> {
-        let output = Command::new("./target/debug/deploy")
-            .arg("--stack")
-            .arg("deploy")
-            .arg("--name")
-            .arg(stack_name)
-            .arg("--file")
-            .arg(compose_file)
-            .output()
-            .map_err(|e| e.to_string())?;
-        
-        if !output.status.success() {
-            return Err(format!("Command failed with status: {}", output.status));
+    fn assign_services(
+        &mut self,
+        bins: &mut HashMap<Node, Resource>,
+        demands: &HashMap<Service, Resource>,
+        services: Vec<Service>
+    ) -> HashMap<Service, Option<HashSet<Node>>> {
+        let mut assignment_map: HashMap<Service, Option<HashSet<Node>>> = HashMap::new();
+        let rule = self.config.packing;
+
+        // the sizes are taken relative to the total capacity in each dimension, so that they add up
+        let total = bins.values().fold(Resource::default(), |mut total, bin| {
+            total.add(&positive(bin));
+            total
+        });
+
+        let mut nodes: Vec<Node> = bins.keys().cloned().collect();
+        nodes.sort_by_key(|node| node.id);
+
+        // the largest services first
+        let mut services = services;
+        services.sort_by(|a, b| {
+            let size_a = size(&demand_of(demands, a), &total);
+            let size_b = size(&demand_of(demands, b), &total);
+            size_b.total_cmp(&size_a)
+        });
+
+        let replicas = stack::get_replicas(&self.stack_config);
+        self.unplaced.clear();
+
+        for service in services {
+            let demand = demand_of(demands, &service);
+            // each replica is packed into a distinct node
+            let wanted = *replicas.get(&service.name).unwrap_or(&1);
+            let mut placed: HashSet<Node> = HashSet::new();
+
+            while (placed.len() as u32) < wanted {
+                let mut candidates = nodes.iter()
+                    .filter(|node| !placed.contains(*node) && fits(&bins[*node], &demand));
+
+                // the capacity a node has left once the replica is in
+                let slack = |node: &&Node| {
+                    let mut left = bins[*node].clone();
+                    left.sub(&demand);
+                    size(&left, &total)
+                };
+
+                let chosen = match rule {
+                    PackingRule::FirstFitDecreasing => candidates.next().cloned(),
+                    PackingRule::BestFit => candidates.min_by(|a, b| slack(a).total_cmp(&slack(b))).cloned(),
+                    PackingRule::WorstFit => candidates.max_by(|a, b| slack(a).total_cmp(&slack(b))).cloned(),
+                };
+
+                match chosen {
+                    Some(node) => {
+                        bins.get_mut(&node).unwrap().sub(&demand);
+                        placed.insert(node);
+                    }
+                    None => break,
+                }
+            }
+
+            if (placed.len() as u32) < wanted {
+                self.unplaced.push(Unplaced { service: service.clone(), wanted, placed: placed.len() as u32 });
+            }
+
+            if placed.is_empty() {
+                assignment_map.insert(service, None);
+            } else {
+                assignment_map.insert(service, Some(placed));
+            }
         }
 
-        Ok(output)
+        assignment_map
+    }
+}
+
+fn demand_of(demands: &HashMap<Service, Resource>, service: &Service) -> Resource {
+    demands.get(service).map(positive).unwrap_or(Resource::default())
+}
+
+// negative measurements are noise
+fn positive(resource: &Resource) -> Resource {
+    Resource {
+        cpu: resource.cpu.max(0.0),
+        memory: resource.memory.max(0.0),
+        disk: resource.disk.max(0.0),
+        network: resource.network.max(0.0),
+    }
+}
+
+// a demand fits a bin if it fits in every dimension
+fn fits(bin: &Resource, demand: &Resource) -> bool {
+    bin.cpu >= demand.cpu
+        && bin.memory >= demand.memory
+        && bin.disk >= demand.disk
+        && bin.network >= demand.network
+}
+
+// the sum of the dimensions, each relative to the total capacity of the cluster
+fn size(resource: &Resource, total: &Resource) -> f64 {
+    let share = |value: f64, total: f64| if total > 0.0 { value / total } else { 0.0 };
+
+    share(resource.cpu, total.cpu)
+        + share(resource.memory, total.memory)
+        + share(resource.disk, total.disk)
+        + share(resource.network, total.network)
+}
+
+#[async_trait(?Send)]
+impl PlacementStrategy for Binpack {
+    fn name(&self) -> &'static str {
+        "binpack"
     }
 
-    pub async fn binpack_0(&mut self) -> Result<HashMap<Service, Option<HashSet<Node>>>, Box<dyn std::error::Error>> {
-        println!("Running the Binpack Strategy 0");
+    async fn plan(&mut self) -> Result<PlacementMap, Box<dyn std::error::Error>> {
+        println!("Running the Binpack Strategy 0 ({:?})", self.config.packing);
 
         self.placement = Some(HashMap::new());
 
-        // get bins for each service
-        let mut bins = self.get_bins().await;
+        // get the bins and the demands of the services
+        let snapshot = self.snapshots.get(&self.api_client, &self.config).await;
+        let mut bins = self.get_bins(&snapshot);
+        let demands = self.get_demands(&snapshot, &self.config.services);
 
         // print the bins
         for (node, bin) in &bins {
-            println!("Node: {:?}, Bins: {:?}", node, bin);
+            println!("Node: {}, Bin: {:?}", node.name, bin);
         }
 
+        // the bins before packing, for the services the affinity rules move
+        let replica_nodes = ReplicaNodes { ranked: Vec::new(), capacity: bins.clone(), demand: demands.clone() };
+
         // create the placement
-        let assignment_map = self.assign_services(&mut bins, self.config.services.clone());
+        let mut assignment_map = self.assign_services(&mut bins, &demands, self.config.services.clone());
+
+        // move the services the affinity rules place together or apart, within the capacity of the bins
+        affinity::enforce(&mut assignment_map, &affinity::rules(&self.config), &self.config.cluster.nodes, &replica_nodes)?;
+
+        for unplaced in &self.unplaced {
+            println!("Service {} has {} replica(s) but only {} could be packed", unplaced.service.name, unplaced.wanted, unplaced.placed);
+        }
 
         self.placement = Some(assignment_map);
 
         let placement_map = self.placement.clone().unwrap();
 
-        //print the placement_map
-        // for (service, nodes) in &placement_map {
-        //     println!("Service: {:?}", service);
-        //     match nodes {
-        //         Some(node_set) => {
-        //             for node in node_set {
-        //                 println!("Node: {:?}", node);
-        //             }
-        //         },
-        //         None => {
-        //             println!("No nodes assigned");
-        //         }
-        //     }
-        // }
-
         Ok(placement_map)
     }
 
+    async fn apply(&mut self, placement_map: PlacementMap) -> Result<(), String> {
+        strategy::deploy_placement(self.name(), &self.stack_name, &mut self.stack_config, placement_map).await
+    }
 
-    fn assign_services(
-        &mut self,
-        bins: &mut HashMap<Node, u32>,
-        services: Vec<Service>
-    ) -> HashMap<Service, Option<HashSet<Node>>> {
-        let mut assignment_map: HashMap<Service, Option<HashSet<Node>>> = HashMap::new();
-        
-        // Collect nodes and bins into a mutable vector and sort by bin capacity in ascending order
-        let mut nodes: Vec<_> = bins.iter_mut().collect();
-        nodes.sort_by(|a, b| a.1.cmp(&b.1)); // Ascending order by bin capacity
-    
-        for service in services {
-            let mut assigned = false;
-            for (node, bin) in nodes.iter_mut() {
-                if **bin > 0 {
-                    // Check if the bin has enough capacity for the service
-                    if **bin >= 300 { // Ensure the bin has enough capacity | assumption that each service requires a capacity of 300
-                        // Use `or_insert_with` to insert a new `Some(HashSet::new())` if not present
-                        let node_set = assignment_map.entry(service.clone())
-                            .or_insert_with(|| Some(HashSet::new()))
-                            .as_mut()
-                            .unwrap();
-                        
-                        node_set.insert(node.clone());
-                        **bin -= 300; // Adjust decrement based on your use case
-                        assigned = true;
-                        break;
-                    } else {
-                        // Optionally handle cases where the bin is insufficient
-                        println!("Node {} does not have enough capacity for service {}", node.id, service.id);
-                    }
-                }
-            }
-    
-            if !assigned {
-                println!("Service {} could not be assigned to any node", service.id);
-            }
-        }
-    
-        assignment_map
+    fn placement(&self) -> Option<&PlacementMap> {
+        self.placement.as_ref()
     }
-    pub fn run(&mut self, placement_map: Option<HashMap<Service, Option<HashSet<Node>>>>) {
-        match placement_map {
-            Some(map) => {
-                // Update and clean up the stack config
-                let mut local_stack_config = self.stack_config.clone();
-                stack::update_node_constraints(&mut local_stack_config, map);
-                stack::populate_volumes(&mut local_stack_config);
-                stack::delete_null_placement(&mut local_stack_config);
+}
 
-                // Update the stack config
-                self.stack_config = local_stack_config;
+pub fn register(registry: &mut StrategyRegistry) {
+    registry.register("binpack", |context: StrategyContext| {
+        let mut binpack = Binpack::new(context.config, None, context.stack_name, context.stack_config, context.api_client);
+        binpack.snapshots = context.snapshots;
+        Box::new(binpack)
+    });
+}
 
-                // Create the YAML
-                let yaml_str = serde_yaml::to_string(&self.stack_config).unwrap();
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
 
-                // Provide the YAML to the deploy binary
-                let compose_file = format!("binpack_{}.yml", Local::now().format("%Y-%m-%d_%H-%M-%S"));
+    use crate::api_client::ApiClient;
+    use crate::binpack::{fits, Binpack};
+    use crate::stack::StackConfig;
+    use crate::utility::{Config, Node, Resource, Service};
 
-                // Write the YAML to a file
-                std::fs::write(&compose_file, yaml_str).expect("Failed to write the YAML configuration file");
+    fn binpack(packing: &str, stack: &str) -> Binpack {
+        let config: Config = serde_yaml::from_str(&format!(r#"
+cluster:
+  nodes:
+    - {{ id: 1, name: "node1", ip: "10.0.0.1", resource: {{ cpu: 8, memory: 16000, disk: 100000, network: 1000 }} }}
+    - {{ id: 2, name: "node2", ip: "10.0.0.2", resource: {{ cpu: 8, memory: 16000, disk: 100000, network: 1000 }} }}
+    - {{ id: 3, name: "node3", ip: "10.0.0.3", resource: {{ cpu: 8, memory: 16000, disk: 100000, network: 1000 }} }}
+  prometheus: {{ url: "", label: "", stack: "", query: "", metric: "" }}
+database: {{ uri: "mongodb://localhost:27017", db: "yonga", collections: [] }}
+services:
+  - {{ id: "1", name: "big" }}
+  - {{ id: "2", name: "small" }}
+weights: []
+packing: {}
+"#, packing)).unwrap();
+        let stack_config: StackConfig = serde_yaml::from_str(stack).unwrap();
 
-                // Deploy the stack
-                if let Err(e) = self.run_deploy(&self.stack_name, &compose_file) {
-                    println!("Failed to deploy: {}", e);
+        Binpack::new(config, None, "test".to_string(), stack_config, ApiClient::new("http://localhost"))
+    }
 
-                    // Clean up the file
-                    std::fs::remove_file(&compose_file).expect("Failed to remove the YAML configuration file");
-                }
+    const SINGLE: &str = r#"
+version: "3.8"
+services:
+  big: {}
+  small: {}
+volumes: {}
+"#;
 
-                else {
-                    println!("Deployed the stack successfully!");
+    // the bins of the nodes - the cpus given, plenty of the rest
+    fn bins(binpack: &Binpack, cpus: [f64; 3]) -> HashMap<Node, Resource> {
+        binpack.config.cluster.nodes.iter().zip(cpus)
+            .map(|(node, cpu)| (node.clone(), Resource::new(cpu, 100.0, 100.0, 100.0)))
+            .collect()
+    }
 
-                    // Clean up the file
-                    std::fs::remove_file(&compose_file).expect("Failed to remove the YAML configuration file");
-                }
-            },
-            None => {
-                println!("No placement solution provided");
-            }
-        }
+    fn demands(binpack: &Binpack, cpus: [f64; 2]) -> HashMap<Service, Resource> {
+        binpack.config.services.iter().zip(cpus)
+            .map(|(service, cpu)| (service.clone(), Resource::new(cpu, 0.0, 0.0, 0.0)))
+            .collect()
     }
-}
 
+    fn nodes_of(placement: &HashMap<Service, Option<HashSet<Node>>>, name: &str) -> Vec<i64> {
+        let service = placement.keys().find(|service| service.name == name).unwrap();
+        let mut ids: Vec<i64> = placement[service].iter().flatten().map(|node| node.id).collect();
+        ids.sort();
+        ids
+    }
+
+    fn pack(packing: &str) -> HashMap<Service, Option<HashSet<Node>>> {
+        let mut binpack = binpack(packing, SINGLE);
+        let mut bins = bins(&binpack, [4.0, 10.0, 6.0]);
+        let demands = demands(&binpack, [5.0, 3.0]);
+        let services = binpack.config.services.clone();
+
+        binpack.assign_services(&mut bins, &demands, services)
+    }
+
+    #[test]
+    fn test_first_fit_decreasing() {
+        // big does not fit node 1, small does
+        let placement = pack("first_fit_decreasing");
+        assert_eq!(nodes_of(&placement, "big"), vec![2]);
+        assert_eq!(nodes_of(&placement, "small"), vec![1]);
+    }
+
+    #[test]
+    fn test_best_fit() {
+        // each service takes the node it leaves the least room on
+        let placement = pack("best_fit");
+        assert_eq!(nodes_of(&placement, "big"), vec![3]);
+        assert_eq!(nodes_of(&placement, "small"), vec![1]);
+    }
+
+    #[test]
+    fn test_worst_fit() {
+        // each service takes the node it leaves the most room on
+        let placement = pack("worst_fit");
+        assert_eq!(nodes_of(&placement, "big"), vec![2]);
+        assert_eq!(nodes_of(&placement, "small"), vec![3]);
+    }
+
+    #[test]
+    fn test_replicas_on_distinct_nodes() {
+        let mut binpack = binpack("first_fit_decreasing", r#"
+version: "3.8"
+services:
+  big:
+    deploy:
+      replicas: 4
+  small:
+    deploy:
+      replicas: 2
+volumes: {}
+"#);
+        let mut bins = bins(&binpack, [10.0, 10.0, 10.0]);
+        let demands = demands(&binpack, [2.0, 1.0]);
+        let services = binpack.config.services.clone();
+
+        let placement = binpack.assign_services(&mut bins, &demands, services);
+
+        // a node holds one replica of a service at most - the fourth one of big is left out
+        assert_eq!(nodes_of(&placement, "big"), vec![1, 2, 3]);
+        assert_eq!(nodes_of(&placement, "small"), vec![1, 2]);
+        assert_eq!(binpack.unplaced.len(), 1);
+        assert_eq!((binpack.unplaced[0].service.name.as_str(), binpack.unplaced[0].wanted, binpack.unplaced[0].placed), ("big", 4, 3));
+    }
+
+    #[test]
+    fn test_nothing_fits() {
+        let mut binpack = binpack("best_fit", SINGLE);
+        let mut bins = bins(&binpack, [1.0, 1.0, 1.0]);
+        let demands = demands(&binpack, [5.0, 3.0]);
+        let services = binpack.config.services.clone();
+
+        let placement = binpack.assign_services(&mut bins, &demands, services);
+
+        assert!(placement.values().all(|nodes| nodes.is_none()));
+        assert_eq!(binpack.unplaced.len(), 2);
+    }
+
+    #[test]
+    fn test_fits() {
+        let bin = Resource::new(2.0, 100.0, 10.0, 50.0);
+
+        assert!(fits(&bin, &Resource::new(2.0, 100.0, 10.0, 50.0)));
+        assert!(!fits(&bin, &Resource::new(1.0, 100.0, 10.0, 51.0)));
+    }
+}
@@ -0,0 +1,343 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use serde::Deserialize;
+
+// augmentation of the Tchebycheff and reference-point scores - separates weakly dominated points
+const RHO: f64 = 1e-4;
+
+// How one point of the Pareto front is picked as the placement
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum DecisionMethod {
+    // the sum of the raw objective values
+    Sum,
+    // the point furthest below the hyperplane through the extreme points of the front
+    #[default]
+    Knee,
+    // the smallest weighted distance to the ideal point, in its worst objective - unlisted objectives weigh 1
+    Tchebycheff {
+        #[serde(default)]
+        weights: HashMap<String, f64>,
+    },
+    // the point that best achieves the aspiration level of each objective, in raw units - unlisted
+    // objectives aspire to the ideal
+    ReferencePoint {
+        #[serde(default)]
+        aspiration: HashMap<String, f64>,
+    },
+    // the best point in the first objective of `order`, ties (in bands of `tolerance` of the objective range)
+    // broken by the next one - unlisted objectives come last
+    Lexicographic {
+        #[serde(default)]
+        order: Vec<String>,
+        #[serde(default)]
+        tolerance: f64,
+    },
+}
+
+// A solution to decide on: its objective values (all minimised) and its constraint violation
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub objectives: Vec<f64>,
+    pub violation: f64,
+}
+
+// A candidate in the order of preference
+#[derive(Debug, Clone)]
+pub struct Ranked {
+    // position of the candidate in the input
+    pub index: usize,
+    // position in the order of preference, 0 for the chosen point
+    pub rank: usize,
+    // the non-domination front of the candidate, 0 for the Pareto front
+    pub front: usize,
+    // the value of the method, the lower the better
+    pub score: f64,
+    // euclidean distance to the ideal point, with the objectives normalised over the front
+    pub distance_to_ideal: f64,
+}
+
+// Order the candidates from the preferred one: feasible before infeasible, then by front, then by the method.
+// The objectives are normalised between the ideal and nadir points of the first front.
+pub fn rank(method: &DecisionMethod, names: &[String], candidates: &[Candidate]) -> Vec<Ranked> {
+    if candidates.is_empty() {
+        return Vec::new();
+    }
+
+    let fronts = non_dominated_fronts(candidates);
+    let first: Vec<usize> = (0..candidates.len()).filter(|i| fronts[*i] == 0).collect();
+    let (ideal, nadir) = bounds(candidates, &first);
+
+    let normalised: Vec<Vec<f64>> = candidates.iter()
+        .map(|candidate| normalise(&candidate.objectives, &ideal, &nadir))
+        .collect();
+
+    let scores: Vec<f64> = candidates.iter().zip(&normalised).map(|(candidate, normalised)| {
+        match method {
+            DecisionMethod::Sum => candidate.objectives.iter().sum(),
+            DecisionMethod::Knee => knee_score(normalised),
+            DecisionMethod::Tchebycheff { weights } => {
+                let weights: Vec<f64> = names.iter().map(|name| *weights.get(name).unwrap_or(&1.0)).collect();
+                tchebycheff(normalised, &weights)
+            }
+            DecisionMethod::ReferencePoint { aspiration } => {
+                let reference: Vec<f64> = names.iter().enumerate()
+                    .map(|(k, name)| aspiration.get(name).map_or(0.0, |value| normalise_value(*value, ideal[k], nadir[k])))
+                    .collect();
+                achievement(normalised, &reference)
+            }
+            // ordered below
+            DecisionMethod::Lexicographic { .. } => 0.0,
+        }
+    }).collect();
+
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    order.sort_by(|a, b| {
+        let feasible_a = candidates[*a].violation <= 0.0;
+        let feasible_b = candidates[*b].violation <= 0.0;
+
+        feasible_b.cmp(&feasible_a)
+            .then(candidates[*a].violation.total_cmp(&candidates[*b].violation))
+            .then(fronts[*a].cmp(&fronts[*b]))
+            .then_with(|| match method {
+                DecisionMethod::Lexicographic { order, tolerance } => {
+                    lexicographic(&normalised[*a], &normalised[*b], &priorities(names, order), *tolerance)
+                }
+                _ => scores[*a].total_cmp(&scores[*b]),
+            })
+    });
+
+    order.into_iter().enumerate().map(|(rank, index)| Ranked {
+        index,
+        rank,
+        front: fronts[index],
+        score: match method {
+            DecisionMethod::Lexicographic { .. } => rank as f64,
+            _ => scores[index],
+        },
+        distance_to_ideal: normalised[index].iter().map(|value| value * value).sum::<f64>().sqrt(),
+    }).collect()
+}
+
+// Whether the chosen candidate should replace the deployed one, both evaluated on the same objectives:
+// the method must prefer it and it must lower one objective by min_improvement (relative), unless it
+// reduces the constraint violation of the deployed one
+pub fn improves(method: &DecisionMethod, names: &[String], deployed: &Candidate, chosen: &Candidate, min_improvement: f64) -> bool {
+    let ranking = rank(method, names, &[deployed.clone(), chosen.clone()]);
+    if ranking[0].index != 1 {
+        return false;
+    }
+
+    chosen.violation < deployed.violation || deployed.objectives.iter().zip(&chosen.objectives)
+        .any(|(current, new)| new < current && *new <= current - current.abs() * min_improvement)
+}
+
+// the front of each candidate - the feasibility is left to the ordering
+fn non_dominated_fronts(candidates: &[Candidate]) -> Vec<usize> {
+    let mut fronts = vec![usize::MAX; candidates.len()];
+    let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+    let mut front = 0;
+
+    while !remaining.is_empty() {
+        let current: Vec<usize> = remaining.iter().copied()
+            .filter(|a| !remaining.iter().any(|b| dominates(&candidates[*b].objectives, &candidates[*a].objectives)))
+            .collect();
+
+        for index in &current {
+            fronts[*index] = front;
+        }
+
+        remaining.retain(|index| fronts[*index] == usize::MAX);
+        front += 1;
+    }
+
+    fronts
+}
+
+fn dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x <= y) && a.iter().zip(b).any(|(x, y)| x < y)
+}
+
+// the ideal (best) and nadir (worst) value of each objective over the given candidates
+fn bounds(candidates: &[Candidate], indices: &[usize]) -> (Vec<f64>, Vec<f64>) {
+    let size = candidates[0].objectives.len();
+    let mut ideal = vec![f64::INFINITY; size];
+    let mut nadir = vec![f64::NEG_INFINITY; size];
+
+    for index in indices {
+        for (k, value) in candidates[*index].objectives.iter().enumerate() {
+            ideal[k] = ideal[k].min(*value);
+            nadir[k] = nadir[k].max(*value);
+        }
+    }
+
+    (ideal, nadir)
+}
+
+fn normalise(objectives: &[f64], ideal: &[f64], nadir: &[f64]) -> Vec<f64> {
+    objectives.iter().enumerate().map(|(k, value)| normalise_value(*value, ideal[k], nadir[k])).collect()
+}
+
+// an objective without range on the front does not tell the points apart
+fn normalise_value(value: f64, ideal: f64, nadir: f64) -> f64 {
+    let range = nadir - ideal;
+    if range.is_finite() && range > f64::EPSILON {
+        (value - ideal) / range
+    } else {
+        0.0
+    }
+}
+
+// Once normalised, the extreme points of the front lie on the unit axes and the hyperplane through them is
+// sum(f) = 1. The knee bulges furthest towards the ideal point: the signed distance below the plane is
+// (1 - sum(f)) / sqrt(M), so the lowest sum is the knee.
+fn knee_score(normalised: &[f64]) -> f64 {
+    let size = normalised.len().max(1) as f64;
+    (normalised.iter().sum::<f64>() - 1.0) / size.sqrt()
+}
+
+fn tchebycheff(normalised: &[f64], weights: &[f64]) -> f64 {
+    let terms: Vec<f64> = normalised.iter().zip(weights).map(|(value, weight)| weight * value).collect();
+    terms.iter().cloned().fold(f64::NEG_INFINITY, f64::max) + RHO * terms.iter().sum::<f64>()
+}
+
+// the achievement scalarising function of Wierzbicki against the reference point
+fn achievement(normalised: &[f64], reference: &[f64]) -> f64 {
+    let terms: Vec<f64> = normalised.iter().zip(reference).map(|(value, reference)| value - reference).collect();
+    terms.iter().cloned().fold(f64::NEG_INFINITY, f64::max) + RHO * terms.iter().sum::<f64>()
+}
+
+// the objective indices in the order of priority
+fn priorities(names: &[String], order: &[String]) -> Vec<usize> {
+    let mut priorities: Vec<usize> = order.iter()
+        .filter_map(|name| names.iter().position(|other| other == name))
+        .collect();
+
+    for k in 0..names.len() {
+        if !priorities.contains(&k) {
+            priorities.push(k);
+        }
+    }
+
+    priorities
+}
+
+// the values are compared in bands of `tolerance` (so that the order stays total), then as they are
+fn lexicographic(a: &[f64], b: &[f64], priorities: &[usize], tolerance: f64) -> Ordering {
+    let band = |value: f64| if tolerance > 0.0 { (value / tolerance).floor() } else { value };
+
+    priorities.iter()
+        .map(|k| band(a[*k]).total_cmp(&band(b[*k])))
+        .chain(priorities.iter().map(|k| a[*k].total_cmp(&b[*k])))
+        .find(|ordering| *ordering != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+// Print the chosen point
+pub fn print_decision(method: &DecisionMethod, names: &[String], candidates: &[Candidate], chosen: &Ranked) {
+    let objectives: Vec<String> = names.iter().zip(&candidates[chosen.index].objectives)
+        .map(|(name, value)| format!("{} {:.4}", name, value))
+        .collect();
+
+    println!(
+        "Decision ({:?}): rank {} on front {}, score {:.4}, distance to the ideal point {:.4} - {}",
+        method, chosen.rank, chosen.front, chosen.score, chosen.distance_to_ideal, objectives.join(", ")
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::decision::{improves, rank, Candidate, DecisionMethod};
+
+    fn names() -> Vec<String> {
+        vec!["f1".to_string(), "f2".to_string()]
+    }
+
+    fn candidate(objectives: &[f64], violation: f64) -> Candidate {
+        Candidate { objectives: objectives.to_vec(), violation }
+    }
+
+    // the extreme points, a knee between them and a point dominated by the knee
+    fn front() -> Vec<Candidate> {
+        vec![
+            candidate(&[0.0, 1.0], 0.0),
+            candidate(&[0.3, 0.3], 0.0),
+            candidate(&[1.0, 0.0], 0.0),
+            candidate(&[0.5, 0.5], 0.0),
+        ]
+    }
+
+    fn order(method: &DecisionMethod, candidates: &[Candidate]) -> Vec<usize> {
+        rank(method, &names(), candidates).iter().map(|ranked| ranked.index).collect()
+    }
+
+    #[test]
+    fn test_knee_and_sum() {
+        assert_eq!(order(&DecisionMethod::Knee, &front()), vec![1, 0, 2, 3]);
+        assert_eq!(order(&DecisionMethod::Sum, &front()), vec![1, 0, 2, 3]);
+
+        let ranking = rank(&DecisionMethod::Knee, &names(), &front());
+        assert_eq!(ranking[0].front, 0);
+        assert_eq!(ranking[3].front, 1);
+    }
+
+    #[test]
+    fn test_weighted_methods() {
+        // a heavy f1 prefers the point that is best in f1
+        let tchebycheff = DecisionMethod::Tchebycheff { weights: HashMap::from([("f1".to_string(), 10.0)]) };
+        assert_eq!(order(&tchebycheff, &front()), vec![0, 1, 2, 3]);
+
+        // aspiring to the worst f2 leaves f1 to decide
+        let reference = DecisionMethod::ReferencePoint { aspiration: HashMap::from([("f2".to_string(), 1.0)]) };
+        assert_eq!(order(&reference, &front()), vec![0, 1, 2, 3]);
+
+        let lexicographic = DecisionMethod::Lexicographic { order: vec!["f2".to_string()], tolerance: 0.0 };
+        assert_eq!(order(&lexicographic, &front()), vec![2, 1, 0, 3]);
+    }
+
+    #[test]
+    fn test_infeasible_last() {
+        let mut candidates = front();
+        candidates[1].violation = 1.0;
+
+        assert_eq!(order(&DecisionMethod::Knee, &candidates), vec![0, 2, 3, 1]);
+    }
+
+    #[test]
+    fn test_improves() {
+        let deployed = candidate(&[0.5, 0.5], 0.0);
+        let better = candidate(&[0.3, 0.3], 0.0);
+
+        assert!(improves(&DecisionMethod::Knee, &names(), &deployed, &better, 0.1));
+        // 0.3 is not 50% below 0.5
+        assert!(!improves(&DecisionMethod::Knee, &names(), &deployed, &better, 0.5));
+        // the same objectives do not replace the deployed placement
+        assert!(!improves(&DecisionMethod::Knee, &names(), &deployed, &deployed, 0.0));
+        // neither does a worse one
+        assert!(!improves(&DecisionMethod::Knee, &names(), &better, &deployed, 0.0));
+    }
+
+    #[test]
+    fn test_improves_trade_off() {
+        let deployed = candidate(&[0.2, 0.6], 0.0);
+        let chosen = candidate(&[0.6, 0.1], 0.0);
+
+        // the method decides between two points of the same front
+        assert!(improves(&DecisionMethod::Sum, &names(), &deployed, &chosen, 0.1));
+
+        let lexicographic = DecisionMethod::Lexicographic { order: vec!["f1".to_string()], tolerance: 0.0 };
+        assert!(!improves(&lexicographic, &names(), &deployed, &chosen, 0.1));
+    }
+
+    #[test]
+    fn test_improves_violation() {
+        // the same objectives without the violation of the deployed placement
+        let deployed = candidate(&[0.3, 0.3], 2.0);
+        let chosen = candidate(&[0.3, 0.3], 0.0);
+
+        assert!(improves(&DecisionMethod::Knee, &names(), &deployed, &chosen, 0.5));
+        assert!(!improves(&DecisionMethod::Knee, &names(), &chosen, &deployed, 0.5));
+    }
+}
@@ -2,7 +2,8 @@ use clap::{Arg, Command, ArgAction};
 use yonga::docker_client::DockerClient;
 //use yonga::stack::StackConfig;
 
-fn main() {
+#[tokio::main]
+async fn main() {
     let matches = Command::new("YongaDeploy")
         .arg(Arg::new("container")
             .long("container")
@@ -49,41 +50,45 @@ fn main() {
     let docker_client = DockerClient::new();
 
     if let Some(container) = matches.get_one::<String>("container") {
-        handle_container(container, &matches, &docker_client);
+        handle_container(container, &matches, &docker_client).await;
     }
 
     if let Some(stack) = matches.get_one::<String>("stack") {
-        handle_stack(stack, &matches, &docker_client);
+        handle_stack(stack, &matches, &docker_client).await;
     }
 
     if let Some(service) = matches.get_one::<String>("service") {
-        handle_service(service, &matches, &docker_client);
+        handle_service(service, &matches, &docker_client).await;
     }
 }
 
-fn handle_container(container: &str, matches: &clap::ArgMatches, docker_client: &DockerClient) {
+async fn handle_container(container: &str, matches: &clap::ArgMatches, docker_client: &DockerClient) {
     match container {
-        "ls" => match docker_client.list_containers() {
-            Ok(containers) => println!("{}", containers),
+        "ls" => match docker_client.list_containers().await {
+            Ok(containers) => {
+                for container in containers {
+                    println!("{}\t{}\t{}\t{}", &container.id[..12.min(container.id.len())], container.names.join(","), container.image, container.status);
+                }
+            }
             Err(e) => eprintln!("Error listing containers: {}", e),
         },
         "start" => match matches.get_one::<String>("id") {
-            Some(id) => match docker_client.start_container(id) {
-                Ok(output) => println!("{}", output),
+            Some(id) => match docker_client.start_container(id).await {
+                Ok(()) => println!("{}", id),
                 Err(e) => eprintln!("Error starting container: {}", e),
             },
             None => eprintln!("Container ID is required to start a container."),
         },
         "stop" => match matches.get_one::<String>("id") {
-            Some(id) => match docker_client.stop_container(id) {
-                Ok(output) => println!("{}", output),
+            Some(id) => match docker_client.stop_container(id).await {
+                Ok(()) => println!("{}", id),
                 Err(e) => eprintln!("Error stopping container: {}", e),
             },
             None => eprintln!("Container ID is required to stop a container."),
         },
         "rm" => match matches.get_one::<String>("id") {
-            Some(id) => match docker_client.remove_container(id) {
-                Ok(output) => println!("{}", output),
+            Some(id) => match docker_client.remove_container(id).await {
+                Ok(()) => println!("{}", id),
                 Err(e) => eprintln!("Error removing container: {}", e),
             },
             None => eprintln!("Container ID is required to remove a container."),
@@ -92,7 +97,7 @@ fn handle_container(container: &str, matches: &clap::ArgMatches, docker_client:
     }
 }
 
-fn handle_stack(stack: &str, matches: &clap::ArgMatches, docker_client: &DockerClient) {
+async fn handle_stack(stack: &str, matches: &clap::ArgMatches, docker_client: &DockerClient) {
     match stack {
         "deploy" => match (matches.get_one::<String>("name"), matches.get_one::<String>("file")) {
             (Some(name), Some(file)) => match docker_client.deploy_stack(name, file) {
@@ -102,15 +107,33 @@ fn handle_stack(stack: &str, matches: &clap::ArgMatches, docker_client: &DockerC
             _ => eprintln!("Stack name and compose file are required to deploy a stack."),
         },
         "ls" => match matches.get_one::<String>("name") {
-            Some(name) => match docker_client.list_stack_services(name) {
-                Ok(services) => println!("{}", services),
+            Some(name) => match docker_client.list_stack_services(name).await {
+                Ok(services) => {
+                    for service in services {
+                        let replicas = service.replicas().map(|r| r.to_string()).unwrap_or("global".to_string());
+                        println!("{}\t{}\t{}\t{:?}", &service.id[..12.min(service.id.len())], service.name(), replicas, service.constraints());
+                    }
+                }
                 Err(e) => eprintln!("Error listing stack services: {}", e),
             },
             None => eprintln!("Stack name is required to list stack services."),
         },
         "rm" => match matches.get_one::<String>("name") {
-            Some(name) => match docker_client.remove_stack(name) {
-                Ok(output) => println!("{}", output),
+            Some(name) => match docker_client.remove_stack(name).await {
+                Ok(resources) => {
+                    for service in &resources.services {
+                        println!("Removing service {}", service.name());
+                    }
+                    for network in &resources.networks {
+                        println!("Removing network {}", network.name);
+                    }
+                    for secret in &resources.secrets {
+                        println!("Removing secret {}", secret.name);
+                    }
+                    for config in &resources.configs {
+                        println!("Removing config {}", config.name);
+                    }
+                }
                 Err(e) => eprintln!("Error removing stack: {}", e),
             },
             None => eprintln!("Stack name is required to remove a stack."),
@@ -119,22 +142,36 @@ fn handle_stack(stack: &str, matches: &clap::ArgMatches, docker_client: &DockerC
     }
 }
 
-fn handle_service(service: &str, matches: &clap::ArgMatches, docker_client: &DockerClient) {
+async fn handle_service(service: &str, matches: &clap::ArgMatches, docker_client: &DockerClient) {
     match service {
         "scale" => match (matches.get_one::<String>("name"), matches.get_one::<String>("replica")) {
-            (Some(name), Some(replica)) => match docker_client.scale_service(name, replica) {
-                Ok(output) => println!("{}", output),
-                Err(e) => eprintln!("Error scaling service: {}", e),
+            (Some(name), Some(replica)) => match replica.parse::<u64>() {
+                Ok(replicas) => match docker_client.scale_service(name, replicas).await {
+                    Ok(()) => println!("{} scaled to {}", name, replicas),
+                    Err(e) => eprintln!("Error scaling service: {}", e),
+                },
+                Err(e) => eprintln!("Invalid replica count {}: {}", replica, e),
             },
             _ => eprintln!("Service name and replica count are required to scale a service."),
         },
         "update" => match (matches.get_one::<String>("name"), matches.get_one::<String>("constraint")) {
-            (Some(name), Some(constraint)) => match docker_client.update_placement(name, constraint) {
-                Ok(output) => println!("{}", output),
+            (Some(name), Some(constraint)) => match docker_client.update_placement(name, constraint).await {
+                Ok(()) => println!("{} updated with constraint {}", name, constraint),
                 Err(e) => eprintln!("Error updating service placement: {}", e),
             },
             _ => eprintln!("Service name and constraint are required to update service placement."),
         },
+        "ps" => match matches.get_one::<String>("name") {
+            Some(name) => match docker_client.list_tasks(name).await {
+                Ok(tasks) => {
+                    for task in tasks {
+                        println!("{}\t{}\t{}\t{}\t{}", &task.id[..12.min(task.id.len())], task.node_id.unwrap_or_default(), task.desired_state, task.status.state, task.status.err.unwrap_or_default());
+                    }
+                }
+                Err(e) => eprintln!("Error listing service tasks: {}", e),
+            },
+            None => eprintln!("Service name is required to list its tasks."),
+        },
         _ => eprintln!("Invalid service command."),
     }
 }
\ No newline at end of file
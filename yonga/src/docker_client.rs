@@ -1,117 +1,794 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::process::Command;
 use std::str;
 
+use chrono::{DateTime, Utc};
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::{Method, Request};
+use hyper_util::client::legacy::Client;
+use hyperlocal::{UnixClientExt, UnixConnector, Uri};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
 use crate::stack::StackConfig;
 
-pub struct DockerClient;
+pub const DOCKER_SOCKET: &str = "/var/run/docker.sock";
+
+// the Engine API version - Docker 20.10 and later
+const API_VERSION: &str = "v1.41";
+
+// the label docker stack deploy puts on every resource of a stack
+const STACK_NAMESPACE_LABEL: &str = "com.docker.stack.namespace";
+
+#[derive(Debug)]
+pub enum DockerError {
+    // the daemon could not be reached
+    Connection(String),
+    // the daemon answered with an error status
+    Api { status: u16, message: String },
+    // the response could not be decoded
+    Decode(String),
+    // a docker CLI command failed
+    Command(String),
+}
+
+impl fmt::Display for DockerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DockerError::Connection(e) => write!(f, "Failed to reach the docker daemon: {}", e),
+            DockerError::Api { status, message } => write!(f, "Docker API error ({}): {}", status, message),
+            DockerError::Decode(e) => write!(f, "Failed to decode the docker response: {}", e),
+            DockerError::Command(e) => write!(f, "Docker command failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for DockerError {}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObjectVersion {
+    #[serde(rename = "Index")]
+    pub index: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Container {
+    #[serde(rename = "Id")]
+    pub id: String,
+    #[serde(rename = "Names", default)]
+    pub names: Vec<String>,
+    #[serde(rename = "Image", default)]
+    pub image: String,
+    #[serde(rename = "State", default)]
+    pub state: String,
+    #[serde(rename = "Status", default)]
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SwarmService {
+    #[serde(rename = "ID")]
+    pub id: String,
+    #[serde(rename = "Version")]
+    pub version: ObjectVersion,
+    // kept as is so that updates send back every field of the spec
+    #[serde(rename = "Spec")]
+    pub spec: Value,
+    // the state of the last update - absent if the service was never updated
+    #[serde(rename = "UpdateStatus")]
+    pub update_status: Option<UpdateStatus>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateStatus {
+    // updating, paused, completed, rollback_started, rollback_paused or rollback_completed
+    #[serde(rename = "State", default)]
+    pub state: String,
+    #[serde(rename = "Message", default)]
+    pub message: String,
+    // the status stays on the service once the update ended, until the next one
+    #[serde(rename = "StartedAt", default)]
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+impl UpdateStatus {
+    // the update stopped or was reverted - the service will not reach the new spec by waiting
+    pub fn is_failed(&self) -> bool {
+        self.state == "paused" || self.state.starts_with("rollback_")
+    }
+}
+
+impl SwarmService {
+    pub fn name(&self) -> &str {
+        self.spec["Name"].as_str().unwrap_or_default()
+    }
+
+    pub fn replicas(&self) -> Option<u64> {
+        self.spec["Mode"]["Replicated"]["Replicas"].as_u64()
+    }
+
+    pub fn constraints(&self) -> Vec<String> {
+        string_array(&self.spec["TaskTemplate"]["Placement"]["Constraints"])
+    }
+
+    pub fn max_replicas_per_node(&self) -> Option<u64> {
+        self.spec["TaskTemplate"]["Placement"]["MaxReplicas"].as_u64()
+    }
+
+    pub fn stack(&self) -> Option<&str> {
+        self.spec["Labels"][STACK_NAMESPACE_LABEL].as_str()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaskStatus {
+    #[serde(rename = "State")]
+    pub state: String,
+    #[serde(rename = "Message", default)]
+    pub message: String,
+    #[serde(rename = "Err")]
+    pub err: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Task {
+    #[serde(rename = "ID")]
+    pub id: String,
+    #[serde(rename = "ServiceID")]
+    pub service_id: String,
+    #[serde(rename = "NodeID")]
+    pub node_id: Option<String>,
+    #[serde(rename = "Slot")]
+    pub slot: Option<u64>,
+    #[serde(rename = "DesiredState")]
+    pub desired_state: String,
+    #[serde(rename = "Status")]
+    pub status: TaskStatus,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NodeDescription {
+    #[serde(rename = "Hostname", default)]
+    pub hostname: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NodeStatus {
+    #[serde(rename = "State")]
+    pub state: String,
+    #[serde(rename = "Addr")]
+    pub addr: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SwarmNode {
+    #[serde(rename = "ID")]
+    pub id: String,
+    #[serde(rename = "Version")]
+    pub version: ObjectVersion,
+    #[serde(rename = "Spec")]
+    pub spec: Value,
+    #[serde(rename = "Description")]
+    pub description: NodeDescription,
+    #[serde(rename = "Status")]
+    pub status: NodeStatus,
+}
+
+impl SwarmNode {
+    pub fn labels(&self) -> HashMap<String, String> {
+        self.spec["Labels"].as_object()
+            .map(|labels| labels.iter().filter_map(|(key, value)| value.as_str().map(|value| (key.clone(), value.to_string()))).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn availability(&self) -> &str {
+        self.spec["Availability"].as_str().unwrap_or_default()
+    }
+
+    // the name the placement constraints use - the name label, or the hostname without one
+    pub fn name(&self) -> String {
+        self.labels().get("name").cloned().unwrap_or_else(|| self.description.hostname.clone())
+    }
+}
+
+// A network, secret or config of a stack
+#[derive(Debug, Clone)]
+pub struct StackObject {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct StackResources {
+    pub services: Vec<SwarmService>,
+    pub networks: Vec<StackObject>,
+    pub secrets: Vec<StackObject>,
+    pub configs: Vec<StackObject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NetworkSummary {
+    #[serde(rename = "Id")]
+    id: String,
+    #[serde(rename = "Name")]
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpecSummary {
+    #[serde(rename = "Name", default)]
+    name: String,
+}
+
+// secrets and configs
+#[derive(Debug, Deserialize)]
+struct ObjectSummary {
+    #[serde(rename = "ID")]
+    id: String,
+    #[serde(rename = "Spec")]
+    spec: SpecSummary,
+}
+
+// A client of the Docker Engine API over the unix socket of the daemon
+#[derive(Debug, Clone)]
+pub struct DockerClient {
+    client: Client<UnixConnector, Full<Bytes>>,
+    socket: String,
+}
+
+impl Default for DockerClient {
+    fn default() -> Self {
+        DockerClient::new()
+    }
+}
 
 impl DockerClient {
     pub fn new() -> Self {
-        DockerClient
+        Self::with_socket(DOCKER_SOCKET)
+    }
+
+    // A client of the daemon (or a fake one) listening on another socket
+    pub fn with_socket(socket: &str) -> Self {
+        DockerClient {
+            client: Client::unix(),
+            socket: socket.to_string(),
+        }
+    }
+
+    // Helper function to send a request and return the body of a successful response
+    async fn request(&self, method: Method, path: &str, body: Option<Value>) -> Result<Bytes, DockerError> {
+        let uri: hyper::Uri = Uri::new(&self.socket, &format!("/{}{}", API_VERSION, path)).into();
+
+        let body = match body {
+            Some(body) => Full::new(Bytes::from(body.to_string())),
+            None => Full::new(Bytes::new()),
+        };
+
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json")
+            .body(body)
+            .map_err(|e| DockerError::Connection(e.to_string()))?;
+
+        let response = self.client.request(request).await.map_err(|e| DockerError::Connection(e.to_string()))?;
+        let status = response.status();
+
+        let bytes = response.into_body().collect().await
+            .map_err(|e| DockerError::Connection(e.to_string()))?
+            .to_bytes();
+
+        if !status.is_success() {
+            // the daemon explains errors as {"message": "..."}
+            let message = serde_json::from_slice::<Value>(&bytes).ok()
+                .and_then(|error| error["message"].as_str().map(str::to_string))
+                .unwrap_or_else(|| String::from_utf8_lossy(&bytes).to_string());
+
+            return Err(DockerError::Api { status: status.as_u16(), message });
+        }
+
+        Ok(bytes)
+    }
+
+    // Helper function to handle GET requests and parse JSON responses
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, DockerError> {
+        let bytes = self.request(Method::GET, path, None).await?;
+        serde_json::from_slice(&bytes).map_err(|e| DockerError::Decode(e.to_string()))
+    }
+
+    // Containers
+
+    pub async fn list_containers(&self) -> Result<Vec<Container>, DockerError> {
+        self.get_json("/containers/json?all=true").await
+    }
+
+    pub async fn start_container(&self, container_id: &str) -> Result<(), DockerError> {
+        self.request(Method::POST, &format!("/containers/{}/start", container_id), None).await?;
+        Ok(())
+    }
+
+    pub async fn stop_container(&self, container_id: &str) -> Result<(), DockerError> {
+        self.request(Method::POST, &format!("/containers/{}/stop", container_id), None).await?;
+        Ok(())
+    }
+
+    pub async fn remove_container(&self, container_id: &str) -> Result<(), DockerError> {
+        self.request(Method::DELETE, &format!("/containers/{}", container_id), None).await?;
+        Ok(())
+    }
+
+    // Services
+
+    pub async fn list_services(&self) -> Result<Vec<SwarmService>, DockerError> {
+        self.get_json("/services").await
+    }
+
+    // the service by name or id
+    pub async fn inspect_service(&self, service_name: &str) -> Result<SwarmService, DockerError> {
+        self.get_json(&format!("/services/{}", service_name)).await
+    }
+
+    // replace the spec of a service - the version must be the one the spec was read at
+    pub async fn update_service(&self, service: &SwarmService, spec: Value) -> Result<(), DockerError> {
+        let path = format!("/services/{}/update?version={}", service.id, service.version.index);
+        let bytes = self.request(Method::POST, &path, Some(spec)).await?;
+
+        if let Ok(response) = serde_json::from_slice::<Value>(&bytes) {
+            for warning in string_array(&response["Warnings"]) {
+                println!("Warning updating service {}: {}", service.name(), warning);
+            }
+        }
+
+        Ok(())
+    }
+
+    // docker service scale
+    pub async fn scale_service(&self, service_name: &str, replicas: u64) -> Result<(), DockerError> {
+        let service = self.inspect_service(service_name).await?;
+
+        if service.replicas().is_none() {
+            return Err(DockerError::Api { status: 400, message: format!("Service {} is not in replicated mode", service_name) });
+        }
+
+        let mut spec = service.spec.clone();
+        spec["Mode"]["Replicated"]["Replicas"] = json!(replicas);
+
+        self.update_service(&service, spec).await
+    }
+
+    // add or update a placement constraint
+    pub async fn update_placement(&self, service_name: &str, constraint: &str) -> Result<(), DockerError> {
+        self.move_placement(service_name, &[], &[constraint.to_string()], None).await
+    }
+
+    // remove a placement constraint
+    pub async fn remove_placement(&self, service_name: &str, constraint: &str) -> Result<(), DockerError> {
+        self.move_placement(service_name, &[constraint.to_string()], &[], None).await
+    }
+
+    // swap placement constraints (and the replicas per node) in a single service update - one rolling restart
+    pub async fn move_placement(&self, service_name: &str, remove: &[String], add: &[String], max_replicas_per_node: Option<u64>) -> Result<(), DockerError> {
+        let service = self.inspect_service(service_name).await?;
+
+        let mut constraints: Vec<String> = service.constraints().into_iter().filter(|c| !remove.contains(c)).collect();
+        for constraint in add {
+            if !constraints.contains(constraint) {
+                constraints.push(constraint.clone());
+            }
+        }
+
+        let mut spec = service.spec.clone();
+        spec["TaskTemplate"]["Placement"]["Constraints"] = json!(constraints);
+        set_max_replicas(&mut spec, max_replicas_per_node);
+
+        self.update_service(&service, spec).await
+    }
+
+    // docker service update --force - the tasks are rescheduled under the same constraints, e.g. after
+    // the labels of the nodes they match changed
+    pub async fn reschedule_service(&self, service_name: &str, max_replicas_per_node: Option<u64>) -> Result<(), DockerError> {
+        let service = self.inspect_service(service_name).await?;
+
+        let mut spec = service.spec.clone();
+        let force_update = spec["TaskTemplate"]["ForceUpdate"].as_u64().unwrap_or(0);
+        spec["TaskTemplate"]["ForceUpdate"] = json!(force_update + 1);
+        set_max_replicas(&mut spec, max_replicas_per_node);
+
+        self.update_service(&service, spec).await
+    }
+
+    // Tasks
+
+    pub async fn list_tasks(&self, service_name: &str) -> Result<Vec<Task>, DockerError> {
+        self.get_json(&format!("/tasks?filters={}", filters("service", service_name))).await
+    }
+
+    // Nodes
+
+    pub async fn list_nodes(&self) -> Result<Vec<SwarmNode>, DockerError> {
+        self.get_json("/nodes").await
+    }
+
+    // the node by hostname or id
+    pub async fn inspect_node(&self, node: &str) -> Result<SwarmNode, DockerError> {
+        self.get_json(&format!("/nodes/{}", node)).await
+    }
+
+    pub async fn set_node_labels(&self, node: &str, labels: HashMap<String, String>) -> Result<(), DockerError> {
+        let swarm_node = self.inspect_node(node).await?;
+
+        let mut spec = swarm_node.spec.clone();
+        spec["Labels"] = json!(labels);
+
+        let path = format!("/nodes/{}/update?version={}", swarm_node.id, swarm_node.version.index);
+        self.request(Method::POST, &path, Some(spec)).await?;
+
+        Ok(())
+    }
+
+    pub async fn add_node_label(&self, node: &str, key: &str, value: &str) -> Result<(), DockerError> {
+        let mut labels = self.inspect_node(node).await?.labels();
+        labels.insert(key.to_string(), value.to_string());
+
+        self.set_node_labels(node, labels).await
+    }
+
+    pub async fn remove_node_label(&self, node: &str, key: &str) -> Result<(), DockerError> {
+        let mut labels = self.inspect_node(node).await?.labels();
+        labels.remove(key);
+
+        self.set_node_labels(node, labels).await
+    }
+
+    // Stacks
+
+    // the services, networks, secrets and configs of a stack
+    pub async fn stack_resources(&self, stack_name: &str) -> Result<StackResources, DockerError> {
+        let label = filters("label", &format!("{}={}", STACK_NAMESPACE_LABEL, stack_name));
+
+        let services: Vec<SwarmService> = self.get_json(&format!("/services?filters={}", label)).await?;
+
+        let networks: Vec<NetworkSummary> = self.get_json(&format!("/networks?filters={}", label)).await?;
+        let secrets: Vec<ObjectSummary> = self.get_json(&format!("/secrets?filters={}", label)).await?;
+        let configs: Vec<ObjectSummary> = self.get_json(&format!("/configs?filters={}", label)).await?;
+
+        let to_stack_object = |object: ObjectSummary| StackObject { id: object.id, name: object.spec.name };
+
+        Ok(StackResources {
+            services,
+            networks: networks.into_iter().map(|network| StackObject { id: network.id, name: network.name }).collect(),
+            secrets: secrets.into_iter().map(to_stack_object).collect(),
+            configs: configs.into_iter().map(to_stack_object).collect(),
+        })
+    }
+
+    // docker list stack services
+    pub async fn list_stack_services(&self, stack_name: &str) -> Result<Vec<SwarmService>, DockerError> {
+        Ok(self.stack_resources(stack_name).await?.services)
     }
 
-    pub fn run_command(&self, args: &[&str]) -> Result<std::process::Output, String> {
+    // docker stack rm - services first, then the networks, secrets and configs they used
+    pub async fn remove_stack(&self, stack_name: &str) -> Result<StackResources, DockerError> {
+        let resources = self.stack_resources(stack_name).await?;
+
+        for service in &resources.services {
+            self.request(Method::DELETE, &format!("/services/{}", service.id), None).await?;
+        }
+        for network in &resources.networks {
+            self.request(Method::DELETE, &format!("/networks/{}", network.id), None).await?;
+        }
+        for secret in &resources.secrets {
+            self.request(Method::DELETE, &format!("/secrets/{}", secret.id), None).await?;
+        }
+        for config in &resources.configs {
+            self.request(Method::DELETE, &format!("/configs/{}", config.id), None).await?;
+        }
+
+        Ok(resources)
+    }
+
+    // docker stack deploy hotelreservation -c hotelreservation.yml
+    // The Engine API has no stack endpoint - the compose file is translated by the docker CLI.
+    pub fn deploy_stack(&self, stack_name: &str, compose_file: &str) -> Result<String, DockerError> {
         let output = Command::new("docker")
-            .args(args)
+            .args(["stack", "deploy", stack_name, "-c", compose_file])
             .output()
-            .map_err(|e| e.to_string())?;
-        
+            .map_err(|e| DockerError::Command(e.to_string()))?;
+
         if !output.status.success() {
-            return Err(format!("Command failed with status: {}", output.status));
+            return Err(DockerError::Command(format!("stack deploy failed with status {}: {}", output.status, String::from_utf8_lossy(&output.stderr))));
         }
 
-        Ok(output)
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
-    pub fn list_containers(&self) -> Result<String, String> {
-        let output = self.run_command(&["ps", "-a"])?;
+    pub fn deploy_stack_config(&self, stack_name: &str, stack_config: &StackConfig) -> Result<String, DockerError> {
+        let stack_yaml = serde_yaml::to_string(stack_config).map_err(|e| DockerError::Command(e.to_string()))?;
 
-        let stdout = str::from_utf8(&output.stdout)
-            .map_err(|_| "Failed to parse output as UTF-8".to_string())?
-            .to_string();
+        let compose_file = std::env::temp_dir().join(format!("{}.yml", stack_name));
+        std::fs::write(&compose_file, stack_yaml).map_err(|e| DockerError::Command(e.to_string()))?;
 
-        Ok(stdout)
+        let result = self.deploy_stack(stack_name, &compose_file.to_string_lossy());
+
+        let _ = std::fs::remove_file(&compose_file);
+
+        result
     }
+}
 
-    pub fn start_container(&self, container_id: &str) -> Result<String, String> {
-        let output = self.run_command(&["start", container_id])?;
+// the filters query parameter of the Engine API, e.g. {"label":["key=value"]}
+fn filters(key: &str, value: &str) -> String {
+    percent_encode(&json!({ key: [value] }).to_string())
+}
+
+fn percent_encode(value: &str) -> String {
+    value.bytes().map(|byte| match byte {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+        _ => format!("%{:02X}", byte),
+    }).collect()
+}
 
-        let stdout = str::from_utf8(&output.stdout)
-            .map_err(|_| "Failed to parse output as UTF-8".to_string())?
-            .to_string();
+fn string_array(value: &Value) -> Vec<String> {
+    value.as_array()
+        .map(|values| values.iter().filter_map(|value| value.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
 
-        Ok(stdout)
+// the replicas per node of a service spec - none lifts the limit
+fn set_max_replicas(spec: &mut Value, max_replicas_per_node: Option<u64>) {
+    if let Some(placement) = spec["TaskTemplate"]["Placement"].as_object_mut() {
+        match max_replicas_per_node {
+            Some(max_replicas) => placement.insert("MaxReplicas".to_string(), json!(max_replicas)),
+            None => placement.remove("MaxReplicas"),
+        };
     }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
 
-    pub fn stop_container(&self, container_id: &str) -> Result<String, String> {
-        let output = self.run_command(&["stop", container_id])?;
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{UnixListener, UnixStream};
 
-        let stdout = str::from_utf8(&output.stdout)
-            .map_err(|_| "Failed to parse output as UTF-8".to_string())?
-            .to_string();
+    use crate::docker_client::{DockerClient, DockerError};
 
-        Ok(stdout)
+    // A request received by the fake daemon
+    #[derive(Debug, Clone)]
+    struct Received {
+        method: String,
+        path: String,
+        body: Option<Value>,
     }
 
-    pub fn remove_container(&self, container_id: &str) -> Result<String, String> {
-        let output = self.run_command(&["rm", container_id])?;
+    // A fake daemon on a temporary socket - every request is answered with the canned status and body
+    // of its method and path
+    pub(crate) struct FakeEngine {
+        socket: PathBuf,
+        received: Arc<Mutex<Vec<Received>>>,
+    }
 
-        let stdout = str::from_utf8(&output.stdout)
-            .map_err(|_| "Failed to parse output as UTF-8".to_string())?
-            .to_string();
+    impl FakeEngine {
+        pub(crate) fn start(name: &str, respond: fn(&str, &str) -> (u16, String)) -> Self {
+            let socket = std::env::temp_dir().join(format!("yonga-{}-{}.sock", name, std::process::id()));
+            let _ = std::fs::remove_file(&socket);
 
-        Ok(stdout)
-    }   
+            let listener = UnixListener::bind(&socket).unwrap();
+            let received = Arc::new(Mutex::new(Vec::new()));
+            let log = received.clone();
 
-    // docker stack deploy hotelreservation -c hotelreservation.yml
-    pub fn deploy_stack(&self, stack_name: &str, compose_file: &str) -> Result<String, String> {
-        let output = self.run_command(&["stack", "deploy", stack_name, "-c", compose_file])?;
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let request = read_request(&mut stream).await;
+                    let (status, body) = respond(&request.method, &request.path);
+                    log.lock().unwrap().push(request);
+
+                    let response = format!(
+                        "HTTP/1.1 {} Fake\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status, body.len(), body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                }
+            });
+
+            FakeEngine { socket, received }
+        }
+
+        pub(crate) fn client(&self) -> DockerClient {
+            DockerClient::with_socket(self.socket.to_str().unwrap())
+        }
 
-        Ok(str::from_utf8(&output.stdout)
-            .unwrap_or("Failed to parse output")
-            .to_string())
+        fn received(&self) -> Vec<Received> {
+            self.received.lock().unwrap().clone()
+        }
+    }
+
+    impl Drop for FakeEngine {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.socket);
+        }
     }
 
-    pub fn deploy_stack_config(&self, stack_name: &str, stack_config: &StackConfig) -> Result<String, String> {
-        let stack_yaml = serde_yaml::to_string(stack_config).unwrap();
+    // Read the request line, the headers and the body of a request
+    async fn read_request(stream: &mut UnixStream) -> Received {
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut chunk = [0u8; 4096];
+
+        let header_end = loop {
+            if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                break end + 4;
+            }
+            let read = stream.read(&mut chunk).await.unwrap();
+            if read == 0 {
+                break buffer.len();
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        };
+
+        let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+        let mut lines = head.lines();
+        let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+        let method = request_line.next().unwrap_or_default().to_string();
+        let path = request_line.next().unwrap_or_default().to_string();
 
-        self.deploy_stack(stack_name, &stack_yaml)
+        let length = lines
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+
+        while buffer.len() < header_end + length {
+            let read = stream.read(&mut chunk).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        }
+
+        Received { method, path, body: serde_json::from_slice(&buffer[header_end..]).ok() }
     }
 
-    // docker list stack services
-    pub fn list_stack_services(&self, stack_name: &str) -> Result<String, String> {
-        let output = self.run_command(&["stack", "services", stack_name])?;
+    fn service() -> Value {
+        json!({
+            "ID": "web-id",
+            "Version": { "Index": 42 },
+            "Spec": {
+                "Name": "stack_web",
+                "Labels": { "com.docker.stack.namespace": "stack" },
+                "Mode": { "Replicated": { "Replicas": 2 } },
+                "TaskTemplate": {
+                    "Placement": {
+                        "Constraints": ["node.labels.name == node1", "node.role == worker"],
+                        "MaxReplicas": 2
+                    }
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn test_list_tasks() {
+        let engine = FakeEngine::start("list-tasks", |_, _| {
+            (200, json!([
+                {
+                    "ID": "task-1", "ServiceID": "web-id", "NodeID": "node-1", "Slot": 1, "DesiredState": "running",
+                    "Status": { "State": "running", "Message": "started" }
+                },
+                {
+                    "ID": "task-2", "ServiceID": "web-id", "Slot": 2, "DesiredState": "running",
+                    "Status": { "State": "pending", "Err": "no suitable node" }
+                }
+            ]).to_string())
+        });
+
+        let tasks = engine.client().list_tasks("stack_web").await.unwrap();
 
-        Ok(str::from_utf8(&output.stdout)
-            .unwrap_or("Failed to parse output")
-            .to_string())
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].node_id.as_deref(), Some("node-1"));
+        assert_eq!(tasks[0].status.state, "running");
+        assert_eq!(tasks[1].node_id, None);
+        assert_eq!(tasks[1].status.err.as_deref(), Some("no suitable node"));
+
+        // the filters are percent-encoded JSON
+        let received = engine.received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].method, "GET");
+        assert_eq!(received[0].path, "/v1.41/tasks?filters=%7B%22service%22%3A%5B%22stack_web%22%5D%7D");
     }
 
-    // docker stack rm
-    pub fn remove_stack(&self, stack_name: &str) -> Result<String, String> {
-        let output = self.run_command(&["stack", "rm", stack_name])?;
+    #[tokio::test]
+    async fn test_move_placement() {
+        let engine = FakeEngine::start("move-placement", |method, path| match (method, path) {
+            ("GET", "/v1.41/services/stack_web") => (200, service().to_string()),
+            ("POST", "/v1.41/services/web-id/update?version=42") => (200, json!({ "Warnings": null }).to_string()),
+            _ => (404, json!({ "message": "unexpected request" }).to_string()),
+        });
+
+        let remove = vec!["node.labels.name == node1".to_string()];
+        let add = vec!["node.labels.ocas.web == true".to_string()];
+        engine.client().move_placement("stack_web", &remove, &add, Some(1)).await.unwrap();
+
+        let received = engine.received();
+        assert_eq!(received.len(), 2);
 
-        Ok(str::from_utf8(&output.stdout)
-            .unwrap_or("Failed to parse output")
-            .to_string())
+        // the update is sent at the version the spec was read at
+        let update = &received[1];
+        assert_eq!(update.method, "POST");
+        assert_eq!(update.path, "/v1.41/services/web-id/update?version=42");
+
+        // the constraint is swapped, the rest of the spec is sent back as is
+        let spec = update.body.as_ref().unwrap();
+        assert_eq!(spec["TaskTemplate"]["Placement"]["Constraints"], json!(["node.role == worker", "node.labels.ocas.web == true"]));
+        assert_eq!(spec["TaskTemplate"]["Placement"]["MaxReplicas"], json!(1));
+        assert_eq!(spec["Name"], json!("stack_web"));
+        assert_eq!(spec["Mode"]["Replicated"]["Replicas"], json!(2));
     }
 
-    // docker service scale
-    pub fn scale_service(&self, service_name: &str, replicas: &str) -> Result<String, String> {
-        let output = self.run_command(&["service", "scale", service_name, "=", replicas])?;
+    #[tokio::test]
+    async fn test_move_placement_without_max_replicas() {
+        let engine = FakeEngine::start("move-placement-unlimited", |method, _| match method {
+            "GET" => (200, service().to_string()),
+            _ => (200, String::new()),
+        });
+
+        engine.client().move_placement("stack_web", &[], &["node.role == worker".to_string()], None).await.unwrap();
 
-        Ok(str::from_utf8(&output.stdout)
-            .unwrap_or("Failed to parse output")
-            .to_string())
+        // no limit per node, and no constraint added twice
+        let spec = engine.received()[1].body.clone().unwrap();
+        assert_eq!(spec["TaskTemplate"]["Placement"]["Constraints"], json!(["node.labels.name == node1", "node.role == worker"]));
+        assert!(spec["TaskTemplate"]["Placement"].get("MaxReplicas").is_none());
     }
 
-    // add or update a placement constraint
-    pub fn update_placement(&self, service_name: &str, constraint: &str) -> Result<String, String> {
-        let output = self.run_command(&["service", "update", "--constraint-add", constraint, service_name])?;
+    #[tokio::test]
+    async fn test_api_error() {
+        let engine = FakeEngine::start("api-error", |_, path| match path {
+            "/v1.41/services/missing" => (404, json!({ "message": "service missing not found" }).to_string()),
+            _ => (500, "daemon exploded".to_string()),
+        });
+
+        // the message of a JSON error body
+        match engine.client().inspect_service("missing").await {
+            Err(DockerError::Api { status, message }) => {
+                assert_eq!(status, 404);
+                assert_eq!(message, "service missing not found");
+            }
+            other => panic!("Expected an API error, got {:?}", other),
+        }
+
+        // any other body is the message as is
+        match engine.client().list_nodes().await {
+            Err(DockerError::Api { status, message }) => {
+                assert_eq!(status, 500);
+                assert_eq!(message, "daemon exploded");
+            }
+            other => panic!("Expected an API error, got {:?}", other),
+        }
+    }
 
-        Ok(str::from_utf8(&output.stdout)
-            .unwrap_or("Failed to parse output")
-            .to_string())
-    }   
+    #[tokio::test]
+    async fn test_decode_error() {
+        let engine = FakeEngine::start("decode-error", |_, _| (200, json!({ "not": "a list" }).to_string()));
 
+        match engine.client().list_tasks("stack_web").await {
+            Err(DockerError::Decode(_)) => {}
+            other => panic!("Expected a decode error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connection_error() {
+        let socket = std::env::temp_dir().join(format!("yonga-no-daemon-{}.sock", std::process::id()));
+        let client = DockerClient::with_socket(socket.to_str().unwrap());
+
+        match client.list_nodes().await {
+            Err(DockerError::Connection(_)) => {}
+            other => panic!("Expected a connection error, got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,334 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use mongodb::options::{FindOneOptions, FindOptions, IndexOptions};
+use mongodb::{Client, Collection, Database, IndexModel};
+use mongodb::bson::doc;
+use futures::stream::StreamExt;
+use crate::strategy::PlacementMap;
+use crate::utility;
+
+// the collection holding the placement revisions
+pub const PLACEMENT_COLLECTION: &str = "placements";
+
+// What happened to a revision once it was deployed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DeployOutcome {
+    // every service runs its replicas
+    Converged,
+    // deployed, but the tasks could not be watched
+    Unverified,
+    // the revision failed and the stack went back to an earlier one
+    RolledBack { reason: String, to: u32 },
+    // the revision failed and nothing could be rolled back to
+    Failed { reason: String },
+}
+
+impl fmt::Display for DeployOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeployOutcome::Converged => write!(f, "converged"),
+            DeployOutcome::Unverified => write!(f, "unverified"),
+            DeployOutcome::RolledBack { reason, to } => write!(f, "{} - rolled back to revision {}", reason, to),
+            DeployOutcome::Failed { reason } => write!(f, "{}", reason),
+        }
+    }
+}
+
+// A placement revision as stored in the database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacementRecord {
+    // the stack the revision was deployed to - revisions are numbered per stack
+    pub stack: String,
+    pub revision: u32,
+    pub timestamp: DateTime<Utc>,
+    pub strategy: String,
+    // service name -> names of the nodes it is placed on
+    pub placement: BTreeMap<String, Vec<String>>,
+    // objective name -> value, for the strategies that optimise
+    pub objectives: BTreeMap<String, f64>,
+    pub obj_value: Option<f64>,
+    pub constraint_violation: Option<f64>,
+    pub outcome: DeployOutcome,
+}
+
+impl PlacementRecord {
+    pub fn new(stack: &str, revision: u32, strategy: &str, placement_map: &PlacementMap, outcome: DeployOutcome) -> Self {
+        PlacementRecord {
+            stack: stack.to_string(),
+            revision,
+            timestamp: Utc::now(),
+            strategy: strategy.to_string(),
+            placement: placement_names(placement_map),
+            objectives: BTreeMap::new(),
+            obj_value: None,
+            constraint_violation: None,
+            outcome,
+        }
+    }
+
+    pub fn with_objectives(mut self, objectives: &HashMap<String, f64>, obj_value: Option<f64>, constraint_violation: Option<f64>) -> Self {
+        self.objectives = objectives.iter().map(|(name, value)| (name.clone(), *value)).collect();
+        self.obj_value = obj_value;
+        self.constraint_violation = constraint_violation;
+        self
+    }
+}
+
+// A service placed differently in two revisions
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceMove {
+    pub service: String,
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlacementDiff {
+    pub from: u32,
+    pub to: u32,
+    pub moved: Vec<ServiceMove>,
+    pub unchanged: usize,
+}
+
+// Compare the placements of two revisions
+pub fn diff_records(from: &PlacementRecord, to: &PlacementRecord) -> PlacementDiff {
+    let mut services: Vec<&String> = from.placement.keys().chain(to.placement.keys()).collect();
+    services.sort();
+    services.dedup();
+
+    let mut moved = Vec::new();
+    let mut unchanged = 0;
+
+    for service in services {
+        let before = from.placement.get(service).cloned().unwrap_or_default();
+        let after = to.placement.get(service).cloned().unwrap_or_default();
+
+        if before == after {
+            unchanged += 1;
+        } else {
+            moved.push(ServiceMove { service: service.clone(), from: before, to: after });
+        }
+    }
+
+    PlacementDiff {
+        from: from.revision,
+        to: to.revision,
+        moved,
+        unchanged,
+    }
+}
+
+// service name -> sorted node names
+fn placement_names(placement_map: &PlacementMap) -> BTreeMap<String, Vec<String>> {
+    placement_map.iter().map(|(service, nodes)| {
+        let mut names: Vec<String> = nodes.iter().flatten().map(|node| node.name.clone()).collect();
+        names.sort();
+        (service.name.clone(), names)
+    }).collect()
+}
+
+// The placement revisions of a stack, kept across restarts
+#[derive(Debug, Clone)]
+pub struct PlacementHistory {
+    collection: Collection<PlacementRecord>,
+    stack: String,
+}
+
+impl PlacementHistory {
+    pub fn new(database: &Database, stack: &str) -> Self {
+        PlacementHistory {
+            collection: database.collection::<PlacementRecord>(PLACEMENT_COLLECTION),
+            stack: stack.to_string(),
+        }
+    }
+
+    pub async fn connect(database: &utility::Database, stack: &str) -> Result<Self, mongodb::error::Error> {
+        let client = Client::with_uri_str(&database.uri).await?;
+        let history = Self::new(&client.database(&database.db), stack);
+        history.ensure_index().await?;
+        Ok(history)
+    }
+
+    // a revision is recorded once per stack
+    pub async fn ensure_index(&self) -> Result<(), mongodb::error::Error> {
+        let index = IndexModel::builder()
+            .keys(doc! { "stack": 1, "revision": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+
+        self.collection.create_index(index, None).await?;
+        Ok(())
+    }
+
+    pub async fn record(&self, record: &PlacementRecord) -> Result<(), mongodb::error::Error> {
+        self.collection.insert_one(record, None).await?;
+        Ok(())
+    }
+
+    // the latest revisions first, of every strategy or only the given one
+    pub async fn list(&self, limit: i64, strategy: Option<&str>) -> Result<Vec<PlacementRecord>, mongodb::error::Error> {
+        let mut filter = doc! { "stack": &self.stack };
+        if let Some(strategy) = strategy {
+            filter.insert("strategy", strategy);
+        }
+
+        let find_options = FindOptions::builder()
+            .sort(Some(doc! { "revision": -1 }))
+            .limit(Some(limit))
+            .build();
+
+        let mut cursor = self.collection.find(filter, find_options).await?;
+        let mut records = Vec::new();
+
+        while let Some(result) = cursor.next().await {
+            records.push(result?);
+        }
+
+        Ok(records)
+    }
+
+    pub async fn get(&self, revision: u32) -> Result<Option<PlacementRecord>, mongodb::error::Error> {
+        self.collection.find_one(doc! { "stack": &self.stack, "revision": revision }, None).await
+    }
+
+    // the last revision of the stack, whichever strategy deployed it
+    pub async fn latest(&self) -> Result<Option<PlacementRecord>, mongodb::error::Error> {
+        let find_options = FindOneOptions::builder()
+            .sort(Some(doc! { "revision": -1 }))
+            .build();
+
+        self.collection.find_one(doc! { "stack": &self.stack }, find_options).await
+    }
+
+    pub async fn latest_revision(&self) -> Result<Option<u32>, mongodb::error::Error> {
+        Ok(self.latest().await?.map(|record| record.revision))
+    }
+}
+
+// The tests on the database run against YONGA_TEST_MONGO_URI, or a local mongod on the default port. They are
+// skipped if no local mongod answers, and fail if the server set in YONGA_TEST_MONGO_URI does not.
+#[cfg(test)]
+pub(crate) mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use mongodb::bson::doc;
+    use mongodb::{Client, Database};
+
+    use crate::history::{diff_records, DeployOutcome, PlacementHistory, PlacementRecord};
+    use crate::strategy::PlacementMap;
+    use crate::utility::{Node, ResourceInt, Service};
+
+    const MONGO_URI: &str = "YONGA_TEST_MONGO_URI";
+    const LOCAL_MONGO_URI: &str = "mongodb://localhost:27017/?serverSelectionTimeoutMS=1000";
+
+    // The test database, none if no server is set and no local mongod runs
+    pub(crate) async fn test_database() -> Option<Database> {
+        let (uri, required) = match std::env::var(MONGO_URI) {
+            Ok(uri) => (uri, true),
+            Err(_) => (LOCAL_MONGO_URI.to_string(), false),
+        };
+
+        let database = Client::with_uri_str(&uri).await.unwrap().database("yonga_test");
+        match database.run_command(doc! { "ping": 1 }, None).await {
+            Ok(_) => Some(database),
+            Err(e) if required => panic!("No mongo server at {}: {}", uri, e),
+            Err(_) => {
+                println!("No mongod on localhost and {} not set - skipping", MONGO_URI);
+                None
+            }
+        }
+    }
+
+    fn node(id: i64) -> Node {
+        Node::new(id, &format!("node{}", id), "127.0.0.1", ResourceInt::new(4, 8, 100_000, 1000))
+    }
+
+    fn placement(services: &[(&str, &[i64])]) -> PlacementMap {
+        services.iter()
+            .map(|(name, ids)| {
+                let nodes: HashSet<Node> = ids.iter().map(|id| node(*id)).collect();
+                (Service::new(name, name, None, None), if nodes.is_empty() { None } else { Some(nodes) })
+            })
+            .collect()
+    }
+
+    fn record(stack: &str, revision: u32, placement_map: &PlacementMap) -> PlacementRecord {
+        PlacementRecord::new(stack, revision, "yonga", placement_map, DeployOutcome::Converged)
+    }
+
+    #[test]
+    fn test_placement_names() {
+        let record = record("app", 1, &placement(&[("a", &[2, 1]), ("b", &[3]), ("c", &[])]));
+
+        assert_eq!(record.placement["a"], vec!["node1", "node2"]);
+        assert_eq!(record.placement["b"], vec!["node3"]);
+        assert!(record.placement["c"].is_empty());
+        assert!(record.objectives.is_empty());
+
+        let objectives = HashMap::from([("resource_cost".to_string(), 0.25)]);
+        let record = record.with_objectives(&objectives, Some(0.25), Some(0.0));
+        assert_eq!(record.objectives["resource_cost"], 0.25);
+        assert_eq!(record.obj_value, Some(0.25));
+    }
+
+    #[test]
+    fn test_diff_records() {
+        let from = record("app", 1, &placement(&[("a", &[1]), ("b", &[1]), ("c", &[2])]));
+        let to = record("app", 2, &placement(&[("a", &[1]), ("b", &[2]), ("d", &[3])]));
+
+        let diff = diff_records(&from, &to);
+        assert_eq!((diff.from, diff.to, diff.unchanged), (1, 2, 1));
+
+        // in the order of the services, the removed and added ones included
+        let moved: Vec<(&str, Vec<String>, Vec<String>)> = diff.moved.iter()
+            .map(|service_move| (service_move.service.as_str(), service_move.from.clone(), service_move.to.clone()))
+            .collect();
+        assert_eq!(moved, vec![
+            ("b", vec!["node1".to_string()], vec!["node2".to_string()]),
+            ("c", vec!["node2".to_string()], vec![]),
+            ("d", vec![], vec!["node3".to_string()]),
+        ]);
+    }
+
+    #[test]
+    fn test_outcome() {
+        let outcome = DeployOutcome::RolledBack { reason: "not converged".to_string(), to: 3 };
+
+        assert_eq!(outcome.to_string(), "not converged - rolled back to revision 3");
+        assert_eq!(serde_json::to_value(&outcome).unwrap(), serde_json::json!({ "status": "rolled_back", "reason": "not converged", "to": 3 }));
+        assert_eq!(serde_json::from_value::<DeployOutcome>(serde_json::json!({ "status": "converged" })).unwrap(), DeployOutcome::Converged);
+    }
+
+    #[tokio::test]
+    async fn test_revisions_per_stack() {
+        let database = match test_database().await {
+            Some(database) => database,
+            None => return,
+        };
+        let stacks: Vec<String> = ["app", "other"].iter().map(|stack| format!("test-{}-{}", stack, std::process::id())).collect();
+        let app = PlacementHistory::new(&database, &stacks[0]);
+        let other = PlacementHistory::new(&database, &stacks[1]);
+        app.ensure_index().await.unwrap();
+
+        let placement_map = placement(&[("a", &[1])]);
+        app.record(&record(&stacks[0], 1, &placement_map)).await.unwrap();
+        app.record(&record(&stacks[0], 2, &placement_map)).await.unwrap();
+
+        // the revisions are numbered per stack - once each
+        other.record(&record(&stacks[1], 1, &placement_map)).await.unwrap();
+        assert!(app.record(&record(&stacks[0], 2, &placement_map)).await.is_err());
+
+        assert_eq!(app.latest_revision().await.unwrap(), Some(2));
+        assert_eq!(other.latest_revision().await.unwrap(), Some(1));
+        assert_eq!(app.list(10, None).await.unwrap().iter().map(|record| record.revision).collect::<Vec<_>>(), vec![2, 1]);
+        assert!(app.list(10, Some("spread")).await.unwrap().is_empty());
+        assert!(other.get(2).await.unwrap().is_none());
+
+        for stack in &stacks {
+            app.collection.delete_many(doc! { "stack": stack }, None).await.unwrap();
+        }
+    }
+}
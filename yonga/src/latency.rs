@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use serde::Deserialize;
+
+use crate::node::NodeTree;
+use crate::trace::ServiceGraph;
+use crate::utility::{self, Node, Service};
+
+// name of the constraint on the p99 latency of the slowest request path
+pub const LATENCY_SLO: &str = "latency_slo";
+
+// The end-to-end latency of the longest request paths
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct Latency {
+    // add latency (the expected latency of the slowest path, relative to its worst case) as an objective
+    pub objective: bool,
+    // p99 latency (ms) the slowest path must stay under - no constraint if not set
+    pub slo: Option<f64>,
+}
+
+impl Default for Latency {
+    fn default() -> Self {
+        Latency {
+            objective: true,
+            slo: None,
+        }
+    }
+}
+
+// A longest request path of the service graph and the (mean, p99) duration of its first call in ms -
+// the span of the first call covers the calls below it
+#[derive(Debug, Clone)]
+pub struct RequestPath {
+    pub services: Vec<Service>,
+    pub duration: (f64, f64),
+}
+
+// Estimates the latency of the request paths under a placement: the duration of the first call plus the
+// latency of each call that crosses nodes
+#[derive(Debug, Clone)]
+pub struct LatencyModel {
+    pub paths: Vec<RequestPath>,
+    // the (mean, p99) latency of each link between two nodes, ms
+    pub links: HashMap<(Node, Node), (f64, f64)>,
+}
+
+impl LatencyModel {
+    // The longest paths of the service graph with the measured link latencies. A link without measurements
+    // takes its aggregated cost, scaled to the slowest measured link.
+    pub fn new(service_tree: &ServiceGraph, node_tree: &NodeTree, services: &[Service], nodes: &[Node]) -> Self {
+        let (longest_paths, _) = service_tree.longest_paths();
+
+        let paths: Vec<RequestPath> = longest_paths.iter().filter(|path| path.len() > 1).filter_map(|path| {
+            let services: Option<Vec<Service>> = path.iter().map(|name| utility::get_service_by_name(name.clone(), services)).collect();
+            let duration = service_tree.call_duration(&path[0], &path[1])?;
+            Some(RequestPath { services: services?, duration })
+        }).collect();
+
+        let pairs: Vec<(Node, Node)> = nodes.iter()
+            .flat_map(|source| nodes.iter().filter(move |destination| *destination != source).map(move |destination| (source.clone(), destination.clone())))
+            .collect();
+
+        let measured: HashMap<(Node, Node), (f64, f64)> = pairs.iter()
+            .filter_map(|(source, destination)| Some(((source.clone(), destination.clone()), node_tree.get_latency(source, destination)?)))
+            .filter(|(_, (mean, p99))| mean.is_finite() && p99.is_finite())
+            .collect();
+
+        let slowest = measured.values().fold((0.0, 0.0), |slowest: (f64, f64), (mean, p99)| (slowest.0.max(*mean), slowest.1.max(*p99)));
+        let worst_cost = node_tree.get_worst_cost();
+
+        let mut links = measured.clone();
+        for (source, destination) in &pairs {
+            if links.contains_key(&(source.clone(), destination.clone())) {
+                continue;
+            }
+
+            let share = match node_tree.get_link_cost(source, destination) {
+                Some(cost) if worst_cost > 0.0 && cost.is_finite() => cost / worst_cost,
+                _ => 1.0,
+            };
+            println!("No measured latency from {} to {} - taking {:.2} of the slowest link", source.name, destination.name, share);
+            links.insert((source.clone(), destination.clone()), (slowest.0 * share, slowest.1 * share));
+        }
+
+        LatencyModel { paths, links }
+    }
+
+    fn link(&self, source: &Node, destination: &Node) -> (f64, f64) {
+        if source == destination {
+            return (0.0, 0.0);
+        }
+        *self.links.get(&(source.clone(), destination.clone())).unwrap_or(&(0.0, 0.0))
+    }
+
+    // The expected and p99 latency (ms) of the slowest path. A call between replicated services takes the
+    // average latency of the links between their nodes. Calls to unplaced services add nothing.
+    pub fn estimate(&self, placements: &HashMap<Service, HashSet<Node>>) -> (f64, f64) {
+        self.paths.iter().fold((0.0, 0.0), |slowest, path| {
+            let (mut expected, mut p99) = path.duration;
+
+            for hop in path.services.windows(2) {
+                if let (Some(sources), Some(destinations)) = (placements.get(&hop[0]), placements.get(&hop[1])) {
+                    let pairs = (sources.len() * destinations.len()).max(1) as f64;
+
+                    for source in sources {
+                        for destination in destinations {
+                            let (mean, tail) = self.link(source, destination);
+                            expected += mean / pairs;
+                            p99 += tail / pairs;
+                        }
+                    }
+                }
+            }
+
+            (slowest.0.max(expected), slowest.1.max(p99))
+        })
+    }
+
+    // The latency of the slowest path with every call crossing the slowest link
+    pub fn worst_case(&self) -> (f64, f64) {
+        let slowest = self.links.values().fold((0.0, 0.0), |slowest: (f64, f64), (mean, p99)| (slowest.0.max(*mean), slowest.1.max(*p99)));
+
+        self.paths.iter().fold((0.0, 0.0), |worst, path| {
+            let hops = (path.services.len() - 1) as f64;
+            (worst.0.max(path.duration.0 + hops * slowest.0), worst.1.max(path.duration.1 + hops * slowest.1))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::latency::{Latency, LatencyModel, RequestPath};
+    use crate::utility::{Node, ResourceInt, Service};
+
+    fn node(id: i64) -> Node {
+        Node::new(id, &format!("node{}", id), "127.0.0.1", ResourceInt::new(4, 8, 100_000, 1000))
+    }
+
+    fn service(name: &str) -> Service {
+        Service::new(name, name, None, None)
+    }
+
+    // a -> b -> c takes 10 ms (20 ms p99) and c -> a 30 ms (40 ms p99); the links to node 3 are the slow ones
+    fn model() -> LatencyModel {
+        let mut links = HashMap::new();
+        for (source, destination, latency) in [(1, 2, (1.0, 2.0)), (1, 3, (4.0, 8.0)), (2, 3, (3.0, 6.0))] {
+            links.insert((node(source), node(destination)), latency);
+            links.insert((node(destination), node(source)), latency);
+        }
+
+        LatencyModel {
+            paths: vec![
+                RequestPath { services: vec![service("a"), service("b"), service("c")], duration: (10.0, 20.0) },
+                RequestPath { services: vec![service("c"), service("a")], duration: (3.0, 5.0) },
+            ],
+            links,
+        }
+    }
+
+    fn placements(nodes: &[(&str, i64)]) -> HashMap<Service, HashSet<Node>> {
+        nodes.iter().map(|(name, id)| (service(name), HashSet::from([node(*id)]))).collect()
+    }
+
+    #[test]
+    fn test_estimate() {
+        let model = model();
+
+        // no call crosses nodes
+        assert_eq!(model.estimate(&placements(&[("a", 1), ("b", 1), ("c", 1)])), (10.0, 20.0));
+
+        // b -> c crosses from node 1 to node 3
+        assert_eq!(model.estimate(&placements(&[("a", 1), ("b", 1), ("c", 3)])), (14.0, 28.0));
+
+        // a -> b and b -> c cross: 10 + 1 + 3 and 20 + 2 + 6
+        assert_eq!(model.estimate(&placements(&[("a", 1), ("b", 2), ("c", 3)])), (14.0, 28.0));
+
+        // the calls to c add nothing while it is not placed
+        assert_eq!(model.estimate(&placements(&[("a", 1), ("b", 2)])), (11.0, 22.0));
+    }
+
+    #[test]
+    fn test_replicas() {
+        let model = model();
+
+        // b runs on nodes 1 and 3: half of the calls from a and to c cross to node 3 - 10 + 2 + 2 and 20 + 4 + 4
+        let mut placements = placements(&[("a", 1), ("c", 1)]);
+        placements.insert(service("b"), HashSet::from([node(1), node(3)]));
+        assert_eq!(model.estimate(&placements), (14.0, 28.0));
+    }
+
+    #[test]
+    fn test_slowest_path() {
+        let mut model = model();
+        model.paths[1].duration = (30.0, 40.0);
+
+        // c -> a crosses from node 3 to node 1 and is the slowest
+        assert_eq!(model.estimate(&placements(&[("a", 1), ("b", 1), ("c", 3)])), (34.0, 48.0));
+    }
+
+    #[test]
+    fn test_worst_case() {
+        let model = model();
+
+        // both calls of a -> b -> c on the slowest link: 10 + 2 x 4 and 20 + 2 x 8
+        assert_eq!(model.worst_case(), (18.0, 36.0));
+        assert!(model.estimate(&placements(&[("a", 1), ("b", 3), ("c", 1)])).0 <= model.worst_case().0);
+    }
+
+    #[test]
+    fn test_config() {
+        let latency: Latency = serde_yaml::from_str("slo: 250").unwrap();
+        assert!(latency.objective);
+        assert_eq!(latency.slo, Some(250.0));
+
+        let latency: Latency = serde_yaml::from_str("objective: false").unwrap();
+        assert!(!latency.objective);
+        assert_eq!(latency.slo, None);
+    }
+}
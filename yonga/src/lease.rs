@@ -0,0 +1,223 @@
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument};
+use mongodb::error::{Error, ErrorKind, WriteFailure};
+use mongodb::{Client, Collection, Database};
+use tokio::time::Duration;
+use crate::utility;
+
+// the collection holding the controller leases
+pub const LEASE_COLLECTION: &str = "leases";
+
+// the code mongo returns when an insert hits an existing _id
+const DUPLICATE_KEY: i32 = 11000;
+
+// A lease on a stack: only its holder may plan and deploy. The document is
+// { _id: <stack>, holder, expires_at, renewed_at, renew_interval }.
+#[derive(Debug, Clone)]
+pub struct Lease {
+    collection: Collection<Document>,
+    pub name: String,
+    pub holder: String,
+    pub duration: Duration,
+    pub renew_interval: Duration,
+}
+
+impl Lease {
+    pub fn new(database: &Database, name: &str, holder: &str, duration: Duration, renew_interval: Duration) -> Self {
+        Lease {
+            collection: database.collection::<Document>(LEASE_COLLECTION),
+            name: name.to_string(),
+            holder: holder.to_string(),
+            duration,
+            renew_interval,
+        }
+    }
+
+    pub async fn connect(database: &utility::Database, name: &str, holder: &str, duration: Duration, renew_interval: Duration) -> Result<Self, Error> {
+        let client = Client::with_uri_str(&database.uri).await?;
+        Ok(Self::new(&client.database(&database.db), name, holder, duration, renew_interval))
+    }
+
+    // Take or renew the lease - true if this controller holds it afterwards.
+    // The update only matches a lease held by us or expired; otherwise the upsert
+    // collides with the existing _id and someone else holds the lease.
+    pub async fn try_acquire(&self) -> Result<bool, Error> {
+        let now = DateTime::now();
+        let expires_at = DateTime::from_millis(now.timestamp_millis() + self.duration.as_millis() as i64);
+
+        let filter = doc! {
+            "_id": &self.name,
+            "$or": [
+                { "holder": &self.holder },
+                { "expires_at": { "$lt": now } },
+            ],
+        };
+
+        let update = doc! {
+            "$set": {
+                "holder": &self.holder,
+                "expires_at": expires_at,
+                "renewed_at": now,
+                "renew_interval": self.renew_interval.as_secs() as i64,
+            }
+        };
+
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+
+        match self.collection.find_one_and_update(filter, update, options).await {
+            Ok(_) => Ok(true),
+            Err(e) if is_duplicate_key(&e) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    // the current holder of the lease, if it has not expired
+    pub async fn current_holder(&self) -> Result<Option<String>, Error> {
+        let lease = self.collection.find_one(doc! { "_id": &self.name, "expires_at": { "$gte": DateTime::now() } }, None).await?;
+
+        Ok(lease.and_then(|lease| lease.get_str("holder").ok().map(str::to_string)))
+    }
+
+    // Give the lease up so that a standby controller takes over without waiting for it to expire
+    pub async fn release(&self) -> Result<(), Error> {
+        self.collection.delete_one(doc! { "_id": &self.name, "holder": &self.holder }, None).await?;
+        Ok(())
+    }
+}
+
+fn is_duplicate_key(error: &Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::Command(command_error) => command_error.code == DUPLICATE_KEY,
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => write_error.code == DUPLICATE_KEY,
+        _ => false,
+    }
+}
+
+// An id for this controller - the host name and the process id
+pub fn default_holder() -> String {
+    let host = std::env::var("HOSTNAME").ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok().map(|name| name.trim().to_string()))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "ocas".to_string());
+
+    format!("{}-{}", host, std::process::id())
+}
+
+// These tests need a mongo server - see history::test::test_database
+#[cfg(test)]
+mod test {
+    use mongodb::bson::{doc, Document};
+    use tokio::time::Duration;
+
+    use crate::history::test::test_database;
+    use crate::lease::{is_duplicate_key, Lease};
+
+    // A lease of the test database with a name of its own, none if no server answers
+    async fn lease(name: &str, holder: &str, duration: Duration) -> Option<Lease> {
+        let database = test_database().await?;
+        let name = format!("test-{}-{}", name, std::process::id());
+
+        Some(Lease::new(&database, &name, holder, duration, Duration::from_secs(1)))
+    }
+
+    // the same lease for another controller
+    fn other(lease: &Lease, holder: &str) -> Lease {
+        Lease { holder: holder.to_string(), ..lease.clone() }
+    }
+
+    async fn lease_document(lease: &Lease) -> Document {
+        lease.collection.find_one(doc! { "_id": &lease.name }, None).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_acquire() {
+        let lease = match lease("acquire", "a", Duration::from_secs(60)).await {
+            Some(lease) => lease,
+            None => return,
+        };
+
+        assert!(lease.try_acquire().await.unwrap());
+        assert_eq!(lease.current_holder().await.unwrap(), Some("a".to_string()));
+
+        lease.release().await.unwrap();
+        assert_eq!(lease.current_holder().await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_renew() {
+        let lease = match lease("renew", "a", Duration::from_secs(60)).await {
+            Some(lease) => lease,
+            None => return,
+        };
+
+        assert!(lease.try_acquire().await.unwrap());
+        let expires_at = lease_document(&lease).await.get_datetime("expires_at").unwrap().timestamp_millis();
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // the holder extends its own lease
+        assert!(lease.try_acquire().await.unwrap());
+        let renewed = lease_document(&lease).await.get_datetime("expires_at").unwrap().timestamp_millis();
+        assert!(renewed > expires_at);
+
+        lease.release().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_held_by_another_controller() {
+        let lease = match lease("held", "a", Duration::from_secs(60)).await {
+            Some(lease) => lease,
+            None => return,
+        };
+        let standby = other(&lease, "b");
+
+        assert!(lease.try_acquire().await.unwrap());
+
+        // the upsert of the standby collides with the lease of a - not an error
+        assert!(!standby.try_acquire().await.unwrap());
+        assert_eq!(standby.current_holder().await.unwrap(), Some("a".to_string()));
+
+        // only the holder releases the lease
+        standby.release().await.unwrap();
+        assert_eq!(lease.current_holder().await.unwrap(), Some("a".to_string()));
+
+        lease.release().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_steal_after_expiry() {
+        let lease = match lease("steal", "a", Duration::from_millis(10)).await {
+            Some(lease) => lease,
+            None => return,
+        };
+        let standby = other(&lease, "b");
+
+        assert!(lease.try_acquire().await.unwrap());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // the lease of a expired - the standby takes over
+        assert_eq!(standby.current_holder().await.unwrap(), None);
+        assert!(standby.try_acquire().await.unwrap());
+        assert_eq!(lease_document(&standby).await.get_str("holder").unwrap(), "b");
+
+        standby.release().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_key() {
+        let lease = match lease("duplicate", "a", Duration::from_secs(60)).await {
+            Some(lease) => lease,
+            None => return,
+        };
+        let collection = &lease.collection;
+
+        collection.insert_one(doc! { "_id": &lease.name }, None).await.unwrap();
+        let error = collection.insert_one(doc! { "_id": &lease.name }, None).await.unwrap_err();
+        assert!(is_duplicate_key(&error));
+
+        collection.delete_one(doc! { "_id": &lease.name }, None).await.unwrap();
+    }
+}
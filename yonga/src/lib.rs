@@ -13,4 +13,14 @@ pub mod trace;
 pub mod node;
 pub mod nsga2;
 pub mod nsga2opticas;
+pub mod simulator;
+pub mod rollback;
+pub mod history;
+pub mod lease;
+pub mod scoring;
+pub mod milp;
+pub mod snapshot;
+pub mod decision;
+pub mod latency;
+pub mod affinity;
 // pub mod constraint;
\ No newline at end of file
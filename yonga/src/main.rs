@@ -1,13 +1,11 @@
 use clap::{Command, ArgAction, Arg};
-use yonga::spread::Spread;
-use yonga::binpack::Binpack;
-use yonga::random::Random;
 use std::fs;
 use yonga::stack::StackConfig;
-use yonga::yonga::Yonga;
+use yonga::strategy::{self, StrategyContext, StrategyRegistry};
 use yonga::utility::Config;
-use yonga::solver::Solver;
 use yonga::api_client::ApiClient;   
+use yonga::lease::{self, Lease};
+use tokio::time::Duration;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -37,13 +35,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .short('s')
             .required(false)
             .action(ArgAction::Set))
+        .arg(Arg::new("dry-run") // plan and show the diff without deploying
+            .long("dry-run")
+            .short('d')
+            .required(false)
+            .action(ArgAction::SetTrue))
         .get_matches();
 
     let yaml_config = matches.get_one::<String>("compose").unwrap();
-    let strategy = matches.get_one::<String>("placement").unwrap(); // this can either be spread, binpack or random or yonga
+    let strategy_name = matches.get_one::<String>("placement").unwrap(); // any strategy in the registry, e.g. spread, binpack, random or yonga
     let cluster_config = matches.get_one::<String>("config").unwrap();
     let url = matches.get_one::<String>("url").unwrap(); // the base URL for the API client
     let stack_name = matches.get_one::<String>("stack").unwrap(); // the name of the stack  
+    let dry_run = matches.get_flag("dry-run");
 
     // Read the config file
     let yaml_str = fs::read_to_string(yaml_config).expect("Failed to read the YAML configuration file");
@@ -61,74 +65,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     //println!("Cluster Config: {:?}", cluster_config);
 
     // determine the strategy
-    match strategy.as_str() {
-        "spread" => {
-            println!("Spread strategy selected");
-
-            //let api_client = ApiClient::new(url);
-            let mut spread = Spread::new(cluster_config.clone(), None, stack_name.to_string(), stack_config.clone());
-
-           // get the placement
-            let placement = spread.spread_0().await;
-
-            match placement {
-                Ok(map) => {
-                    spread.run(Some(map));
-                }
-                Err(_) => {
-                    println!("No placement solution found");
-                }
-            }
-
-        }
-        "binpack" => {
-            println!("Binpack strategy selected");
-
-            let api_client = ApiClient::new(url);
-            let mut binpack = Binpack::new(cluster_config.clone(), None, stack_name.to_string(), stack_config.clone(), api_client);
-
-            let placement = binpack.binpack_0().await;
-
-            match placement {
-                Ok(map) => {
-                    binpack.run(Some(map));
-                }
-                Err(_) => {
-                    println!("No placement solution found");
-                }
+    let registry = StrategyRegistry::with_defaults();
+    let database = cluster_config.database.clone();
+    let control = cluster_config.control.clone();
+    let context = StrategyContext::new(cluster_config.clone(), stack_name.to_string(), stack_config.clone(), ApiClient::new(url));
+
+    match registry.create(strategy_name, context) {
+        Some(mut placement) => {
+            println!("{} strategy selected", placement.name());
+
+            if dry_run {
+                strategy::dry_run(placement.as_mut(), &stack_config, &cluster_config, stack_name).await?;
+            } else {
+                // one controller per stack re-plans - the others stay on standby. A one-shot placement needs no lease.
+                let lease = if placement.replans() {
+                    let lease = Lease::connect(&database, stack_name, &lease::default_holder(), Duration::from_secs(control.lease_duration), Duration::from_secs(control.lease_renew)).await?;
+                    println!("Controller {} for stack {}", lease.holder, stack_name);
+                    Some(lease)
+                } else {
+                    None
+                };
+
+                strategy::run(placement.as_mut(), lease.as_ref()).await;
             }
-
-
-        }
-        "random" => {
-            println!("Random strategy selected");
-
-            let mut random = Random::new(cluster_config.clone(), None, stack_name.to_string(), stack_config.clone());
-
-            let placement = random.random_0().await;
-
-            match placement {
-                Ok(map) => {
-                    random.run(Some(map));
-                }
-                Err(_) => {
-                    println!("No placement solution found");
-                }
-            }
-        }
-        "yonga" => {
-            // print the nodes and attributes
-            println!("Yonga strategy selected");
-
-            // create the solver & API client
-            let api_client = ApiClient::new(url);
-            let solver = Solver::new(cluster_config.clone(), api_client);
-
-            let mut placement = Yonga::new(cluster_config, stack_name.to_string(), stack_config, solver);
-            placement.start().await;
         }
-        _ => {
-            println!("Non-implemented strategy selected or none provided - exiting");
+        None => {
+            println!("Non-implemented strategy selected or none provided - available: {:?} - exiting", registry.names());
 
             return Ok(());
         }
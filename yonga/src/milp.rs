@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+use microlp::{ComparisonOp, LinearExpr, OptimizationDirection, Problem, Variable};
+use tokio::time::Instant;
+use opticas::core::OConstraintKind;
+use crate::affinity::AffinityKind;
+use crate::nsga2opticas::OMicroservicePlacementProblem;
+use crate::solver::Solver;
+use crate::stack;
+use crate::strategy::{PlacementMap, StrategyContext, StrategyRegistry};
+use crate::utility::{self, Node, Resource, Service};
+use crate::yonga::{Backend, Yonga};
+
+// An assignment of every service to one node, proven optimal
+#[derive(Debug, Clone)]
+pub struct MilpSolution {
+    pub placements: HashMap<Service, Node>,
+    // the normalised communication and resource cost - the same as the first two NSGA2 objectives
+    pub communication_cost: f64,
+    pub resource_cost: f64,
+    // communication + resource cost, the minimum over every feasible assignment
+    pub objective: f64,
+    pub variables: usize,
+    pub constraints: usize,
+    pub elapsed_ms: u128,
+}
+
+// The placement problem as a mixed-integer linear program:
+//   x[s][n] = 1 if service s is placed on node n (binary), sum_n x[s][n] = 1
+//   sum_s demand[s] * x[s][n] <= ResourceInt[n] - base usage[n], for cpu, memory, disk and network
+//   minimise resource cost (sum cost[n] * x[s][n]) + communication cost
+// The communication cost messages(s, t) * link(a, b) * x[s][a] * x[t][b] is linearised with
+// z[s, t][a][b] >= 0, sum_b z[s, t][a][b] = x[s][a] and sum_a z[s, t][a][b] = x[t][b] - tighter
+// than the usual z >= x[s][a] + x[t][b] - 1, which keeps the branch and bound small.
+// The imbalance objective is quadratic and left to NSGA2.
+struct Model {
+    problem: Problem,
+    // x[s][n], in the order of the services and nodes below
+    x: Vec<Vec<Variable>>,
+    // the terms of each cost, to split the optimum
+    communication: Vec<(Variable, f64)>,
+    resource: Vec<(Variable, f64)>,
+    services: Vec<Service>,
+    nodes: Vec<Node>,
+    variables: usize,
+    constraints: usize,
+}
+
+// Solve the problem to optimality. The placements in `excluded` (e.g. the ones that failed to converge)
+// are cut off: at least one service has to run on a node it did not have there.
+pub fn solve(problem: &OMicroservicePlacementProblem, excluded: &[PlacementMap]) -> Result<MilpSolution, String> {
+    let start = Instant::now();
+    let mut model = build_model(problem)?;
+
+    for placement_map in excluded {
+        exclude(&mut model, placement_map);
+    }
+
+    println!("Solving the MILP: {} services, {} nodes, {} variables, {} constraints",
+        model.services.len(), model.nodes.len(), model.variables, model.constraints);
+
+    let solution = model.problem.solve().map_err(|e| format!("MILP: {}", e))?;
+
+    let mut placements = HashMap::new();
+
+    for (s, service) in model.services.iter().enumerate() {
+        let n = (0..model.nodes.len())
+            .max_by(|a, b| solution[model.x[s][*a]].total_cmp(&solution[model.x[s][*b]]))
+            .ok_or("MILP: no node to place the services on")?;
+        placements.insert(service.clone(), model.nodes[n].clone());
+    }
+
+    let cost = |terms: &Vec<(Variable, f64)>| terms.iter().map(|(var, coeff)| solution[*var] * coeff).sum::<f64>();
+
+    Ok(MilpSolution {
+        placements,
+        communication_cost: cost(&model.communication),
+        resource_cost: cost(&model.resource),
+        objective: solution.objective(),
+        variables: model.variables,
+        constraints: model.constraints,
+        elapsed_ms: start.elapsed().as_millis(),
+    })
+}
+
+// How far a value is above the optimum, relative to the optimum
+pub fn optimality_gap(value: f64, optimum: f64) -> f64 {
+    if optimum.abs() < f64::EPSILON {
+        value - optimum
+    } else {
+        (value - optimum) / optimum.abs()
+    }
+}
+
+fn build_model(problem: &OMicroservicePlacementProblem) -> Result<Model, String> {
+    let services = problem.config.services.clone();
+    let mut nodes = problem.config.cluster.nodes.clone();
+    nodes.sort_by_key(|node| node.id);
+
+    if services.is_empty() || nodes.is_empty() {
+        return Err("MILP: no services or no nodes".to_string());
+    }
+
+    let mut lp = Problem::new(OptimizationDirection::Minimize);
+    let mut variables = 0;
+    let mut constraints = 0;
+
+    // the resource cost, normalised as in the NSGA2 objective
+    let max_resource_cost = problem.bounds.minmax_node_cost.1 * services.len() as f64;
+    let mut resource = Vec::new();
+
+    let x: Vec<Vec<Variable>> = services.iter().map(|_| {
+        nodes.iter().map(|node| {
+            let cost = sanitize(*problem.cost.get(node).unwrap_or(&0.0));
+            let coeff = if max_resource_cost > 0.0 { cost / max_resource_cost } else { 0.0 };
+            let var = lp.add_binary_var(coeff);
+            resource.push((var, coeff));
+            variables += 1;
+            var
+        }).collect()
+    }).collect();
+
+    // every service on exactly one node
+    for row in &x {
+        lp.add_constraint(row.iter().map(|var| (*var, 1.0)), ComparisonOp::Eq, 1.0);
+        constraints += 1;
+    }
+
+    // the pinned services (e.g. the most popular ones on the cheapest node)
+    for constraint in problem.constraints.iter().flatten() {
+        let (s, target) = match (services.iter().position(|service| service.name == constraint.name()), constraint.kind()) {
+            (Some(s), OConstraintKind::Assignment { node }) => (s, *node),
+            _ => continue,
+        };
+
+        let n = match nodes.iter().position(|node| node.id as u64 == target) {
+            Some(n) => n,
+            None => continue,
+        };
+
+        lp.add_constraint([(x[s][n], 1.0)], ComparisonOp::Eq, 1.0);
+        constraints += 1;
+    }
+
+    // the hard affinity rules - the soft ones are left to NSGA2
+    for rule in &problem.affinity {
+        let members: Vec<usize> = rule.services.iter()
+            .filter_map(|name| services.iter().position(|service| service.name == *name))
+            .collect();
+
+        if members.len() < 2 {
+            continue;
+        }
+
+        for (n, _) in nodes.iter().enumerate() {
+            match rule.kind {
+                // x[s][n] = x[t][n] for every service t of the rule
+                AffinityKind::MustColocate => {
+                    for t in members.iter().skip(1) {
+                        lp.add_constraint([(x[members[0]][n], 1.0), (x[*t][n], -1.0)], ComparisonOp::Eq, 0.0);
+                        constraints += 1;
+                    }
+                }
+                // one service of the rule per node at most
+                AffinityKind::MustSeparate => {
+                    lp.add_constraint(members.iter().map(|s| (x[*s][n], 1.0)), ComparisonOp::Le, 1.0);
+                    constraints += 1;
+                }
+                AffinityKind::ShouldColocate => {}
+            }
+        }
+    }
+
+    // the capacity left on each node once the usage of everything but the services is counted
+    let demands: Vec<Resource> = services.iter().map(|service| problem.service_demand(service)).collect();
+
+    for (n, node) in nodes.iter().enumerate() {
+        let base = problem.node_resources.get(node).cloned().unwrap_or(Resource::default());
+        let left = utility::node_capacity(node, &base);
+        let capacity = [left.cpu, left.memory, left.disk, left.network];
+
+        for (k, available) in capacity.iter().enumerate() {
+            let terms: Vec<(Variable, f64)> = demands.iter().enumerate()
+                .map(|(s, demand)| (x[s][n], sanitize([demand.cpu, demand.memory, demand.disk, demand.network][k])))
+                .filter(|(_, coeff)| *coeff > 0.0)
+                .collect();
+
+            if terms.is_empty() {
+                continue;
+            }
+
+            lp.add_constraint(terms, ComparisonOp::Le, available.max(0.0));
+            constraints += 1;
+        }
+    }
+
+    // the communication cost, normalised as in the NSGA2 objective
+    let max_communication_cost = problem.bounds.max_opt_cost * problem.service_comms.len() as f64;
+    let mut communication = Vec::new();
+
+    for ((s1, s2), (message_count, _latency)) in &problem.service_comms {
+        let (s, t) = match (services.iter().position(|service| service == s1), services.iter().position(|service| service == s2)) {
+            (Some(s), Some(t)) if s != t => (s, t),
+            _ => continue,
+        };
+
+        // z[a][b] - s on node a and t on node b
+        let z: Vec<Vec<Variable>> = nodes.iter().map(|a| {
+            nodes.iter().map(|b| {
+                let coeff = if a == b || max_communication_cost <= 0.0 {
+                    0.0
+                } else {
+                    *message_count as f64 * link_cost(problem, a, b) / max_communication_cost
+                };
+                let var = lp.add_var(coeff, (0.0, 1.0));
+                if coeff > 0.0 {
+                    communication.push((var, coeff));
+                }
+                variables += 1;
+                var
+            }).collect()
+        }).collect();
+
+        for a in 0..nodes.len() {
+            let mut expr: LinearExpr = z[a].iter().map(|var| (*var, 1.0)).collect();
+            expr.add(x[s][a], -1.0);
+            lp.add_constraint(expr, ComparisonOp::Eq, 0.0);
+
+            let mut expr: LinearExpr = z.iter().map(|row| (row[a], 1.0)).collect();
+            expr.add(x[t][a], -1.0);
+            lp.add_constraint(expr, ComparisonOp::Eq, 0.0);
+
+            constraints += 2;
+        }
+    }
+
+    Ok(Model {
+        problem: lp,
+        x,
+        communication,
+        resource,
+        services,
+        nodes,
+        variables,
+        constraints,
+    })
+}
+
+// no-good cut: the services cannot all stay on the nodes they had in the placement
+fn exclude(model: &mut Model, placement_map: &PlacementMap) {
+    let mut expr = LinearExpr::empty();
+    let mut count = 0;
+
+    for (s, service) in model.services.iter().enumerate() {
+        let placed = match placement_map.get(service) {
+            Some(Some(placed)) if !placed.is_empty() => placed,
+            // a service left out cannot be cut on
+            _ => return,
+        };
+
+        for (n, node) in model.nodes.iter().enumerate() {
+            if placed.iter().any(|other| other.id == node.id) {
+                expr.add(model.x[s][n], 1.0);
+            }
+        }
+        count += 1;
+    }
+
+    model.problem.add_constraint(expr, ComparisonOp::Le, count as f64 - 1.0);
+    model.constraints += 1;
+}
+
+// a link missing from the node tree costs nothing, as in the NSGA2 objective
+fn link_cost(problem: &OMicroservicePlacementProblem, a: &Node, b: &Node) -> f64 {
+    problem.node_comms
+        .get(a)
+        .and_then(|edges| edges.iter().find(|edge| edge.destination == *b))
+        .map_or(0.0, |edge| sanitize(edge.edge))
+}
+
+fn sanitize(value: f64) -> f64 {
+    if value.is_finite() { value } else { 0.0 }
+}
+
+pub fn register(registry: &mut StrategyRegistry) {
+    registry.register("milp", |context: StrategyContext| {
+        let mut solver = Solver::new(context.config.clone(), context.api_client);
+        solver.replicas = stack::get_replicas(&context.stack_config);
+        solver.volumes = stack::get_volumes(&context.stack_config);
+        solver.snapshots = context.snapshots;
+        let mut yonga = Yonga::new(context.config, context.stack_name, context.stack_config, solver);
+        yonga.backend = Backend::Milp;
+        yonga.recording = context.recording;
+        Box::new(yonga)
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::milp::{optimality_gap, solve};
+    use crate::node::AggLinkEdge;
+    use crate::nsga2opticas::{ObjectiveBounds, OMicroservicePlacementProblem};
+    use crate::strategy::PlacementMap;
+    use crate::utility::{Config, Node, Resource, Service};
+
+    // 3 services on 2 nodes - node 1 is the cheaper one
+    fn config(cpu: u32, network: u32) -> Config {
+        serde_yaml::from_str(&format!(r#"
+cluster:
+  nodes:
+    - {{ id: 1, name: "node1", ip: "10.0.0.1", resource: {{ cpu: {cpu}, memory: 16000, disk: 100000, network: {network} }} }}
+    - {{ id: 2, name: "node2", ip: "10.0.0.2", resource: {{ cpu: 8, memory: 16000, disk: 100000, network: 1000 }} }}
+  prometheus: {{ url: "", label: "", stack: "", query: "", metric: "" }}
+database: {{ uri: "mongodb://localhost:27017", db: "yonga", collections: [] }}
+services:
+  - {{ id: "1", name: "a" }}
+  - {{ id: "2", name: "b" }}
+  - {{ id: "3", name: "c" }}
+weights: []
+"#, cpu = cpu, network = network)).unwrap()
+    }
+
+    // a and b talk to each other, every service takes 1 cpu and 60 of network
+    fn problem(config: Config) -> OMicroservicePlacementProblem {
+        let services = config.services.clone();
+        let nodes = config.cluster.nodes.clone();
+
+        let service_comms = HashMap::from([((services[0].clone(), services[1].clone()), (100, 1.0))]);
+        let node_comms = HashMap::from([
+            (nodes[0].clone(), vec![AggLinkEdge { destination: nodes[1].clone(), edge: 10.0 }]),
+            (nodes[1].clone(), vec![AggLinkEdge { destination: nodes[0].clone(), edge: 10.0 }]),
+        ]);
+        let cost = HashMap::from([(nodes[0].clone(), 0.2), (nodes[1].clone(), 0.8)]);
+        let utilization = services.iter()
+            .map(|service| (service.clone(), vec![Some((nodes[0].clone(), Resource::new(1.0, 100.0, 1.0, 60.0)))]))
+            .collect();
+        let node_resources = nodes.iter().map(|node| (node.clone(), Resource::default())).collect();
+
+        let bounds = ObjectiveBounds { max_opt_cost: 1000.0, minmax_node_cost: (0.2, 0.8), minmax_resource_imbalance: (0.0, 1.0) };
+
+        OMicroservicePlacementProblem::new(config, service_comms, node_comms, cost, bounds, utilization, node_resources)
+    }
+
+    fn node_of(placements: &HashMap<Service, Node>, name: &str) -> i64 {
+        placements.iter().find(|(service, _)| service.name == name).unwrap().1.id
+    }
+
+    #[test]
+    fn test_everything_on_the_cheapest_node() {
+        let solution = solve(&problem(config(8, 1000)), &[]).unwrap();
+
+        for name in ["a", "b", "c"] {
+            assert_eq!(node_of(&solution.placements, name), 1);
+        }
+        assert_eq!(solution.communication_cost, 0.0);
+        // 3 x 0.2 out of 3 x 0.8
+        assert!((solution.resource_cost - 0.25).abs() < 1e-6);
+        assert!((solution.objective - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cpu_capacity() {
+        // two services fit node 1 - the pair that talks stays together
+        let solution = solve(&problem(config(2, 1000)), &[]).unwrap();
+
+        assert_eq!(node_of(&solution.placements, "a"), 1);
+        assert_eq!(node_of(&solution.placements, "b"), 1);
+        assert_eq!(node_of(&solution.placements, "c"), 2);
+        assert!((solution.resource_cost - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_network_capacity() {
+        // enough cpu, but the network of node 1 takes two services only
+        let solution = solve(&problem(config(8, 150)), &[]).unwrap();
+
+        assert_eq!(node_of(&solution.placements, "c"), 2);
+        assert_eq!(node_of(&solution.placements, "a"), node_of(&solution.placements, "b"));
+    }
+
+    #[test]
+    fn test_excluded_placement() {
+        let problem = problem(config(8, 1000));
+        let optimum = solve(&problem, &[]).unwrap();
+
+        let excluded: PlacementMap = optimum.placements.iter()
+            .map(|(service, node)| (service.clone(), Some(HashSet::from([node.clone()]))))
+            .collect();
+        let solution = solve(&problem, &[excluded]).unwrap();
+
+        // the next best placement moves c (no communication) to node 2
+        assert_eq!(node_of(&solution.placements, "c"), 2);
+        assert!(solution.objective > optimum.objective);
+        assert!(optimality_gap(solution.objective, optimum.objective) > 0.0);
+    }
+
+    #[test]
+    fn test_optimality_gap() {
+        assert_eq!(optimality_gap(1.5, 1.0), 0.5);
+        assert_eq!(optimality_gap(1.0, 1.0), 0.0);
+        assert_eq!(optimality_gap(-0.5, -1.0), 0.5);
+        // no relative gap to a zero optimum
+        assert_eq!(optimality_gap(0.25, 0.0), 0.25);
+    }
+}
@@ -12,7 +12,7 @@ use std::cmp::Ordering;
 // use clap::{Arg, Command, ArgAction};
 // use std::fs;
 
-use crate::utility::{Node, EnvironmentMetric, Network, Config};
+use crate::utility::{Node, EnvironmentMetric, Network, Config, NodeMongo};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NodeEntryMongo {
@@ -22,6 +22,17 @@ pub struct NodeEntryMongo {
     environment: Vec<EnvironmentMetric>,
 }
 
+// The node graph only needs the network part of a recorded node document
+impl From<&NodeMongo> for NodeEntryMongo {
+    fn from(node_mongo: &NodeMongo) -> Self {
+        NodeEntryMongo {
+            timestamp: node_mongo.timestamp.to_chrono(),
+            metadata: node_mongo.metadata.clone(),
+            environment: node_mongo.environment.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct ServerNode {
     node: Node,
@@ -50,6 +61,8 @@ pub struct AggLinkEdge {
 pub struct NodeTree {
     config: Config,
     tree: HashMap<Node, Vec<AggLinkEdge>>,
+    // the measured (mean, 99th percentile) latency of each link, ms
+    latency: HashMap<(Node, Node), (f64, f64)>,
 }
 
 
@@ -84,6 +97,7 @@ impl NodeTree {
         Self {
             config,
             tree: HashMap::new(),
+            latency: HashMap::new(),
         }
     }
 
@@ -127,6 +141,14 @@ impl NodeTree {
 
             for (destination, networks) in edge_networks {
                 let aggregated_edge = Network::aggregate_network(self.config.clone(), &networks, maxmin_network);
+
+                // keep the latency in ms next to the aggregated cost
+                let mut latencies: Vec<f64> = networks.iter().map(|network| network.latency).collect();
+                latencies.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let mean = latencies.iter().sum::<f64>() / latencies.len() as f64;
+                let p99 = latencies[(latencies.len() as f64 * 0.99).ceil() as usize - 1];
+                self.latency.insert((server_node.node.clone(), destination.clone()), (mean, p99));
+
                 self.add_edge(server_node.node.clone(), destination, aggregated_edge);
             }
         }
@@ -242,6 +264,16 @@ impl NodeTree {
         &self.tree
     }
 
+    // The aggregated cost of the link from source to destination
+    pub fn get_link_cost(&self, source: &Node, destination: &Node) -> Option<f64> {
+        self.tree.get(source)?.iter().find(|edge| edge.destination == *destination).map(|edge| edge.edge)
+    }
+
+    // The measured (mean, 99th percentile) latency of the link from source to destination, ms
+    pub fn get_latency(&self, source: &Node, destination: &Node) -> Option<(f64, f64)> {
+        self.latency.get(&(source.clone(), destination.clone())).cloned()
+    }
+
 }
 
 // Custom deserializer function for DateTime
@@ -272,21 +304,7 @@ impl NodeGraph {
     ) -> Result<()> {
         for collection in collections {
             if let Ok(node_entries) = Self::get_latest_node_entries_aggregation(&collection, limit).await {
-                for entry in node_entries {
-                    let node = entry.metadata.clone();
-                    if let Some(server_node) = self.nodes.iter_mut().find(|n| n.node == node) {
-                        for env in entry.environment {
-                            let destination = env.node.clone();
-                            let edge = LinkEdge {
-                                destination,
-                                network: env.network,
-                            };
-                            server_node.edges.push(edge);
-                        }
-                    } else {
-                        eprintln!("Node not found in graph: {}", node.name);
-                    }
-                }
+                self.add_entries(node_entries);
             } else {
                 eprintln!("Error fetching node entries from collection: {:?}", collection.name());
             }
@@ -295,6 +313,25 @@ impl NodeGraph {
         Ok(())
     }
 
+    // Add the links of node entries to the graph (entries from the database or a recording)
+    pub fn add_entries(&mut self, node_entries: Vec<NodeEntryMongo>) {
+        for entry in node_entries {
+            let node = entry.metadata.clone();
+            if let Some(server_node) = self.nodes.iter_mut().find(|n| n.node == node) {
+                for env in entry.environment {
+                    let destination = env.node.clone();
+                    let edge = LinkEdge {
+                        destination,
+                        network: env.network,
+                    };
+                    server_node.edges.push(edge);
+                }
+            } else {
+                eprintln!("Node not found in graph: {}", node.name);
+            }
+        }
+    }
+
 
     async fn get_latest_node_entries_aggregation(
         collection: &Collection<NodeEntryMongo>,
@@ -1,89 +1,268 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::error::Error;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 
 use opticas::core::{
-    OChoice, OConstraint, OEvaluationResult, OEvaluator, OIndividual, 
-    OOError, OObjective, OObjectiveDirection, OProblem, OVariableType, OVariableValue
+    OChoice, OConstraint, OConstraintKind, OConstraintValue, ORelationalOperator, OEvaluationResult, OEvaluator, OIndividual, 
+    OOError, OObjective, OObjectiveDirection, OProblem, OSubset, OVariableType, OVariableValue
 };
 
 use crate::node::AggLinkEdge;
+use crate::latency::{LatencyModel, LATENCY_SLO};
+use crate::affinity::{self, AffinityRule};
+use crate::decision::Candidate;
 use crate::utility::{Node, Service, Config, Resource};
 
+// prefix of the capacity constraint of each node
+const CAPACITY_PREFIX: &str = "capacity_";
+
+// The name of the capacity constraint of a node
+pub fn capacity_constraint_name(node: &Node) -> String {
+    format!("{}{}", CAPACITY_PREFIX, node.name)
+}
+
 
 // Define the structure for the multi-objective problem
 #[derive(Debug)]
 pub struct OMicroservicePlacementProblem {
-    config: Config,
-    service_comms: HashMap<(Service, Service), (u32, f64)>, // (number of messages, 99-% latency)
-    node_comms: HashMap<Node, Vec<AggLinkEdge>>, // (node, (neighbour, link property))
-    cost: HashMap<Node, f64>,
-    max_opt_cost: f64,
-    minmax_node_cost: (f64, f64),
-    minmax_resource_imbalance: (f64, f64),
-    utilization: HashMap<Service, Vec<Option<(Node, Resource)>>>, // Resource utilization per service
-    node_resources: HashMap<Node, Resource>, // Available resources per node
-    constraints: Option<Vec<OConstraint>>,
+    pub(crate) config: Config,
+    pub(crate) service_comms: HashMap<(Service, Service), (u32, f64)>, // (number of messages, 99-% latency)
+    pub(crate) node_comms: HashMap<Node, Vec<AggLinkEdge>>, // (node, (neighbour, link property))
+    pub(crate) cost: HashMap<Node, f64>,
+    pub(crate) bounds: ObjectiveBounds,
+    pub(crate) utilization: HashMap<Service, Vec<Option<(Node, Resource)>>>, // Resource utilization per service
+    pub(crate) node_resources: HashMap<Node, Resource>, // Available resources per node
+    pub(crate) constraints: Option<Vec<OConstraint>>,
+    pub(crate) migration: Option<MigrationCost>,
+    pub(crate) latency: Option<LatencyModel>,
+    // the affinity rules of the config - hard ones are constraints, soft ones the affinity_penalty objective
+    pub(crate) affinity: Vec<AffinityRule>,
+    // number of replicas of each service - a replicated service picks a subset of distinct nodes
+    pub(crate) replicas: HashMap<String, u32>,
+}
+
+// The bounds the objectives are normalised with
+#[derive(Debug, Clone)]
+pub struct ObjectiveBounds {
+    pub max_opt_cost: f64,
+    pub minmax_node_cost: (f64, f64),
+    pub minmax_resource_imbalance: (f64, f64),
+}
+
+// The placement the services would migrate from and how much moving each one costs
+#[derive(Debug, Clone)]
+pub struct MigrationCost {
+    pub current: HashMap<Service, HashSet<Node>>,
+    pub weights: HashMap<Service, f64>,
 }
 
 
 impl OMicroservicePlacementProblem {
-    // Create the problem with the three objectives
-    pub fn create(
+    // The problem data - `into_problem` wraps it for the optimisation, on its own it evaluates given placements
+    pub fn new(
         config: Config,
         service_comms: HashMap<(Service, Service), (u32, f64)>, // (number of messages, 99-% latency)
         node_comms: HashMap<Node, Vec<AggLinkEdge>>, // (node, (neighbour, link property))
         cost: HashMap<Node, f64>,
-        max_opt_cost: f64,
-        minmax_node_cost: (f64, f64),
-        minmax_resource_imbalance: (f64, f64),
+        bounds: ObjectiveBounds,
         utilization: HashMap<Service, Vec<Option<(Node, Resource)>>>, // Resource utilization per service
         node_resources: HashMap<Node, Resource>, // Available resources per node
-        constraints: Option<Vec<OConstraint>>,
-    ) -> Result<OProblem, OOError> {
-        let objectives = vec![
+    ) -> Self {
+        OMicroservicePlacementProblem {
+            service_comms,
+            node_comms,
+            bounds,
+            cost,
+            utilization,
+            node_resources,
+            constraints: None,
+            affinity: affinity::rules(&config),
+            config,
+            migration: None,
+            latency: None,
+            replicas: HashMap::new(),
+        }
+    }
+
+    // The placement constraints - the services pinned to a node and the capacity of each node
+    pub fn with_constraints(mut self, constraints: Vec<OConstraint>) -> Self {
+        self.constraints = Some(constraints);
+        self
+    }
+
+    // Add the cost of migrating from the current placement as a fourth objective
+    pub fn with_migration(mut self, migration: MigrationCost) -> Self {
+        self.migration = Some(migration);
+        self
+    }
+
+    // Estimate the latency of the request paths - an objective if enabled in the config, and the model of
+    // the SLO constraint
+    pub fn with_latency(mut self, latency: LatencyModel) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    // Search the nodes of the replicas of each service along with the placement
+    pub fn with_replicas(mut self, replicas: HashMap<String, u32>) -> Self {
+        self.replicas = replicas;
+        self
+    }
+
+    // The number of distinct nodes a service is placed on
+    pub fn replica_count(&self, service: &Service) -> usize {
+        (*self.replicas.get(&service.name).unwrap_or(&1) as usize).clamp(1, self.config.cluster.nodes.len().max(1))
+    }
+
+    // Wrap the problem with its objectives (migration_cost and latency if set) and one variable per service:
+    // a choice of node, or a subset of nodes for a replicated service
+    pub fn into_problem(self) -> Result<OProblem, OOError> {
+        let mut objectives = vec![
             OObjective::new("communication_cost", OObjectiveDirection::OMinimise),
             OObjective::new("resource_cost", OObjectiveDirection::OMinimise),
             OObjective::new("resource_imbalance", OObjectiveDirection::OMinimise),
         ];
 
+        if self.migration.is_some() {
+            objectives.push(OObjective::new("migration_cost", OObjectiveDirection::OMinimise));
+        }
+
+        if self.latency.is_some() && self.config.latency.objective {
+            objectives.push(OObjective::new("latency", OObjectiveDirection::OMinimise));
+        }
+
+        if self.affinity.iter().any(|rule| !rule.kind.is_hard()) {
+            objectives.push(OObjective::new("affinity_penalty", OObjectiveDirection::OMinimise));
+        }
+
 
-        let choices: Vec<u64> = config.cluster.nodes.iter().map(|node| node.id.clone() as u64).collect();
+        let choices: Vec<u64> = self.config.cluster.nodes.iter().map(|node| node.id as u64).collect();
 
-        let services = config.services.clone();
+        let services = self.config.services.clone();
         //let nodes = config.cluster.nodes.clone();
 
         let variables: Vec<OVariableType> = services.iter().map(|service| {
-            OVariableType::OChoice(OChoice::new(&service.name, choices.clone()))
-        }).collect();
+            match self.replica_count(service) {
+                1 => Ok(OVariableType::OChoice(OChoice::new(&service.name, choices.clone()))),
+                size => Ok(OVariableType::OSubset(OSubset::new(&service.name, choices.clone(), size)?)),
+            }
+        }).collect::<Result<_, OOError>>()?;
 
-        let e = Box::new(OMicroservicePlacementProblem {
-            config,
-            service_comms,
-            node_comms,
-            max_opt_cost,
-            minmax_node_cost,
-            minmax_resource_imbalance,
-            cost,
-            utilization,
-            node_resources,
-            constraints: constraints.clone(),
-        });
+        // a hard affinity rule holds when none of its services is out of place
+        let mut constraints = self.constraints.clone().unwrap_or_default();
+        for (index, rule) in self.affinity.iter().enumerate() {
+            if rule.kind.is_hard() {
+                constraints.push(OConstraint::new(&affinity::constraint_name(index), ORelationalOperator::LessOrEqualTo, 0.0));
+            }
+        }
+        let constraints = Some(constraints);
+
+        OProblem::new(objectives, variables, constraints, Box::new(self))
+    }
 
-        OProblem::new(objectives, variables, constraints, e)
+    // Evaluate a given placement - returns the communication cost, resource cost and resource imbalance
+    pub fn evaluate_placement(&self, placements: &HashMap<Service, HashSet<Node>>) -> (f64, f64, f64) {
+        (
+            self.communication_cost(self.bounds.max_opt_cost, placements),
+            self.resource_cost(placements),
+            self.resource_imbalance(placements),
+        )
     }
 
-    // Calculate the resource cost
-    pub fn resource_cost(&self, placements: &HashMap<Service, Node>) -> f64 {
+    // Calculate the migration cost - the weight of the services moved off their current nodes, relative to
+    // the weight of all of them. A replicated service weighs the share of its replicas that moved, and a
+    // service that is not placed yet moves for free.
+    pub fn migration_cost(&self, placements: &HashMap<Service, HashSet<Node>>) -> f64 {
+        let migration = match &self.migration {
+            Some(migration) => migration,
+            None => return 0.0,
+        };
+
+        let mut moved = 0.0;
+        let mut total = 0.0;
+
+        for (service, nodes) in placements {
+            let weight = *migration.weights.get(service).unwrap_or(&1.0);
+            total += weight;
+
+            if let Some(current) = migration.current.get(service) {
+                let replicas_moved = nodes.iter().filter(|node| !current.contains(node)).count();
+                moved += weight * replicas_moved as f64 / nodes.len().max(1) as f64;
+            }
+        }
+
+        if total > 0.0 { moved / total } else { 0.0 }
+    }
+
+    // Calculate the latency - the expected latency of the slowest request path relative to its worst case,
+    // where every call crosses the slowest link
+    pub fn latency(&self, placements: &HashMap<Service, HashSet<Node>>) -> f64 {
+        let model = match &self.latency {
+            Some(model) => model,
+            None => return 0.0,
+        };
+
+        let (expected, _) = model.estimate(placements);
+        let (worst, _) = model.worst_case();
+
+        if worst > 0.0 { (expected / worst).min(1.0) } else { 0.0 }
+    }
+
+    // The expected and p99 latency (ms) of the slowest request path
+    pub fn path_latency(&self, placements: &HashMap<Service, HashSet<Node>>) -> (f64, f64) {
+        self.latency.as_ref().map_or((0.0, 0.0), |model| model.estimate(placements))
+    }
+
+    // The usage of a service, wherever it was measured
+    pub fn service_demand(&self, service: &Service) -> Resource {
+        let mut demand = Resource::default();
+
+        for (_, resource) in self.utilization.get(service).iter().flat_map(|usage| usage.iter().flatten()) {
+            demand.add(resource);
+        }
+
+        demand
+    }
+
+    // The demand of the services placed on each node (cpu, memory, disk, network) - the capacity constraint
+    // of the node compares it with the capacity left by the rest of its usage. A replicated service spreads
+    // its demand evenly over its nodes.
+    pub fn node_demand(&self, replica_nodes: &HashMap<Service, HashSet<Node>>) -> HashMap<Node, [f64; 4]> {
+        let mut demands: HashMap<Node, Resource> = HashMap::new();
+        for (service, nodes) in replica_nodes {
+            let demand = self.service_demand(service);
+            let share = 1.0 / nodes.len().max(1) as f64;
+
+            let replica = Resource::new(demand.cpu * share, demand.memory * share, demand.disk * share, demand.network * share);
+
+            for node in nodes {
+                demands.entry(node.clone()).or_insert(Resource::default()).add(&replica);
+            }
+        }
+
+        self.config.cluster.nodes.iter().map(|node| {
+            let demand = demands.get(node).cloned().unwrap_or(Resource::default());
+            (node.clone(), [demand.cpu, demand.memory, demand.disk, demand.network])
+        }).collect()
+    }
+
+    // Calculate the resource cost - the cost of the nodes of every replica
+    pub fn resource_cost(&self, placements: &HashMap<Service, HashSet<Node>>) -> f64 {
         // Calculate the total cost based on placements
         let total_cost: f64 = placements
-            .iter()
-            .map(|(_, node)| *self.cost.get(node).unwrap_or(&0.0))
+            .values()
+            .flatten()
+            .map(|node| *self.cost.get(node).unwrap_or(&0.0))
             .sum();
 
-        // get the length of the placements
-        let num_placements = placements.len() as f64;
+        // get the number of replicas placed
+        let num_placements = placements.values().map(|nodes| nodes.len()).sum::<usize>() as f64;
 
-        let (min_cost, max_cost) = self.minmax_node_cost;
+        let (min_cost, max_cost) = self.bounds.minmax_node_cost;
 
         // Find the minimum and maximum resource cost
         // let max_cost = self.cost.values()
@@ -112,21 +291,22 @@ impl OMicroservicePlacementProblem {
         // Print the total cost, normalized cost, and final cost (optional)
         // println!("Total cost: {}, Normalized cost: {}, Final cost: {}", total_cost, normalized_cost, final_cost);
 
-        let final_cost = total_cost / max_resource_cost;
+        // all nodes cost 0 (or nothing is placed) - no cost to normalise
+        let final_cost = if max_resource_cost > 0.0 {
+            total_cost / max_resource_cost
+        } else {
+            0.0
+        };
 
         // print the costs
         // println!("Total cost: {}, Max resource cost: {}, Final cost: {}", total_cost, max_resource_cost, final_cost);
 
-        if final_cost < 0.1 {
-            println!("Total cost: {}, Max resource cost: {}, Final cost: {}", total_cost, max_resource_cost, final_cost);
-            println!("Placement: {:?}", placements);
-        }
-
         final_cost
     }
 
-    // Calculate the communication cost
-    pub fn communication_cost(&self, max_cost: f64, placements: &HashMap<Service, Node>) -> f64 {
+    // Calculate the communication cost - the messages between two replicated services spread evenly over
+    // the pairs of their nodes
+    pub fn communication_cost(&self, max_cost: f64, placements: &HashMap<Service, HashSet<Node>>) -> f64 {
         let mut total_cost = 0.0;
 
         // get the length of the service comms
@@ -137,19 +317,23 @@ impl OMicroservicePlacementProblem {
 
         // Iterate over the service communication pairs
         for ((s1, s2), (message_count, _latency)) in &self.service_comms {
-            if let (Some(node1), Some(node2)) = (placements.get(s1), placements.get(s2)) {
-                if node1 == node2 {
-                    // Services are on the same node, no communication cost
-                    continue;
-                } else {
+            if let (Some(nodes1), Some(nodes2)) = (placements.get(s1), placements.get(s2)) {
+                let pairs = (nodes1.len() * nodes2.len()).max(1) as f64;
+
+                for (node1, node2) in nodes1.iter().flat_map(|node1| nodes2.iter().map(move |node2| (node1, node2))) {
+                    if node1 == node2 {
+                        // Services are on the same node, no communication cost
+                        continue;
+                    }
+
                     // Services are on different nodes, get the path cost
                     let path_cost = self.node_comms
                         .get(node1)
                         .and_then(|edges| edges.iter().find(|edge| edge.destination == *node2))
                         .map_or(f64::INFINITY, |edge| edge.edge);
 
-                    // Calculate the total communication cost for this pair
-                    let comm_cost = *message_count as f64 * path_cost;
+                    // Calculate the communication cost for this pair - its share of the messages
+                    let comm_cost = *message_count as f64 * path_cost / pairs;
                     
                     // Check if the cost is finite before adding
                     if comm_cost.is_finite() {
@@ -181,11 +365,11 @@ impl OMicroservicePlacementProblem {
 
     // Consider remaining resources in the resource imbalance objective
 
-    pub fn resource_imbalance(&self, placements: &HashMap<Service, Node>) -> f64 {
+    pub fn resource_imbalance(&self, placements: &HashMap<Service, HashSet<Node>>) -> f64 {
         let mut adjusted_utilization = self.node_resources.clone();
 
-        // Adjust node utilization based on placements by adding service utilization
-        for (service, node) in placements {
+        // Adjust node utilization based on placements by adding service utilization - of each replica
+        for (service, node) in placements.iter().flat_map(|(service, nodes)| nodes.iter().map(move |node| (service, node))) {
             if let Some(service_utilization) = self.utilization.get(service) {
                 if let Some(resource) = service_utilization.iter()
                     .find_map(|util| if let Some((util_node, res)) = util { 
@@ -229,7 +413,7 @@ impl OMicroservicePlacementProblem {
 
         // Calculate overall imbalance as the mean of variances
         let total_imbalance = ((variance.cpu + variance.memory + variance.disk + variance.network).sqrt() / node_count) / 
-        self.minmax_resource_imbalance.0;
+        self.bounds.minmax_resource_imbalance.0;
 
         // Print the total imbalance (optional)
         // println!("Total imbalance: {}", total_imbalance);
@@ -363,7 +547,8 @@ impl OMicroservicePlacementProblem {
 
 impl OEvaluator for OMicroservicePlacementProblem {
     fn evaluate(&self, i: &OIndividual) -> Result<OEvaluationResult, Box<dyn Error>> {
-        let mut placements: HashMap<Service, Node> = HashMap::new();
+        // every node of each service - the objectives take all of them
+        let mut replica_nodes: HashMap<Service, HashSet<Node>> = HashMap::new();
 
         // Decode variables from the individual into service-to-node mapping
         for (_index, service) in self.config.services.iter().enumerate() {
@@ -375,11 +560,21 @@ impl OEvaluator for OMicroservicePlacementProblem {
                 // Assuming `VariableValue::Choice` contains the selected node's name
                 OVariableValue::OChoice(node_name) => {
                     if let Some(node) = self.config.cluster.nodes.iter().find(|n| n.id as u64 == *node_name) {
-                        placements.insert(service.clone(), node.clone());
+                        replica_nodes.insert(service.clone(), HashSet::from([node.clone()]));
                     } else {
                         return Err(format!("Node with name '{}' not found", node_name).into());
                     }
                 },
+                OVariableValue::OSubset(node_ids) => {
+                    let mut nodes = HashSet::new();
+                    for id in node_ids {
+                        match self.config.cluster.nodes.iter().find(|n| n.id as u64 == *id) {
+                            Some(node) => nodes.insert(node.clone()),
+                            None => return Err(format!("Node with id '{}' not found", id).into()),
+                        };
+                    }
+                    replica_nodes.insert(service.clone(), nodes);
+                },
                 // Handle other possible cases if necessary
                 _ => {
                     return Err("Unexpected variable value type".into());
@@ -391,48 +586,59 @@ impl OEvaluator for OMicroservicePlacementProblem {
         // Calculate each objective
         let mut objectives = HashMap::new();
 
-        objectives.insert("resource_cost".to_string(), self.resource_cost(&placements));
-        objectives.insert("communication_cost".to_string(), self.communication_cost(self.max_opt_cost, &placements));
-        //objectives.insert("latency".to_string(), self.latency(&placements));
-        objectives.insert("resource_imbalance".to_string(), self.resource_imbalance(&placements));
+        objectives.insert("resource_cost".to_string(), self.resource_cost(&replica_nodes));
+        objectives.insert("communication_cost".to_string(), self.communication_cost(self.bounds.max_opt_cost, &replica_nodes));
+        objectives.insert("resource_imbalance".to_string(), self.resource_imbalance(&replica_nodes));
+        if self.migration.is_some() {
+            objectives.insert("migration_cost".to_string(), self.migration_cost(&replica_nodes));
+        }
+        if self.latency.is_some() && self.config.latency.objective {
+            objectives.insert("latency".to_string(), self.latency(&replica_nodes));
+        }
 
-        let mut constraints: HashMap<String, (Option<u64>, Option<Vec<HashMap<String, u64>>>, Option<HashMap<u64, (f64, f64, f64, f64)>>)> = HashMap::new();
+        let nodes = affinity::replica_nodes(&replica_nodes);
+        if self.affinity.iter().any(|rule| !rule.kind.is_hard()) {
+            objectives.insert("affinity_penalty".to_string(), affinity::penalty(&self.affinity, &nodes));
+        }
+
+        let mut constraints: HashMap<String, OConstraintValue> = HashMap::new();
 
         for constraint in &self.constraints.clone().unwrap() {
             let name = constraint.name();
-            if let Some(_value) = constraint.target() {
-                let v = placements.get(&self.config.services.iter().find(|s| s.name == name).unwrap()).unwrap().id as u64;
-                constraints.insert(name.to_string(), (Some(v), None, None));
-            } else if let Some(services) = constraint.services() {
-                let services:Vec<HashMap<String, u64>> = services.iter().map(|service| {
-                    let v = placements.get(&self.config.services.iter().find(|s| s.name == service.clone()).unwrap()).unwrap().id as u64;
-                    let mut map = HashMap::new();
-                    map.insert(service.to_string(), v);
-                    map
+            if name.starts_with(CAPACITY_PREFIX) {
+                // set below for every node
+                continue;
+            } else if name == LATENCY_SLO {
+                // the p99 latency of the slowest path
+                let (_, p99) = self.path_latency(&replica_nodes);
+                constraints.insert(name.to_string(), OConstraintValue::Scalar(p99));
+            } else if let OConstraintKind::Assignment { node } = constraint.kind() {
+                // a replicated service meets the assignment if any of its nodes does
+                let service = self.config.services.iter().find(|s| s.name == name).unwrap();
+                let nodes = replica_nodes.get(service).unwrap();
+                let v = match nodes.iter().find(|n| n.id as u64 == *node) {
+                    Some(assigned) => assigned.id as u64,
+                    None => lowest_node(nodes).unwrap().id as u64,
+                };
+                constraints.insert(name.to_string(), OConstraintValue::Node(v));
+            } else if let OConstraintKind::Group { services } = constraint.kind() {
+                // a replicated service is grouped on its node with the lowest id
+                let services: HashMap<String, u64> = services.iter().filter_map(|service| {
+                    let v = lowest_node(replica_nodes.get(self.config.services.iter().find(|s| s.name == *service)?)?)?.id as u64;
+                    Some((service.clone(), v))
                 }).collect();
-                constraints.insert(name.to_string(), (None, Some(services.clone()), None));
-            } else if let Some(_resource) = constraint.resource() {
-                for node in &self.config.cluster.nodes {
-                    let mut resource_constraint: HashMap<u64, (f64, f64, f64, f64)> = HashMap::new();
-                    // initialize the resource requests for each node
-                    let mut r = Resource::default();
-                    // determine resource requests for each service placed on this node in placements
-                    for (service, _) in &placements {
-                        if let Some(service_util) = self.utilization.get(service){
-                            let service_util = service_util.clone();
-                            for util in service_util {
-                                if let Some((nodex, resource)) = util {
-                                    if node.clone() == nodex {
-                                        r.add(&resource);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    resource_constraint.insert(node.id as u64, (r.cpu, r.memory, r.disk, r.network));
-                    // add the constraint
-                    constraints.insert(node.name.clone(), (None, None, Some(resource_constraint)));
-                }
+                constraints.insert(name.to_string(), OConstraintValue::Group(services));
+            }
+        }
+
+        // the demand on each node
+        for (node, demand) in self.node_demand(&replica_nodes) {
+            constraints.insert(capacity_constraint_name(&node), OConstraintValue::Demand(demand));
+        }
+
+        for (index, rule) in self.affinity.iter().enumerate() {
+            if rule.kind.is_hard() {
+                constraints.insert(affinity::constraint_name(index), OConstraintValue::Scalar(rule.violations(&nodes) as f64));
             }
         }
 
@@ -443,40 +649,371 @@ impl OEvaluator for OMicroservicePlacementProblem {
     }
 }
 
-// a function that takes individuals, and the direction (max/min) and returns the best individual
-pub fn opticas_get_best_individual(individuals: &Vec<OIndividual>, direction: OObjectiveDirection) -> (OIndividual, f64) {
-    let mut best_individual = individuals[0].clone();
+// The node of a service with the lowest id
+fn lowest_node(nodes: &HashSet<Node>) -> Option<&Node> {
+    nodes.iter().min_by_key(|node| node.id)
+}
 
-    for individual in individuals {
+// Evaluate a solution of the problem (the current placement of `warm_start_solutions`) like an individual -
+// None if it misses a variable, such as a service that is not placed yet
+pub fn evaluate_solution(problem: Arc<OProblem>, solution: &HashMap<String, OVariableValue>) -> Result<Option<Candidate>, Box<dyn Error>> {
+    let mut individual = OIndividual::new(problem.clone());
+    for name in problem.variable_names() {
+        match solution.get(&name) {
+            Some(value) => individual.update_variable(&name, value.clone())?,
+            None => return Ok(None),
+        }
+    }
 
-        let a = sum_objective_values(individual);
-        let b = sum_objective_values(&best_individual);
+    let result = problem.evaluator().evaluate(&individual)?;
 
-        if direction == OObjectiveDirection::OMinimise {
-            if a < b {
-                best_individual = individual.clone();
-            }
-        } else if direction == OObjectiveDirection::OMaximise {
-            if a > b {
-                best_individual = individual.clone();
+    let objectives = problem.objective_names().iter()
+        .map(|name| result.objectives.get(name).copied().ok_or_else(|| format!("The objective '{}' was not evaluated", name)))
+        .collect::<Result<Vec<f64>, String>>()?;
+
+    let constraints = result.constraints.unwrap_or_default();
+    let violation = problem.constraints().iter()
+        .map(|(name, constraint)| constraints.get(name).map_or(0.0, |value| constraint.constraint_violation(value)))
+        .sum();
+
+    Ok(Some(Candidate { objectives, violation }))
+}
+
+// The number of nodes a service takes in a solution - one per replica, at most every node of the cluster
+fn solution_size(config: &Config, replicas: &HashMap<String, u32>, service: &str) -> usize {
+    (*replicas.get(service).unwrap_or(&1) as usize).clamp(1, config.cluster.nodes.len().max(1))
+}
+
+// The node ids of every placed service: as many of its nodes as it has replicas (the lowest ids, topped up
+// with the first nodes of the cluster)
+fn placement_ids(config: &Config, placement_map: &HashMap<Service, Option<HashSet<Node>>>, replicas: &HashMap<String, u32>) -> HashMap<String, BTreeSet<u64>> {
+    config.services.iter().filter_map(|service| {
+        let mut ids: Vec<u64> = placement_map.get(service)?.as_ref()?.iter().map(|node| node.id as u64).collect();
+        if ids.is_empty() {
+            return None;
+        }
+        ids.sort();
+
+        let wanted = solution_size(config, replicas, &service.name);
+        let mut nodes: BTreeSet<u64> = ids.into_iter().take(wanted).collect();
+        for node in &config.cluster.nodes {
+            if nodes.len() >= wanted {
+                break;
             }
+            nodes.insert(node.id as u64);
         }
+        Some((service.name.clone(), nodes))
+    }).collect()
+}
+
+// The variable values of the node ids of each service - a choice for a single replica, a subset otherwise
+fn variable_values(config: &Config, replicas: &HashMap<String, u32>, solution: HashMap<String, BTreeSet<u64>>) -> HashMap<String, OVariableValue> {
+    solution.into_iter().map(|(service, nodes)| {
+        let value = match solution_size(config, replicas, &service) {
+            1 => OVariableValue::OChoice(*nodes.iter().next().unwrap()),
+            _ => OVariableValue::OSubset(nodes),
+        };
+        (service, value)
+    }).collect()
+}
+
+// A placement as variable values of the problem, to evaluate it with `evaluate_solution`. Unplaced services
+// have no value.
+pub fn placement_solution(config: &Config, placement_map: &HashMap<Service, Option<HashSet<Node>>>, replicas: &HashMap<String, u32>) -> HashMap<String, OVariableValue> {
+    variable_values(config, replicas, placement_ids(config, placement_map, replicas))
+}
+
+// The current placement and a neighbourhood of one- and two-service moves around it, as variable values
+// to seed the population with - at most `count` of them. The moves are drawn at random from `seed`.
+// The current placement is the one of `placement_solution` and a move swaps one node of a service for
+// another node. Unplaced services are left random.
+pub fn warm_start_solutions(config: &Config, placement_map: &HashMap<Service, Option<HashSet<Node>>>, replicas: &HashMap<String, u32>, count: usize, seed: u64) -> Vec<HashMap<String, OVariableValue>> {
+    let choices: Vec<u64> = config.cluster.nodes.iter().map(|node| node.id as u64).collect();
+    let current = placement_ids(config, placement_map, replicas);
+
+    if current.is_empty() || count == 0 {
+        return Vec::new();
     }
 
-    // print the objective values of the best individual
-    println!("Best individual obj. values: {:?}", best_individual.get_objective_values().unwrap());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut solutions: Vec<HashMap<String, BTreeSet<u64>>> = vec![current.clone()];
+
+    let mut services: Vec<String> = current.keys().cloned().collect();
+    services.sort();
+
+    // one node of a service swapped for a node it does not use
+    let swap = |nodes: &BTreeSet<u64>, from: u64, to: u64| {
+        let mut nodes = nodes.clone();
+        nodes.remove(&from);
+        nodes.insert(to);
+        nodes
+    };
+
+    // every single move, shuffled - they take half of the neighbourhood
+    let mut one_moves: Vec<HashMap<String, BTreeSet<u64>>> = services.iter().flat_map(|service| {
+        let nodes = &current[service];
+        nodes.iter().flat_map(|from| {
+            choices.iter()
+                .filter(|choice| !nodes.contains(choice))
+                .map(|to| {
+                    let mut solution = current.clone();
+                    solution.insert(service.clone(), swap(nodes, *from, *to));
+                    solution
+                })
+                .collect::<Vec<_>>()
+        }).collect::<Vec<_>>()
+    }).collect();
+    one_moves.shuffle(&mut rng);
+
+    let one_move_count = (count - 1).div_ceil(2);
+    solutions.extend(one_moves.into_iter().take(one_move_count));
+
+    // two services moved to other nodes at once
+    if choices.len() > 1 && services.len() > 1 {
+        let mut attempts = 0;
+
+        while solutions.len() < count && attempts < 10 * count {
+            attempts += 1;
+
+            let mut solution = current.clone();
+            for service in services.choose_multiple(&mut rng, 2) {
+                let nodes = &current[service];
+                let others: Vec<u64> = choices.iter().copied().filter(|choice| !nodes.contains(choice)).collect();
+                if others.is_empty() {
+                    continue;
+                }
 
-    // print the constraints of the best individual
-    println!("Best individual obj. vars: {:?}", best_individual.get_variable_values().unwrap());
+                let from = *nodes.iter().nth(rng.gen_range(0..nodes.len())).unwrap();
+                let to = others[rng.gen_range(0..others.len())];
+                solution.insert(service.clone(), swap(nodes, from, to));
+            }
+
+            if !solutions.contains(&solution) {
+                solutions.push(solution);
+            }
+        }
+    }
 
-    (best_individual.clone(), sum_objective_values(&best_individual))
+    solutions.truncate(count);
+
+    solutions.into_iter().map(|solution| variable_values(config, replicas, solution)).collect()
 }
 
-// a function that takes an individual and returns a sum of its objective values
-pub fn sum_objective_values(individual: &OIndividual) -> f64 {
-    let mut sum = 0.0;
-    for value in individual.get_objective_values().unwrap() {
-        sum += value;
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+    use std::sync::Arc;
+
+    use opticas::core::OVariableValue;
+
+    use crate::nsga2opticas::{evaluate_solution, placement_solution, warm_start_solutions, MigrationCost, ObjectiveBounds, OMicroservicePlacementProblem};
+    use crate::utility::{Config, Node, Service};
+
+    fn config(services: &[&str]) -> Config {
+        let services: Vec<String> = services.iter().enumerate()
+            .map(|(i, name)| format!("  - {{ id: \"{}\", name: \"{}\" }}", i + 1, name))
+            .collect();
+
+        serde_yaml::from_str(&format!(r#"
+cluster:
+  nodes:
+    - {{ id: 1, name: "node1", ip: "10.0.0.1", resource: {{ cpu: 8, memory: 16000, disk: 100000, network: 1000 }} }}
+    - {{ id: 2, name: "node2", ip: "10.0.0.2", resource: {{ cpu: 8, memory: 16000, disk: 100000, network: 1000 }} }}
+    - {{ id: 3, name: "node3", ip: "10.0.0.3", resource: {{ cpu: 8, memory: 16000, disk: 100000, network: 1000 }} }}
+  prometheus: {{ url: "", label: "", stack: "", query: "", metric: "" }}
+database: {{ uri: "mongodb://localhost:27017", db: "yonga", collections: [] }}
+services:
+{}
+weights: []
+"#, services.join("\n"))).unwrap()
+    }
+
+    // every service on node 1
+    fn placement(config: &Config) -> HashMap<Service, Option<HashSet<Node>>> {
+        config.services.iter()
+            .map(|service| (service.clone(), Some(HashSet::from([config.cluster.nodes[0].clone()]))))
+            .collect()
+    }
+
+    // the node ids of each service of a solution
+    fn nodes(solution: &HashMap<String, OVariableValue>) -> BTreeMap<String, Vec<u64>> {
+        solution.iter().map(|(service, value)| {
+            let ids = match value {
+                OVariableValue::OChoice(id) => vec![*id],
+                OVariableValue::OSubset(ids) => ids.iter().copied().collect(),
+                _ => panic!("Service {} is neither a choice nor a subset", service),
+            };
+            (service.clone(), ids)
+        }).collect()
+    }
+
+    fn moved(solution: &BTreeMap<String, Vec<u64>>, current: &BTreeMap<String, Vec<u64>>) -> usize {
+        solution.iter().filter(|(service, ids)| current[*service] != **ids).count()
+    }
+
+    #[test]
+    fn test_neighbourhood_size() {
+        let config = config(&["a", "b", "c"]);
+        let solutions: Vec<_> = warm_start_solutions(&config, &placement(&config), &HashMap::new(), 10, 10).iter().map(nodes).collect();
+
+        assert_eq!(solutions.len(), 10);
+
+        // the current placement first, then the one-service moves, then the two-service moves
+        let current = &solutions[0];
+        assert!(current.values().all(|ids| *ids == vec![1]));
+        assert!(solutions[1..6].iter().all(|solution| moved(solution, current) == 1));
+        assert!(solutions[6..].iter().all(|solution| moved(solution, current) == 2));
+
+        let distinct: HashSet<_> = solutions.iter().collect();
+        assert_eq!(distinct.len(), solutions.len());
+    }
+
+    #[test]
+    fn test_small_neighbourhood() {
+        // 2 services and 2 nodes: the current placement, 2 single moves and 1 double move
+        let mut config = config(&["a", "b"]);
+        config.cluster.nodes.truncate(2);
+
+        let solutions = warm_start_solutions(&config, &placement(&config), &HashMap::new(), 20, 10);
+        assert_eq!(solutions.len(), 4);
+    }
+
+    #[test]
+    fn test_replicas() {
+        // a has 2 replicas but runs on node 1 only - it is topped up with node 2
+        let config = config(&["a", "b"]);
+        let replicas = HashMap::from([("a".to_string(), 2)]);
+        let solutions: Vec<_> = warm_start_solutions(&config, &placement(&config), &replicas, 6, 10).iter().map(nodes).collect();
+
+        assert_eq!(solutions[0]["a"], vec![1, 2]);
+        assert!(solutions.iter().all(|solution| solution["a"].len() == 2 && solution["b"].len() == 1));
+    }
+
+    #[test]
+    fn test_no_warm_start() {
+        let config = config(&["a", "b"]);
+
+        assert!(warm_start_solutions(&config, &HashMap::new(), &HashMap::new(), 10, 10).is_empty());
+        assert!(warm_start_solutions(&config, &placement(&config), &HashMap::new(), 0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_seed() {
+        let config = config(&["a", "b", "c"]);
+        let draw = |seed| -> Vec<_> {
+            warm_start_solutions(&config, &placement(&config), &HashMap::new(), 8, seed).iter().map(nodes).collect()
+        };
+
+        assert_eq!(draw(10), draw(10));
     }
-    sum
-}
\ No newline at end of file
+
+    #[test]
+    fn test_placement_solution() {
+        let config = config(&["a", "b", "c"]);
+        let replicas = HashMap::from([("a".to_string(), 2)]);
+
+        // c is not placed - it has no value
+        let mut placement_map = placement(&config);
+        placement_map.insert(config.services[2].clone(), None);
+        let solution = nodes(&placement_solution(&config, &placement_map, &replicas));
+
+        assert_eq!(solution, BTreeMap::from([("a".to_string(), vec![1, 2]), ("b".to_string(), vec![1])]));
+
+        // the first solution of the warm start
+        let warm_start = warm_start_solutions(&config, &placement_map, &replicas, 4, 10);
+        assert_eq!(nodes(&warm_start[0]), solution);
+    }
+
+    // the given cost on each node, nothing else
+    fn cost_problem(config: &Config, costs: [f64; 3]) -> OMicroservicePlacementProblem {
+        let cost: HashMap<Node, f64> = config.cluster.nodes.iter().cloned().zip(costs).collect();
+        let minmax = (costs.iter().cloned().fold(f64::INFINITY, f64::min), costs.iter().cloned().fold(f64::NEG_INFINITY, f64::max));
+
+        let bounds = ObjectiveBounds { max_opt_cost: 0.0, minmax_node_cost: minmax, minmax_resource_imbalance: (0.0, 1.0) };
+
+        OMicroservicePlacementProblem::new(config.clone(), HashMap::new(), HashMap::new(), cost, bounds, HashMap::new(), HashMap::new())
+            .with_constraints(Vec::new())
+    }
+
+    #[test]
+    fn test_resource_cost() {
+        let config = config(&["a", "b"]);
+        let nodes = &config.cluster.nodes;
+        let placements = HashMap::from([
+            (config.services[0].clone(), HashSet::from([nodes[0].clone()])),
+            (config.services[1].clone(), HashSet::from([nodes[2].clone()])),
+        ]);
+
+        // (0.2 + 0.8) out of 2 x 0.8
+        let cost = cost_problem(&config, [0.2, 0.5, 0.8]).resource_cost(&placements);
+        assert!((cost - 0.625).abs() < 1e-9);
+
+        // nodes that cost nothing do not divide by 0
+        assert_eq!(cost_problem(&config, [0.0; 3]).resource_cost(&placements), 0.0);
+        assert_eq!(cost_problem(&config, [0.2, 0.5, 0.8]).resource_cost(&HashMap::new()), 0.0);
+    }
+
+    #[test]
+    fn test_replicas_in_objectives() {
+        // a runs on 2 nodes and b on node 1
+        let config = config(&["a", "b"]);
+        let problem = cost_problem(&config, [0.2, 0.5, 0.8]).with_replicas(HashMap::from([("a".to_string(), 2)]));
+        let problem = Arc::new(problem.into_problem().unwrap());
+        let index = problem.objective_names().iter().position(|name| name == "resource_cost").unwrap();
+
+        let resource_cost = |a: [u64; 2]| {
+            let solution = HashMap::from([
+                ("a".to_string(), OVariableValue::OSubset(BTreeSet::from(a))),
+                ("b".to_string(), OVariableValue::OChoice(1)),
+            ]);
+            evaluate_solution(problem.clone(), &solution).unwrap().unwrap().objectives[index]
+        };
+
+        // the replica of a on node 1 stays, the other one moves from node 2 to node 3:
+        // (0.2 + 0.5 + 0.2) and then (0.2 + 0.8 + 0.2) out of 3 x 0.8
+        assert!((resource_cost([1, 2]) - 0.375).abs() < 1e-9);
+        assert!((resource_cost([1, 3]) - 0.5).abs() < 1e-9);
+    }
+
+    // a on node 1 and b on node 2 - moving b weighs 3, the other services 1
+    fn migration_problem(config: &Config) -> OMicroservicePlacementProblem {
+        let nodes = &config.cluster.nodes;
+        let migration = MigrationCost {
+            current: HashMap::from([
+                (config.services[0].clone(), HashSet::from([nodes[0].clone()])),
+                (config.services[1].clone(), HashSet::from([nodes[1].clone()])),
+            ]),
+            weights: HashMap::from([(config.services[1].clone(), 3.0)]),
+        };
+
+        cost_problem(config, [0.0; 3]).with_migration(migration)
+    }
+
+    #[test]
+    fn test_migration_cost() {
+        let config = config(&["a", "b", "c"]);
+        let problem = migration_problem(&config);
+        let (a, b, c) = (&config.services[0], &config.services[1], &config.services[2]);
+        let nodes = &config.cluster.nodes;
+        let on = |node: usize| HashSet::from([nodes[node].clone()]);
+
+        // every service stays put - c is not placed yet and moves for free
+        let stay = HashMap::from([(a.clone(), on(0)), (b.clone(), on(1)), (c.clone(), on(2))]);
+        assert_eq!(problem.migration_cost(&stay), 0.0);
+
+        // a moves: 1 out of 1 + 3 + 1
+        let moved = HashMap::from([(a.clone(), on(2)), (b.clone(), on(1)), (c.clone(), on(2))]);
+        assert!((problem.migration_cost(&moved) - 0.2).abs() < 1e-9);
+
+        // b moves: 3 out of 5
+        let moved = HashMap::from([(a.clone(), on(0)), (b.clone(), on(0)), (c.clone(), on(0))]);
+        assert!((problem.migration_cost(&moved) - 0.6).abs() < 1e-9);
+
+        // one of the two replicas of b moves: half of its weight
+        let replicated = HashMap::from([(a.clone(), on(0)), (b.clone(), HashSet::from([nodes[1].clone(), nodes[2].clone()])), (c.clone(), on(0))]);
+        assert!((problem.migration_cost(&replicated) - 0.3).abs() < 1e-9);
+
+        // nothing to migrate from
+        assert_eq!(cost_problem(&config, [0.0; 3]).migration_cost(&moved), 0.0);
+    }
+}
@@ -1,11 +1,11 @@
 use std::collections::{HashMap, HashSet};
+use async_trait::async_trait;
 use crate::utility::{Config, Node, Service};
 use crate::stack::{self, StackConfig};
-use chrono::Local;
-use std::process::Command;
+use crate::strategy::{self, PlacementMap, PlacementStrategy, ReplicaNodes, StrategyContext, StrategyRegistry};
+use crate::affinity;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
-use rand::Rng;
 
 #[derive(Debug)]
 pub struct Random {
@@ -25,31 +25,49 @@ impl Random {
         }
     }
 
-    pub fn run_deploy(&self, stack_name: &str, compose_file: &str) -> Result<std::process::Output, String> {
-        let output = Command::new("./target/debug/deploy")
-            .arg("--stack")
-            .arg("deploy")
-            .arg("--name")
-            .arg(stack_name)
-            .arg("--file")
-            .arg(compose_file)
-            .output()
-            .map_err(|e| e.to_string())?;
-        
-        if !output.status.success() {
-            return Err(format!("Command failed with status: {}", output.status));
+    fn assign_services(&mut self) -> HashMap<Service, Option<HashSet<Node>>> {
+        let mut assignment_map: HashMap<Service, Option<HashSet<Node>>> = HashMap::new();
+        let mut rng = thread_rng();
+    
+        // Shuffle nodes
+        let mut nodes = self.config.cluster.nodes.clone();
+        nodes.shuffle(&mut rng);
+        let services = self.config.services.clone();
+        let replicas = stack::get_replicas(&self.stack_config);
+    
+        for service in services {
+            // Select one node per replica, each on a distinct node
+            let num_nodes_to_assign = (*replicas.get(&service.name).unwrap_or(&1) as usize).clamp(1, nodes.len());
+            
+            // Select a random set of nodes for this service
+            let selected_nodes: HashSet<Node> = nodes
+                .choose_multiple(&mut rng, num_nodes_to_assign)
+                .cloned()
+                .collect();
+            
+            assignment_map.insert(service.clone(), Some(selected_nodes));
         }
+    
+        assignment_map
+    }
+}
 
-        Ok(output)
+#[async_trait(?Send)]
+impl PlacementStrategy for Random {
+    fn name(&self) -> &'static str {
+        "random"
     }
 
-    pub async fn random_0(&mut self) -> Result<HashMap<Service, Option<HashSet<Node>>>, Box<dyn std::error::Error>> {
+    async fn plan(&mut self) -> Result<PlacementMap, Box<dyn std::error::Error>> {
         println!("Running the Random Strategy 0");
 
         self.placement = Some(HashMap::new());
 
         // create the placement
-        let assignment_map = self.assign_services();
+        let mut assignment_map = self.assign_services();
+
+        // move the services the affinity rules place together or apart - the capacity of the nodes is not known
+        affinity::enforce(&mut assignment_map, &affinity::rules(&self.config), &self.config.cluster.nodes, &ReplicaNodes::default())?;
 
         self.placement = Some(assignment_map);
 
@@ -73,71 +91,17 @@ impl Random {
         Ok(placement_map)
     }
 
-
-    fn assign_services(&mut self) -> HashMap<Service, Option<HashSet<Node>>> {
-        let mut assignment_map: HashMap<Service, Option<HashSet<Node>>> = HashMap::new();
-        let mut rng = thread_rng();
-    
-        // Shuffle nodes
-        let mut nodes = self.config.cluster.nodes.clone();
-        nodes.shuffle(&mut rng);
-        let services = self.config.services.clone();
-    
-        for service in services {
-            // Randomly select the number of nodes to assign to this service
-            let num_nodes_to_assign = rng.gen_range(1..2);
-            
-            // Select a random set of nodes for this service
-            let selected_nodes: HashSet<Node> = nodes
-                .choose_multiple(&mut rng, num_nodes_to_assign)
-                .cloned()
-                .collect();
-            
-            assignment_map.insert(service.clone(), Some(selected_nodes));
-        }
-    
-        assignment_map
+    async fn apply(&mut self, placement_map: PlacementMap) -> Result<(), String> {
+        strategy::deploy_placement(self.name(), &self.stack_name, &mut self.stack_config, placement_map).await
     }
 
-    pub fn run(&mut self, placement_map: Option<HashMap<Service, Option<HashSet<Node>>>>) {
-        match placement_map {
-            Some(map) => {
-                // Update and clean up the stack config
-                let mut local_stack_config = self.stack_config.clone();
-                stack::update_node_constraints(&mut local_stack_config, map);
-                stack::populate_volumes(&mut local_stack_config);
-                stack::delete_null_placement(&mut local_stack_config);
-
-                // Update the stack config
-                self.stack_config = local_stack_config;
-
-                // Create the YAML
-                let yaml_str = serde_yaml::to_string(&self.stack_config).unwrap();
-
-                // Provide the YAML to the deploy binary
-                let compose_file = format!("random_{}.yml", Local::now().format("%Y-%m-%d_%H-%M-%S"));
-
-                // Write the YAML to a file
-                std::fs::write(&compose_file, yaml_str).expect("Failed to write the YAML configuration file");
-
-                // Deploy the stack
-                if let Err(e) = self.run_deploy(&self.stack_name, &compose_file) {
-                    println!("Failed to deploy: {}", e);
-
-                    // Clean up the file
-                    std::fs::remove_file(&compose_file).expect("Failed to remove the YAML configuration file");
-                }
-
-                else {
-                    println!("Deployed the stack successfully!");
-
-                    // Clean up the file
-                    std::fs::remove_file(&compose_file).expect("Failed to remove the YAML configuration file");
-                }
-            },
-            None => {
-                println!("No placement solution provided");
-            }
-        }
+    fn placement(&self) -> Option<&PlacementMap> {
+        self.placement.as_ref()
     }
-}
\ No newline at end of file
+}
+
+pub fn register(registry: &mut StrategyRegistry) {
+    registry.register("random", |context: StrategyContext| {
+        Box::new(Random::new(context.config, None, context.stack_name, context.stack_config))
+    });
+}
@@ -0,0 +1,358 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use chrono::{DateTime, Utc};
+use tokio::time::{Duration, Instant};
+use crate::docker_client::{DockerClient, DockerError, Task};
+use crate::stack::{self, StackConfig};
+use crate::strategy::PlacementMap;
+
+// number of stack revisions kept to roll back to
+pub const REVISION_RING_SIZE: usize = 5;
+
+// how often the tasks are checked while waiting
+pub const CONVERGENCE_INTERVAL: Duration = Duration::from_secs(5);
+
+// A deployed revision of the stack
+#[derive(Debug, Clone)]
+pub struct StackRevision {
+    pub revision: u32,
+    pub stack_config: StackConfig,
+    pub placement: Option<PlacementMap>,
+    // the objective value of the placement, to restore the solver on rollback
+    pub obj_value: Option<f64>,
+    // whether the services converged after the deploy
+    pub healthy: bool,
+}
+
+// The last few revisions of the stack, oldest first
+#[derive(Debug)]
+pub struct RevisionRing {
+    revisions: VecDeque<StackRevision>,
+    capacity: usize,
+}
+
+impl RevisionRing {
+    pub fn new(capacity: usize) -> Self {
+        RevisionRing {
+            revisions: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, revision: StackRevision) {
+        if self.revisions.len() == self.capacity {
+            self.revisions.pop_front();
+        }
+        self.revisions.push_back(revision);
+    }
+
+    // the most recent revision whose services converged
+    pub fn last_good(&self) -> Option<&StackRevision> {
+        self.revisions.iter().rev().find(|revision| revision.healthy)
+    }
+
+    pub fn len(&self) -> usize {
+        self.revisions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.revisions.is_empty()
+    }
+}
+
+// Why a service did not converge
+#[derive(Debug, Clone)]
+pub struct ServiceFailure {
+    pub service: String,
+    pub running: u32,
+    pub desired: u32,
+    // e.g. the task error - "no suitable node (scheduling constraints not satisfied on 3 nodes)"
+    pub reason: String,
+    // waiting longer will not help - the update was paused or rolled back
+    pub terminal: bool,
+}
+
+#[derive(Debug)]
+pub enum Convergence {
+    Converged,
+    Failed(Vec<ServiceFailure>),
+}
+
+// Watch the tasks of the stack until every service runs its replicas on the nodes it was placed on,
+// or the timeout expires. Only the updates started from `deployed_at` on are the ones of this deploy.
+pub async fn wait_for_convergence(docker_client: &DockerClient, stack_name: &str, stack_config: &StackConfig, placement_map: &PlacementMap, deployed_at: DateTime<Utc>, timeout: Duration) -> Result<Convergence, DockerError> {
+    let replicas = stack::get_replicas(stack_config);
+    let deadline = Instant::now() + timeout;
+
+    let mut services: Vec<&String> = replicas.keys().collect();
+    services.sort();
+
+    // service name -> names of the nodes it may run on
+    let placed: HashMap<String, HashSet<String>> = placement_map.iter()
+        .filter_map(|(service, nodes)| Some((service.name.clone(), nodes.as_ref()?.iter().map(|node| node.name.clone()).collect::<HashSet<String>>())))
+        .filter(|(_, nodes)| !nodes.is_empty())
+        .collect();
+
+    loop {
+        // swarm node id -> node name
+        let node_names: HashMap<String, String> = docker_client.list_nodes().await?.into_iter()
+            .map(|node| (node.id.clone(), node.name()))
+            .collect();
+
+        let mut failures = Vec::new();
+
+        for service in &services {
+            // swarm service names are prefixed with the stack name
+            let service_name = format!("{}_{}", stack_name, service);
+            let desired = replicas[*service];
+
+            if let Some(failure) = check_service(docker_client, &service_name, desired, placed.get(*service), &node_names, deployed_at).await? {
+                failures.push(failure);
+            }
+        }
+
+        if failures.is_empty() {
+            println!("All {} service(s) of stack {} converged", services.len(), stack_name);
+            return Ok(Convergence::Converged);
+        }
+
+        if Instant::now() >= deadline || failures.iter().any(|failure| failure.terminal) {
+            return Ok(Convergence::Failed(failures));
+        }
+
+        println!("Waiting for {} service(s) to converge", failures.len());
+        tokio::time::sleep(CONVERGENCE_INTERVAL).await;
+    }
+}
+
+// None if the service runs all its replicas, on the nodes it was placed on. Right after an update the
+// old tasks still run, so a task counts only once it runs on one of those nodes.
+async fn check_service(docker_client: &DockerClient, service_name: &str, desired: u32, nodes: Option<&HashSet<String>>, node_names: &HashMap<String, String>, deployed_at: DateTime<Utc>) -> Result<Option<ServiceFailure>, DockerError> {
+    let service = docker_client.inspect_service(service_name).await?;
+    let tasks = docker_client.list_tasks(service_name).await?;
+
+    let running_tasks: Vec<&Task> = tasks.iter()
+        .filter(|task| task.desired_state == "running" && task.status.state == "running")
+        .collect();
+
+    let running = running_tasks.iter()
+        .filter(|task| is_placed(task, nodes, node_names))
+        .count() as u32;
+
+    // a service this deploy did not update keeps the status of its last update, failed or not. If the clock
+    // of the manager lags behind, a failed update still shows as the tasks left on the old nodes.
+    let update_status = service.update_status.as_ref()
+        .filter(|update_status| update_status.is_failed())
+        .filter(|update_status| update_status.started_at.is_some_and(|started_at| started_at >= deployed_at));
+
+    if let Some(update_status) = update_status {
+        return Ok(Some(ServiceFailure {
+            service: service_name.to_string(),
+            running,
+            desired,
+            reason: format!("update {}: {}", update_status.state, update_status.message),
+            terminal: true,
+        }));
+    }
+
+    let misplaced = running_tasks.len() as u32 - running;
+
+    if running >= desired && misplaced == 0 {
+        return Ok(None);
+    }
+
+    // a task that should run but does not (pending on "no suitable node", starting, ...)
+    let stuck = tasks.iter()
+        .find(|task| task.desired_state == "running" && task.status.state != "running")
+        .map(|task| format!("{}: {}", task.status.state, task.status.err.clone().unwrap_or_else(|| task.status.message.clone())));
+
+    // otherwise the last task that failed or was rejected
+    let failed = tasks.iter()
+        .find(|task| task.status.state == "failed" || task.status.state == "rejected")
+        .map(|task| format!("{}: {}", task.status.state, task.status.err.clone().unwrap_or_else(|| task.status.message.clone())));
+
+    // otherwise the tasks the update has not moved yet
+    let not_moved = (misplaced > 0).then(|| format!("{} task(s) still running outside the nodes {:?}", misplaced, nodes.map(sorted).unwrap_or_default()));
+
+    Ok(Some(ServiceFailure {
+        service: service_name.to_string(),
+        running,
+        desired,
+        reason: stuck.or(failed).or(not_moved).unwrap_or_else(|| "no task scheduled".to_string()),
+        terminal: false,
+    }))
+}
+
+// whether a task runs on one of the nodes of the service - any node if the service was not placed
+fn is_placed(task: &Task, nodes: Option<&HashSet<String>>, node_names: &HashMap<String, String>) -> bool {
+    match nodes {
+        None => true,
+        Some(nodes) => task.node_id.as_ref()
+            .and_then(|node_id| node_names.get(node_id))
+            .is_some_and(|name| nodes.contains(name)),
+    }
+}
+
+fn sorted(nodes: &HashSet<String>) -> Vec<String> {
+    let mut nodes: Vec<String> = nodes.iter().cloned().collect();
+    nodes.sort();
+    nodes
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use chrono::{DateTime, Utc};
+    use serde_json::{json, Value};
+    use tokio::time::Duration;
+
+    use crate::docker_client::test::FakeEngine;
+    use crate::rollback::{check_service, wait_for_convergence, Convergence, ServiceFailure};
+    use crate::stack::StackConfig;
+    use crate::strategy::PlacementMap;
+    use crate::utility::{Node, ResourceInt, Service};
+
+    const STACK: &str = r#"
+version: "3.8"
+services:
+  web:
+    deploy:
+      replicas: 1
+volumes: {}
+"#;
+
+    // the deploy the tests wait for
+    fn deployed_at() -> DateTime<Utc> {
+        "2024-05-01T12:00:00Z".parse().unwrap()
+    }
+
+    // web placed on node1
+    fn placement_map() -> PlacementMap {
+        let node = Node::new(1, "node1", "127.0.0.1", ResourceInt::new(4, 8, 100_000, 1000));
+        HashMap::from([(Service::new("web", "web", None, None), Some(HashSet::from([node])))])
+    }
+
+    fn nodes() -> Value {
+        json!([
+            { "ID": "id-1", "Version": { "Index": 1 }, "Spec": { "Labels": { "name": "node1" } }, "Description": { "Hostname": "host1" }, "Status": { "State": "ready" } },
+            { "ID": "id-2", "Version": { "Index": 1 }, "Spec": { "Labels": { "name": "node2" } }, "Description": { "Hostname": "host2" }, "Status": { "State": "ready" } }
+        ])
+    }
+
+    fn service(update_status: Value) -> Value {
+        json!({
+            "ID": "web-id",
+            "Version": { "Index": 7 },
+            "Spec": { "Name": "stack_web", "Mode": { "Replicated": { "Replicas": 1 } } },
+            "UpdateStatus": update_status
+        })
+    }
+
+    fn task(node_id: &str, state: &str, err: Option<&str>) -> Value {
+        json!({
+            "ID": "task-1", "ServiceID": "web-id", "NodeID": node_id, "Slot": 1, "DesiredState": "running",
+            "Status": { "State": state, "Message": state, "Err": err }
+        })
+    }
+
+    // The nodes, the web service with the update status and the tasks of a test
+    fn respond(path: &str, update_status: Value, tasks: Value) -> (u16, String) {
+        match path {
+            "/v1.41/nodes" => (200, nodes().to_string()),
+            "/v1.41/services/stack_web" => (200, service(update_status).to_string()),
+            path if path.starts_with("/v1.41/tasks") => (200, tasks.to_string()),
+            _ => (404, json!({ "message": "unexpected request" }).to_string()),
+        }
+    }
+
+    async fn wait(engine: &FakeEngine) -> Convergence {
+        let stack_config: StackConfig = serde_yaml::from_str(STACK).unwrap();
+        // no time to wait - the services are checked once
+        wait_for_convergence(&engine.client(), "stack", &stack_config, &placement_map(), deployed_at(), Duration::ZERO).await.unwrap()
+    }
+
+    fn failure(convergence: Convergence) -> ServiceFailure {
+        match convergence {
+            Convergence::Failed(mut failures) => {
+                assert_eq!(failures.len(), 1);
+                failures.remove(0)
+            }
+            Convergence::Converged => panic!("Expected web to fail to converge"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_converged() {
+        let engine = FakeEngine::start("converged", |_, path| {
+            respond(path, Value::Null, json!([task("id-1", "running", None)]))
+        });
+
+        assert!(matches!(wait(&engine).await, Convergence::Converged));
+    }
+
+    #[tokio::test]
+    async fn test_task_on_wrong_node() {
+        let engine = FakeEngine::start("wrong-node", |_, path| {
+            respond(path, Value::Null, json!([task("id-2", "running", None)]))
+        });
+
+        let failure = failure(wait(&engine).await);
+        assert_eq!(failure.service, "stack_web");
+        assert_eq!((failure.running, failure.desired), (0, 1));
+        assert_eq!(failure.reason, "1 task(s) still running outside the nodes [\"node1\"]");
+        assert!(!failure.terminal);
+    }
+
+    #[tokio::test]
+    async fn test_pending_without_suitable_node() {
+        let engine = FakeEngine::start("no-suitable-node", |_, path| {
+            respond(path, Value::Null, json!([task("", "pending", Some("no suitable node (scheduling constraints not satisfied on 2 nodes)"))]))
+        });
+
+        let failure = failure(wait(&engine).await);
+        assert_eq!(failure.running, 0);
+        assert_eq!(failure.reason, "pending: no suitable node (scheduling constraints not satisfied on 2 nodes)");
+        // the timeout decides, the swarm may still find a node
+        assert!(!failure.terminal);
+    }
+
+    #[tokio::test]
+    async fn test_paused_update() {
+        let engine = FakeEngine::start("paused-update", |_, path| {
+            let update_status = json!({ "State": "paused", "Message": "update paused due to failure", "StartedAt": "2024-05-01T12:00:05Z" });
+            respond(path, update_status, json!([task("id-2", "running", None)]))
+        });
+
+        let failure = failure(wait(&engine).await);
+        assert_eq!(failure.reason, "update paused: update paused due to failure");
+        assert!(failure.terminal);
+    }
+
+    #[tokio::test]
+    async fn test_rolled_back_update() {
+        let engine = FakeEngine::start("rolled-back-update", |_, path| {
+            let update_status = json!({ "State": "rollback_completed", "Message": "rollback completed", "StartedAt": "2024-05-01T12:00:05Z" });
+            respond(path, update_status, json!([task("id-1", "running", None)]))
+        });
+
+        // the tasks run where they were placed, but on the spec the swarm rolled back to
+        let failure = failure(wait(&engine).await);
+        assert_eq!(failure.running, 1);
+        assert_eq!(failure.reason, "update rollback_completed: rollback completed");
+        assert!(failure.terminal);
+    }
+
+    #[tokio::test]
+    async fn test_stale_update_status() {
+        let engine = FakeEngine::start("stale-update", |_, path| {
+            // an update of an earlier deploy that this one did not touch
+            let update_status = json!({ "State": "rollback_completed", "Message": "rollback completed", "StartedAt": "2024-04-30T08:00:00Z" });
+            respond(path, update_status, json!([task("id-1", "running", None)]))
+        });
+
+        let node_names = HashMap::from([("id-1".to_string(), "node1".to_string())]);
+        let nodes = HashSet::from(["node1".to_string()]);
+        let failure = check_service(&engine.client(), "stack_web", 1, Some(&nodes), &node_names, deployed_at()).await.unwrap();
+        assert!(failure.is_none());
+    }
+}
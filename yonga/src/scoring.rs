@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use crate::utility::{Config, Network, Node, Resource};
+
+// How the nodes are ranked from their resources and network
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringMethod {
+    // closeness to the ideal node and distance from the worst one
+    #[default]
+    Topsis,
+    // compromise between the group utility and the individual regret
+    Vikor,
+    // min-max normalised criteria, weighted and summed
+    WeightedSum,
+}
+
+// weight of the group utility against the individual regret in VIKOR
+const VIKOR_V: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    // the more the better
+    Benefit,
+    // the less the better
+    Cost,
+}
+
+// A criterion of the node ranking - its weight is the config weight of the same name
+#[derive(Debug, Clone, Copy)]
+pub struct Criterion {
+    pub name: &'static str,
+    pub direction: Direction,
+    value: fn(&Resource, &Network) -> f64,
+}
+
+pub const CRITERIA: [Criterion; 8] = [
+    Criterion { name: "cpu", direction: Direction::Benefit, value: |resource, _| resource.cpu },
+    Criterion { name: "memory", direction: Direction::Benefit, value: |resource, _| resource.memory },
+    Criterion { name: "disk", direction: Direction::Benefit, value: |resource, _| resource.disk },
+    Criterion { name: "network", direction: Direction::Cost, value: |resource, _| resource.network },
+    Criterion { name: "bandwidth", direction: Direction::Benefit, value: |_, network| network.bandwidth },
+    Criterion { name: "latency", direction: Direction::Cost, value: |_, network| network.latency },
+    Criterion { name: "packet_loss", direction: Direction::Cost, value: |_, network| network.packet_loss },
+    Criterion { name: "available", direction: Direction::Benefit, value: |_, network| network.available },
+];
+
+// The share of a criterion in the score of a node
+#[derive(Debug, Clone)]
+pub struct Contribution {
+    pub criterion: &'static str,
+    // the measured value
+    pub value: f64,
+    // the term of the criterion in the method: weighted value (weighted sum, TOPSIS) or regret (VIKOR)
+    pub term: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeScore {
+    pub node: Node,
+    // in [0, 1] - the higher the better
+    pub score: f64,
+    pub contributions: Vec<Contribution>,
+}
+
+// Score every node of the resource map, best first
+pub fn score_nodes(method: ScoringMethod, resource_map: &HashMap<Node, (Resource, Network)>, config: &Config) -> Vec<NodeScore> {
+    let mut nodes: Vec<&Node> = resource_map.keys().collect();
+    nodes.sort_by_key(|node| node.id);
+
+    // values[i][j] - criterion j of node i
+    let values: Vec<Vec<f64>> = nodes.iter().map(|node| {
+        let (resource, network) = &resource_map[*node];
+        CRITERIA.iter().map(|criterion| sanitize((criterion.value)(resource, network))).collect()
+    }).collect();
+
+    let weights = normalized_weights(config);
+
+    let (scores, terms) = match method {
+        ScoringMethod::Topsis => topsis(&values, &weights),
+        ScoringMethod::Vikor => vikor(&values, &weights, VIKOR_V),
+        ScoringMethod::WeightedSum => weighted_sum(&values, &weights),
+    };
+
+    let mut node_scores: Vec<NodeScore> = nodes.iter().enumerate().map(|(i, node)| NodeScore {
+        node: (*node).clone(),
+        score: scores[i],
+        contributions: CRITERIA.iter().enumerate().map(|(j, criterion)| Contribution {
+            criterion: criterion.name,
+            value: values[i][j],
+            term: terms[i][j],
+        }).collect(),
+    }).collect();
+
+    node_scores.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.node.id.cmp(&b.node.id)));
+
+    node_scores
+}
+
+// The cost of every node of the resource map for the placement problem - 1 minus its score, in [0, 1]
+// and the lower the better
+pub fn node_costs(method: ScoringMethod, resource_map: &HashMap<Node, (Resource, Network)>, config: &Config) -> HashMap<Node, f64> {
+    score_nodes(method, resource_map, config).into_iter()
+        .map(|node_score| (node_score.node, (1.0 - node_score.score).clamp(0.0, 1.0)))
+        .collect()
+}
+
+// Log the score of each node and the contribution of each criterion
+pub fn print_scores(method: ScoringMethod, scores: &[NodeScore]) {
+    println!("Node scores ({:?}):", method);
+
+    for node_score in scores {
+        println!("Node: {}, Score: {:.4}", node_score.node.name, node_score.score);
+
+        for contribution in &node_score.contributions {
+            println!("    {}: value {:.4}, term {:.4}", contribution.criterion, contribution.value, contribution.term);
+        }
+    }
+}
+
+// the config weights of the criteria, summing to 1 (equal weights if none is set)
+fn normalized_weights(config: &Config) -> Vec<f64> {
+    let weights: Vec<f64> = CRITERIA.iter().map(|criterion| config.get_weight(criterion.name).max(0.0)).collect();
+    let total: f64 = weights.iter().sum();
+
+    if total == 0.0 {
+        return vec![1.0 / CRITERIA.len() as f64; CRITERIA.len()];
+    }
+
+    weights.iter().map(|weight| weight / total).collect()
+}
+
+// missing metrics count as 0 instead of poisoning the ranking
+fn sanitize(value: f64) -> f64 {
+    if value.is_finite() { value } else { 0.0 }
+}
+
+// the best and worst value of each criterion
+fn ideals(values: &[Vec<f64>]) -> Vec<(f64, f64)> {
+    CRITERIA.iter().enumerate().map(|(j, criterion)| {
+        let max = values.iter().map(|row| row[j]).fold(f64::NEG_INFINITY, f64::max);
+        let min = values.iter().map(|row| row[j]).fold(f64::INFINITY, f64::min);
+
+        match criterion.direction {
+            Direction::Benefit => (max, min),
+            Direction::Cost => (min, max),
+        }
+    }).collect()
+}
+
+// min-max normalisation to [0, 1], 1 being the best - a criterion equal on all nodes does not discriminate
+fn min_max(values: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let ideals = ideals(values);
+
+    values.iter().map(|row| {
+        row.iter().enumerate().map(|(j, value)| {
+            let (best, worst) = ideals[j];
+            if best == worst { 1.0 } else { (value - worst) / (best - worst) }
+        }).collect()
+    }).collect()
+}
+
+fn weighted_sum(values: &[Vec<f64>], weights: &[f64]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let terms: Vec<Vec<f64>> = min_max(values).iter()
+        .map(|row| row.iter().zip(weights).map(|(value, weight)| value * weight).collect())
+        .collect();
+
+    let scores = terms.iter().map(|row| row.iter().sum()).collect();
+
+    (scores, terms)
+}
+
+fn topsis(values: &[Vec<f64>], weights: &[f64]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    // vector normalisation, weighted
+    let norms: Vec<f64> = (0..CRITERIA.len())
+        .map(|j| values.iter().map(|row| row[j].powi(2)).sum::<f64>().sqrt())
+        .collect();
+
+    let weighted: Vec<Vec<f64>> = values.iter().map(|row| {
+        row.iter().enumerate().map(|(j, value)| {
+            if norms[j] == 0.0 { 0.0 } else { value / norms[j] * weights[j] }
+        }).collect()
+    }).collect();
+
+    let ideals = ideals(&weighted);
+
+    let scores = weighted.iter().map(|row| {
+        let to_best = row.iter().zip(&ideals).map(|(value, (best, _))| (value - best).powi(2)).sum::<f64>().sqrt();
+        let to_worst = row.iter().zip(&ideals).map(|(value, (_, worst))| (value - worst).powi(2)).sum::<f64>().sqrt();
+
+        // every node is the same
+        if to_best + to_worst == 0.0 { 1.0 } else { to_worst / (to_best + to_worst) }
+    }).collect();
+
+    (scores, weighted)
+}
+
+fn vikor(values: &[Vec<f64>], weights: &[f64], v: f64) -> (Vec<f64>, Vec<Vec<f64>>) {
+    // weighted regret of each criterion - 0 on the best value
+    let regrets: Vec<Vec<f64>> = min_max(values).iter()
+        .map(|row| row.iter().zip(weights).map(|(value, weight)| (1.0 - value) * weight).collect())
+        .collect();
+
+    // group utility and individual regret
+    let s: Vec<f64> = regrets.iter().map(|row| row.iter().sum()).collect();
+    let r: Vec<f64> = regrets.iter().map(|row| row.iter().cloned().fold(0.0, f64::max)).collect();
+
+    let bounds = |x: &Vec<f64>| (x.iter().cloned().fold(f64::INFINITY, f64::min), x.iter().cloned().fold(f64::NEG_INFINITY, f64::max));
+    let (s_best, s_worst) = bounds(&s);
+    let (r_best, r_worst) = bounds(&r);
+
+    let scaled = |x: f64, best: f64, worst: f64| if worst == best { 0.0 } else { (x - best) / (worst - best) };
+
+    // Q is the lower the better
+    let scores = s.iter().zip(&r).map(|(si, ri)| {
+        let q = v * scaled(*si, s_best, s_worst) + (1.0 - v) * scaled(*ri, r_best, r_worst);
+        1.0 - q
+    }).collect();
+
+    (scores, regrets)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::scoring::{node_costs, score_nodes, ScoringMethod};
+    use crate::utility::{Config, Network, Node, Resource};
+
+    fn config(weights: &str) -> Config {
+        serde_yaml::from_str(&format!(r#"
+cluster:
+  nodes:
+    - {{ id: 1, name: "node1", ip: "10.0.0.1", resource: {{ cpu: 8, memory: 16000, disk: 100000, network: 1000 }} }}
+    - {{ id: 2, name: "node2", ip: "10.0.0.2", resource: {{ cpu: 8, memory: 16000, disk: 100000, network: 1000 }} }}
+    - {{ id: 3, name: "node3", ip: "10.0.0.3", resource: {{ cpu: 8, memory: 16000, disk: 100000, network: 1000 }} }}
+  prometheus: {{ url: "", label: "", stack: "", query: "", metric: "" }}
+database: {{ uri: "mongodb://localhost:27017", db: "yonga", collections: [] }}
+services: []
+weights:
+{}
+"#, weights)).unwrap()
+    }
+
+    const EQUAL_WEIGHTS: &str = r#"
+  - { name: "cpu", value: 1.0 }
+  - { name: "memory", value: 1.0 }
+  - { name: "disk", value: 1.0 }
+  - { name: "network", value: 1.0 }
+  - { name: "bandwidth", value: 1.0 }
+  - { name: "latency", value: 1.0 }
+  - { name: "packet_loss", value: 1.0 }
+  - { name: "available", value: 1.0 }
+"#;
+
+    // node 1 is the best on every criterion and node 3 the worst
+    fn resource_map(config: &Config) -> HashMap<Node, (Resource, Network)> {
+        let nodes = &config.cluster.nodes;
+        HashMap::from([
+            (nodes[0].clone(), (Resource::new(6.0, 12000.0, 80.0, 100.0), Network::new(1.0, 1000.0, 1.0, 0.0))),
+            (nodes[1].clone(), (Resource::new(4.0, 8000.0, 50.0, 300.0), Network::new(1.0, 500.0, 5.0, 0.01))),
+            (nodes[2].clone(), (Resource::new(2.0, 4000.0, 20.0, 600.0), Network::new(0.0, 100.0, 20.0, 0.05))),
+        ])
+    }
+
+    fn ranking(method: ScoringMethod, config: &Config, resource_map: &HashMap<Node, (Resource, Network)>) -> Vec<i64> {
+        score_nodes(method, resource_map, config).iter().map(|node_score| node_score.node.id).collect()
+    }
+
+    #[test]
+    fn test_topsis_ranking() {
+        let config = config(EQUAL_WEIGHTS);
+        let scores = score_nodes(ScoringMethod::Topsis, &resource_map(&config), &config);
+
+        assert_eq!(scores.iter().map(|node_score| node_score.node.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        // the ideal node and the anti-ideal node
+        assert!((scores[0].score - 1.0).abs() < 1e-9);
+        assert!(scores[2].score.abs() < 1e-9);
+        assert!(scores[1].score > 0.0 && scores[1].score < 1.0);
+        assert_eq!(scores[0].contributions.len(), 8);
+    }
+
+    #[test]
+    fn test_methods_agree_on_dominance() {
+        let config = config(EQUAL_WEIGHTS);
+        let resource_map = resource_map(&config);
+
+        for method in [ScoringMethod::Topsis, ScoringMethod::Vikor, ScoringMethod::WeightedSum] {
+            assert_eq!(ranking(method, &config, &resource_map), vec![1, 2, 3], "{:?}", method);
+        }
+    }
+
+    #[test]
+    fn test_weights() {
+        // only the latency counts
+        let config = config(r#"  - { name: "latency", value: 1.0 }"#);
+        let mut resource_map = resource_map(&config);
+
+        // node 3 gets the lowest latency and stays the worst on everything else
+        resource_map.get_mut(&config.cluster.nodes[2]).unwrap().1.latency = 0.5;
+
+        assert_eq!(ranking(ScoringMethod::Topsis, &config, &resource_map)[0], 3);
+    }
+
+    #[test]
+    fn test_node_costs() {
+        let config = config(EQUAL_WEIGHTS);
+        let mut resource_map = resource_map(&config);
+
+        // a missing metric does not make a cost infinite
+        resource_map.get_mut(&config.cluster.nodes[1]).unwrap().1.latency = f64::INFINITY;
+
+        let costs = node_costs(ScoringMethod::Topsis, &resource_map, &config);
+
+        assert_eq!(costs.len(), 3);
+        assert!(costs.values().all(|cost| cost.is_finite() && (0.0..=1.0).contains(cost)));
+        assert!(costs[&config.cluster.nodes[0]] < costs[&config.cluster.nodes[2]]);
+    }
+
+    #[test]
+    fn test_identical_nodes() {
+        let config = config(EQUAL_WEIGHTS);
+        let resource = (Resource::new(4.0, 8000.0, 50.0, 300.0), Network::new(1.0, 500.0, 5.0, 0.01));
+        let resource_map: HashMap<Node, (Resource, Network)> = config.cluster.nodes.iter().map(|node| (node.clone(), resource.clone())).collect();
+
+        // no node is worse than another - ties are broken by id
+        let scores = score_nodes(ScoringMethod::Topsis, &resource_map, &config);
+        assert!(scores.iter().all(|node_score| node_score.score == 1.0));
+        assert_eq!(scores.iter().map(|node_score| node_score.node.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+}
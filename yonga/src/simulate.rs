@@ -0,0 +1,76 @@
+use clap::{Command, ArgAction, Arg};
+use std::fs;
+use yonga::stack::StackConfig;
+use yonga::simulator::{self, Recording};
+use yonga::utility::Config;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let matches = Command::new("OCAS-Simulator")
+        .arg(Arg::new("compose") //docker-compose file
+            .long("compose")
+            .short('m')
+            .required(true)
+            .action(ArgAction::Set))
+        .arg(Arg::new("config") // configuration file
+            .long("config")
+            .short('c')
+            .required(true)
+            .action(ArgAction::Set))
+        .arg(Arg::new("recording") // recorded node documents and traces (JSON) - synthetic if not given
+            .long("recording")
+            .short('r')
+            .required(false)
+            .action(ArgAction::Set))
+        .arg(Arg::new("seed") // seed of the synthetic recording
+            .long("seed")
+            .required(false)
+            .default_value("10")
+            .action(ArgAction::Set))
+        .arg(Arg::new("save") // write the recording used to a file
+            .long("save")
+            .required(false)
+            .action(ArgAction::Set))
+        .arg(Arg::new("rounds") // plan rounds per strategy
+            .long("rounds")
+            .short('n')
+            .required(false)
+            .default_value("2")
+            .action(ArgAction::Set))
+        .arg(Arg::new("stack")
+            .long("stack")
+            .short('s')
+            .required(false)
+            .default_value("hotelreservation")
+            .action(ArgAction::Set))
+        .get_matches();
+
+    let yaml_config = matches.get_one::<String>("compose").unwrap();
+    let cluster_config = matches.get_one::<String>("config").unwrap();
+    let seed = matches.get_one::<String>("seed").unwrap().parse::<u64>()?;
+    let rounds = matches.get_one::<String>("rounds").unwrap().parse::<usize>()?;
+    let stack_name = matches.get_one::<String>("stack").unwrap();
+
+    // Read the config files
+    let yaml_str = fs::read_to_string(yaml_config).expect("Failed to read the YAML configuration file");
+    let cluster_str = fs::read_to_string(cluster_config).expect("Failed to read the cluster configuration file");
+
+    let stack_config: StackConfig = serde_yaml::from_str(&yaml_str)?;
+    let cluster_config: Config = serde_yaml::from_str(&cluster_str)?;
+
+    // Replay a recording or generate one
+    let recording = match matches.get_one::<String>("recording") {
+        Some(path) => Recording::load(path)?,
+        None => Recording::synthetic(&cluster_config, seed, 20, 2000),
+    };
+
+    if let Some(path) = matches.get_one::<String>("save") {
+        recording.save(path)?;
+    }
+
+    let results = simulator::simulate(&cluster_config, stack_name, &stack_config, recording, rounds).await?;
+
+    simulator::print_results(&results);
+
+    Ok(())
+}
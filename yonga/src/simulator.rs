@@ -0,0 +1,640 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::sync::Arc;
+
+use actix_web::{get, web, App, HttpResponse, HttpServer, Responder};
+use actix_web::dev::ServerHandle;
+use chrono::{Duration, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::api_client::ApiClient;
+use crate::milp;
+use crate::node::{NodeGraph, NodeTree, NodeEntryMongo};
+use crate::nsga2opticas::OMicroservicePlacementProblem;
+use crate::snapshot::SnapshotCache;
+use crate::solver::Solver;
+use crate::stack::StackConfig;
+use crate::strategy::{self, Evaluation, PlacementMap, StrategyContext, StrategyRegistry};
+use crate::trace::{TraceEntry, ServiceGraph, build_trees};
+use crate::utility::{Config, EnvironmentMetric, Network, Node, NodeMongo, Resource, Service, ServiceMetric};
+
+// the api averages the latest documents of a node
+const API_DOCUMENTS: usize = 20;
+
+// the limits used by yonga when reading the database
+const GRAPH_NODE_DOCUMENTS: usize = 500;
+const GRAPH_TRACE_ENTRIES: usize = 500000;
+
+// A snapshot of the cluster as stored by the monitor: the node documents (resources, network
+// environment and services of each node) and the traces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    pub nodes: Vec<NodeMongo>,
+    pub traces: Vec<TraceEntry>,
+}
+
+impl Recording {
+    // Load a recording from a JSON file
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let recording_str = fs::read_to_string(path)?;
+        let recording: Recording = serde_json::from_str(&recording_str)?;
+
+        println!("Loaded the recording {} - {} node documents, {} trace entries", path, recording.nodes.len(), recording.traces.len());
+
+        Ok(recording)
+    }
+
+    // Save the recording to a JSON file
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        println!("Recording written to {}", path);
+
+        Ok(())
+    }
+
+    // Generate a recording for the cluster and services of the config. The same seed gives the same recording.
+    pub fn synthetic(config: &Config, seed: u64, documents: usize, traces: usize) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let nodes = &config.cluster.nodes;
+        let services = &config.services;
+        let now = Utc::now();
+
+        // the network of each link, jittered in every document
+        let mut links: HashMap<(String, String), Network> = HashMap::new();
+        for source in nodes {
+            for destination in nodes {
+                if source.name != destination.name {
+                    let network = Network::new(1.0, rng.gen_range(50.0..1000.0), rng.gen_range(1.0..200.0), rng.gen_range(0.0..2.0));
+                    links.insert((source.name.clone(), destination.name.clone()), network);
+                }
+            }
+        }
+
+        // the current placement - every service runs on one random node
+        let current: Vec<(Service, Node)> = services.iter().map(|service| {
+            (service.clone(), nodes[rng.gen_range(0..nodes.len())].clone())
+        }).collect();
+
+        let usage: HashMap<String, Resource> = services.iter().map(|service| {
+            let resource = Resource::new(rng.gen_range(0.01..0.5), rng.gen_range(10.0..300.0), rng.gen_range(0.0..1.0), rng.gen_range(0.0..50.0));
+            (service.name.clone(), resource)
+        }).collect();
+
+        let mut node_documents = Vec::new();
+
+        for index in 0..documents {
+            let timestamp = now - Duration::seconds(30 * index as i64);
+            let mut jitter = |value: f64| value * rng.gen_range(0.9..1.1);
+
+            for node in nodes {
+                // the available resources of the node
+                let resource = Resource::new(
+                    jitter(node.resource.cpu as f64 * 0.6),
+                    jitter(node.resource.memory as f64 * 0.6),
+                    jitter(node.resource.disk as f64 / 1000.0 * 0.6),
+                    jitter(500.0),
+                );
+
+                let environment = nodes.iter().filter(|other| other.name != node.name).map(|other| {
+                    let link = &links[&(node.name.clone(), other.name.clone())];
+                    EnvironmentMetric {
+                        node: other.clone(),
+                        network: Network::new(link.available, jitter(link.bandwidth), jitter(link.latency), jitter(link.packet_loss)),
+                    }
+                }).collect();
+
+                // the monitor records the services with the stack prefix
+                let node_services = current.iter().filter(|(_, n)| n.name == node.name).map(|(service, _)| {
+                    let name = format!("{}{}", config.cluster.prometheus.stack, service.name);
+                    let used = &usage[&service.name];
+                    ServiceMetric {
+                        service: Service::new(&name, &name, Some("".to_string()), Some("".to_string())),
+                        utilization: Resource::new(jitter(used.cpu), jitter(used.memory), jitter(used.disk), jitter(used.network)),
+                    }
+                }).collect();
+
+                node_documents.push(NodeMongo {
+                    timestamp: bson::DateTime::from_chrono(timestamp),
+                    metadata: node.clone(),
+                    resource,
+                    environment,
+                    services: node_services,
+                });
+            }
+        }
+
+        // a call tree over the services - the first service is the entry point
+        let callers: Vec<Option<usize>> = (0..services.len()).map(|index| {
+            if index == 0 { None } else { Some(rng.gen_range(0..index)) }
+        }).collect();
+
+        let mut trace_entries = Vec::new();
+
+        for trace in 0..traces {
+            let trace_id = format!("trace-{}", trace);
+            let start_time = now - Duration::milliseconds(rng.gen_range(0..3_600_000));
+            let mut spans: Vec<(usize, String)> = Vec::new();
+            let mut entries = Vec::new();
+
+            for (index, caller) in callers.iter().enumerate() {
+                // a callee is only part of the trace if its caller is, and not on every request
+                let parent_span = match caller {
+                    None => None,
+                    Some(caller) => match spans.iter().find(|(service, _)| service == caller) {
+                        Some((_, span_id)) if rng.gen_bool(0.7) => Some(span_id.clone()),
+                        _ => continue,
+                    },
+                };
+
+                let span_id = format!("{}-{}", trace_id, index);
+                let duration = rng.gen_range(100..20000); // microseconds
+                let service = &services[index];
+
+                entries.push(TraceEntry::new(&trace_id, &span_id, &service.name, start_time, duration, parent_span.as_deref(), &service.name));
+                spans.push((index, span_id));
+            }
+
+            // children before their parents, like the latest-first entries of the database
+            trace_entries.extend(entries.into_iter().rev());
+        }
+
+        println!("Generated a synthetic recording - {} node documents, {} trace entries", node_documents.len(), trace_entries.len());
+
+        Recording {
+            nodes: node_documents,
+            traces: trace_entries,
+        }
+    }
+
+    // The latest documents of a node
+    pub fn node_documents(&self, node: &str, limit: usize) -> Vec<&NodeMongo> {
+        let mut documents: Vec<&NodeMongo> = self.nodes.iter().filter(|document| document.metadata.name == node).collect();
+        documents.sort_by_key(|document| std::cmp::Reverse(document.timestamp));
+        documents.truncate(limit);
+
+        documents
+    }
+
+    // Where the services run according to the latest document of each node
+    pub fn current_placement(&self, config: &Config) -> PlacementMap {
+        let mut placement_map: PlacementMap = HashMap::new();
+
+        for node in &config.cluster.nodes {
+            if let Some(document) = self.node_documents(&node.name, 1).first() {
+                for metric in &document.services {
+                    let name = metric.service.name.strip_prefix(&config.cluster.prometheus.stack).unwrap_or(&metric.service.name);
+                    if let Some(service) = config.services.iter().find(|service| service.name == name) {
+                        placement_map.entry(service.clone()).or_insert_with(|| Some(HashSet::new())).as_mut().unwrap().insert(node.clone());
+                    }
+                }
+            }
+        }
+
+        placement_map
+    }
+
+    // Build the service graph and the node tree like yonga does from the database
+    pub fn build_graphs(&self, config: &Config) -> (ServiceGraph, NodeTree) {
+        let mut trace_entries = self.traces.clone();
+        trace_entries.truncate(GRAPH_TRACE_ENTRIES);
+
+        let trees = build_trees(trace_entries);
+
+        let mut service_tree = ServiceGraph::new();
+        service_tree.build_from_traces(&trees);
+
+        let mut node_graph = NodeGraph::new(config.cluster.nodes.clone());
+        for node in &config.cluster.nodes {
+            let entries: Vec<NodeEntryMongo> = self.node_documents(&node.name, GRAPH_NODE_DOCUMENTS).into_iter().map(NodeEntryMongo::from).collect();
+            node_graph.add_entries(entries);
+        }
+
+        let maxmin_network = node_graph.get_maxmin_network();
+
+        let mut node_tree = NodeTree::new(config.clone());
+        node_tree.aggregate_edges(&node_graph, &maxmin_network);
+
+        (service_tree, node_tree)
+    }
+}
+
+#[derive(Debug)]
+struct SimulatedState {
+    recording: Arc<Recording>,
+    config: Config,
+}
+
+// get - number of services in a node
+#[get("/node/{node}/services/count")]
+async fn get_service_count(state: web::Data<Arc<SimulatedState>>, node: web::Path<String>) -> impl Responder {
+    match state.recording.node_documents(&node, 1).first() {
+        Some(document) => HttpResponse::Ok().body(format!("{}", document.services.len())),
+        None => HttpResponse::NotFound().body("No documents found for the node\n"),
+    }
+}
+
+// get - services in a node
+#[get("/node/{node}/services")]
+async fn get_node_services(state: web::Data<Arc<SimulatedState>>, node: web::Path<String>) -> impl Responder {
+    match state.recording.node_documents(&node, 1).first() {
+        Some(document) => {
+            let service_names: Vec<String> = document.services.iter().map(|metric| metric.service.name.clone()).collect();
+            HttpResponse::Ok().json(service_names)
+        }
+        None => HttpResponse::NotFound().body("No documents found for the node\n"),
+    }
+}
+
+// get - resource utilization in a node
+#[get("/node/{node}/utilization")]
+async fn get_node_utilization(state: web::Data<Arc<SimulatedState>>, node: web::Path<String>) -> impl Responder {
+    let documents = state.recording.node_documents(&node, API_DOCUMENTS);
+
+    if documents.is_empty() {
+        return HttpResponse::NotFound().body("No documents found for the node\n");
+    }
+
+    let resources: Vec<&Resource> = documents.iter().map(|document| &document.resource).collect();
+
+    HttpResponse::Ok().json(average_resource(&resources))
+}
+
+// get - service resource utilization on a node
+#[get("/node/{node}/service/{service}/utilization")]
+async fn get_node_service_utilization(state: web::Data<Arc<SimulatedState>>, path: web::Path<(String, String)>) -> impl Responder {
+    let (node, service) = path.into_inner();
+
+    let metric = state.recording.node_documents(&node, 1).first()
+        .and_then(|document| document.services.iter().find(|metric| metric.service.name == service));
+
+    match metric {
+        Some(metric) => HttpResponse::Ok().json(&metric.utilization),
+        None => HttpResponse::InternalServerError().body("Failed to extract service resource metrics.\n"),
+    }
+}
+
+// get - total utilization of a service in the cluster
+#[get("/service/{service}/utilization")]
+async fn get_service_utilization(state: web::Data<Arc<SimulatedState>>, service: web::Path<String>) -> impl Responder {
+    let service = format!("{}{}", state.config.cluster.prometheus.stack, service);
+
+    // the average utilization of the service on every node running it
+    let mut node_averages = Vec::new();
+
+    for node in &state.config.cluster.nodes {
+        let resources: Vec<&Resource> = state.recording.node_documents(&node.name, API_DOCUMENTS).into_iter()
+            .filter_map(|document| document.services.iter().find(|metric| metric.service.name == service))
+            .map(|metric| &metric.utilization)
+            .collect();
+
+        if !resources.is_empty() {
+            node_averages.push(average_resource(&resources));
+        }
+    }
+
+    if node_averages.is_empty() {
+        return HttpResponse::NotFound().body("Service not found on any node\n");
+    }
+
+    HttpResponse::Ok().json(average_resource(&node_averages.iter().collect::<Vec<&Resource>>()))
+}
+
+// get the environment metrics for a node - how the other nodes see it
+#[get("/node/{node}/environment")]
+async fn get_node_environment(state: web::Data<Arc<SimulatedState>>, node: web::Path<String>) -> impl Responder {
+    let node = node.into_inner();
+    let mut networks = Vec::new();
+
+    for other in &state.config.cluster.nodes {
+        if other.name == node {
+            continue;
+        }
+
+        let links: Vec<&Network> = state.recording.node_documents(&other.name, API_DOCUMENTS).into_iter()
+            .filter_map(|document| document.environment.iter().find(|metric| metric.node.name == node))
+            .map(|metric| &metric.network)
+            .collect();
+
+        if !links.is_empty() {
+            networks.push(average_network(&links));
+        }
+    }
+
+    if networks.is_empty() {
+        return HttpResponse::NotFound().body("No metrics found for other nodes\n");
+    }
+
+    HttpResponse::Ok().json(average_network(&networks.iter().collect::<Vec<&Network>>()))
+}
+
+fn average_resource(resources: &[&Resource]) -> Resource {
+    let mut total = Resource::default();
+    for resource in resources {
+        total.add(resource);
+    }
+
+    let len = resources.len() as f64;
+    Resource::new(total.cpu / len, total.memory / len, total.disk / len, total.network / len)
+}
+
+fn average_network(networks: &[&Network]) -> Network {
+    let mut total = Network::default();
+    for network in networks {
+        total.available += network.available;
+        total.bandwidth += network.bandwidth;
+        total.latency += network.latency;
+        total.packet_loss += network.packet_loss;
+    }
+
+    let len = networks.len() as f64;
+    Network::new(total.available / len, total.bandwidth / len, total.latency / len, total.packet_loss / len)
+}
+
+// The api routes used by ApiClient, served from a recording on a local port
+pub struct SimulatedApi {
+    pub url: String,
+    handle: ServerHandle,
+}
+
+impl SimulatedApi {
+    pub async fn start(recording: Arc<Recording>, config: Config) -> std::io::Result<Self> {
+        let state = Arc::new(SimulatedState {
+            recording,
+            config,
+        });
+
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(state.clone()))
+                .service(get_service_count)
+                .service(get_node_services)
+                .service(get_node_utilization)
+                .service(get_node_service_utilization)
+                .service(get_service_utilization)
+                .service(get_node_environment)
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))?;
+
+        let url = format!("http://{}", server.addrs()[0]);
+        let server = server.run();
+        let handle = server.handle();
+        tokio::spawn(server);
+
+        println!("Simulated api listening on {}", url);
+
+        Ok(SimulatedApi {
+            url,
+            handle,
+        })
+    }
+
+    pub async fn stop(&self) {
+        self.handle.stop(true).await;
+    }
+}
+
+// The objectives of a placement of a strategy in one round of the simulation
+#[derive(Debug, Clone)]
+pub struct SimulationResult {
+    pub strategy: String,
+    pub round: usize,
+    pub communication_cost: f64,
+    pub resource_cost: f64,
+    pub resource_imbalance: f64,
+    // p99 latency (ms) of the slowest request path
+    pub latency: f64,
+    pub migrations: usize,
+    pub unplaced: usize,
+    // communication + resource cost above the MILP optimum, relative to it
+    pub gap: Option<f64>,
+}
+
+// Run every default strategy against the same recording for a number of plan rounds.
+// Nothing is deployed - each placement is assumed applied before the next round.
+pub async fn simulate(config: &Config, stack_name: &str, stack_config: &StackConfig, recording: Recording, rounds: usize) -> Result<Vec<SimulationResult>, Box<dyn Error>> {
+    simulate_strategies(&StrategyRegistry::with_defaults(), config, stack_name, stack_config, recording, rounds).await
+}
+
+// Run the strategies of a registry against the same recording for a number of plan rounds
+pub async fn simulate_strategies(registry: &StrategyRegistry, config: &Config, stack_name: &str, stack_config: &StackConfig, recording: Recording, rounds: usize) -> Result<Vec<SimulationResult>, Box<dyn Error>> {
+    let recording = Arc::new(recording);
+    let api = SimulatedApi::start(recording.clone(), config.clone()).await?;
+    let api_client = ApiClient::new(&api.url);
+
+    // the recording does not change - every strategy and round plans on the same cluster snapshot
+    let snapshots = SnapshotCache::new(tokio::time::Duration::MAX);
+
+    // every placement is scored with the objectives of the solver on the same snapshot
+    let (service_tree, node_tree) = recording.build_graphs(config);
+    let mut solver = Solver::new(config.clone(), api_client.clone());
+    solver.snapshots = snapshots.clone();
+    let (problem, _) = solver.build_problem(&service_tree, &node_tree).await?;
+
+    // the exact optimum of the snapshot, to measure the strategies against
+    let optimum = match milp::solve(&problem, &[]) {
+        Ok(solution) => Some(solution.objective),
+        Err(e) => {
+            println!("No MILP optimum for the snapshot: {}", e);
+            None
+        }
+    };
+
+    let baseline = recording.current_placement(config);
+    let mut results = Vec::new();
+
+    for name in registry.names() {
+        let context = StrategyContext::new(config.clone(), stack_name.to_string(), stack_config.clone(), api_client.clone())
+            .with_recording(recording.clone())
+            .with_snapshots(snapshots.clone());
+
+        let mut placement = match registry.create(&name, context) {
+            Some(placement) => placement,
+            None => continue,
+        };
+
+        let mut previous = baseline.clone();
+
+        for round in 0..rounds {
+            println!("Simulating the {} strategy - round {}", name, round);
+
+            match placement.plan().await {
+                Ok(placement_map) => {
+                    results.push(score(&name, round, &problem, optimum, &previous, &placement_map));
+                    placement.assume_applied(&placement_map);
+                    previous = placement_map;
+                }
+                Err(e) => {
+                    println!("No placement solution found for {} in round {}: {}", name, round, e);
+                    break;
+                }
+            }
+
+            if placement.evaluate().await == Evaluation::Done {
+                break;
+            }
+        }
+    }
+
+    api.stop().await;
+
+    Ok(results)
+}
+
+fn score(strategy: &str, round: usize, problem: &OMicroservicePlacementProblem, optimum: Option<f64>, previous: &PlacementMap, placement_map: &PlacementMap) -> SimulationResult {
+    // the objectives take every replica of the placed services
+    let placements = strategy::placed_nodes(placement_map);
+    let unplaced = placement_map.len() - placements.len();
+
+    let (communication_cost, resource_cost, resource_imbalance) = problem.evaluate_placement(&placements);
+    let (_, latency) = problem.path_latency(&placements);
+
+    SimulationResult {
+        strategy: strategy.to_string(),
+        round,
+        communication_cost,
+        resource_cost,
+        resource_imbalance,
+        latency,
+        migrations: strategy::diff_placements(previous, placement_map).len(),
+        unplaced,
+        // a partial placement is not comparable
+        gap: optimum.filter(|_| unplaced == 0).map(|optimum| milp::optimality_gap(communication_cost + resource_cost, optimum)),
+    }
+}
+
+// Print the results as a table
+pub fn print_results(results: &[SimulationResult]) {
+    println!("{:<10} {:>5} {:>12} {:>12} {:>12} {:>12} {:>10} {:>8} {:>8}", "strategy", "round", "comm. cost", "res. cost", "imbalance", "p99 (ms)", "migrations", "unplaced", "gap");
+
+    for result in results {
+        let gap = result.gap.map_or("-".to_string(), |gap| format!("{:.1}%", gap * 100.0));
+
+        println!(
+            "{:<10} {:>5} {:>12.4} {:>12.4} {:>12.4} {:>12.2} {:>10} {:>8} {:>8}",
+            result.strategy, result.round, result.communication_cost, result.resource_cost, result.resource_imbalance, result.latency, result.migrations, result.unplaced, gap
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::nsga2opticas::{ObjectiveBounds, OMicroservicePlacementProblem};
+    use crate::simulator::{score, simulate_strategies, Recording, SimulationResult};
+    use crate::stack::StackConfig;
+    use crate::strategy::{PlacementMap, StrategyRegistry};
+    use crate::utility::Config;
+
+    const SEED: u64 = 10;
+
+    fn config() -> Config {
+        serde_yaml::from_str(r#"
+cluster:
+  nodes:
+    - { id: 1, name: "node1", ip: "10.0.0.1", resource: { cpu: 8, memory: 16000, disk: 100000, network: 1000 } }
+    - { id: 2, name: "node2", ip: "10.0.0.2", resource: { cpu: 8, memory: 16000, disk: 100000, network: 1000 } }
+    - { id: 3, name: "node3", ip: "10.0.0.3", resource: { cpu: 8, memory: 16000, disk: 100000, network: 1000 } }
+  prometheus: { url: "", label: "", stack: "test_", query: "", metric: "" }
+database: { uri: "mongodb://localhost:27017", db: "yonga", collections: [] }
+services:
+  - { id: "1", name: "frontend" }
+  - { id: "2", name: "search" }
+  - { id: "3", name: "geo" }
+  - { id: "4", name: "rate" }
+weights: []
+"#).unwrap()
+    }
+
+    fn stack_config() -> StackConfig {
+        serde_yaml::from_str(r#"
+version: "3.8"
+services:
+  frontend: {}
+  search: {}
+  geo: {}
+  rate: {}
+volumes: {}
+"#).unwrap()
+    }
+
+    // binpack and the MILP - neither draws random numbers
+    fn registry() -> StrategyRegistry {
+        let mut registry = StrategyRegistry::new();
+        crate::binpack::register(&mut registry);
+        crate::milp::register(&mut registry);
+        registry
+    }
+
+    async fn simulate() -> Vec<SimulationResult> {
+        let config = config();
+        let recording = Recording::synthetic(&config, SEED, 5, 200);
+
+        let mut results = simulate_strategies(&registry(), &config, "test", &stack_config(), recording, 1).await.unwrap();
+        results.sort_by(|a, b| a.strategy.cmp(&b.strategy));
+        results
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_simulate() {
+        let results = simulate().await;
+
+        // one row per strategy and round
+        let rows: Vec<(&str, usize)> = results.iter().map(|result| (result.strategy.as_str(), result.round)).collect();
+        assert_eq!(rows, vec![("binpack", 0), ("milp", 0)]);
+
+        for result in &results {
+            assert_eq!(result.unplaced, 0, "{} left services unplaced", result.strategy);
+            assert!(result.migrations <= 4, "{} moved {} services", result.strategy, result.migrations);
+        }
+
+        // the MILP is the optimum the gap is measured against, binpack can not beat it
+        let (binpack, milp) = (&results[0], &results[1]);
+        assert!(milp.gap.unwrap().abs() < 1e-6);
+        assert!(binpack.gap.unwrap() > -1e-6);
+
+        // the same seed gives the same report
+        let again = simulate().await;
+        for (result, other) in results.iter().zip(&again) {
+            assert_eq!(result.migrations, other.migrations);
+            assert_eq!(result.gap, other.gap);
+            assert_eq!((result.communication_cost, result.resource_cost), (other.communication_cost, other.resource_cost));
+        }
+    }
+
+    #[test]
+    fn test_score() {
+        let config = config();
+        let nodes = &config.cluster.nodes;
+        let services = &config.services;
+        let on = |node: usize| Some(HashSet::from([nodes[node].clone()]));
+        let bounds = ObjectiveBounds { max_opt_cost: 0.0, minmax_node_cost: (0.0, 0.0), minmax_resource_imbalance: (0.0, 1.0) };
+        let problem = OMicroservicePlacementProblem::new(config.clone(), HashMap::new(), HashMap::new(), HashMap::new(), bounds, HashMap::new(), HashMap::new())
+            .with_constraints(Vec::new());
+
+        let previous: PlacementMap = HashMap::from([
+            (services[0].clone(), on(0)), (services[1].clone(), on(1)), (services[2].clone(), on(2)), (services[3].clone(), on(0)),
+        ]);
+
+        // search moves to node1 and rate is not placed
+        let placement_map: PlacementMap = HashMap::from([
+            (services[0].clone(), on(0)), (services[1].clone(), on(0)), (services[2].clone(), on(2)), (services[3].clone(), None),
+        ]);
+
+        let result = score("binpack", 1, &problem, Some(1.0), &previous, &placement_map);
+        assert_eq!((result.strategy.as_str(), result.round), ("binpack", 1));
+        assert_eq!(result.migrations, 2);
+        assert_eq!(result.unplaced, 1);
+        // a partial placement has no gap
+        assert_eq!(result.gap, None);
+
+        // nothing changes - no migrations and a gap to the optimum
+        let result = score("binpack", 2, &problem, Some(1.0), &previous, &previous);
+        assert_eq!((result.migrations, result.unplaced), (0, 0));
+        assert!(result.gap.is_some());
+    }
+}
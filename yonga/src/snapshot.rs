@@ -0,0 +1,365 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Arc;
+use futures::future::join_all;
+use tokio::sync::Mutex;
+use tokio::time::{timeout, Duration, Instant};
+
+use crate::api_client::ApiClient;
+use crate::utility::{Config, Network, Node, Resource, Service};
+
+// how long a snapshot is reused before the cluster is queried again - shorter than a control cycle
+pub const SNAPSHOT_MAX_AGE: Duration = Duration::from_secs(60);
+
+// What the api returned for a node - None where the request failed or timed out
+#[derive(Debug, Clone)]
+pub struct NodeSnapshot {
+    pub utilization: Option<Resource>,
+    pub environment: Option<Network>,
+    // the services running on the node, with the stack prefix
+    pub services: Option<Vec<String>>,
+    // the utilization of each service on the node, keyed by the name with the stack prefix
+    pub service_utilization: HashMap<String, Option<Resource>>,
+}
+
+// The inputs of every strategy in a cycle, gathered once and concurrently
+#[derive(Debug, Clone)]
+pub struct ClusterSnapshot {
+    pub nodes: HashMap<Node, NodeSnapshot>,
+    // the utilization of each service over the cluster
+    pub services: HashMap<Service, Option<Resource>>,
+    // the prefix of the service names in the cluster
+    pub stack: String,
+    pub collected_at: Instant,
+    pub elapsed_ms: u128,
+}
+
+impl ClusterSnapshot {
+    // Query the api for every node and service at once. Each request has `request_timeout` to answer.
+    pub async fn collect(api_client: &ApiClient, config: &Config, request_timeout: Duration) -> Self {
+        let start = Instant::now();
+        let stack = config.cluster.prometheus.stack.clone();
+
+        let nodes = join_all(config.cluster.nodes.iter().map(|node| {
+            collect_node(api_client, node, &config.services, &stack, request_timeout)
+        })).await;
+
+        let services = join_all(config.services.iter().map(|service| async move {
+            let utilization = fetch(format!("utilization of service {}", service.name), request_timeout,
+                api_client.get_service_utilization(&service.name)).await;
+            (service.clone(), utilization)
+        })).await;
+
+        let snapshot = ClusterSnapshot {
+            nodes: nodes.into_iter().collect(),
+            services: services.into_iter().collect(),
+            stack,
+            collected_at: start,
+            elapsed_ms: start.elapsed().as_millis(),
+        };
+
+        let missing = snapshot.missing();
+        println!("Cluster snapshot collected in {} ms - {} missing value(s)", snapshot.elapsed_ms, missing.len());
+        for value in &missing {
+            println!("Missing from the snapshot: {}", value);
+        }
+
+        snapshot
+    }
+
+    pub fn age(&self) -> Duration {
+        self.collected_at.elapsed()
+    }
+
+    pub fn node(&self, node: &Node) -> Option<&NodeSnapshot> {
+        self.nodes.get(node)
+    }
+
+    // the usage of a config service on a node
+    pub fn node_service_utilization(&self, node: &Node, service: &Service) -> Option<Resource> {
+        let name = format!("{}{}", self.stack, service.name);
+        self.node(node)?.service_utilization.get(&name).cloned().flatten()
+    }
+
+    // the summed usage of the services running on a node - the ones without utilization add nothing
+    pub fn node_services_utilization(&self, node: &Node) -> Resource {
+        let mut total = Resource::default();
+
+        if let Some(snapshot) = self.node(node) {
+            for service in snapshot.services.iter().flatten() {
+                if let Some(Some(utilization)) = snapshot.service_utilization.get(service) {
+                    total.add(utilization);
+                }
+            }
+        }
+
+        total
+    }
+
+    // the usage of each service on each node - None where it does not run or is unknown
+    pub fn service_resources(&self, services: &[Service], nodes: &[Node]) -> HashMap<Service, Vec<Option<(Node, Resource)>>> {
+        services.iter().map(|service| {
+            let usage = nodes.iter()
+                .map(|node| self.node_service_utilization(node, service).map(|resource| (node.clone(), resource)))
+                .collect();
+            (service.clone(), usage)
+        }).collect()
+    }
+
+    // every value the api did not return
+    pub fn missing(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+
+        let mut nodes: Vec<_> = self.nodes.iter().collect();
+        nodes.sort_by_key(|(node, _)| node.id);
+
+        for (node, snapshot) in nodes {
+            if snapshot.utilization.is_none() {
+                missing.push(format!("utilization of node {}", node.name));
+            }
+            if snapshot.environment.is_none() {
+                missing.push(format!("environment of node {}", node.name));
+            }
+            if snapshot.services.is_none() {
+                missing.push(format!("services of node {}", node.name));
+            }
+
+            let mut services: Vec<_> = snapshot.service_utilization.iter()
+                .filter(|(_, utilization)| utilization.is_none())
+                .map(|(service, _)| service.clone())
+                .collect();
+            services.sort();
+
+            for service in services {
+                missing.push(format!("utilization of {} on node {}", service, node.name));
+            }
+        }
+
+        let mut services: Vec<_> = self.services.iter()
+            .filter(|(_, utilization)| utilization.is_none())
+            .map(|(service, _)| service.name.clone())
+            .collect();
+        services.sort();
+
+        for service in services {
+            missing.push(format!("utilization of service {}", service));
+        }
+
+        missing
+    }
+}
+
+async fn collect_node(api_client: &ApiClient, node: &Node, services: &[Service], stack: &str, request_timeout: Duration) -> (Node, NodeSnapshot) {
+    let (utilization, environment, node_services) = tokio::join!(
+        fetch(format!("utilization of node {}", node.name), request_timeout, api_client.get_node_utilization(&node.name)),
+        fetch(format!("environment of node {}", node.name), request_timeout, api_client.get_node_environment(&node.name)),
+        fetch(format!("services of node {}", node.name), request_timeout, api_client.get_node_services(&node.name)),
+    );
+
+    // the services seen on the node and the ones of the config - a service of the config not on the node is simply absent
+    let mut names: HashSet<String> = node_services.iter().flatten().cloned().collect();
+    names.extend(services.iter().map(|service| format!("{}{}", stack, service.name)));
+
+    let service_utilization = join_all(names.into_iter().map(|name| async move {
+        let utilization = timeout(request_timeout, api_client.get_node_service_utilization(&node.name, &name)).await;
+        (name, utilization.ok().and_then(|result| result.ok()))
+    })).await;
+
+    // only the services known to run on the node count as missing when they do not answer
+    let service_utilization = service_utilization.into_iter()
+        .filter(|(name, utilization)| utilization.is_some() || node_services.iter().flatten().any(|service| service == name))
+        .collect();
+
+    (node.clone(), NodeSnapshot { utilization, environment, services: node_services, service_utilization })
+}
+
+// a request that fails or times out is logged and left out
+async fn fetch<T, E: std::fmt::Display>(what: String, request_timeout: Duration, request: impl Future<Output = Result<T, E>>) -> Option<T> {
+    match timeout(request_timeout, request).await {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(e)) => {
+            println!("Failed to retrieve the {}: {}", what, e);
+            None
+        }
+        Err(_) => {
+            println!("Timed out retrieving the {} after {:?}", what, request_timeout);
+            None
+        }
+    }
+}
+
+// The snapshot of the current cycle, shared by the strategies (and the solvers) created from the same context
+#[derive(Debug, Clone)]
+pub struct SnapshotCache {
+    current: Arc<Mutex<Option<Arc<ClusterSnapshot>>>>,
+    max_age: Duration,
+}
+
+impl Default for SnapshotCache {
+    fn default() -> Self {
+        SnapshotCache::new(SNAPSHOT_MAX_AGE)
+    }
+}
+
+impl SnapshotCache {
+    pub fn new(max_age: Duration) -> Self {
+        SnapshotCache {
+            current: Arc::new(Mutex::new(None)),
+            max_age,
+        }
+    }
+
+    // The current snapshot, collected anew once it is older than max_age. Concurrent callers wait for
+    // the same collection.
+    pub async fn get(&self, api_client: &ApiClient, config: &Config) -> Arc<ClusterSnapshot> {
+        let mut current = self.current.lock().await;
+
+        if let Some(snapshot) = current.as_ref() {
+            if snapshot.age() < self.max_age {
+                return snapshot.clone();
+            }
+        }
+
+        let request_timeout = Duration::from_secs(config.control.request_timeout);
+        let snapshot = Arc::new(ClusterSnapshot::collect(api_client, config, request_timeout).await);
+        *current = Some(snapshot.clone());
+
+        snapshot
+    }
+
+    // drop the current snapshot - the next get collects a new one
+    pub async fn invalidate(&self) {
+        *self.current.lock().await = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::time::{Duration, Instant};
+
+    use crate::api_client::ApiClient;
+    use crate::snapshot::{ClusterSnapshot, NodeSnapshot, SnapshotCache};
+    use crate::utility::{Config, Network, Node, Resource, Service};
+
+    // a config without nodes nor services - collecting its snapshot sends no request
+    fn config(nodes: &str, services: &str) -> Config {
+        serde_yaml::from_str(&format!(r#"
+cluster:
+  nodes: {}
+  prometheus: {{ url: "", label: "", stack: "app_", query: "", metric: "" }}
+database: {{ uri: "mongodb://localhost:27017", db: "yonga", collections: [] }}
+services: {}
+weights: []
+"#, nodes, services)).unwrap()
+    }
+
+    // web runs on node 1 with a known usage, db on node 1 without one; node 2 did not answer
+    fn snapshot() -> (ClusterSnapshot, Config) {
+        let config = config(r#"
+    - { id: 1, name: "node1", ip: "10.0.0.1", resource: { cpu: 8, memory: 16000, disk: 100000, network: 1000 } }
+    - { id: 2, name: "node2", ip: "10.0.0.2", resource: { cpu: 8, memory: 16000, disk: 100000, network: 1000 } }"#, r#"
+  - { id: "1", name: "web" }
+  - { id: "2", name: "db" }"#);
+        let nodes = &config.cluster.nodes;
+        let services = &config.services;
+
+        let snapshot = ClusterSnapshot {
+            nodes: HashMap::from([
+                (nodes[0].clone(), NodeSnapshot {
+                    utilization: Some(Resource::new(2.0, 1000.0, 10.0, 50.0)),
+                    environment: Some(Network::new(1.0, 1000.0, 1.0, 0.0)),
+                    services: Some(vec!["app_web".to_string(), "app_db".to_string()]),
+                    service_utilization: HashMap::from([
+                        ("app_web".to_string(), Some(Resource::new(1.0, 200.0, 1.0, 10.0))),
+                        ("app_db".to_string(), None),
+                    ]),
+                }),
+                (nodes[1].clone(), NodeSnapshot { utilization: None, environment: None, services: None, service_utilization: HashMap::new() }),
+            ]),
+            services: HashMap::from([
+                (services[0].clone(), Some(Resource::new(1.0, 200.0, 1.0, 10.0))),
+                (services[1].clone(), None),
+            ]),
+            stack: "app_".to_string(),
+            collected_at: Instant::now(),
+            elapsed_ms: 0,
+        };
+
+        (snapshot, config)
+    }
+
+    fn service(config: &Config, name: &str) -> Service {
+        config.services.iter().find(|service| service.name == name).unwrap().clone()
+    }
+
+    #[test]
+    fn test_node_service_utilization() {
+        let (snapshot, config) = snapshot();
+        let nodes: &Vec<Node> = &config.cluster.nodes;
+
+        // the config names take the stack prefix
+        assert_eq!(snapshot.node_service_utilization(&nodes[0], &service(&config, "web")).unwrap().memory, 200.0);
+        assert!(snapshot.node_service_utilization(&nodes[0], &service(&config, "db")).is_none());
+        assert!(snapshot.node_service_utilization(&nodes[1], &service(&config, "web")).is_none());
+
+        // a service without usage adds nothing to its node
+        let total = snapshot.node_services_utilization(&nodes[0]);
+        assert_eq!((total.cpu, total.memory), (1.0, 200.0));
+        assert_eq!(snapshot.node_services_utilization(&nodes[1]).cpu, 0.0);
+    }
+
+    #[test]
+    fn test_service_resources() {
+        let (snapshot, config) = snapshot();
+        let resources = snapshot.service_resources(&config.services, &config.cluster.nodes);
+
+        let web = &resources[&service(&config, "web")];
+        assert_eq!(web.len(), 2);
+        assert_eq!(web[0].as_ref().map(|(node, _)| node.id), Some(1));
+        assert!(web[1].is_none());
+        assert!(resources[&service(&config, "db")].iter().all(|usage| usage.is_none()));
+    }
+
+    #[test]
+    fn test_missing() {
+        let (snapshot, _) = snapshot();
+
+        assert_eq!(snapshot.missing(), vec![
+            "utilization of app_db on node node1".to_string(),
+            "utilization of node node2".to_string(),
+            "environment of node node2".to_string(),
+            "services of node node2".to_string(),
+            "utilization of service db".to_string(),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_cache_reuse_and_invalidate() {
+        let config = config("[]", "[]");
+        let api_client = ApiClient::new("http://localhost");
+        let cache = SnapshotCache::new(Duration::from_secs(60));
+
+        // the strategies sharing the cache share the snapshot
+        let first = cache.get(&api_client, &config).await;
+        let shared = cache.clone().get(&api_client, &config).await;
+        assert!(Arc::ptr_eq(&first, &shared));
+
+        // after a deploy the cluster is collected again
+        cache.invalidate().await;
+        let second = cache.get(&api_client, &config).await;
+        assert!(!Arc::ptr_eq(&first, &second));
+    }
+
+    #[tokio::test]
+    async fn test_cache_max_age() {
+        let config = config("[]", "[]");
+        let api_client = ApiClient::new("http://localhost");
+        let cache = SnapshotCache::new(Duration::ZERO);
+
+        let first = cache.get(&api_client, &config).await;
+        let second = cache.get(&api_client, &config).await;
+        assert!(!Arc::ptr_eq(&first, &second));
+    }
+}
@@ -1,33 +1,41 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 use std::result::Result::Ok;
-use crate::utility::{Config, Node, Service};
+use crate::utility::{self, Config, Node, Service};
 use crate::api_client::ApiClient;
 use crate::utility::{Network, Resource, resource_diff, get_node_by_id, resource_sum, resource_sum_sub, resource_int_subx};
 use crate::node::NodeTree;
 use crate::trace::ServiceGraph;
-use crate::nsga2::{MicroservicePlacementProblem, get_best_individual};
+use crate::strategy::{self, PlacementMap, ReplicaNodes};
+use crate::scoring;
+use crate::milp;
+use crate::snapshot::{ClusterSnapshot, NodeSnapshot, SnapshotCache};
+use crate::nsga2::MicroservicePlacementProblem;
+use crate::decision::{self, Candidate};
+use crate::latency::{LatencyModel, LATENCY_SLO};
 
 
 use optirustic::algorithms::{
     Algorithm, MaxGenerationValue, NSGA2Arg, StoppingConditionType, NSGA2
 };
-use optirustic::core::{Constraint, ObjectiveDirection, RelationalOperator, VariableValue};
+use optirustic::core::{Constraint, RelationalOperator, VariableValue};
 use optirustic::operators::{PolynomialMutationArgs, SimulatedBinaryCrossoverArgs};
 
-use crate::nsga2opticas::{OMicroservicePlacementProblem, opticas_get_best_individual};
+use crate::nsga2opticas::{capacity_constraint_name, MigrationCost, ObjectiveBounds, OMicroservicePlacementProblem, evaluate_solution, placement_solution, warm_start_solutions};
 
 
 use opticas::algorithms::{
     OAlgorithm, OMaxGenerationValue, NSGA2OPTICASArg, OStoppingConditionType, NSGA2OPTICAS
 };
-use opticas::core::{OConstraint, OObjectiveDirection, ORelationalOperator, OVariableValue};
+use opticas::core::{OIndividual, OConstraint, ORelationalOperator, OVariableValue};
 use opticas::operators::{OPolynomialMutationArgs, OSimulatedBinaryCrossoverArgs};
 
-#[derive(Debug, Clone)]
-pub struct Coordinate {
-    pub x: f64,
-    pub y: f64,
-}
+// number of failed placements remembered by the solver
+const MAX_FAILED_PLACEMENTS: usize = 5;
+
+// individuals of the NSGA2 population and share of them seeded from the current placement and its neighbours
+const POPULATION_SIZE: usize = 100;
+const WARM_START_SHARE: f64 = 0.5;
 
 #[derive(Debug)]
 pub struct Solver {
@@ -37,6 +45,19 @@ pub struct Solver {
     // need to add solver value - f64
     pub obj_value: Option<f64>,
     pub revision: u32,
+    // number of replicas of each service - each replica goes to a distinct node
+    pub replicas: HashMap<String, u32>,
+    // number of volumes mounted by each service - the state moved with it
+    pub volumes: HashMap<String, usize>,
+    // placements that failed to converge after a deploy - not proposed again while listed
+    pub failed_placements: VecDeque<HashMap<Service, Option<HashSet<Node>>>>,
+    // objective values and constraint violation of the current placement
+    pub objectives: Option<HashMap<String, f64>>,
+    pub constraint_violation: Option<f64>,
+    // the cluster inputs, shared with the other strategies of the cycle
+    pub snapshots: SnapshotCache,
+    // the capacity of the nodes and the demand of the services in the last problem
+    pub replica_nodes: ReplicaNodes,
 }
 
 impl Solver {
@@ -47,7 +68,77 @@ impl Solver {
             api_client,
             obj_value: None,
             revision: 0,
+            replicas: HashMap::new(),
+            volumes: HashMap::new(),
+            failed_placements: VecDeque::new(),
+            objectives: None,
+            constraint_violation: None,
+            snapshots: SnapshotCache::default(),
+            replica_nodes: ReplicaNodes::default(),
+        }
+    }
+
+    // The cluster snapshot of the current cycle
+    pub async fn snapshot(&self) -> Arc<ClusterSnapshot> {
+        self.snapshots.get(&self.api_client, &self.config).await
+    }
+
+    // The utilization and environment of every node - a node that cannot be priced fails the problem
+    fn priced_nodes(&self, snapshot: &ClusterSnapshot) -> Result<HashMap<Node, (Resource, Network)>, Box<dyn std::error::Error>> {
+        let mut resource_map: HashMap<Node, (Resource, Network)> = HashMap::new();
+
+        for node in &self.config.cluster.nodes {
+            match snapshot.node(node) {
+                Some(NodeSnapshot { utilization: Some(utilization), environment: Some(environment), .. }) => {
+                    resource_map.insert(node.clone(), (utilization.clone(), environment.clone()));
+                }
+                _ => return Err(format!("No utilization or environment for node {} - it cannot be priced", node.name).into()),
+            }
+        }
+
+        Ok(resource_map)
+    }
+
+    // Remember a placement that failed to converge so that the next solutions avoid it
+    pub fn mark_failed(&mut self, placement_map: HashMap<Service, Option<HashSet<Node>>>) {
+        if self.failed_placements.len() == MAX_FAILED_PLACEMENTS {
+            self.failed_placements.pop_front();
         }
+        self.failed_placements.push_back(placement_map);
+    }
+
+    pub fn is_failed(&self, placement_map: &HashMap<Service, Option<HashSet<Node>>>) -> bool {
+        self.failed_placements.contains(placement_map)
+    }
+
+    // Translate the variables of an individual into a placement map
+    fn individual_placement(&self, individual: &OIndividual, replica_nodes: &ReplicaNodes) -> HashMap<Service, Option<HashSet<Node>>> {
+        let mut placement_map: HashMap<Service, Option<HashSet<Node>>> = HashMap::new();
+
+        for (service, var) in individual.serialise().variable_values {
+            // get Service from service
+            let service = get_services_by_names(service, &self.config.services).unwrap();
+            match var {
+                OVariableValue::OChoice(id) => {
+                    let node = get_node_by_id(id as i64, &self.config.cluster.nodes).unwrap();
+                    placement_map.entry(service.clone()).or_insert_with(|| Some(HashSet::new())).as_mut().unwrap().insert(node);
+                }
+                // every node of a replicated service
+                OVariableValue::OSubset(ids) => {
+                    let nodes = placement_map.entry(service.clone()).or_insert_with(|| Some(HashSet::new())).as_mut().unwrap();
+                    for id in ids {
+                        nodes.insert(get_node_by_id(id as i64, &self.config.cluster.nodes).unwrap());
+                    }
+                }
+                // ignore the rest
+                _ => {}
+            }
+        }
+
+        // the services the search placed on fewer nodes than their replicas take the cheapest nodes with capacity left
+        strategy::place_replicas(&mut placement_map, &self.replicas, replica_nodes);
+
+        placement_map
     }
 
     pub async fn solve_0(&mut self) -> Result<HashMap<Service, Option<HashSet<Node>>>, Box<dyn std::error::Error>> {
@@ -55,34 +146,31 @@ impl Solver {
 
         self.placement = Some(HashMap::new());
 
-        // Create an empty map that has a Node as key and values NR, and NE as f64
-        let mut node_map: HashMap<Node, Coordinate> = HashMap::new();
+        let snapshot = self.snapshot().await;
 
         // Create an empty map to hold Node as key, and Resource & Network as tuple values
         let mut resource_map: HashMap<Node, (Resource, Network)> = HashMap::new();
 
         for node in &self.config.cluster.nodes {
 
-            // Retrieve the node utilization - resources available on the node, safe network
-            let node_utilization = self.api_client.get_node_utilization(&node.name).await?;
-
-            // Retrieve the node environment
-            let node_environment = self.api_client.get_node_environment(&node.name).await?;
+            // the node utilization - resources available on the node, safe network - and environment
+            let (node_utilization, node_environment) = match snapshot.node(node) {
+                Some(NodeSnapshot { utilization: Some(utilization), environment: Some(environment), .. }) => (utilization.clone(), environment.clone()),
+                _ => {
+                    println!("No utilization or environment for node {} - leaving it out of placement 0", node.name);
+                    continue;
+                }
+            };
 
             // get services on the node
-            let services = self.api_client.get_node_services(&node.name).await?;
+            let services = snapshot.node(node).and_then(|node_snapshot| node_snapshot.services.clone()).unwrap_or_default();
 
             if services.len() > 0 {
                 // print the services
                 // println!("Services on node {}: {:?}", node.name, services);
 
-                // initialize the Resource for the services
-                let mut service_resources = Resource::default();
-
-                for service in services {
-                    let service_utilization = self.api_client.get_node_service_utilization(&node.name, &service).await?;
-                    service_resources = resource_sum(service_resources, service_utilization);
-                }
+                // initialize the Resource for the services - a service without utilization adds nothing
+                let service_resources = snapshot.node_services_utilization(node);
 
                 // Add the service_resources to the node_utilization
                 let node_utilization = resource_sum(node_utilization, service_resources);
@@ -117,31 +205,16 @@ impl Solver {
 
         println!("Resource Map: {:?}", resource_map);
 
-        
-        // Use the resource map to populate the node_map
-        for (node, (resource, network)) in &resource_map {
-            node_map.insert(node.clone(), Coordinate{
-                x: self.compute_nr(resource, &resource_map),
-                y: self.compute_network_cost(network, &resource_map), //compute_ne(network, &resource_map),
-            });
+        if resource_map.is_empty() {
+            return Err("No node with a known utilization and environment".into());
         }
 
-        // print the node map
-        println!("Node Map: {:?}", node_map);
-
-        // Get all coordinates in the map
-        let coordinates: Vec<Coordinate> = node_map.values().cloned().collect();
-
-        let mut distance_map: HashMap<Node, f64> = HashMap::new();
-
-        // Compute the Euclidean distance for each node
-        for (node, coordinate) in &node_map {
-            let distance = compute_euclidean_distance(&coordinates, coordinate.x, coordinate.y);
-            distance_map.insert(node.clone(), distance);
+        
+        // Rank the nodes on their resources and network
+        let scores = scoring::score_nodes(self.config.scoring, &resource_map, &self.config);
+        scoring::print_scores(self.config.scoring, &scores);
 
-            // Print the coordinate and distance
-            println!("Node: {}, Coordinate: ({}, {}), Distance: {}", node.name, coordinate.x, coordinate.y, distance);
-        }
+        let score_map: HashMap<Node, f64> = scores.iter().map(|node_score| (node_score.node.clone(), node_score.score)).collect();
 
         // Compute the proportion of services to assign to a node
         let j = self.config.services.len() as u32;
@@ -150,10 +223,10 @@ impl Solver {
         let mut proportion_map: HashMap<Node, u32> = HashMap::new();
 
         // For each node, compute the proportion of services to assign
-        for (node, _) in &node_map {
-            let proportion = compute_proportion(*distance_map.get(node).unwrap(), &distance_map, j);
-            println!("Proportion for node {} is {}", node.name, proportion);
-            proportion_map.insert(node.clone(), proportion);
+        for node_score in &scores {
+            let proportion = compute_proportion(node_score.score, &score_map, j);
+            println!("Proportion for node {} is {}", node_score.node.name, proportion);
+            proportion_map.insert(node_score.node.clone(), proportion);
         }
 
         //let (num_groups, groups) = self.config.group_services();
@@ -174,6 +247,17 @@ impl Solver {
             placement_map.entry(service).or_insert_with(|| Some(HashSet::new())).as_mut().unwrap().insert(node);
         }
 
+        // place the extra replicas on the nodes with the highest proportion and capacity left
+        let mut ranked_nodes: Vec<Node> = proportion_map.keys().cloned().collect();
+        ranked_nodes.sort_by(|a, b| proportion_map[b].cmp(&proportion_map[a]).then(a.id.cmp(&b.id)));
+        let node_usage: HashMap<Node, Resource> = resource_map.iter()
+            .map(|(node, (resource, _network))| (node.clone(), resource_int_subx(node.resource.clone(), resource.clone())))
+            .collect();
+        let service_resources = snapshot.service_resources(&self.config.services, &self.config.cluster.nodes);
+        let replica_nodes = replica_nodes(ranked_nodes, &node_usage, &service_resources);
+        strategy::place_replicas(&mut placement_map, &self.replicas, &replica_nodes);
+        self.replica_nodes = replica_nodes;
+
         // print the placement map
         print_placement_map(placement_map.clone());
 
@@ -255,17 +339,20 @@ impl Solver {
         // print the strongest path
         println!("Strongest Path: {:?}", strongest_path);
     
-        // Retrieve node utilization data
+        let snapshot = self.snapshot().await;
+
+        // Retrieve node utilization data - a node without it takes no service
         let mut node_utilization_map: HashMap<Node, Resource> = HashMap::new();
         for node in &self.config.cluster.nodes {
-            let node_utilization = self.api_client.get_node_utilization(&node.name).await?;
-            node_utilization_map.insert(node.clone(), node_utilization);
+            if let Some(node_utilization) = snapshot.node(node).and_then(|node_snapshot| node_snapshot.utilization.clone()) {
+                node_utilization_map.insert(node.clone(), node_utilization);
+            }
         }
     
-        // Retrieve service utilization data
+        // Retrieve service utilization data - a service without it takes no capacity
         let mut service_utilization_map: HashMap<Service, Resource> = HashMap::new();
         for service in &self.config.services {
-            let service_utilization = self.api_client.get_service_utilization(&service.name).await?;
+            let service_utilization = snapshot.services.get(service).cloned().flatten().unwrap_or(Resource::default());
             service_utilization_map.insert(service.clone(), service_utilization);
         }
 
@@ -360,27 +447,21 @@ impl Solver {
         // create service-service mappings from the service tree
         let service_comms = service_tree.get_service_pairs(all_services.clone());
         let node_comms = node_tree.get_tree();
-        let service_resources = self.get_service_resources(&all_services.clone(), &all_nodes.clone()).await;
+        let snapshot = self.snapshot().await;
+        let service_resources = snapshot.service_resources(all_services, all_nodes);
 
         // Create an empty map to hold Node as key, and Resource & Network as tuple values
-        let mut resource_map: HashMap<Node, (Resource, Network)> = HashMap::new();
+        let resource_map = self.priced_nodes(&snapshot)?;
 
-        for node in &self.config.cluster.nodes {
-            let node_utilization = self.api_client.get_node_utilization(&node.name).await?;
-            let node_environment = self.api_client.get_node_environment(&node.name).await?;
-            resource_map.insert(node.clone(), (node_utilization, node_environment));
-        }
+        // the cost of a node is its shortfall from the best score - the criteria placement 0 ranks the nodes on
+        let node_costs = scoring::node_costs(self.config.scoring, &resource_map, &self.config);
 
-        let mut node_costs: HashMap<Node, f64> = HashMap::new();
-
-        for (node, (_resource, network)) in &resource_map {
-            node_costs.insert(node.clone(), self.compute_network_cost(network, &resource_map));
-        }
+        // the cheapest nodes take the extra replicas
+        let ranked_nodes = rank_nodes_by_cost(&node_costs);
 
         let mut node_resources: HashMap<Node, Resource> = HashMap::new();
-        for node in &self.config.cluster.nodes {
-            let node_utilization = self.api_client.get_node_utilization(&node.name).await?;
-            node_resources.insert(node.clone(), node_utilization);
+        for (node, (node_utilization, _network)) in &resource_map {
+            node_resources.insert(node.clone(), node_utilization.clone());
         }
 
         let mut available_resources: HashMap<Node, Resource> = HashMap::new();
@@ -412,7 +493,7 @@ impl Solver {
             service_comms,
             node_comms.clone(),
             node_costs,
-            service_resources,
+            service_resources.clone(),
             available_resources,
             Some(constraints),
 
@@ -436,7 +517,7 @@ impl Solver {
         // Setup the NSGA2 algorithm
         let args = NSGA2Arg {
             // use 100 individuals and stop the algorithm at 250 generations
-            number_of_individuals: 100,
+            number_of_individuals: POPULATION_SIZE,
             stopping_condition: StoppingConditionType::MaxGeneration(MaxGenerationValue(250)),
             // use default options for the SBX and PM operators
             crossover_operator_options: Some(crossover_operator_options),
@@ -459,7 +540,16 @@ impl Solver {
         // run the algorithm
         algo.run().unwrap();
 
-        let (best, _value) = get_best_individual(&algo.get_results().individuals, ObjectiveDirection::Minimise);
+        let individuals = algo.get_results().individuals.clone();
+        let names = individuals[0].problem().objective_names();
+        let candidates: Vec<Candidate> = individuals.iter().map(|individual| Candidate {
+            objectives: individual.get_objective_values().unwrap(),
+            violation: individual.constraint_violation(),
+        }).collect();
+        let ranking = decision::rank(&self.config.decision, &names, &candidates);
+        decision::print_decision(&self.config.decision, &names, &candidates, &ranking[0]);
+
+        let best = individuals[ranking[0].index].clone();
 
         // get the best individual
         let best_individual = best.serialise();
@@ -483,6 +573,14 @@ impl Solver {
             }
         }
 
+        // place the extra replicas on the cheapest nodes with capacity left
+        let node_usage: HashMap<Node, Resource> = node_resources.iter()
+            .map(|(node, resource)| (node.clone(), resource_int_subx(node.resource.clone(), resource.clone())))
+            .collect();
+        let replica_nodes = replica_nodes(ranked_nodes, &node_usage, &service_resources);
+        strategy::place_replicas(&mut placement_map, &self.replicas, &replica_nodes);
+        self.replica_nodes = replica_nodes;
+
         // update the placement map
         self.placement = Some(placement_map.clone());
 
@@ -502,11 +600,13 @@ impl Solver {
     }
 
 
-    pub async fn solve_lp_nsga2opticas(
+    // Gather the inputs of the placement problem (service communications, node costs, utilization and
+    // constraints) and return the problem along with the nodes the extra replicas may take, cheapest first
+    pub async fn build_problem(
         &mut self,
-        service_tree: ServiceGraph,
-        node_tree: NodeTree,
-    ) -> Result<HashMap<Service, Option<HashSet<Node>>>, Box<dyn std::error::Error>> {
+        service_tree: &ServiceGraph,
+        node_tree: &NodeTree,
+    ) -> Result<(OMicroservicePlacementProblem, ReplicaNodes), Box<dyn std::error::Error>> {
 
         let all_services = &self.config.services;
         let all_nodes = &self.config.cluster.nodes;
@@ -514,7 +614,8 @@ impl Solver {
         // create service-service mappings from the service tree
         let service_comms = service_tree.get_service_pairs(all_services.clone());
         let node_comms = node_tree.get_tree();
-        let service_resources = self.get_service_resources(&all_services.clone(), &all_nodes.clone()).await;
+        let snapshot = self.snapshot().await;
+        let service_resources = snapshot.service_resources(all_services, all_nodes);
 
         // print the service resources
         println!("Service Resources: {:?}", service_resources);
@@ -533,26 +634,20 @@ impl Solver {
         // Create an empty map to hold Node as key, and Resource & Network as tuple values
         let mut resource_map: HashMap<Node, (Resource, Network)> = HashMap::new();
 
-        for node in &self.config.cluster.nodes {
-            let node_available = self.api_client.get_node_utilization(&node.name).await?; // base available resources | may include services using them.
-            let node_environment = self.api_client.get_node_environment(&node.name).await?;
+        for (node, (node_available, node_environment)) in self.priced_nodes(&snapshot)? {
+            // base available resources | may include services using them.
 
             //let available_resources = node_utilization.clone();
 
             // get services on the node
-            let services = self.api_client.get_node_services(&node.name).await?;
+            let services = snapshot.node(&node).and_then(|node_snapshot| node_snapshot.services.clone()).unwrap_or_default();
 
             if services.len() > 0 {
                 // print the services
                 // println!("Services on node {}: {:?}", node.name, services);
 
-                // initialize the Resource for the services
-                let mut service_resources = Resource::default();
-
-                for service in services {
-                    let service_utilization = self.api_client.get_node_service_utilization(&node.name, &service).await?;
-                    service_resources = resource_sum(service_resources, service_utilization);
-                }
+                // initialize the Resource for the services - a service without utilization adds nothing
+                let service_resources = snapshot.node_services_utilization(&node);
 
                 // Add the service_resources to the node_utilization
                 let node_available = resource_sum_sub(node_available, service_resources);
@@ -572,11 +667,11 @@ impl Solver {
         // print the resource map
         // println!("Resource Map: {:?}", resource_map);
 
-        let mut node_costs: HashMap<Node, f64> = HashMap::new();
+        // the cost of a node is its shortfall from the best score - the criteria placement 0 ranks the nodes on
+        let node_costs = scoring::node_costs(self.config.scoring, &resource_map, &self.config);
 
-        for (node, (_resource, network)) in &resource_map {
-            node_costs.insert(node.clone(), self.compute_network_cost(network, &resource_map));
-        }
+        // the cheapest nodes take the extra replicas
+        let ranked_nodes = rank_nodes_by_cost(&node_costs);
 
         // print the node costs
         println!("Node Costs: {:?}", node_costs);
@@ -618,14 +713,7 @@ impl Solver {
         // Create a constraint that places the most popular services on the least cost node
         let mut constraints = Vec::new();
         for service in &most_popular_services {
-           let constraint = OConstraint::new(
-                service, 
-                ORelationalOperator::EqualTo, 
-                Some(lowest_cost_node_id.clone()), 
-                None, 
-                None
-            );
-            constraints.push(constraint);
+            constraints.push(OConstraint::assignment(service, lowest_cost_node_id));
         }
 
         // Create the group constraints (similar services on the same node)
@@ -642,33 +730,131 @@ impl Solver {
         // }
 
 
-        // create node resource constraints
-        // for node in &available_resources {
-        //     let (cpu, memory, disk, network) = (node.1.cpu, node.1.memory, node.1.disk, node.1.network);
-        //     let mut resource_constraint: HashMap<u64, (f64, f64, f64, f64)> = HashMap::new();   
-        //     resource_constraint.insert(node.0.id as u64, (cpu, memory, disk, network));
-        //     let constraint = Constraint::new(&node.0.name, RelationalOperator::LessOrEqualTo, None, None, Some(resource_constraint));
-        //     constraints.push(constraint);
-        // }
+        // create node resource constraints - no node overloaded in cpu, memory, disk or network
+        for node in all_nodes {
+            let base = node_utilization.get(node).cloned().unwrap_or(Resource::default());
+            let left = utility::node_capacity(node, &base);
+            let total = utility::node_capacity(node, &Resource::default());
+            constraints.push(OConstraint::capacity(
+                &capacity_constraint_name(node),
+                [left.cpu, left.memory, left.disk, left.network],
+                [total.cpu, total.memory, total.disk, total.network],
+            ));
+        }
+
+        // the latency of the longest request paths, and the SLO on the p99 of the slowest one
+        let latency = LatencyModel::new(service_tree, node_tree, all_services, all_nodes);
+        println!("Request paths: {:?}, worst-case latency (mean, p99): {:?} ms",
+            latency.paths.iter().map(|path| path.services.iter().map(|service| service.name.as_str()).collect::<Vec<_>>()).collect::<Vec<_>>(),
+            latency.worst_case());
+
+        if let Some(slo) = self.config.latency.slo {
+            if latency.paths.is_empty() {
+                println!("No request paths in the traces - the latency SLO of {} ms is not enforced", slo);
+            } else {
+                constraints.push(OConstraint::new(LATENCY_SLO, ORelationalOperator::LessOrEqualTo, slo));
+            }
+        }
 
         // print all the constraints
         println!("Constraints: {:?}", constraints);
 
 
+        // the cost of moving away from the current placement
+        let migration = self.migration_cost(&service_resources);
+
         // Create the problem
-        let problem = OMicroservicePlacementProblem::create(
+        let problem = OMicroservicePlacementProblem::new(
             self.config.clone(),
             service_comms,
             node_comms.clone(),
             node_costs,
-            max_optimization_cost,
-            minmax_node_cost,
-            minmax_resource_imbalance,
+            ObjectiveBounds {
+                max_opt_cost: max_optimization_cost,
+                minmax_node_cost,
+                minmax_resource_imbalance,
+            },
             service_resources,
             node_utilization,
-            Some(constraints),
+        ).with_constraints(constraints);
 
-        )?;
+        let problem = match migration {
+            Some(migration) => problem.with_migration(migration),
+            None => problem,
+        };
+
+        let problem = if latency.paths.is_empty() { problem } else { problem.with_latency(latency) };
+        let problem = problem.with_replicas(self.replicas.clone());
+
+        let replica_nodes = replica_nodes(ranked_nodes, &problem.node_resources, &problem.utilization);
+        self.replica_nodes = replica_nodes.clone();
+
+        Ok((problem, replica_nodes))
+    }
+
+    // The current placement and the weight of moving each service: 1, plus volume_weight for each volume
+    // and the disk (GB) it uses, plus cache_weight if it is the cache of another service
+    fn migration_cost(&self, service_resources: &HashMap<Service, Vec<Option<(Node, Resource)>>>) -> Option<MigrationCost> {
+        if !self.config.migration.objective {
+            return None;
+        }
+
+        let current = strategy::placed_nodes(self.placement.as_ref()?);
+
+        // nothing to migrate from before the first placement
+        if current.is_empty() {
+            return None;
+        }
+
+        let caches: HashSet<&String> = self.config.services.iter()
+            .filter_map(|service| service.cache.as_ref())
+            .filter(|cache| !cache.is_empty())
+            .collect();
+
+        let weights: HashMap<Service, f64> = self.config.services.iter().map(|service| {
+            let mut weight = 1.0;
+
+            let volumes = *self.volumes.get(&service.name).unwrap_or(&0);
+            if volumes > 0 {
+                let disk: f64 = service_resources.get(service).iter()
+                    .flat_map(|usage| usage.iter().flatten())
+                    .map(|(_, resource)| resource.disk.max(0.0))
+                    .sum();
+                weight += volumes as f64 * self.config.migration.volume_weight + disk;
+            }
+
+            if caches.contains(&service.name) {
+                weight += self.config.migration.cache_weight;
+            }
+
+            (service.clone(), weight)
+        }).collect();
+
+        println!("Migration weights: {:?}", weights.iter().map(|(service, weight)| (service.name.as_str(), *weight)).collect::<HashMap<_, _>>());
+
+        Some(MigrationCost { current, weights })
+    }
+
+    pub async fn solve_lp_nsga2opticas(
+        &mut self,
+        service_tree: ServiceGraph,
+        node_tree: NodeTree,
+    ) -> Result<HashMap<Service, Option<HashSet<Node>>>, Box<dyn std::error::Error>> {
+
+        // Create the problem
+        let (problem, replica_nodes) = self.build_problem(&service_tree, &node_tree).await?;
+        let problem = problem.into_problem()?;
+
+        // search near what is running - the current placement and its one- and two-service moves, the rest random
+        let initial_solutions = match &self.placement {
+            Some(placement_map) if !placement_map.is_empty() => {
+                let count = (POPULATION_SIZE as f64 * WARM_START_SHARE) as usize;
+                let solutions = warm_start_solutions(&self.config, placement_map, &self.replicas, count, 10);
+                println!("Warm-starting NSGA2 with {} of {} individuals from the current placement", solutions.len(), POPULATION_SIZE);
+                Some(solutions)
+            }
+            _ => None,
+        };
 
             //let mutation_operator_options = PolynomialMutationArgs::default(&problem);
         let mutation_operator_options = OPolynomialMutationArgs {
@@ -688,7 +874,7 @@ impl Solver {
         // Setup the NSGA2 algorithm
         let args = NSGA2OPTICASArg {
             // use 100 individuals and stop the algorithm at 250 generations
-            number_of_individuals: 100,
+            number_of_individuals: POPULATION_SIZE,
             stopping_condition: OStoppingConditionType::MaxGeneration(OMaxGenerationValue(250)),
             // use default options for the SBX and PM operators
             crossover_operator_options: Some(crossover_operator_options),
@@ -699,6 +885,7 @@ impl Solver {
             // do not export intermediate solutions
             export_history: None,
             resume_from_file: None,
+            initial_solutions,
             // to reproduce results
             seed: Some(10),
         };
@@ -711,80 +898,132 @@ impl Solver {
         // run the algorithm
         algo.run().unwrap();
 
-        let (best, value) = opticas_get_best_individual(&algo.get_results().individuals, OObjectiveDirection::OMinimise);
+        // order the population with the decision method of the config
+        let individuals = algo.get_results().individuals.clone();
+        let names = individuals[0].problem().objective_names();
+        let candidates: Vec<Candidate> = individuals.iter().map(|individual| Candidate {
+            objectives: individual.get_objective_values().unwrap(),
+            violation: individual.constraint_violation(),
+        }).collect();
+        let ranking = decision::rank(&self.config.decision, &names, &candidates);
+
+        // a placement that failed to converge is not proposed again - take the next preferred one
+        let mut choice = None;
+        for ranked in &ranking {
+            let candidate = self.individual_placement(&individuals[ranked.index], &replica_nodes);
+            if !self.is_failed(&candidate) {
+                choice = Some((ranked, candidate));
+                break;
+            }
+            println!("The placement ranked {} failed to converge before - looking for the next one", ranked.rank);
+        }
 
-        // get the best individual
-        let best_individual = best.serialise();
+        let (ranked, placement_map) = choice.ok_or("All the placements found failed to converge before")?;
+        decision::print_decision(&self.config.decision, &names, &candidates, ranked);
 
-        // get values of the best individual
-        let best_values = best_individual.variable_values;
+        // the deployed placement, evaluated on the objectives of this run
+        let deployed = match &self.placement {
+            Some(current) if !current.is_empty() => evaluate_solution(individuals[0].problem(), &placement_solution(&self.config, current, &self.replicas))?,
+            _ => None,
+        };
 
-        // print the best values
-        // println!("Objective solution value: {:?}", value);
+        self.accept_placement(placement_map, &names, candidates[ranked.index].clone(), deployed, timestamp0)
+    }
 
-        // create the placement map
-        let mut placement_map: HashMap<Service, Option<HashSet<Node>>> = HashMap::new();
+    // Solve the same problem exactly with the MILP - the communication and resource costs are
+    // minimised, the imbalance of the optimum is reported as is
+    pub async fn solve_milp(
+        &mut self,
+        service_tree: ServiceGraph,
+        node_tree: NodeTree,
+    ) -> Result<HashMap<Service, Option<HashSet<Node>>>, Box<dyn std::error::Error>> {
 
-        for (service, var) in best_values {
-            // get Service from service
-            let service = get_services_by_names(service, &self.config.services).unwrap();
-            match var {
-                OVariableValue::OChoice(id) => {
-                    let node = get_node_by_id(id as i64, &self.config.cluster.nodes).unwrap();
-                    placement_map.entry(service.clone()).or_insert_with(|| Some(HashSet::new())).as_mut().unwrap().insert(node);
-                }
-                // ignore the rest
-                //_ => {}
-            }
-        }
+        let (problem, replica_nodes) = self.build_problem(&service_tree, &node_tree).await?;
+
+        // set the timestamp
+        let timestamp0 = chrono::Utc::now().timestamp();
 
-        // compute the placement difference
-        let diff = self.compute_placement_diff(&placement_map, &self.placement.as_ref().unwrap());
+        // the placements that failed to converge are cut off the MILP
+        let excluded: Vec<_> = self.failed_placements.iter().cloned().collect();
+        let solution = milp::solve(&problem, &excluded)?;
 
-        if self.revision <= 1 {
-            println!("First run of solve_lp");
-            self.obj_value = Some(value);
-            self.revision += 1;
-            self.placement = Some(placement_map.clone());
+        println!("MILP optimum: {:.4} (communication cost {:.4}, resource cost {:.4}) in {} ms",
+            solution.objective, solution.communication_cost, solution.resource_cost, solution.elapsed_ms);
 
-            // print the placement map
-            print_placement_map(placement_map.clone());
+        let mut placement_map: HashMap<Service, Option<HashSet<Node>>> = solution.placements.iter()
+            .map(|(service, node)| (service.clone(), Some(HashSet::from([node.clone()]))))
+            .collect();
 
-            // get the timestamp
-            let timestamp1 = chrono::Utc::now().timestamp();
+        // place the extra replicas on the cheapest nodes with capacity left
+        strategy::place_replicas(&mut placement_map, &self.replicas, &replica_nodes);
 
-            // print the time taken
-            println!("Time taken to solve the problem: {} seconds", timestamp1 - timestamp0);
+        // the same objectives as the NSGA2 individuals, over every replica, so that the values compare
+        let (communication_cost, resource_cost, resource_imbalance) = problem.evaluate_placement(&strategy::placed_nodes(&placement_map));
 
-            Ok(placement_map)
-        }
+        let names = vec!["communication_cost".to_string(), "resource_cost".to_string(), "resource_imbalance".to_string()];
+        let chosen = Candidate { objectives: vec![communication_cost, resource_cost, resource_imbalance], violation: 0.0 };
 
-        else {
-            if value > self.obj_value.unwrap() {
-            //if diff > (0.5 * self.config.services.len() as f64) && value > self.obj_value.unwrap() {
-                println!("Placement objectives value {} > current value {}", value, self.obj_value.unwrap());
-                Err("No solution found".into())
-            }
+        // the deployed placement on the same objectives - if every service of it is placed
+        let deployed = self.placement.as_ref()
+            .map(strategy::placed_nodes)
+            .filter(|placements| self.config.services.iter().all(|service| placements.contains_key(service)))
+            .map(|placements| {
+                let (communication_cost, resource_cost, resource_imbalance) = problem.evaluate_placement(&placements);
+                Candidate { objectives: vec![communication_cost, resource_cost, resource_imbalance], violation: 0.0 }
+            });
 
-            else {
-                println!("Placement objectives value {} <= current value {}", value, self.obj_value.unwrap());
-                self.obj_value = Some(value);
-                self.revision += 1;
-                self.placement = Some(placement_map.clone());
+        self.accept_placement(placement_map, &names, chosen, deployed, timestamp0)
+    }
 
-                // print the placement map  
-                // println!("Placement Map: {:?}", placement_map);
-                print_placement_map(placement_map.clone());
+    // Take a new placement if there is no deployed one to compare with, or if it improves on it in the objectives of
+    // this run (see decision::improves)
+    fn accept_placement(
+        &mut self,
+        placement_map: PlacementMap,
+        names: &[String],
+        chosen: Candidate,
+        deployed: Option<Candidate>,
+        timestamp0: i64,
+    ) -> Result<PlacementMap, Box<dyn std::error::Error>> {
+
+        let describe = |candidate: &Candidate| {
+            let objectives: Vec<String> = names.iter().zip(&candidate.objectives)
+                .map(|(name, value)| format!("{} {:.4}", name, value))
+                .collect();
+            format!("{} (violation {:.4})", objectives.join(", "), candidate.violation)
+        };
 
-                // get the timestamp
-                let timestamp1 = chrono::Utc::now().timestamp();
+        match &deployed {
+            // the first placement - or a service of the current one is not placed yet
+            None => println!("No deployed placement to compare with - taking {}", describe(&chosen)),
+            Some(deployed) => {
+                let min_improvement = self.config.control.min_improvement;
 
-                // print the time taken
-                println!("Time taken to solve the problem: {} seconds", timestamp1 - timestamp0);
+                if !decision::improves(&self.config.decision, names, deployed, &chosen, min_improvement) {
+                    println!("Placement {} does not improve on the deployed {} by {}", describe(&chosen), describe(deployed), min_improvement);
+                    return Err("No solution found".into());
+                }
 
-                Ok(placement_map)
+                println!("Placement {} improves on the deployed {} by {}", describe(&chosen), describe(deployed), min_improvement);
             }
         }
+
+        self.obj_value = Some(chosen.objectives.iter().sum());
+        self.objectives = Some(names.iter().cloned().zip(chosen.objectives.iter().copied()).collect());
+        self.constraint_violation = Some(chosen.violation);
+        self.revision += 1;
+        self.placement = Some(placement_map.clone());
+
+        // print the placement map
+        print_placement_map(placement_map.clone());
+
+        // get the timestamp
+        let timestamp1 = chrono::Utc::now().timestamp();
+
+        // print the time taken
+        println!("Time taken to solve the problem: {} seconds", timestamp1 - timestamp0);
+
+        Ok(placement_map)
     }
 
     pub fn assign_services(
@@ -829,163 +1068,6 @@ impl Solver {
         assignment_map
     }
 
-    fn compute_nr(&mut self, utilization: &Resource, resource_map: &HashMap<Node, (Resource, Network)>) -> f64 {
-        let mut max_cpu = 0.0;
-        let mut max_memory = 0.0;
-        let mut min_network = f64::MAX;
-        let mut max_disk = 0.0;
-    
-        for (_, (resource, _)) in resource_map {
-            if resource.cpu > max_cpu {
-                max_cpu = resource.cpu;
-            }
-            if resource.memory > max_memory {
-                max_memory = resource.memory;
-            }
-            if resource.network < min_network {
-                min_network = resource.network;
-            }
-            if resource.disk > max_disk {
-                max_disk = resource.disk;
-            }
-        }
-    
-        let cpu = utilization.cpu;
-        let memory = utilization.memory;
-        let network = utilization.network;
-        let disk = utilization.disk;
-    
-        // Safeguard against division by zero
-        let safe_div = |num: f64, denom: f64| if denom == 0.0 { f64::MAX } else { num / denom };
-    
-        let nr = safe_div(cpu, max_cpu) * self.config.get_weight("cpu") +
-                 safe_div(memory, max_memory) * self.config.get_weight("memory") + 
-                 safe_div(disk, max_disk) * self.config.get_weight("disk") +
-                 safe_div(min_network, network) * self.config.get_weight("network");
-    
-        nr
-    }
-    
-    fn _compute_ne(&mut self, network: &Network, resource_map: &HashMap<Node, (Resource, Network)>) -> f64 {
-        let mut max_bandwidth = 0.0;
-        let mut min_latency = f64::MAX;
-        let mut min_packet_loss = f64::MAX;
-        let mut max_available = 0.0;
-    
-        // Find best network attributes across all nodes
-        for (_, (_, node_network)) in resource_map {
-            if node_network.bandwidth > max_bandwidth {
-                max_bandwidth = node_network.bandwidth;
-            }
-            if node_network.latency < min_latency {
-                min_latency = node_network.latency;
-            }
-            if node_network.packet_loss < min_packet_loss {
-                min_packet_loss = node_network.packet_loss;
-            }
-            if node_network.available > max_available {
-                max_available = node_network.available;
-            }
-        }
-    
-        let bandwidth = network.bandwidth;
-        let latency = network.latency;
-        let packet_loss = network.packet_loss;
-        let available = network.available as f64;
-    
-        // Debug print all attributes and their weights
-        // println!("Bandwidth: {}, Latency: {}, Packet Loss: {}, Available: {}", bandwidth, latency, packet_loss, available);
-        // println!("Max Bandwidth: {}, Min Latency: {}, Min Packet Loss: {}, Max Available: {}", max_bandwidth, min_latency, min_packet_loss, max_available);
-        // println!("Weights: Bandwidth: {}, Latency: {}, Packet Loss: {}, Available: {}", 
-        //          self.config.get_weight("bandwidth"), 
-        //          self.config.get_weight("latency"), 
-        //          self.config.get_weight("packet_loss"), 
-        //          self.config.get_weight("available"));
-    
-        // Safeguard against division by zero
-        let safe_div = |num: f64, denom: f64| if denom == 0.0 { 0.0 } else { num / denom };
-    
-        let ne = safe_div(bandwidth, max_bandwidth) * self.config.get_weight("bandwidth") +
-                 safe_div(min_latency, latency) * self.config.get_weight("latency") +
-                 safe_div(min_packet_loss, packet_loss) * self.config.get_weight("packet_loss") +
-                 safe_div(available, max_available) * self.config.get_weight("available");
-    
-        ne
-    }
-
-    fn compute_network_cost(&mut self, network: &Network, resource_map: &HashMap<Node, (Resource, Network)>) -> f64 {
-        let mut max_bandwidth = 0.0;
-        let mut min_latency = f64::MAX;
-        let mut min_packet_loss = f64::MAX;
-        let mut max_available = 0.0;
-    
-        // Find best network attributes across all nodes
-        for (_, (_, node_network)) in resource_map {
-            if node_network.bandwidth > max_bandwidth {
-                max_bandwidth = node_network.bandwidth;
-            }
-            if node_network.latency < min_latency {
-                min_latency = node_network.latency;
-            }
-            if node_network.packet_loss < min_packet_loss {
-                min_packet_loss = node_network.packet_loss;
-            }
-            if node_network.available > max_available {
-                max_available = node_network.available;
-            }
-        }
-    
-        let bandwidth = network.bandwidth;
-        let latency = network.latency;
-        let packet_loss = network.packet_loss;
-        let available = network.available as f64;
-    
-        // Safeguard against division by zero
-        let safe_div = |num: f64, denom: f64| if denom == 0.0 { f64::MAX } else { num / denom };
-    
-        // Print debugging information
-        println!("Bandwidth: {}, Latency: {}, Packet Loss: {}, Available: {}", bandwidth, latency, packet_loss, available);
-        println!("Max Bandwidth: {}, Min Latency: {}, Min Packet Loss: {}, Max Available: {}", max_bandwidth, min_latency, min_packet_loss, max_available);
-        println!("Weights: Bandwidth: {}, Latency: {}, Packet Loss: {}, Available: {}", 
-                 self.config.get_weight("bandwidth"), 
-                 self.config.get_weight("latency"), 
-                 self.config.get_weight("packet_loss"), 
-                 self.config.get_weight("available"));
-    
-        // Compute the cost (`ne`) based on inversed normalization for cost minimization
-        let ne = safe_div(max_bandwidth, bandwidth) * self.config.get_weight("bandwidth") +
-                 safe_div(latency, min_latency) * self.config.get_weight("latency") +
-                 safe_div(packet_loss, min_packet_loss) * self.config.get_weight("packet_loss") +
-                 safe_div(max_available, available) * self.config.get_weight("available");
-    
-        ne
-    }
-
-    async fn get_service_resources(&self, services: &Vec<Service>, nodes: &Vec<Node>) -> HashMap<Service, Vec<Option<(Node, Resource)>>> {
-        let mut service_resources: HashMap<Service, Vec<Option<(Node, Resource)>>> = HashMap::new();
-
-        for service in services {
-            // determine the node for the service
-            for node in nodes {
-                let service_name_0 = service.name.clone();
-                // add the stack name to the service name
-                let service_name = format!("{}{}", self.config.cluster.prometheus.stack, service_name_0);
-
-                let service_node_util = self.api_client.get_node_service_utilization(&node.name, &service_name);
-                match service_node_util.await {
-                    Ok(resource) => {
-                        service_resources.entry(service.clone()).or_insert_with(|| Vec::new()).push(Some((node.clone(), resource)));
-                    }
-                    Err(_) => {
-                        service_resources.entry(service.clone()).or_insert_with(|| Vec::new()).push(None);
-                    }
-                }
-            }
-        }
-
-        service_resources
-    }
-
     // A function that takes node costs and returns the node with the lowest cost
     fn get_lowest_cost_node(&self, node_costs: &HashMap<Node, f64>) -> Node {
         let mut lowest_cost = f64::MAX;
@@ -1140,95 +1222,24 @@ impl Solver {
     }
 
 
-    // A function that takes two placements and computes the difference
-    fn compute_placement_diff(&self, placement1: &HashMap<Service, Option<HashSet<Node>>>, placement2: &HashMap<Service, Option<HashSet<Node>>>) -> f64 {
-        let mut diff = 0.0;
-
-        for (service, node) in placement1 {
-            if let Some(nodes) = placement2.get(service) {
-                if nodes.is_some() {
-                    if nodes != node {
-                        diff += 1.0;
-                    }
-                }
-            }
-        }
-
-        diff
-    }
-
-}
-
-
-
-fn get_lowest_coordinates(coordinates: &[Coordinate]) -> (f64, f64) {
-    let mut lowest_x = coordinates[0].x;
-    let mut lowest_y = coordinates[0].y;
-
-    for coordinate in coordinates {
-        if coordinate.x < lowest_x {
-            lowest_x = coordinate.x;
-        }
-        if coordinate.y < lowest_y {
-            lowest_y = coordinate.y;
-        }
-    }
-
-    (lowest_x, lowest_y)
-}
-
-fn compute_euclidean_distance(coordinates: &[Coordinate], target_x: f64, target_y: f64) -> f64 {
-    // Ensure coordinates is not empty
-    if coordinates.is_empty() {
-        return f64::MAX; // Or handle it as appropriate for your application
-    }
-
-    let (lowest_x, lowest_y) = get_lowest_coordinates(coordinates);
-    let distance = ((target_x - lowest_x).powi(2) + (target_y - lowest_y).powi(2)).sqrt();
-
-    // Return a small value if the distance is 0
-    if distance == 0.0 {
-        return 1e-10; // or any small positive value you prefer
-    }
-
-    distance
 }
 
-// fn compute_proportion(distance: f64, distance_map: &HashMap<Node, f64>, j: u32) -> u32 {
-//     let total_distance: f64 = distance_map.values().sum();
 
-//     let proportion = (distance / total_distance) * j as f64;
 
-//     proportion.round() as u32
-// }
+// The services a node gets, in proportion to its score
+fn compute_proportion(score: f64, score_map: &HashMap<Node, f64>, j: u32) -> u32 {
+    let total_score: f64 = score_map.values().sum();
 
-fn compute_proportion(distance: f64, distance_map: &HashMap<Node, f64>, j: u32) -> u32 {
-    // Handle zero distance to avoid division by zero
-    if distance <= 0.0 {
-        println!("Invalid distance: {}", distance);
-        return 0;
+    // all nodes are scored 0 - share the services equally
+    if total_score <= 0.0 {
+        return (j as f64 / score_map.len().max(1) as f64).ceil() as u32;
     }
 
-    // Calculate total inverse distance for all nodes
-    let total_inverse_distance: f64 = distance_map.values()
-        .map(|&d| 1.0 / d)
-        .sum();
-
-    // Calculate the proportion of services to assign to the node
-    let proportion = (1.0 / distance) / total_inverse_distance * j as f64;
-
-    // Print number of services and proportion
-    // println!("Number of services: {}, Proportion: {}", j, proportion);
-
     // Round the proportion up to the next integer
-    let rounded_proportion = proportion.ceil() as u32;
+    let proportion = (score / total_score * j as f64).ceil() as u32;
 
     // Ensure the proportion does not exceed the total number of services
-    if rounded_proportion > j {
-        return j;
-    }
-
-    rounded_proportion
+    proportion.min(j)
 }
 
 // a function that takes a service and returns all dependencies based on db, cache attributes of Service
@@ -1329,6 +1340,31 @@ fn get_services_by_names(service: String, all_services: &Vec<Service>) -> Option
 }
 
 
+// The nodes the extra replicas may take, with the capacity left by the usage of each node and the
+// demand of each service summed over its replicas
+fn replica_nodes(ranked: Vec<Node>, node_usage: &HashMap<Node, Resource>, service_resources: &HashMap<Service, Vec<Option<(Node, Resource)>>>) -> ReplicaNodes {
+    let capacity = node_usage.iter()
+        .map(|(node, usage)| (node.clone(), utility::node_capacity(node, usage)))
+        .collect();
+
+    let demand = service_resources.iter().map(|(service, usage)| {
+        let mut demand = Resource::default();
+        for (_, resource) in usage.iter().flatten() {
+            demand.add(resource);
+        }
+        (service.clone(), demand)
+    }).collect();
+
+    ReplicaNodes { ranked, capacity, demand }
+}
+
+// Nodes sorted from the lowest to the highest cost
+fn rank_nodes_by_cost(node_costs: &HashMap<Node, f64>) -> Vec<Node> {
+    let mut ranked_nodes: Vec<(&Node, &f64)> = node_costs.iter().collect();
+    ranked_nodes.sort_by(|a, b| a.1.partial_cmp(b.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.id.cmp(&b.0.id)));
+    ranked_nodes.into_iter().map(|(node, _)| node.clone()).collect()
+}
+
 fn print_placement_map(placement_map: HashMap<Service, Option<HashSet<Node>>>) {
     // Create a new map to hold nodes and their assigned services
     let mut node_service_map: HashMap<Node, HashSet<Service>> = HashMap::new();
@@ -1353,4 +1389,69 @@ fn print_placement_map(placement_map: HashMap<Service, Option<HashSet<Node>>>) {
             println!("  - Service: {}", service.name);
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::api_client::ApiClient;
+    use crate::nsga2opticas::{ObjectiveBounds, OMicroservicePlacementProblem};
+    use crate::solver::Solver;
+    use crate::utility::{Config, Resource};
+
+    // c is the cache of a
+    fn config() -> Config {
+        serde_yaml::from_str(r#"
+cluster:
+  nodes:
+    - { id: 1, name: "node1", ip: "10.0.0.1", resource: { cpu: 8, memory: 16000, disk: 100000, network: 1000 } }
+    - { id: 2, name: "node2", ip: "10.0.0.2", resource: { cpu: 8, memory: 16000, disk: 100000, network: 1000 } }
+  prometheus: { url: "", label: "", stack: "", query: "", metric: "" }
+database: { uri: "mongodb://localhost:27017", db: "yonga", collections: [] }
+services:
+  - { id: "1", name: "a", cache: "c" }
+  - { id: "2", name: "b" }
+  - { id: "3", name: "c" }
+weights: []
+"#).unwrap()
+    }
+
+    #[test]
+    fn test_migration_weights() {
+        let config = config();
+        let nodes = &config.cluster.nodes;
+        let (a, b, c) = (&config.services[0], &config.services[1], &config.services[2]);
+        let on = |node: usize| HashSet::from([nodes[node].clone()]);
+
+        // b mounts 2 volumes and uses 3 GB of disk on node 2
+        let mut solver = Solver::new(config.clone(), ApiClient::new("http://localhost"));
+        solver.volumes = HashMap::from([("b".to_string(), 2)]);
+        let usage = HashMap::from([(b.clone(), vec![Some((nodes[1].clone(), Resource::new(0.5, 100.0, 3.0, 1.0)))])]);
+
+        // nothing to migrate from before the first placement
+        assert!(solver.migration_cost(&usage).is_none());
+
+        solver.placement = Some(HashMap::from([(a.clone(), Some(on(0))), (b.clone(), Some(on(1))), (c.clone(), Some(on(0)))]));
+        let migration = solver.migration_cost(&usage).unwrap();
+
+        // 1 per service, plus 2 x 10 for the volumes of b and its 3 GB, plus 5 for the cache
+        assert_eq!(migration.weights[a], 1.0);
+        assert_eq!(migration.weights[b], 24.0);
+        assert_eq!(migration.weights[c], 6.0);
+
+        let bounds = ObjectiveBounds { max_opt_cost: 0.0, minmax_node_cost: (0.0, 0.0), minmax_resource_imbalance: (0.0, 1.0) };
+        let problem = OMicroservicePlacementProblem::new(config.clone(), HashMap::new(), HashMap::new(), HashMap::new(), bounds, HashMap::new(), HashMap::new())
+            .with_constraints(Vec::new())
+            .with_migration(migration);
+
+        // b moves with its volumes: 24 out of 31, the cache c: 6 out of 31
+        let moved = HashMap::from([(a.clone(), on(0)), (b.clone(), on(0)), (c.clone(), on(0))]);
+        assert!((problem.migration_cost(&moved) - 24.0 / 31.0).abs() < 1e-9);
+        let moved = HashMap::from([(a.clone(), on(0)), (b.clone(), on(1)), (c.clone(), on(1))]);
+        assert!((problem.migration_cost(&moved) - 6.0 / 31.0).abs() < 1e-9);
+
+        // the objective is off
+        solver.config.migration.objective = false;
+        assert!(solver.migration_cost(&usage).is_none());
+    }
+}
@@ -1,12 +1,13 @@
 use std::collections::{HashMap, HashSet};
+use async_trait::async_trait;
 use crate::utility::{Config, Node, Service};
 use crate::stack::{self, StackConfig};
-use chrono::Local;
+use crate::strategy::{self, PlacementMap, PlacementStrategy, ReplicaNodes, StrategyContext, StrategyRegistry};
+use crate::affinity;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 
 //use crate::api_client::ApiClient;
-use std::process::Command;
 // use crate::utility::{Network, Resource};
 
 #[derive(Debug)]
@@ -29,25 +30,59 @@ impl Spread {
         }
     }
 
-    pub fn run_deploy(&self, stack_name: &str, compose_file: &str) -> Result<std::process::Output, String> {
-        let output = Command::new("./target/debug/deploy")
-            .arg("--stack")
-            .arg("deploy")
-            .arg("--name")
-            .arg(stack_name)
-            .arg("--file")
-            .arg(compose_file)
-            .output()
-            .map_err(|e| e.to_string())?;
-        
-        if !output.status.success() {
-            return Err(format!("Command failed with status: {}", output.status));
+    fn assign_services(&self, proportion_map: &HashMap<Node, u32>, _groups: u32, services: Vec<Service>) -> HashMap<Service, Option<HashSet<Node>>> {
+        let mut rng = thread_rng();
+        let mut shuffled_services = services.clone();
+        shuffled_services.shuffle(&mut rng);
+    
+        let mut assignment_map: HashMap<Service, Option<HashSet<Node>>> = HashMap::new();
+        let mut node_capacity: HashMap<Node, u32> = proportion_map.clone();
+    
+        let mut nodes: Vec<&Node> = proportion_map.keys().collect();
+        let replicas = stack::get_replicas(&self.stack_config);
+    
+        // Iterate over all services
+        for service in shuffled_services {
+            // spread the replicas of the service over distinct nodes
+            let wanted = *replicas.get(&service.name).unwrap_or(&1);
+            let mut assigned = 0;
+    
+            nodes.shuffle(&mut rng); // Shuffle nodes before each assignment
+    
+            for node in &nodes {
+                if assigned >= wanted {
+                    break;
+                }
+                if let Some(capacity) = node_capacity.get_mut(node) {
+                    if *capacity > 0 {
+                        assignment_map.entry(service.clone())
+                                      .or_insert_with(|| Some(HashSet::new()))
+                                      .as_mut()
+                                      .unwrap()
+                                      .insert((*node).clone());
+                        *capacity -= 1;
+                        assigned += 1;
+                    }
+                }
+            }
+    
+            if assigned == 0 {
+                assignment_map.insert(service.clone(), None);
+            }
         }
+    
+        assignment_map
+    
+    }
+}
 
-        Ok(output)
+#[async_trait(?Send)]
+impl PlacementStrategy for Spread {
+    fn name(&self) -> &'static str {
+        "spread"
     }
 
-    pub async fn spread_0(&mut self) -> Result<HashMap<Service, Option<HashSet<Node>>>, Box<dyn std::error::Error>> {
+    async fn plan(&mut self) -> Result<PlacementMap, Box<dyn std::error::Error>> {
         println!("Running the Spread Strategy 0");
 
         self.placement = Some(HashMap::new());
@@ -75,7 +110,10 @@ impl Spread {
         // }
 
         // create the placement
-        let assignment_map = self.assign_services(&proportion_map, 1, self.config.services.clone());
+        let mut assignment_map = self.assign_services(&proportion_map, 1, self.config.services.clone());
+
+        // move the services the affinity rules place together or apart - the capacity of the nodes is not known
+        affinity::enforce(&mut assignment_map, &affinity::rules(&self.config), &self.config.cluster.nodes, &ReplicaNodes::default())?;
 
         self.placement = Some(assignment_map);
 
@@ -99,86 +137,17 @@ impl Spread {
         Ok(placement_map)
     }
 
-    fn assign_services(&self, proportion_map: &HashMap<Node, u32>, _groups: u32, services: Vec<Service>) -> HashMap<Service, Option<HashSet<Node>>> {
-        let mut rng = thread_rng();
-        let mut shuffled_services = services.clone();
-        shuffled_services.shuffle(&mut rng);
-    
-        let mut assignment_map: HashMap<Service, Option<HashSet<Node>>> = HashMap::new();
-        let mut node_capacity: HashMap<Node, u32> = proportion_map.clone();
-    
-        let mut nodes: Vec<&Node> = proportion_map.keys().collect();
-    
-        // Iterate over all services
-        for service in shuffled_services {
-            let mut assigned = false;
-    
-            nodes.shuffle(&mut rng); // Shuffle nodes before each assignment
-    
-            for node in &nodes {
-                if let Some(capacity) = node_capacity.get_mut(node) {
-                    if *capacity > 0 {
-                        assignment_map.entry(service.clone())
-                                      .or_insert_with(|| Some(HashSet::new()))
-                                      .as_mut()
-                                      .unwrap()
-                                      .insert((*node).clone());
-                        *capacity -= 1;
-                        assigned = true;
-                        break;
-                    }
-                }
-            }
-    
-            if !assigned {
-                assignment_map.insert(service.clone(), None);
-            }
-        }
-    
-        assignment_map
-    
+    async fn apply(&mut self, placement_map: PlacementMap) -> Result<(), String> {
+        strategy::deploy_placement(self.name(), &self.stack_name, &mut self.stack_config, placement_map).await
     }
 
-    pub fn run(&mut self, placement_map: Option<HashMap<Service, Option<HashSet<Node>>>>) {
-        match placement_map {
-            Some(map) => {
-                // Update and clean up the stack config
-                let mut local_stack_config = self.stack_config.clone();
-                stack::update_node_constraints(&mut local_stack_config, map);
-                stack::populate_volumes(&mut local_stack_config);
-                stack::delete_null_placement(&mut local_stack_config);
-
-                // Update the stack config
-                self.stack_config = local_stack_config;
-
-                // Create the YAML
-                let yaml_str = serde_yaml::to_string(&self.stack_config).unwrap();
-
-                // Provide the YAML to the deploy binary
-                let compose_file = format!("spread_{}.yml", Local::now().format("%Y-%m-%d_%H-%M-%S"));
-
-                // Write the YAML to a file
-                std::fs::write(&compose_file, yaml_str).expect("Failed to write the YAML configuration file");
-
-                // Deploy the stack
-                if let Err(e) = self.run_deploy(&self.stack_name, &compose_file) {
-                    println!("Failed to deploy: {}", e);
-
-                    // Clean up the file
-                    std::fs::remove_file(&compose_file).expect("Failed to remove the YAML configuration file");
-                }
-
-                else {
-                    println!("Deployed the stack successfully!");
-
-                    // Clean up the file
-                    std::fs::remove_file(&compose_file).expect("Failed to remove the YAML configuration file");
-                }
-            },
-            None => {
-                println!("No placement solution provided");
-            }
-        }
+    fn placement(&self) -> Option<&PlacementMap> {
+        self.placement.as_ref()
     }
 }
 
+pub fn register(registry: &mut StrategyRegistry) {
+    registry.register("spread", |context: StrategyContext| {
+        Box::new(Spread::new(context.config, None, context.stack_name, context.stack_config))
+    });
+}
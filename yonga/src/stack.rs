@@ -79,6 +79,8 @@ pub struct RestartPolicy {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Placement {
     pub constraints: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_replicas_per_node: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -142,40 +144,160 @@ pub fn update_replicas(config: &mut StackConfig, service: Service, replicas: u32
 
 }
 
+// Returns the number of replicas of each service (1 if not set)
+pub fn get_replicas(config: &StackConfig) -> HashMap<String, u32> {
+    config.services.iter().map(|(name, service_config)| {
+        let replicas = service_config.deploy.as_ref().and_then(|deploy| deploy.replicas).unwrap_or(1);
+        (name.clone(), replicas)
+    }).collect()
+}
+
+// Returns the number of volumes mounted by each service - the state a migration has to move
+pub fn get_volumes(config: &StackConfig) -> HashMap<String, usize> {
+    config.services.iter().map(|(name, service_config)| {
+        (name.clone(), service_config.volumes.as_ref().map_or(0, |volumes| volumes.len()))
+    }).collect()
+}
+
+// prefix of the node labels marking where the services placed on several nodes may run
+pub const PLACEMENT_LABEL_PREFIX: &str = "ocas.";
+
+/// The node label carried by the nodes a service placed on several nodes may run on.
+pub fn placement_label(service: &str) -> String {
+    format!("{}{}", PLACEMENT_LABEL_PREFIX, service)
+}
+
+/// Builds the placement constraints restricting a service to a set of nodes. A single node is pinned
+/// with `==`. Swarm ANDs constraints, so a set of nodes is expressed with a label only these nodes carry
+/// (see [`placement_labels`]) - the swarm nodes outside the config never carry it.
+pub fn get_set_constraints(service: &str, selected: &HashSet<Node>) -> Vec<String> {
+    if selected.len() == 1 {
+        return selected.iter().map(|node| format!("node.labels.name == {}", node.name)).collect();
+    }
+
+    vec![format!("node.labels.{} == true", placement_label(service))]
+}
+
+/// Returns, for every service of the placement, its placement label and the names of the nodes that must
+/// carry it - none for a service placed on a single node, so that stale labels are taken off.
+pub fn placement_labels(placement_map: &HashMap<Service, Option<HashSet<Node>>>) -> HashMap<String, HashSet<String>> {
+    placement_map.iter()
+        .filter_map(|(service, selected)| {
+            let selected = selected.as_ref()?;
+            let nodes = if selected.len() > 1 {
+                selected.iter().map(|node| node.name.clone()).collect()
+            } else {
+                HashSet::new()
+            };
+            Some((placement_label(&service.name), nodes))
+        })
+        .collect()
+}
+
+/// Reads back the placement a stack config was rendered with: a service pinned with
+/// `node.labels.name == X` runs on X, a service excluded with `node.labels.name != X` on the other nodes.
+/// The services restricted with a placement label, or not restricted at all, are left out.
+pub fn placement_from_constraints(config: &StackConfig, services: &[Service], nodes: &[Node]) -> HashMap<Service, Option<HashSet<Node>>> {
+    let mut placement_map = HashMap::new();
+
+    for service in services {
+        let constraints = match config.services.get(&service.name) {
+            Some(service_config) => get_node_constraints(service_config),
+            None => continue,
+        };
+
+        let mut pinned: Option<String> = None;
+        let mut excluded: HashSet<String> = HashSet::new();
+
+        for constraint in &constraints {
+            if let Some((key, value)) = constraint.split_once("==") {
+                if key.trim() == "node.labels.name" {
+                    pinned = Some(value.trim().to_string());
+                }
+            } else if let Some((key, value)) = constraint.split_once("!=") {
+                if key.trim() == "node.labels.name" {
+                    excluded.insert(value.trim().to_string());
+                }
+            }
+        }
+
+        let selected: HashSet<Node> = match pinned {
+            Some(name) => nodes.iter().filter(|node| node.name == name).cloned().collect(),
+            None if !excluded.is_empty() => nodes.iter().filter(|node| !excluded.contains(&node.name)).cloned().collect(),
+            None => continue,
+        };
+
+        if !selected.is_empty() {
+            placement_map.insert(service.clone(), Some(selected));
+        }
+    }
+
+    placement_map
+}
+
+/// Whether a service of the stack config is restricted with its placement label.
+pub fn has_placement_label(config: &StackConfig, service: &str) -> bool {
+    let label = format!("node.labels.{}", placement_label(service));
+
+    config.services.get(service)
+        .map(get_node_constraints)
+        .unwrap_or_default()
+        .iter()
+        .any(|constraint| constraint.split_once("==").is_some_and(|(key, _)| key.trim() == label))
+}
+
 pub fn update_node_constraints(config: &mut StackConfig, placement_map: HashMap<Service, Option<HashSet<Node>>>) {
-    for (service, nodes) in placement_map {
-        if let Some(nodes) = nodes {
+    let mut spread_replicas = false;
+
+    for (service, selected) in placement_map {
+        if let Some(selected) = selected {
+            if selected.is_empty() {
+                continue;
+            }
+
             if let Some(service_config) = config.services.get_mut(&service.name) {
                 if let Some(deploy) = &mut service_config.deploy {
+                    // delete the constraints and restrict the service to the selected nodes
+                    let constraints = get_set_constraints(&service.name, &selected);
+
+                    // spread the replicas over the selected nodes (anti-affinity)
+                    let replicas = deploy.replicas.unwrap_or(1);
+                    let max_replicas_per_node = if selected.len() > 1 {
+                        spread_replicas = true;
+                        Some((replicas as f64 / selected.len() as f64).ceil() as u32)
+                    } else {
+                        None
+                    };
+
                     if let Some(placement) = &mut deploy.placement {
-                        if let Some(constraints) = &mut placement.constraints {
-                            for node in nodes {
-                                // delete the constraints
-                                constraints.clear();
-
-                                constraints.push(format!("node.labels.name == {}", node.name));
-                                //[node.role == manager]
-                            }
-                        } else {
-                            placement.constraints = Some(
-                                nodes.into_iter()
-                                    .map(|node| format!("node.labels.name == {}", node.name))
-                                    .collect(),
-                            );
-                        }
+                        placement.constraints = Some(constraints);
+                        placement.max_replicas_per_node = max_replicas_per_node;
                     } else {
                         deploy.placement = Some(Placement {
-                            constraints: Some(
-                                nodes.into_iter()
-                                    .map(|node| format!("node.labels.name == {}", node.name))
-                                    .collect(),
-                            ),
+                            constraints: Some(constraints),
+                            max_replicas_per_node,
                         });
                     }
                 }
             }
         }
     }
+
+    // max_replicas_per_node needs compose file format 3.8
+    if spread_replicas {
+        ensure_version(config, (3, 8));
+    }
+}
+
+// Raise the compose file version to at least the given (major, minor)
+pub fn ensure_version(config: &mut StackConfig, minimum: (u32, u32)) {
+    let mut parts = config.version.split('.').map(|part| part.trim().parse::<u32>().unwrap_or(0));
+    let current = (parts.next().unwrap_or(0), parts.next().unwrap_or(0));
+
+    if current < minimum {
+        println!("Raising the compose file version from {} to {}.{}", config.version, minimum.0, minimum.1);
+        config.version = format!("{}.{}", minimum.0, minimum.1);
+    }
 }
 
 
@@ -191,6 +313,7 @@ pub fn delete_null_placement(config: &mut StackConfig) {
                     //println!("Deleting null placement constraints for a service X");
                     deploy.placement = Some(Placement {
                         constraints: Some(vec![]), // Assuming constraints is a Vec<String>
+                        max_replicas_per_node: None,
                     });
                 }
             }
@@ -199,12 +322,44 @@ pub fn delete_null_placement(config: &mut StackConfig) {
                 // println!("No placement constraints for a service X");
                 deploy.placement = Some(Placement {
                     constraints: Some(vec![]), // Assuming constraints is a Vec<String>
+                    max_replicas_per_node: None,
                 });
             }
         }
     }
 }
 
+// Returns the placement constraints of a service, empty if it has none
+pub fn get_node_constraints(service_config: &ServiceConfig) -> Vec<String> {
+    service_config.deploy.as_ref()
+        .and_then(|deploy| deploy.placement.as_ref())
+        .and_then(|placement| placement.constraints.clone())
+        .unwrap_or_default()
+}
+
+/// Compares the placement constraints of two stack configs and returns, for every service whose
+/// constraints differ, the service name with its constraints before and after (sorted by service name).
+pub fn diff_node_constraints(before: &StackConfig, after: &StackConfig) -> Vec<(String, Vec<String>, Vec<String>)> {
+    let mut service_names: Vec<&String> = before.services.keys().chain(after.services.keys()).collect();
+    service_names.sort();
+    service_names.dedup();
+
+    let mut diff = Vec::new();
+
+    for name in service_names {
+        let mut old_constraints = before.services.get(name).map(get_node_constraints).unwrap_or_default();
+        let mut new_constraints = after.services.get(name).map(get_node_constraints).unwrap_or_default();
+        old_constraints.sort();
+        new_constraints.sort();
+
+        if old_constraints != new_constraints {
+            diff.push((name.clone(), old_constraints, new_constraints));
+        }
+    }
+
+    diff
+}
+
 // fn main() -> Result<(), Box<dyn std::error::Error>> {
 //     let matches = Command::new("YongaStack")
 //         .arg(Arg::new("config")
@@ -291,4 +446,90 @@ pub fn delete_null_placement(config: &mut StackConfig) {
 
 //     println!("YAML config updated successfully.");
 //     Ok(())
-// }
\ No newline at end of file
+// }
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::stack::{diff_node_constraints, has_placement_label, placement_from_constraints, StackConfig};
+    use crate::utility::{Node, ResourceInt, Service};
+
+    fn stack_config(yaml: &str) -> StackConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn nodes() -> Vec<Node> {
+        (1..=3).map(|id| Node::new(id, &format!("node{}", id), "127.0.0.1", ResourceInt::new(4, 8, 100_000, 1000))).collect()
+    }
+
+    fn service(name: &str) -> Service {
+        Service::new(name, name, None, None)
+    }
+
+    const BEFORE: &str = r#"
+version: "3.8"
+services:
+  web:
+    deploy:
+      placement:
+        constraints: ["node.labels.name == node1"]
+  api:
+    deploy:
+      placement:
+        constraints: ["node.labels.name != node1"]
+  db:
+    deploy:
+      placement:
+        constraints: ["node.labels.ocas.db == true"]
+  cache:
+    image: redis
+volumes: {}
+"#;
+
+    #[test]
+    fn test_diff_node_constraints() {
+        let before = stack_config(BEFORE);
+        let mut after = before.clone();
+        after.services.get_mut("web").unwrap().deploy.as_mut().unwrap().placement.as_mut().unwrap().constraints =
+            Some(vec!["node.labels.name == node2".to_string()]);
+
+        let diff = diff_node_constraints(&before, &after);
+        assert_eq!(diff, vec![(
+            "web".to_string(),
+            vec!["node.labels.name == node1".to_string()],
+            vec!["node.labels.name == node2".to_string()],
+        )]);
+
+        // an unchanged stack has no diff
+        assert!(diff_node_constraints(&before, &before.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_placement_from_constraints() {
+        let config = stack_config(BEFORE);
+        let nodes = nodes();
+        let services: Vec<Service> = ["web", "api", "db", "cache"].iter().map(|name| service(name)).collect();
+
+        let placement = placement_from_constraints(&config, &services, &nodes);
+
+        let names = |service: &Service| -> HashSet<String> {
+            placement[service].as_ref().unwrap().iter().map(|node| node.name.clone()).collect()
+        };
+        assert_eq!(names(&services[0]), HashSet::from(["node1".to_string()]));
+        assert_eq!(names(&services[1]), HashSet::from(["node2".to_string(), "node3".to_string()]));
+
+        // a label or no constraint does not tell the nodes
+        assert!(!placement.contains_key(&services[2]));
+        assert!(!placement.contains_key(&services[3]));
+    }
+
+    #[test]
+    fn test_has_placement_label() {
+        let config = stack_config(BEFORE);
+
+        assert!(has_placement_label(&config, "db"));
+        assert!(!has_placement_label(&config, "web"));
+        assert!(!has_placement_label(&config, "cache"));
+        assert!(!has_placement_label(&config, "unknown"));
+    }
+}
@@ -1,46 +1,772 @@
-use crate::stack::StackConfig;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::process::Command;
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Local;
+use tokio::time::{Duration, Instant};
 
-#[derive(Debug)]
-pub struct Yonga {
-    pub config: StackConfig,
+use crate::affinity::AffinityRule;
+use crate::api_client::ApiClient;
+use crate::docker_client::DockerClient;
+use crate::history::PlacementHistory;
+use crate::lease::Lease;
+use crate::simulator::Recording;
+use crate::snapshot::SnapshotCache;
+use crate::stack::{self, StackConfig};
+use crate::utility::{Config, Control, Node, Resource, Service};
+
+// The placement produced by every strategy: a service and the nodes it may run on
+pub type PlacementMap = HashMap<Service, Option<HashSet<Node>>>;
+
+// What a strategy wants to happen after a plan/apply cycle
+#[derive(Debug, Clone, PartialEq)]
+pub enum Evaluation {
+    // the strategy is finished - one-shot placements
+    Done,
+    // re-plan after the given interval
+    Replan(Duration),
+}
+
+// Everything a strategy needs to be constructed
+#[derive(Debug, Clone)]
+pub struct StrategyContext {
+    pub config: Config, //YAML
+    pub stack_name: String,
+    pub stack_config: StackConfig, //Docker Swarm
+    pub api_client: ApiClient,
+    // replaces the database when simulating
+    pub recording: Option<Arc<Recording>>,
+    // the cluster snapshot shared by the strategies created from this context
+    pub snapshots: SnapshotCache,
+}
+
+impl StrategyContext {
+    pub fn new(config: Config, stack_name: String, stack_config: StackConfig, api_client: ApiClient) -> Self {
+        StrategyContext {
+            config,
+            stack_name,
+            stack_config,
+            api_client,
+            recording: None,
+            snapshots: SnapshotCache::default(),
+        }
+    }
+
+    pub fn with_recording(mut self, recording: Arc<Recording>) -> Self {
+        self.recording = Some(recording);
+        self
+    }
+
+    pub fn with_snapshots(mut self, snapshots: SnapshotCache) -> Self {
+        self.snapshots = snapshots;
+        self
+    }
+}
+
+#[async_trait(?Send)]
+pub trait PlacementStrategy {
+    // the name the strategy is registered under
+    fn name(&self) -> &'static str;
+
+    // compute the placement map
+    async fn plan(&mut self) -> Result<PlacementMap, Box<dyn std::error::Error>>;
+
+    // render the placement into the stack config and deploy it
+    async fn apply(&mut self, placement_map: PlacementMap) -> Result<(), String>;
+
+    // take the placement as deployed without touching the cluster (simulations)
+    fn assume_applied(&mut self, _placement_map: &PlacementMap) {}
+
+    // the last placement computed by the strategy, if any
+    fn placement(&self) -> Option<&PlacementMap> {
+        None
+    }
+
+    // whether the strategy keeps planning after its first placement - only these hold the controller lease
+    fn replans(&self) -> bool {
+        false
+    }
+
+    // assess the deployed placement and decide whether to plan again
+    async fn evaluate(&mut self) -> Evaluation {
+        Evaluation::Done
+    }
+}
+
+pub type StrategyFactory = fn(StrategyContext) -> Box<dyn PlacementStrategy>;
+
+// A registry of placement strategies keyed by name
+pub struct StrategyRegistry {
+    factories: BTreeMap<String, StrategyFactory>,
+}
+
+impl Default for StrategyRegistry {
+    fn default() -> Self {
+        StrategyRegistry::new()
+    }
+}
+
+impl StrategyRegistry {
+    pub fn new() -> Self {
+        StrategyRegistry {
+            factories: BTreeMap::new(),
+        }
+    }
+
+    // A registry with all the strategies shipped with yonga
+    pub fn with_defaults() -> Self {
+        let mut registry = StrategyRegistry::new();
+
+        crate::spread::register(&mut registry);
+        crate::binpack::register(&mut registry);
+        crate::random::register(&mut registry);
+        crate::yonga::register(&mut registry);
+        crate::milp::register(&mut registry);
+
+        registry
+    }
+
+    pub fn register(&mut self, name: &str, factory: StrategyFactory) {
+        if self.factories.insert(name.to_string(), factory).is_some() {
+            println!("Strategy {} was already registered - replacing it", name);
+        }
+    }
+
+    pub fn create(&self, name: &str, context: StrategyContext) -> Option<Box<dyn PlacementStrategy>> {
+        self.factories.get(name).map(|factory| factory(context))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.factories.keys().cloned().collect()
+    }
+}
+
+// Drive a strategy through its plan, apply and evaluate phases. With a lease, only its holder
+// plans and deploys - a standby controller waits and takes over once the lease expires.
+pub async fn run(strategy: &mut dyn PlacementStrategy, lease: Option<&Lease>) {
+    println!("Starting the {} placement strategy", strategy.name());
+
+    loop {
+        if let Some(lease) = lease {
+            if !hold_lease(lease).await {
+                tokio::time::sleep(lease.renew_interval).await;
+                continue;
+            }
+        }
+
+        match strategy.plan().await {
+            Ok(map) => {
+                // a plan may outlast the lease - check it is still ours before deploying
+                let held = match lease {
+                    Some(lease) => hold_lease(lease).await,
+                    None => true,
+                };
+
+                if !held {
+                    println!("Lost the lease while planning - not deploying");
+                    continue;
+                }
+
+                if let Err(e) = strategy.apply(map).await {
+                    println!("Failed to deploy: {}", e);
+                }
+            }
+            Err(e) => {
+                println!("No placement solution found: {}", e);
+            }
+        }
+
+        match strategy.evaluate().await {
+            Evaluation::Done => break,
+            Evaluation::Replan(interval) => wait_renewing(interval, lease).await,
+        }
+    }
+
+    if let Some(lease) = lease {
+        if let Err(e) = lease.release().await {
+            println!("Failed to release the lease: {}", e);
+        }
+    }
+}
+
+// Take or renew the lease, reporting who holds it otherwise
+async fn hold_lease(lease: &Lease) -> bool {
+    match lease.try_acquire().await {
+        Ok(true) => true,
+        Ok(false) => {
+            match lease.current_holder().await {
+                Ok(Some(holder)) => println!("Standby - stack {} is controlled by {}", lease.name, holder),
+                _ => println!("Standby - waiting for the lease on stack {}", lease.name),
+            }
+            false
+        }
+        Err(e) => {
+            println!("Failed to acquire the lease on stack {}: {}", lease.name, e);
+            false
+        }
+    }
+}
+
+// Sleep until the next plan, renewing the lease meanwhile
+async fn wait_renewing(interval: Duration, lease: Option<&Lease>) {
+    let lease = match lease {
+        Some(lease) => lease,
+        None => return tokio::time::sleep(interval).await,
+    };
+
+    let deadline = Instant::now() + interval;
+
+    while Instant::now() < deadline {
+        tokio::time::sleep(lease.renew_interval.min(deadline - Instant::now())).await;
+
+        if Instant::now() < deadline {
+            if let Err(e) = lease.try_acquire().await {
+                println!("Failed to renew the lease on stack {}: {}", lease.name, e);
+            }
+        }
+    }
+}
+
+// Plan a single placement and show what would change, without deploying anything
+pub async fn dry_run(strategy: &mut dyn PlacementStrategy, deployed: &StackConfig, config: &Config, stack_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Dry run of the {} placement strategy - nothing will be deployed", strategy.name());
+
+    // the placement running now - the strategy has not placed anything yet
+    let previous = deployed_placement(deployed, config, stack_name).await;
+
+    let placement_map = strategy.plan().await?;
+    let rendered = render_stack_config(deployed, placement_map.clone());
+
+    // Compare against the compose file that is currently deployed
+    let compose_diff = stack::diff_node_constraints(deployed, &rendered);
+    println!("Compose diff ({} of {} services changed):", compose_diff.len(), rendered.services.len());
+    for (service, before, after) in &compose_diff {
+        println!("  {}: {:?} -> {:?}", service, before, after);
+    }
+
+    // Compare against the placement of the deployed stack
+    match previous {
+        Some(previous) => {
+            let placement_diff = diff_placements(&previous, &placement_map);
+            println!("Placement diff ({} of {} services moved):", placement_diff.len(), placement_map.len());
+            for (service, before, after) in &placement_diff {
+                println!("  {}: {:?} -> {:?}", service, before, after);
+            }
+        }
+        None => {
+            println!("No deployed placement found - every service is a new placement");
+        }
+    }
+
+    // Keep the rendered compose file so that it can be reviewed and deployed as is
+    let compose_file = format!("{}_dry-run_{}.yml", strategy.name(), Local::now().format("%Y-%m-%d_%H-%M-%S"));
+    std::fs::write(&compose_file, serde_yaml::to_string(&rendered)?)?;
+    println!("Rendered compose file written to {}", compose_file);
+
+    Ok(())
+}
+
+// The placement of the deployed stack: its constraints give the nodes of the services pinned to nodes, the
+// latest revision in the placement history those of the services placed with a label
+async fn deployed_placement(deployed: &StackConfig, config: &Config, stack_name: &str) -> Option<PlacementMap> {
+    let mut placement_map = stack::placement_from_constraints(deployed, &config.services, &config.cluster.nodes);
+
+    let labelled: Vec<&Service> = config.services.iter()
+        .filter(|service| !placement_map.contains_key(*service) && stack::has_placement_label(deployed, &service.name))
+        .collect();
+
+    if !labelled.is_empty() {
+        let latest = match PlacementHistory::connect(&config.database, stack_name).await {
+            Ok(history) => history.latest().await.unwrap_or_else(|e| {
+                println!("Failed to read the placement history: {}", e);
+                None
+            }),
+            Err(e) => {
+                println!("Failed to connect to the placement history: {}", e);
+                None
+            }
+        };
+
+        for service in labelled {
+            match latest.as_ref().and_then(|record| record.placement.get(&service.name)) {
+                Some(names) => {
+                    let nodes = config.cluster.nodes.iter().filter(|node| names.contains(&node.name)).cloned().collect();
+                    placement_map.insert(service.clone(), Some(nodes));
+                }
+                None => println!("No deployed placement found for service {} - it is placed with a node label", service.name),
+            }
+        }
+    }
+
+    if placement_map.is_empty() {
+        None
+    } else {
+        Some(placement_map)
+    }
+}
+
+// The nodes of every placed service - the unplaced ones are left out
+pub fn placed_nodes(placement_map: &PlacementMap) -> HashMap<Service, HashSet<Node>> {
+    placement_map.iter()
+        .filter_map(|(service, nodes)| Some((service.clone(), nodes.clone()?)))
+        .filter(|(_, nodes)| !nodes.is_empty())
+        .collect()
+}
+
+// Returns, for every service whose nodes differ, the service name with its sorted node names before and after
+pub fn diff_placements(before: &PlacementMap, after: &PlacementMap) -> Vec<(String, Vec<String>, Vec<String>)> {
+    let node_names = |map: &PlacementMap, service: &Service| -> Vec<String> {
+        let mut names: Vec<String> = map.get(service)
+            .and_then(|nodes| nodes.as_ref())
+            .map(|nodes| nodes.iter().map(|node| node.name.clone()).collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    };
+
+    let mut services: Vec<&Service> = before.keys().chain(after.keys()).collect();
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+    services.dedup();
+
+    let mut diff = Vec::new();
+
+    for service in services {
+        let old_nodes = node_names(before, service);
+        let new_nodes = node_names(after, service);
+
+        if old_nodes != new_nodes {
+            diff.push((service.name.clone(), old_nodes, new_nodes));
+        }
+    }
+
+    diff
+}
+
+// Keep the services that may not move yet on their current nodes: a service moves again only after
+// min_dwell seconds, and at most max_moves services move per cycle - those settled the longest first.
+// The services bound by a hard affinity rule move together or not at all, so that the mix of the
+// current and the proposed placement keeps the rules both of them satisfy.
+pub fn limit_moves(current: &PlacementMap, proposed: PlacementMap, moved_at: &HashMap<String, Instant>, control: &Control, rules: &[AffinityRule]) -> PlacementMap {
+    let now = Instant::now();
+    let min_dwell = Duration::from_secs(control.min_dwell);
+
+    // the services placed differently
+    let changed: Vec<Service> = proposed.iter()
+        .filter(|(service, nodes)| current.get(*service).is_some_and(|current_nodes| current_nodes != *nodes))
+        .map(|(service, _)| service.clone())
+        .collect();
+
+    // the groups moving together, with the last move of any of their services - those never moved first
+    let mut candidates: Vec<(Vec<Service>, Option<Instant>)> = move_groups(changed, rules).into_iter()
+        .map(|group| {
+            let last_move = group.iter().filter_map(|service| moved_at.get(&service.name).copied()).max();
+            (group, last_move)
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0[0].name.cmp(&b.0[0].name)));
+
+    let mut limited = proposed;
+    let mut moves = 0;
+
+    for (group, last_move) in candidates {
+        let settled = last_move.is_none_or(|at| now.duration_since(at) >= min_dwell);
+        let within_budget = control.max_moves.is_none_or(|max_moves| moves + group.len() <= max_moves);
+
+        if settled && within_budget {
+            moves += group.len();
+            continue;
+        }
+
+        let names: Vec<&str> = group.iter().map(|service| service.name.as_str()).collect();
+        if !settled {
+            println!("Keeping services {:?} on their nodes - moved less than {}s ago", names, control.min_dwell);
+        } else {
+            println!("Keeping services {:?} on their nodes - {} move(s) per cycle at most", names, control.max_moves.unwrap_or_default());
+        }
+
+        for service in group {
+            let nodes = current[&service].clone();
+            limited.insert(service, nodes);
+        }
+    }
+
+    limited
 }
 
-impl Yonga {
-    pub fn new(config: StackConfig) -> Self {
-        Yonga { config }
+// Split the moved services into the groups that must move together - those sharing a hard affinity
+// rule, directly or through other moved services. Each group is sorted by name.
+fn move_groups(changed: Vec<Service>, rules: &[AffinityRule]) -> Vec<Vec<Service>> {
+    let bound = |a: &Service, b: &Service| rules.iter()
+        .any(|rule| rule.kind.is_hard() && rule.services.contains(&a.name) && rule.services.contains(&b.name));
+
+    let mut groups: Vec<Vec<Service>> = Vec::new();
+
+    for service in changed {
+        // the groups bound to the service merge with it
+        let (bound_groups, mut other_groups): (Vec<Vec<Service>>, Vec<Vec<Service>>) = groups.into_iter()
+            .partition(|group| group.iter().any(|other| bound(&service, other)));
+
+        let mut group: Vec<Service> = bound_groups.into_iter().flatten().collect();
+        group.push(service);
+        other_groups.push(group);
+        groups = other_groups;
+    }
+
+    for group in groups.iter_mut() {
+        group.sort_by(|a, b| a.name.cmp(&b.name));
     }
+
+    groups
+}
+
+// What the extra replicas of the services may take: the nodes best first, the capacity each node has left
+// before the placement and the demand of each service
+#[derive(Debug, Clone, Default)]
+pub struct ReplicaNodes {
+    pub ranked: Vec<Node>,
+    pub capacity: HashMap<Node, Resource>,
+    pub demand: HashMap<Service, Resource>,
 }
 
+impl ReplicaNodes {
+    // The demand of a service split evenly over the given number of replicas
+    pub fn share(&self, service: &Service, replicas: usize) -> Resource {
+        let demand = self.demand.get(service).cloned().unwrap_or(Resource::default());
+        let share = 1.0 / replicas.max(1) as f64;
 
-#[derive(Debug)]
-pub struct Spread{
-    pub config: StackConfig,
+        Resource::new(demand.cpu * share, demand.memory * share, demand.disk * share, demand.network * share)
+    }
+
+    // The capacity left once the services already placed are counted
+    pub fn capacity_left(&self, placement_map: &PlacementMap) -> HashMap<Node, Resource> {
+        let mut capacity = self.capacity.clone();
+        for (service, nodes) in placement_map.iter() {
+            if let Some(nodes) = nodes {
+                let share = self.share(service, nodes.len());
+                for node in nodes {
+                    if let Some(left) = capacity.get_mut(node) {
+                        left.sub(&share);
+                    }
+                }
+            }
+        }
+        capacity
+    }
 }
 
-impl Spread {
-    pub fn new(config: StackConfig) -> Self {
-        Spread { config }
+// Extend the placement of every replicated service to as many distinct nodes as it has replicas,
+// taking the extra nodes in the order given (best first). A node only takes a replica if its capacity
+// left holds the share of the service; nodes of unknown capacity are skipped. Services without a
+// placement are left alone.
+pub fn place_replicas(placement_map: &mut PlacementMap, replicas: &HashMap<String, u32>, replica_nodes: &ReplicaNodes) {
+    let mut capacity = replica_nodes.capacity_left(placement_map);
+
+    // the services in a fixed order, so the capacity is taken the same way every run
+    let mut services: Vec<Service> = placement_map.keys().cloned().collect();
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for service in services {
+        let nodes = match placement_map.get_mut(&service) {
+            Some(Some(nodes)) => nodes,
+            _ => continue,
+        };
+
+        let wanted = (*replicas.get(&service.name).unwrap_or(&1) as usize).min(replica_nodes.ranked.len());
+        if nodes.len() >= wanted {
+            continue;
+        }
+
+        let before = replica_nodes.share(&service, nodes.len());
+        let after = replica_nodes.share(&service, wanted);
+
+        // the nodes already placed hand over part of their share
+        for node in nodes.iter() {
+            if let Some(left) = capacity.get_mut(node) {
+                left.add(&before);
+                left.sub(&after);
+            }
+        }
+
+        for node in &replica_nodes.ranked {
+            if nodes.len() >= wanted {
+                break;
+            }
+
+            if nodes.contains(node) {
+                continue;
+            }
+
+            match capacity.get_mut(node) {
+                Some(left) if fits(left, &after) => {
+                    left.sub(&after);
+                    nodes.insert(node.clone());
+                }
+                _ => {}
+            }
+        }
+
+        if nodes.len() < wanted {
+            println!("Service {} placed on {} node(s) of the {} it has replicas for - no other node has capacity left",
+                service.name, nodes.len(), wanted);
+        }
     }
 }
 
-#[derive(Debug)]
-pub struct Binpack{
-    pub config: StackConfig,
+// The demand fits the capacity left in cpu, memory, disk and network
+pub fn fits(capacity: &Resource, demand: &Resource) -> bool {
+    demand.cpu <= capacity.cpu && demand.memory <= capacity.memory && demand.disk <= capacity.disk && demand.network <= capacity.network
+}
+
+// Update and clean up the stack config for a placement
+pub fn render_stack_config(stack_config: &StackConfig, placement_map: PlacementMap) -> StackConfig {
+    let mut local_stack_config = stack_config.clone();
+    stack::update_node_constraints(&mut local_stack_config, placement_map);
+    stack::populate_volumes(&mut local_stack_config);
+    stack::delete_null_placement(&mut local_stack_config);
+
+    local_stack_config
 }
 
-impl Binpack {
-    pub fn new(config: StackConfig) -> Self {
-        Binpack { config }
+// Render the placement, label the nodes of the services placed on several nodes, write it to a compose
+// file and deploy the stack. The stack config is only replaced once the deployment succeeds.
+pub async fn deploy_placement(strategy: &str, stack_name: &str, stack_config: &mut StackConfig, placement_map: PlacementMap) -> Result<(), String> {
+    let docker_client = DockerClient::new();
+    let relabelled = label_nodes(&docker_client, &placement_map).await?;
+
+    let local_stack_config = render_stack_config(stack_config, placement_map);
+
+    deploy_stack_config(strategy, stack_name, &local_stack_config)?;
+    reschedule_relabelled(&docker_client, stack_name, stack_config, &local_stack_config, relabelled).await?;
+
+    // Update the stack config
+    *stack_config = local_stack_config;
+
+    Ok(())
+}
+
+// Write the stack config to a compose file and deploy the stack
+pub fn deploy_stack_config(strategy: &str, stack_name: &str, stack_config: &StackConfig) -> Result<(), String> {
+    // Create the YAML
+    let yaml_str = serde_yaml::to_string(stack_config).map_err(|e| e.to_string())?;
+
+    // Provide the YAML to the deploy binary
+    let compose_file = format!("{}_{}.yml", strategy, Local::now().format("%Y-%m-%d_%H-%M-%S"));
+
+    // Write the YAML to a file
+    std::fs::write(&compose_file, yaml_str).map_err(|e| format!("Failed to write the YAML configuration file: {}", e))?;
+
+    // Deploy the stack
+    let result = run_deploy(stack_name, &compose_file);
+
+    // Clean up the file
+    if let Err(e) = std::fs::remove_file(&compose_file) {
+        println!("Failed to remove the YAML configuration file {}: {}", compose_file, e);
     }
+
+    result?;
+
+    println!("Deployed the stack successfully!");
+
+    Ok(())
 }
 
-#[derive(Debug)]
-pub struct Random{
-    pub config: StackConfig,
+// Apply a placement by updating only the services whose constraints changed, instead of
+// redeploying the whole stack. Returns the names of the services that were moved.
+pub async fn migrate_placement(stack_name: &str, stack_config: &mut StackConfig, placement_map: PlacementMap) -> Result<Vec<String>, String> {
+    let docker_client = DockerClient::new();
+    let relabelled = label_nodes(&docker_client, &placement_map).await?;
+
+    let rendered = render_stack_config(stack_config, placement_map);
+    let diff = stack::diff_node_constraints(stack_config, &rendered);
+
+    if diff.is_empty() && relabelled.is_empty() {
+        println!("No service moved - nothing to migrate");
+        return Ok(Vec::new());
+    }
+
+    let mut moved = Vec::new();
+
+    for (service, before, after) in diff {
+        let remove: Vec<String> = before.iter().filter(|c| !after.contains(c)).cloned().collect();
+        let add: Vec<String> = after.iter().filter(|c| !before.contains(c)).cloned().collect();
+
+        // swarm service names are prefixed with the stack name
+        let service_name = format!("{}_{}", stack_name, service);
+
+        println!("Migrating service {}: removing {:?}, adding {:?}", service_name, remove, add);
+
+        if let Err(e) = docker_client.move_placement(&service_name, &remove, &add, max_replicas_per_node(&rendered, &service)).await {
+            return Err(format!("Failed to migrate service {} after moving {:?}: {}", service_name, moved, e));
+        }
+
+        // Keep the stack config in sync with what is deployed
+        if let Some(service_config) = rendered.services.get(&service) {
+            stack_config.services.insert(service.clone(), service_config.clone());
+        }
+
+        moved.push(service);
+    }
+
+    let rescheduled = reschedule_relabelled(&docker_client, stack_name, stack_config, &rendered, relabelled).await
+        .map_err(|e| format!("{} after moving {:?}", e, moved))?;
+
+    for service in rescheduled {
+        if let Some(service_config) = rendered.services.get(&service) {
+            stack_config.services.insert(service.clone(), service_config.clone());
+        }
+        moved.push(service);
+    }
+
+    println!("Migrated {} service(s) successfully!", moved.len());
+
+    Ok(moved)
+}
+
+// Reschedule the relabelled services whose constraints are the same before and after - swarm leaves the
+// running tasks where they are when only the labels of the nodes change. Returns the services rescheduled.
+pub async fn reschedule_relabelled(docker_client: &DockerClient, stack_name: &str, before: &StackConfig, after: &StackConfig, relabelled: HashSet<String>) -> Result<Vec<String>, String> {
+    let changed: HashSet<String> = stack::diff_node_constraints(before, after).into_iter().map(|(service, _, _)| service).collect();
+
+    let mut services: Vec<String> = relabelled.into_iter()
+        .filter(|service| !changed.contains(service) && after.services.contains_key(service))
+        .collect();
+    services.sort();
+
+    let mut rescheduled = Vec::new();
+
+    for service in services {
+        let service_name = format!("{}_{}", stack_name, service);
+        println!("Rescheduling service {} on its relabelled nodes", service_name);
+
+        if let Err(e) = docker_client.reschedule_service(&service_name, max_replicas_per_node(after, &service)).await {
+            return Err(format!("Failed to reschedule service {} after {:?}: {}", service_name, rescheduled, e));
+        }
+
+        rescheduled.push(service);
+    }
+
+    Ok(rescheduled)
+}
+
+// The replicas per node a service is rendered with
+fn max_replicas_per_node(stack_config: &StackConfig, service: &str) -> Option<u64> {
+    stack_config.services.get(service)
+        .and_then(|service_config| service_config.deploy.as_ref())
+        .and_then(|deploy| deploy.placement.as_ref())
+        .and_then(|placement| placement.max_replicas_per_node)
+        .map(u64::from)
+}
+
+// Put the placement label of each service placed on several nodes on these nodes, and take it off the
+// others - the constraints rendered for the placement only match the labelled nodes. Returns the
+// services whose label moved on some node.
+pub async fn label_nodes(docker_client: &DockerClient, placement_map: &PlacementMap) -> Result<HashSet<String>, String> {
+    let wanted = stack::placement_labels(placement_map);
+
+    let swarm_nodes = docker_client.list_nodes().await.map_err(|e| format!("Failed to list the swarm nodes: {}", e))?;
+
+    let mut relabelled = HashSet::new();
+
+    for swarm_node in swarm_nodes {
+        let name = swarm_node.name();
+        let labels = swarm_node.labels();
+
+        let mut updated = labels.clone();
+        for (label, nodes) in &wanted {
+            let changed = if nodes.contains(&name) {
+                updated.insert(label.clone(), "true".to_string()).as_deref() != Some("true")
+            } else {
+                updated.remove(label).is_some()
+            };
+
+            if changed {
+                relabelled.insert(label.trim_start_matches(stack::PLACEMENT_LABEL_PREFIX).to_string());
+            }
+        }
+
+        if updated != labels {
+            let mut placed: Vec<&String> = updated.keys().filter(|key| key.starts_with(stack::PLACEMENT_LABEL_PREFIX)).collect();
+            placed.sort();
+            println!("Labelling node {} with {:?}", name, placed);
+
+            docker_client.set_node_labels(&swarm_node.id, updated).await
+                .map_err(|e| format!("Failed to label node {}: {}", name, e))?;
+        }
+    }
+
+    Ok(relabelled)
+}
+
+pub fn run_deploy(stack_name: &str, compose_file: &str) -> Result<std::process::Output, String> {
+    let output = Command::new("./target/debug/deploy")
+        .arg("--stack")
+        .arg("deploy")
+        .arg("--name")
+        .arg(stack_name)
+        .arg("--file")
+        .arg(compose_file)
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(format!("Command failed with status: {}", output.status));
+    }
+
+    Ok(output)
 }
 
-impl Random {
-    pub fn new(config: StackConfig) -> Self {
-        Random { config }
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+    use tokio::time::Instant;
+
+    use crate::affinity::{AffinityKind, AffinityRule};
+    use crate::strategy::{limit_moves, PlacementMap};
+    use crate::utility::{Control, Node, ResourceInt, Service};
+
+    fn node(id: i64) -> Node {
+        Node::new(id, &format!("node{}", id), "127.0.0.1", ResourceInt::new(4, 8, 100_000, 1000))
+    }
+
+    fn placement(services: &[(&str, i64)]) -> PlacementMap {
+        services.iter()
+            .map(|(name, id)| (Service::new(name, name, None, None), Some(HashSet::from([node(*id)]))))
+            .collect()
     }
-}
\ No newline at end of file
+
+    fn colocate(services: &[&str]) -> AffinityRule {
+        AffinityRule { services: services.iter().map(|name| name.to_string()).collect(), kind: AffinityKind::MustColocate, penalty: 1.0 }
+    }
+
+    fn node_of(placement_map: &PlacementMap, name: &str) -> i64 {
+        let service = placement_map.keys().find(|service| service.name == name).unwrap();
+        placement_map[service].as_ref().unwrap().iter().next().unwrap().id
+    }
+
+    #[test]
+    fn test_limit_moves_keeps_groups_together() {
+        let current = placement(&[("a", 1), ("b", 1), ("c", 1)]);
+        let proposed = placement(&[("a", 2), ("b", 2), ("c", 3)]);
+        let control = Control { max_moves: Some(1), ..Control::default() };
+
+        // a and b would need two moves - only c fits the budget
+        let limited = limit_moves(&current, proposed.clone(), &HashMap::new(), &control, &[colocate(&["a", "b"])]);
+        assert_eq!((node_of(&limited, "a"), node_of(&limited, "b"), node_of(&limited, "c")), (1, 1, 3));
+
+        // without the rule, a moves alone and leaves b behind
+        let limited = limit_moves(&current, proposed, &HashMap::new(), &control, &[]);
+        assert_eq!((node_of(&limited, "a"), node_of(&limited, "b"), node_of(&limited, "c")), (2, 1, 1));
+    }
+
+    #[test]
+    fn test_limit_moves_dwell() {
+        let current = placement(&[("a", 1), ("b", 1), ("c", 1)]);
+        let proposed = placement(&[("a", 2), ("b", 2), ("c", 3)]);
+        let control = Control { min_dwell: 3600, ..Control::default() };
+
+        // b moved just now, so a stays with it
+        let moved_at = HashMap::from([("b".to_string(), Instant::now())]);
+        let limited = limit_moves(&current, proposed, &moved_at, &control, &[colocate(&["a", "b"])]);
+        assert_eq!((node_of(&limited, "a"), node_of(&limited, "b"), node_of(&limited, "c")), (1, 1, 3));
+    }
+}
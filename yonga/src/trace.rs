@@ -23,13 +23,35 @@ pub struct TraceEntry {
     traceID: String,
     spanID: String,
     operationName: String,
-    #[serde(deserialize_with = "deserialize_date_time")]
+    #[serde(deserialize_with = "deserialize_date_time", serialize_with = "serialize_date_time")]
     startTime: DateTime<Utc>,
     duration: i64,
     references: Vec<serde_json::Value>,
     process: Process,
 }
 
+impl TraceEntry {
+    // A span of a service, child of the parent span if any (recorded or synthetic traces)
+    pub fn new(trace_id: &str, span_id: &str, operation_name: &str, start_time: DateTime<Utc>, duration: i64, parent_span_id: Option<&str>, service_name: &str) -> Self {
+        let references = match parent_span_id {
+            Some(parent_span_id) => vec![serde_json::json!({ "refType": "CHILD_OF", "traceID": trace_id, "spanID": parent_span_id })],
+            None => Vec::new(),
+        };
+
+        TraceEntry {
+            traceID: trace_id.to_string(),
+            spanID: span_id.to_string(),
+            operationName: operation_name.to_string(),
+            startTime: start_time,
+            duration,
+            references,
+            process: Process {
+                serviceName: service_name.to_string(),
+            },
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TreeNode {
     entry: TraceEntry,
@@ -51,6 +73,14 @@ where
     Ok(bson_date.to_chrono())
 }
 
+// Custom serializer function for DateTime - the counterpart of deserialize_date_time
+fn serialize_date_time<S>(date_time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    bson::DateTime::from_chrono(*date_time).serialize(serializer)
+}
+
 pub async fn get_latest_trace_entries(collection: &Collection<TraceEntry>, limit: i64) -> Result<Vec<TraceEntry>, mongodb::error::Error> {
     let find_options = FindOptions::builder()
         .sort(Some(doc! { "timestamp": -1 })) // Sort by 'timestamp' field in descending order
@@ -197,6 +227,17 @@ impl ServiceGraph {
         ((*lower_value as f64 + *upper_value as f64) / 2.0) / 1000.0 // Convert from microseconds to milliseconds
     }
 
+    // The mean and 99th percentile duration (ms) of the calls from one service to another
+    pub fn call_duration(&self, from: &str, to: &str) -> Option<(f64, f64)> {
+        let edge = self.nodes.get(from)?.edges.get(to)?;
+        if edge.durations.is_empty() {
+            return None;
+        }
+
+        let mean = edge.durations.iter().sum::<i64>() as f64 / edge.durations.len() as f64 / 1000.0; // Convert from microseconds to milliseconds
+        Some((mean, Self::calculate_percentile(&edge.durations, 99.0)))
+    }
+
     pub fn build_from_traces(&mut self, trees: &HashMap<String, TreeNode>) {
         for root in trees.values() {
             // print the root
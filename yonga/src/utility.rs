@@ -6,6 +6,11 @@ use actix_web::HttpResponse;
 use mongodb::{bson::doc, bson::Document, options::FindOneOptions, options::FindOptions};
 use std::collections::{HashMap, HashSet};
 use futures::stream::StreamExt; // For `next`
+use crate::scoring::ScoringMethod;
+use crate::binpack::PackingRule;
+use crate::decision::DecisionMethod;
+use crate::latency::Latency;
+use crate::affinity::Affinity;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Cluster {
@@ -45,7 +50,7 @@ impl Service {
 }
 
 // function that takes a String service name and returns a Service object
-pub fn get_service_by_name(name: String, services: &Vec<Service>) -> Option<Service> {
+pub fn get_service_by_name(name: String, services: &[Service]) -> Option<Service> {
     for service in services {
         if service.name == name {
             return Some(service.clone());
@@ -60,12 +65,89 @@ pub struct Weight {
     pub value: f64,
 }
 
+// The control loop of the strategies that re-plan
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct Control {
+    // seconds between two plans
+    pub interval: u64,
+    // relative improvement of the objectives a new placement must bring, e.g. 0.05 for 5%
+    pub min_improvement: f64,
+    // seconds a service stays on its nodes before it may move again
+    pub min_dwell: u64,
+    // services moved per cycle at most - unlimited if not set
+    pub max_moves: Option<usize>,
+    // seconds the controller lease lasts without renewal - longer than a plan
+    pub lease_duration: u64,
+    // seconds between two renewals (or takeover attempts by a standby)
+    pub lease_renew: u64,
+    // seconds each request for the cluster snapshot may take
+    pub request_timeout: u64,
+    // seconds the services of a revision have to converge after a deploy
+    pub convergence_timeout: u64,
+}
+
+impl Default for Control {
+    fn default() -> Self {
+        Control {
+            interval: 180,
+            min_improvement: 0.0,
+            min_dwell: 0,
+            max_moves: None,
+            lease_duration: 300,
+            lease_renew: 60,
+            request_timeout: 10,
+            convergence_timeout: 120,
+        }
+    }
+}
+
+// The cost of moving services away from the current placement
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct Migration {
+    // add migration_cost as an objective of the placement problem
+    pub objective: bool,
+    // weight of each volume a service mounts, on top of 1 per service - plus the disk it uses (GB)
+    pub volume_weight: f64,
+    // weight of a service that is the cache of another one
+    pub cache_weight: f64,
+}
+
+impl Default for Migration {
+    fn default() -> Self {
+        Migration {
+            objective: true,
+            volume_weight: 10.0,
+            cache_weight: 5.0,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     pub cluster: Cluster,
     pub database: Database,
     pub services: Vec<Service>,
     pub weights: Vec<Weight>,
+    #[serde(default)]
+    pub control: Control,
+    // how solve_0 ranks the nodes: topsis, vikor or weighted_sum
+    #[serde(default)]
+    pub scoring: ScoringMethod,
+    // how binpack picks a node for each replica: first_fit_decreasing, best_fit or worst_fit
+    #[serde(default)]
+    pub packing: PackingRule,
+    #[serde(default)]
+    pub migration: Migration,
+    // how NSGA2 picks the placement from the Pareto front: sum, knee, tchebycheff, reference_point or lexicographic
+    #[serde(default)]
+    pub decision: DecisionMethod,
+    #[serde(default)]
+    pub latency: Latency,
+    // co-location and separation rules over the services
+    #[serde(default)]
+    pub affinity: Affinity,
 }
 
 // implement a function to return value of the weight when given name
@@ -425,14 +507,14 @@ pub struct ServicePrometheus {
     pub node: Vec<Node>
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ServiceMetric {
     pub service: Service,
     pub utilization: Resource,
 }
 
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NodeMongo {
     pub timestamp: DateTime,
     pub metadata: Node,
@@ -725,4 +807,13 @@ pub fn resource_int_subx(resource_int: ResourceInt, resource: Resource) -> Resou
         disk: (resource_int.disk as f64)/1000.0 - resource.disk,
         network: resource.network,
     }
-}
\ No newline at end of file
+}
+// the capacity of a node left once its usage is counted - the disk of the config is in MB, the usage in GB
+pub fn node_capacity(node: &Node, usage: &Resource) -> Resource {
+    Resource {
+        cpu: node.resource.cpu as f64 - usage.cpu,
+        memory: node.resource.memory as f64 - usage.memory,
+        disk: node.resource.disk as f64 / 1000.0 - usage.disk,
+        network: node.resource.network as f64 - usage.network,
+    }
+}
@@ -1,11 +1,28 @@
-use chrono::Local;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use async_trait::async_trait;
 use mongodb::Client;
+use tokio::time::{Duration, Instant};
 use crate::stack::{self, StackConfig};
 use crate::solver::Solver;
+use crate::strategy::{self, Evaluation, PlacementMap, PlacementStrategy, StrategyContext, StrategyRegistry};
 use crate::trace::{TraceEntry, ServiceGraph, get_latest_trace_entries, build_trees};
 use crate::node::{NodeTree, NodeGraph, NodeEntryMongo};
 use crate::utility::Config;
-use std::process::Command;
+use crate::simulator::Recording;
+use crate::docker_client::DockerClient;
+use crate::rollback::{self, Convergence, RevisionRing, StackRevision};
+use crate::history::{DeployOutcome, PlacementHistory, PlacementRecord};
+use crate::affinity;
+
+// How the placements are optimised
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    // NSGA2 over the three objectives, from the heuristic placement 0
+    Nsga2,
+    // the exact MILP over the communication and resource costs
+    Milp,
+}
 
 #[derive(Debug)]
 pub struct Yonga {
@@ -15,6 +32,17 @@ pub struct Yonga {
     pub running: bool,
     pub revision: u32,
     pub solver: Solver,
+    pub backend: Backend,
+    // build the graphs from a recording instead of the database
+    pub recording: Option<Arc<Recording>>,
+    // the last deployed revisions, to roll back to when a deploy does not converge
+    pub revisions: RevisionRing,
+    pub docker_client: DockerClient,
+    // where each revision is stored - connected on the first deploy
+    pub history: Option<PlacementHistory>,
+    // the placement running on the cluster and when each service last moved
+    pub deployed: Option<PlacementMap>,
+    pub moved_at: HashMap<String, Instant>,
 }
 
 impl Yonga {
@@ -26,104 +54,109 @@ impl Yonga {
             running: false,
             revision: 0,
             solver,
+            backend: Backend::Nsga2,
+            recording: None,
+            revisions: RevisionRing::new(rollback::REVISION_RING_SIZE),
+            docker_client: DockerClient::new(),
+            history: None,
+            deployed: None,
+            moved_at: HashMap::new(),
         }
     }
 
-    pub fn run_deploy(&self, stack_name: &str, compose_file: &str) -> Result<std::process::Output, String> {
-        let output = Command::new("./target/debug/deploy")
-            .arg("--stack")
-            .arg("deploy")
-            .arg("--name")
-            .arg(stack_name)
-            .arg("--file")
-            .arg(compose_file)
-            .output()
-            .map_err(|e| e.to_string())?;
-
-        //print the output
-        println!("Output: {:?}", output);
-        
-        if !output.status.success() {
-            return Err(format!("Command failed with status: {}", output.status));
-        }
-
-        Ok(output)
-    }
-
-    pub async fn start(&mut self) {
-        println!("Starting Yonga placement strategy");
-
-        loop {
-            if self.running {
-                self.placement_lp().await;
-            } else {
-                self.placement_0().await;
-            }
-
-            tokio::time::sleep(tokio::time::Duration::from_secs(180)).await;
-        }
-    }
+    // Redeploy the last healthy revision and keep the solver away from the failed placement.
+    // Returns the revision rolled back to.
+    pub async fn rollback(&mut self, failed_placement: PlacementMap, reason: &str) -> Result<u32, String> {
+        self.solver.mark_failed(failed_placement);
 
-    pub async fn placement_0(&mut self) -> Option<StackConfig> {
-        println!("Running the Yonga placement strategy - placement 0");
+        let good = match self.revisions.last_good() {
+            Some(revision) => revision.clone(),
+            None => return Err(format!("{} - no healthy revision to roll back to", reason)),
+        };
 
-        let placement_map = self.solver.solve_0().await;
+        println!("Rolling back to revision {}: {}", good.revision, reason);
 
-        match placement_map {
-            Ok(map) => {
-                // Update and clean up the stack config
-                let mut local_stack_config = self.stack_config.clone();
-                stack::update_node_constraints(&mut local_stack_config, map);
-                stack::populate_volumes(&mut local_stack_config);
-                stack::delete_null_placement(&mut local_stack_config);
+        // the nodes of the services placed on several nodes carry the labels of the healthy placement again
+        let relabelled = match &good.placement {
+            Some(placement) => strategy::label_nodes(&self.docker_client, placement).await
+                .map_err(|e| format!("{} - failed to roll back the node labels to revision {}: {}", reason, good.revision, e))?,
+            None => HashSet::new(),
+        };
 
-                // print the stack config
-                // println!("Stack Config: {:?}", local_stack_config);
+        strategy::deploy_stack_config(self.name(), &self.stack_name, &good.stack_config)
+            .map_err(|e| format!("{} - failed to roll back to revision {}: {}", reason, good.revision, e))?;
 
-                // Update the stack config
-                self.stack_config = local_stack_config;
+        strategy::reschedule_relabelled(&self.docker_client, &self.stack_name, &self.stack_config, &good.stack_config, relabelled).await
+            .map_err(|e| format!("{} - failed to roll back to revision {}: {}", reason, good.revision, e))?;
 
-                // Create the YAML
-                let yaml_str = serde_yaml::to_string(&self.stack_config).unwrap();
+        // the usage measured before the rollback is that of the failed placement
+        self.solver.snapshots.invalidate().await;
 
-                // Provide the YAML to the deploy binary
-                let compose_file = format!("yonga_{}.yml", Local::now().format("%Y-%m-%d_%H-%M-%S"));
+        // Restore the state of the healthy revision
+        self.stack_config = good.stack_config;
+        self.deployed = good.placement.clone();
+        self.solver.placement = good.placement;
+        self.solver.obj_value = good.obj_value;
 
-                // Write the YAML to a file
-                std::fs::write(&compose_file, yaml_str).expect("Failed to write the YAML configuration file");
+        Ok(good.revision)
+    }
 
-                // Deploy the stack
-                if let Err(e) = self.run_deploy(&self.stack_name, &compose_file) {
-                    println!("Failed to deploy: {}", e);
+    // Store the revision in the placement history - simulations keep no history
+    pub async fn record_revision(&mut self, placement_map: &PlacementMap, outcome: DeployOutcome) {
+        if self.recording.is_some() {
+            return;
+        }
 
-                    // Clean up the file
-                    std::fs::remove_file(&compose_file).expect("Failed to remove the YAML configuration file");
+        if self.history.is_none() {
+            match PlacementHistory::connect(&self.config.database, &self.stack_name).await {
+                Ok(history) => self.history = Some(history),
+                Err(e) => {
+                    println!("Failed to connect to the placement history: {}", e);
+                    return;
                 }
+            }
+        }
 
-                else {
-                    println!("Deployed the stack successfully!");
+        let record = PlacementRecord::new(&self.stack_name, self.revision, self.name(), placement_map, outcome)
+            .with_objectives(&self.solver.objectives.clone().unwrap_or_default(), self.solver.obj_value, self.solver.constraint_violation);
 
-                    // Update the run and revision
-                    self.running = true;
-                    self.revision += 1;
+        if let Some(history) = &self.history {
+            if let Err(e) = history.record(&record).await {
+                println!("Failed to record revision {}: {}", self.revision, e);
+            }
+        }
+    }
 
-                    // Clean up the file
-                    std::fs::remove_file(&compose_file).expect("Failed to remove the YAML configuration file");
-                }
+    // Continue the revisions of a previous run
+    async fn resume_revision(&mut self) {
+        if self.recording.is_some() {
+            return;
+        }
 
-                None
-            },
-            Err(_) => {
-                println!("No solution found");
-                None
+        match PlacementHistory::connect(&self.config.database, &self.stack_name).await {
+            Ok(history) => {
+                match history.latest_revision().await {
+                    Ok(Some(revision)) => {
+                        println!("Resuming the placement history at revision {}", revision);
+                        self.revision = revision;
+                    }
+                    Ok(None) => {}
+                    Err(e) => println!("Failed to read the placement history: {}", e),
+                }
+                self.history = Some(history);
             }
+            Err(e) => println!("Failed to connect to the placement history: {}", e),
         }
     }
 
-    pub async fn placement_lp(&mut self) -> Option<StackConfig> { 
-        println!("Evaluating the current state of the placements - revision {}", self.revision);
+    // Build the service graph from the traces and the node tree from the node collections
+    pub async fn build_graphs(&self) -> Result<(ServiceGraph, NodeTree), Box<dyn std::error::Error>> {
+        if let Some(recording) = &self.recording {
+            println!("Building the graphs from the recording");
+            return Ok(recording.build_graphs(&self.config));
+        }
 
-        let client = Client::with_uri_str(&self.config.database.uri).await.unwrap();
+        let client = Client::with_uri_str(&self.config.database.uri).await?;
         let database = client.database(&self.config.database.db);
         let collection_trace = database.collection::<TraceEntry>("trace");
 
@@ -138,7 +171,7 @@ impl Yonga {
         //let limit = 1000000000; // This can be any variable number
         let limit = 500000;
 
-        let trace_entries = get_latest_trace_entries(&collection_trace, limit).await.unwrap();
+        let trace_entries = get_latest_trace_entries(&collection_trace, limit).await?;
 
         // Print the number of trace entries
         println!("Number of trace entries: {}", trace_entries.len());
@@ -172,59 +205,183 @@ impl Yonga {
         // Print the entire node graph
         //node_tree.print_tree();
 
+        Ok((service_tree, node_tree))
+    }
+}
 
-        //let placement_map = self.solver.solve_1(service_tree, node_tree).await;
-        let placement_map = self.solver.solve_lp_nsga2opticas(service_tree, node_tree).await;
-
+#[async_trait(?Send)]
+impl PlacementStrategy for Yonga {
+    fn name(&self) -> &'static str {
+        match self.backend {
+            Backend::Nsga2 => "yonga",
+            Backend::Milp => "milp",
+        }
+    }
 
-        match placement_map {
-            Ok(map) => {
+    async fn plan(&mut self) -> Result<PlacementMap, Box<dyn std::error::Error>> {
+        // the MILP needs no placement to start from
+        let mut placement_map = if !self.running && self.backend == Backend::Nsga2 {
+            println!("Running the Yonga placement strategy - placement 0");
 
-                // print the placement map
-                // println!("Placement Map: {:?}", map);
+            self.solver.solve_0().await?
+        } else {
+            println!("Evaluating the current state of the placements - revision {}", self.revision);
 
-                // Update and clean up the stack config
-                let mut local_stack_config = self.stack_config.clone();
-                stack::update_node_constraints(&mut local_stack_config, map);
-                stack::populate_volumes(&mut local_stack_config);
-                stack::delete_null_placement(&mut local_stack_config);
+            let (service_tree, node_tree) = self.build_graphs().await?;
 
-                // Update the stack config
-                self.stack_config = local_stack_config;
+            //let placement_map = self.solver.solve_1(service_tree, node_tree).await;
+            match self.backend {
+                Backend::Nsga2 => self.solver.solve_lp_nsga2opticas(service_tree, node_tree).await?,
+                Backend::Milp => self.solver.solve_milp(service_tree, node_tree).await?,
+            }
+        };
+
+        // the hard affinity rules are constraints of the problem - a solution that still breaks them
+        // (or the first placement) is repaired, and the repair must not lead back to a placement that
+        // failed to converge. Otherwise the solver continues from what is deployed.
+        let rules: Vec<_> = affinity::rules(&self.config).into_iter().filter(|rule| rule.kind.is_hard()).collect();
+        match affinity::enforce(&mut placement_map, &rules, &self.config.cluster.nodes, &self.solver.replica_nodes) {
+            Ok(0) => {}
+            Ok(_) if self.solver.is_failed(&placement_map) => {
+                self.solver.placement = self.deployed.clone();
+                return Err("The affinity rules lead back to a placement that failed to converge".into());
+            }
+            Ok(_) => self.solver.placement = Some(placement_map.clone()),
+            Err(e) => {
+                self.solver.placement = self.deployed.clone();
+                return Err(e.into());
+            }
+        }
 
-                // Create the YAML
-                let yaml_str = serde_yaml::to_string(&self.stack_config).unwrap();
+        Ok(placement_map)
+    }
 
-                // Provide the YAML to the deploy binary
-                let compose_file = format!("yonga_{}.yml", Local::now().format("%Y-%m-%d_%H-%M-%S"));
+    async fn apply(&mut self, placement_map: PlacementMap) -> Result<(), String> {
+        if !self.running && self.history.is_none() {
+            self.resume_revision().await;
+        }
 
-                // Write the YAML to a file
-                std::fs::write(&compose_file, yaml_str).expect("Failed to write the YAML configuration file");
+        // Hold back the services within their dwell time or beyond the move budget
+        let placement_map = match &self.deployed {
+            Some(deployed) if self.running => {
+                let rules: Vec<_> = affinity::rules(&self.config).into_iter().filter(|rule| rule.kind.is_hard()).collect();
+                let limited = strategy::limit_moves(deployed, placement_map, &self.moved_at, &self.config.control, &rules);
 
-                // Deploy the stack
-                if let Err(e) = self.run_deploy(&self.stack_name, &compose_file) {
-                    println!("Failed to deploy: {}", e);
+                if &limited == deployed {
+                    println!("No service can move in this cycle");
+                    self.solver.placement = Some(limited);
+                    return Ok(());
+                }
 
-                    // Clean up the file
-                    std::fs::remove_file(&compose_file).expect("Failed to remove the YAML configuration file");
+                // the solver continues from what gets deployed
+                self.solver.placement = Some(limited.clone());
+                limited
+            }
+            _ => placement_map,
+        };
+
+        let deployed_at = chrono::Utc::now();
+        let deployed = if self.running {
+            // Only touch the services that moved since the last revision
+            strategy::migrate_placement(&self.stack_name, &mut self.stack_config, placement_map.clone()).await.map(|_| ())
+        } else {
+            strategy::deploy_placement(self.name(), &self.stack_name, &mut self.stack_config, placement_map.clone()).await
+        };
+
+        // Watch the tasks until the services run where they were placed
+        let converged = match deployed {
+            // a migration may have moved some of the services already
+            Err(e) => Err(e),
+            Ok(()) => match rollback::wait_for_convergence(&self.docker_client, &self.stack_name, &self.stack_config, &placement_map, deployed_at, Duration::from_secs(self.config.control.convergence_timeout)).await {
+                Ok(Convergence::Converged) => Ok(DeployOutcome::Converged),
+                Ok(Convergence::Failed(failures)) => {
+                    for failure in &failures {
+                        println!("Service {} runs {}/{} replicas - {}", failure.service, failure.running, failure.desired, failure.reason);
+                    }
+
+                    Err(format!("{} service(s) did not converge", failures.len()))
+                }
+                Err(e) => {
+                    // the revision stays, but is not one to roll back to
+                    println!("Failed to watch the tasks of the stack: {}", e);
+                    Ok(DeployOutcome::Unverified)
                 }
+            },
+        };
 
-                else {
-                    println!("Deployed the stack successfully!");
+        // the services moved - the next plan measures the cluster anew
+        self.solver.snapshots.invalidate().await;
 
-                    // Update the run and revision
-                    self.revision += 1;
+        // Update the revision
+        self.revision += 1;
 
-                    // Clean up the file
-                    std::fs::remove_file(&compose_file).expect("Failed to remove the YAML configuration file");
+        match converged {
+            Ok(outcome) => {
+                self.running = true;
+
+                let now = Instant::now();
+                for (service, nodes) in &placement_map {
+                    let moved = self.deployed.as_ref().map_or(true, |deployed| deployed.get(service) != Some(nodes));
+                    if moved {
+                        self.moved_at.insert(service.name.clone(), now);
+                    }
                 }
-                None
+                self.deployed = Some(placement_map.clone());
+
+                self.revisions.push(StackRevision {
+                    revision: self.revision,
+                    stack_config: self.stack_config.clone(),
+                    placement: Some(placement_map.clone()),
+                    obj_value: self.solver.obj_value,
+                    healthy: outcome == DeployOutcome::Converged,
+                });
+
+                self.record_revision(&placement_map, outcome).await;
+
+                Ok(())
             }
-            Err(_) => {
-                println!("No solution found");
-                None
+            Err(reason) => {
+                let outcome = match self.rollback(placement_map.clone(), &reason).await {
+                    Ok(to) => DeployOutcome::RolledBack { reason, to },
+                    Err(e) => DeployOutcome::Failed { reason: e },
+                };
+
+                self.record_revision(&placement_map, outcome.clone()).await;
+
+                Err(outcome.to_string())
             }
         }
+    }
+
+    fn assume_applied(&mut self, placement_map: &PlacementMap) {
+        self.stack_config = strategy::render_stack_config(&self.stack_config, placement_map.clone());
+
+        // Update the run and revision
+        self.running = true;
+        self.revision += 1;
+    }
 
+    fn placement(&self) -> Option<&PlacementMap> {
+        self.solver.placement.as_ref()
     }
+
+    fn replans(&self) -> bool {
+        true
+    }
+
+    async fn evaluate(&mut self) -> Evaluation {
+        Evaluation::Replan(Duration::from_secs(self.config.control.interval))
+    }
+}
+
+pub fn register(registry: &mut StrategyRegistry) {
+    registry.register("yonga", |context: StrategyContext| {
+        let mut solver = Solver::new(context.config.clone(), context.api_client);
+        solver.replicas = stack::get_replicas(&context.stack_config);
+        solver.volumes = stack::get_volumes(&context.stack_config);
+        solver.snapshots = context.snapshots;
+        let mut yonga = Yonga::new(context.config, context.stack_name, context.stack_config, solver);
+        yonga.recording = context.recording;
+        Box::new(yonga)
+    });
 }